@@ -0,0 +1,261 @@
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+use asm::{AssemblyError, AssemblyErrorKind, Program};
+
+/// Assembles 6502 source at compile time into an `asm::ProgramImage`.
+///
+/// The source uses the same syntax as `asm::assemble`, except that comments
+/// are written as Rust `//` comments. Statements end at a line break or a
+/// `;`. The expansion names `asm::ProgramImage`, so the calling crate needs
+/// `asm` as a dependency too.
+///
+/// ```
+/// # use asm_macros::asm;
+/// let program = asm! {
+///     .org $8000
+/// start: ldx #0
+///     inx; bne start
+/// };
+/// assert_eq!(program.bytes, [0xA2, 0x00, 0xE8, 0xD0, 0xFB]);
+/// assert_eq!(program.symbol("start"), Some(0x8000));
+/// ```
+///
+/// The source is lexed as Rust tokens, so a hex number that starts with a
+/// digit and has an `E` not followed by a digit, such as `$1E` or `$10E`,
+/// is rejected by the compiler as a malformed float. Write those as `0x1E`,
+/// or pass the whole source as a single string literal, which is handed to
+/// `asm::assemble` unchanged (so comments are written with `;` there):
+///
+/// ```
+/// # use asm_macros::asm;
+/// let program = asm!("
+///     lda $1E     ; zero page
+///     sta 0x10E
+/// ");
+/// assert_eq!(program.bytes, [0xA5, 0x1E, 0x8D, 0x0E, 0x01]);
+/// ```
+///
+/// Mistakes in the source are reported as compile errors:
+///
+/// ```compile_fail
+/// # use asm_macros::asm;
+/// let program = asm! { lda #$100 };
+/// ```
+#[proc_macro]
+pub fn asm(input: TokenStream) -> TokenStream {
+    if let Some((source, span)) = string_literal(&input) {
+        return match asm::assemble(&source) {
+            Ok(program) => expand(&program),
+            Err(error) => compile_error(&error.to_string(), span),
+        };
+    }
+    let lines: Vec<Vec<TokenTree>> = split_lines(input);
+    let source: String = lines
+        .iter()
+        .map(|line| render(line))
+        .collect::<Vec<String>>()
+        .join("\n");
+    match asm::assemble(&source) {
+        Ok(program) => expand(&program),
+        Err(error) => {
+            let span: Span = error_span(&error, &lines);
+            compile_error(&error.to_string(), span)
+        }
+    }
+}
+
+/// Returns the contents of the input if it is a single string literal.
+fn string_literal(input: &TokenStream) -> Option<(String, Span)> {
+    let mut tokens = input.clone().into_iter();
+    let (Some(TokenTree::Literal(literal)), None) = (tokens.next(), tokens.next()) else {
+        return None;
+    };
+    let text: String = literal.to_string();
+    let source: String = if let Some(raw) = text.strip_prefix('r') {
+        let hashes: usize = raw.len() - raw.trim_start_matches('#').len();
+        raw[hashes + 1..raw.len() - hashes - 1].to_string()
+    } else {
+        unescape(text.strip_prefix('"')?.strip_suffix('"')?)
+    };
+    Some((source, literal.span()))
+}
+
+/// Undoes the escapes of a (non-raw) Rust string literal.
+fn unescape(text: &str) -> String {
+    let mut unescaped: String = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('t') => unescaped.push('\t'),
+            Some('0') => unescaped.push('\0'),
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                unescaped.push(u8::from_str_radix(&digits, 16).unwrap() as char);
+            }
+            Some('u') => {
+                let digits: String = chars
+                    .by_ref()
+                    .take_while(|&c| c != '}')
+                    .filter(|&c| c != '{' && c != '_')
+                    .collect();
+                let value: u32 = u32::from_str_radix(&digits, 16).unwrap();
+                unescaped.push(char::from_u32(value).unwrap());
+            }
+            // A line continuation skips the line break and the indentation
+            Some('\n') => while chars.next_if(|c| c.is_whitespace()).is_some() {},
+            Some(c) => unescaped.push(c),
+            None => {}
+        }
+    }
+    unescaped
+}
+
+/// Splits the input into statements, one per source line or `;`.
+fn split_lines(input: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut lines: Vec<Vec<TokenTree>> = Vec::new();
+    let mut line: Vec<TokenTree> = Vec::new();
+    let mut previous_line: Option<usize> = None;
+    for token in input {
+        if let TokenTree::Punct(punct) = &token {
+            if punct.as_char() == ';' {
+                lines.push(std::mem::take(&mut line));
+                previous_line = None;
+                continue;
+            }
+        }
+        let token_line: usize = token.span().line();
+        if previous_line.is_some_and(|previous| previous != token_line) && !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        previous_line = Some(token.span().end().line());
+        line.push(token);
+    }
+    lines.push(line);
+    lines
+}
+
+/// Turns tokens back into source text, keeping tokens that were written
+/// next to each other (such as `$` and `FF`) together.
+fn render(tokens: &[TokenTree]) -> String {
+    let mut text: String = String::new();
+    let mut previous_end: Option<(usize, usize)> = None;
+    render_into(tokens, &mut text, &mut previous_end);
+    text
+}
+
+fn render_into(tokens: &[TokenTree], text: &mut String, previous_end: &mut Option<(usize, usize)>) {
+    for token in tokens {
+        match token {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::None => ("", ""),
+                };
+                push_token(text, previous_end, open, group.span_open());
+                let inner: Vec<TokenTree> = group.stream().into_iter().collect();
+                render_into(&inner, text, previous_end);
+                push_token(text, previous_end, close, group.span_close());
+            }
+            _ => push_token(text, previous_end, &token.to_string(), token.span()),
+        }
+    }
+}
+
+fn push_token(
+    text: &mut String,
+    previous_end: &mut Option<(usize, usize)>,
+    token: &str,
+    span: Span,
+) {
+    let start: (usize, usize) = (span.line(), span.column());
+    if previous_end.is_some_and(|end| end != start) {
+        text.push(' ');
+    }
+    text.push_str(token);
+    *previous_end = Some((span.end().line(), span.end().column()));
+}
+
+/// Picks the token an error is about: the named symbol, mnemonic or
+/// directive if there is one, otherwise the operand.
+fn error_span(error: &AssemblyError, lines: &[Vec<TokenTree>]) -> Span {
+    let Some(line) = lines.get(error.line - 1).filter(|line| !line.is_empty()) else {
+        return Span::call_site();
+    };
+    let name: Option<&str> = match &error.kind {
+        AssemblyErrorKind::UnknownMnemonic(name)
+        | AssemblyErrorKind::UndefinedSymbol(name)
+        | AssemblyErrorKind::DuplicateSymbol(name) => Some(name),
+        AssemblyErrorKind::UnknownDirective(name) => name.strip_prefix('.'),
+        _ => None,
+    };
+    if let Some(token) = name.and_then(|name| find_token(line, name)) {
+        return token;
+    }
+    let operand: usize = line
+        .iter()
+        .position(|token| matches!(token, TokenTree::Ident(_)))
+        .map_or(0, |mnemonic| mnemonic + 1);
+    line.get(operand).unwrap_or(&line[0]).span()
+}
+
+fn find_token(tokens: &[TokenTree], name: &str) -> Option<Span> {
+    tokens.iter().find_map(|token| match token {
+        TokenTree::Ident(ident) if ident.to_string() == name => Some(ident.span()),
+        TokenTree::Group(group) => {
+            let inner: Vec<TokenTree> = group.stream().into_iter().collect();
+            find_token(&inner, name)
+        }
+        _ => None,
+    })
+}
+
+fn expand(program: &Program) -> TokenStream {
+    let bytes: Vec<String> = program
+        .image()
+        .iter()
+        .map(|byte| format!("{:#04x}u8", byte))
+        .collect();
+    let symbols: Vec<String> = program
+        .symbols
+        .iter()
+        .map(|(name, value)| format!("({:?}, {:#06x}u16)", name, value))
+        .collect();
+    format!(
+        "::asm::ProgramImage {{ origin: {:#06x}u16, bytes: [{}], symbols: &[{}] }}",
+        program.origin(),
+        bytes.join(", "),
+        symbols.join(", ")
+    )
+    .parse()
+    .unwrap()
+}
+
+fn compile_error(message: &str, span: Span) -> TokenStream {
+    let mut message: Literal = Literal::string(message);
+    message.set_span(span);
+    let tokens: Vec<TokenTree> = vec![
+        Punct::new(':', Spacing::Joint).into(),
+        Punct::new(':', Spacing::Alone).into(),
+        Ident::new("core", span).into(),
+        Punct::new(':', Spacing::Joint).into(),
+        Punct::new(':', Spacing::Alone).into(),
+        Ident::new("compile_error", span).into(),
+        Punct::new('!', Spacing::Alone).into(),
+        Group::new(Delimiter::Parenthesis, TokenTree::from(message).into()).into(),
+    ];
+    tokens
+        .into_iter()
+        .map(|mut token| {
+            token.set_span(span);
+            token
+        })
+        .collect()
+}
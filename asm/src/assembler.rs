@@ -0,0 +1,635 @@
+use std::collections::BTreeMap;
+
+use crate::error::{AssemblyError, AssemblyErrorKind};
+use crate::expression::Expression;
+use crate::opcodes::{self, AddressingMode, Mnemonic, OpcodeInfo, OPCODES, OPCODES_65C02};
+
+/// The output of `assemble`: the bytes of every `.org` block and the final
+/// value of every label and constant.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Program {
+    pub segments: Vec<Segment>,
+    pub symbols: BTreeMap<String, u16>,
+}
+
+/// A run of bytes that is loaded starting at `address`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Segment {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+}
+
+impl Program {
+    /// The lowest address any segment is loaded at.
+    pub fn origin(&self) -> u16 {
+        self.segments
+            .iter()
+            .map(|segment| segment.address)
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Flattens the segments into one image starting at `origin`, with any
+    /// gaps between segments filled with zeroes.
+    pub fn image(&self) -> Vec<u8> {
+        let origin: usize = self.origin() as usize;
+        let end: usize = self
+            .segments
+            .iter()
+            .map(|segment| segment.address as usize + segment.bytes.len())
+            .max()
+            .unwrap_or(origin);
+        let mut image: Vec<u8> = vec![0x00; end - origin];
+        for segment in &self.segments {
+            let start: usize = segment.address as usize - origin;
+            image[start..start + segment.bytes.len()].copy_from_slice(&segment.bytes);
+        }
+        image
+    }
+
+    /// Copies every segment into a 64KB address space, e.g. `Memory::data`.
+    pub fn load_into(&self, memory: &mut [u8]) {
+        for segment in &self.segments {
+            for (offset, byte) in segment.bytes.iter().enumerate() {
+                memory[(segment.address as usize + offset) & 0xFFFF] = *byte;
+            }
+        }
+    }
+}
+
+/// Assembles 6502 source text.
+///
+/// Each line holds an optional `label:`, then an instruction, a directive
+/// or a `NAME = value` constant, then an optional `; comment`. Mnemonics,
+/// directives and register names are case-insensitive. The supported
+/// directives are `.org`, `.byte`, `.word`, `.text` and `.cpu`.
+///
+/// `.cpu 65c02` switches to the WDC 65C02 instruction set for the lines
+/// that follow, and `.cpu 6502` back to the NMOS one. The bit number of
+/// RMB, SMB, BBR and BBS is part of the mnemonic, as in `BBR3 $12,label`.
+///
+/// Labels may be used before they are defined. An operand that is not yet
+/// known during the first pass always uses absolute addressing, even if it
+/// turns out to fit in the zero page.
+pub fn assemble(source: &str) -> Result<Program, AssemblyError> {
+    assemble_for(source, &OPCODES)
+}
+
+/// Like `assemble`, but starting with the instruction set of the given
+/// opcode table instead of the NMOS 6502's.
+pub fn assemble_for(
+    source: &str,
+    opcodes: &'static [OpcodeInfo; 256],
+) -> Result<Program, AssemblyError> {
+    let mut opcodes: &'static [OpcodeInfo; 256] = opcodes;
+    let mut lines: Vec<Line> = Vec::new();
+    for (index, text) in source.lines().enumerate() {
+        let line: Line =
+            parse_line(text, opcodes).map_err(|kind| AssemblyError::new(index + 1, kind))?;
+        if let Statement::Cpu(table) = line.statement {
+            opcodes = table;
+        }
+        lines.push(line);
+    }
+
+    // Pass 1: lay out every line and give each label its address
+    let mut symbols: BTreeMap<String, i64> = BTreeMap::new();
+    let mut modes: Vec<Option<AddressingMode>> = Vec::with_capacity(lines.len());
+    let mut address: i64 = 0;
+    for (index, line) in lines.iter().enumerate() {
+        let error = |kind| AssemblyError::new(index + 1, kind);
+        if let Some(label) = &line.label {
+            define(&mut symbols, label, address).map_err(error)?;
+        }
+        let mut mode: Option<AddressingMode> = None;
+        match &line.statement {
+            Statement::Empty | Statement::Cpu(_) => {}
+            Statement::Constant(name, expression) => {
+                if let Ok(value) = expression.evaluate(&symbols, address) {
+                    define(&mut symbols, name, value).map_err(error)?;
+                }
+            }
+            Statement::Org(expression) => {
+                address = expression.evaluate(&symbols, address).map_err(error)?;
+            }
+            Statement::Byte(items) => {
+                address += items.iter().map(DataItem::length).sum::<i64>();
+            }
+            Statement::Word(items) => address += 2 * items.len() as i64,
+            Statement::Instruction(mnemonic, _, operand) => {
+                let addressing_mode: AddressingMode =
+                    select_addressing_mode(*mnemonic, operand, &symbols, address, line.opcodes)
+                        .map_err(error)?;
+                address += 1 + addressing_mode.operand_length() as i64;
+                mode = Some(addressing_mode);
+            }
+        }
+        if !(0..=0x10000).contains(&address) {
+            return Err(error(AssemblyErrorKind::ValueOutOfRange(address)));
+        }
+        modes.push(mode);
+    }
+
+    // Constants that referred to labels further down can be worked out now
+    let mut pending: Vec<(usize, &String, &Expression)> = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(index, line)| match &line.statement {
+            Statement::Constant(name, expression) if !symbols.contains_key(name) => {
+                Some((index, name, expression))
+            }
+            _ => None,
+        })
+        .collect();
+    while !pending.is_empty() {
+        let count: usize = pending.len();
+        pending.retain(
+            |(_, name, expression)| match expression.evaluate(&symbols, 0) {
+                Ok(value) => {
+                    symbols.insert((*name).clone(), value);
+                    false
+                }
+                Err(_) => true,
+            },
+        );
+        if pending.len() == count {
+            let (index, _, expression) = pending[0];
+            let kind: AssemblyErrorKind = expression.evaluate(&symbols, 0).unwrap_err();
+            return Err(AssemblyError::new(index + 1, kind));
+        }
+    }
+
+    // Pass 2: emit the bytes, now that every symbol is known
+    let mut program: Program = Program::default();
+    let mut segment: Segment = Segment::default();
+    address = 0;
+    for (index, line) in lines.iter().enumerate() {
+        let error = |kind| AssemblyError::new(index + 1, kind);
+        let evaluate = |expression: &Expression| {
+            expression
+                .evaluate(&symbols, address)
+                .map_err(|kind| AssemblyError::new(index + 1, kind))
+        };
+        let bytes: &mut Vec<u8> = &mut segment.bytes;
+        match &line.statement {
+            Statement::Empty | Statement::Cpu(_) | Statement::Constant(..) => {}
+            Statement::Org(expression) => {
+                let origin: i64 = evaluate(expression)?;
+                if !segment.bytes.is_empty() {
+                    program.segments.push(segment);
+                }
+                segment = Segment {
+                    address: origin as u16,
+                    bytes: Vec::new(),
+                };
+                address = origin;
+                continue;
+            }
+            Statement::Byte(items) => {
+                for item in items {
+                    match item {
+                        DataItem::Value(expression) => {
+                            let value: i64 = evaluate(expression)?;
+                            bytes.push(check_range(value, -0x80, 0xFF).map_err(error)? as u8);
+                        }
+                        DataItem::Text(text) => bytes.extend_from_slice(text),
+                    }
+                }
+            }
+            Statement::Word(items) => {
+                for expression in items {
+                    let value: i64 = evaluate(expression)?;
+                    let word: u16 = check_range(value, -0x8000, 0xFFFF).map_err(error)? as u16;
+                    bytes.extend_from_slice(&word.to_le_bytes());
+                }
+            }
+            Statement::Instruction(mnemonic, bit, operand) => {
+                let addressing_mode: AddressingMode = modes[index].unwrap();
+                let opcode: u8 =
+                    opcodes::encode_in(line.opcodes, *mnemonic, addressing_mode).unwrap();
+                bytes.push(opcode + (bit << 4));
+                if let Operand::ZeroPageRelative(zero_page, target) = operand {
+                    bytes.push(check_range(evaluate(zero_page)?, 0, 0xFF).map_err(error)? as u8);
+                    let offset: i64 = evaluate(target)? - (address + 3);
+                    if !(-0x80..=0x7F).contains(&offset) {
+                        return Err(error(AssemblyErrorKind::BranchOutOfRange(offset)));
+                    }
+                    bytes.push(offset as u8);
+                } else if let Some(expression) = operand.expression() {
+                    let value: i64 = evaluate(expression)?;
+                    match addressing_mode {
+                        AddressingMode::Relative => {
+                            let offset: i64 = value - (address + 2);
+                            if !(-0x80..=0x7F).contains(&offset) {
+                                return Err(error(AssemblyErrorKind::BranchOutOfRange(offset)));
+                            }
+                            bytes.push(offset as u8);
+                        }
+                        AddressingMode::Immediate => {
+                            bytes.push(check_range(value, -0x80, 0xFF).map_err(error)? as u8);
+                        }
+                        _ if addressing_mode.operand_length() == 1 => {
+                            bytes.push(check_range(value, 0, 0xFF).map_err(error)? as u8);
+                        }
+                        _ => {
+                            let word: u16 = check_range(value, 0, 0xFFFF).map_err(error)? as u16;
+                            bytes.extend_from_slice(&word.to_le_bytes());
+                        }
+                    }
+                }
+            }
+        }
+        address = segment.address as i64 + segment.bytes.len() as i64;
+    }
+    if !segment.bytes.is_empty() {
+        program.segments.push(segment);
+    }
+    program.symbols = symbols
+        .into_iter()
+        .map(|(name, value)| (name, value as u16))
+        .collect();
+    Ok(program)
+}
+
+struct Line {
+    label: Option<String>,
+    statement: Statement,
+    /// The instruction set selected by the last `.cpu` before the line.
+    opcodes: &'static [OpcodeInfo; 256],
+}
+
+enum Statement {
+    Empty,
+    Constant(String, Expression),
+    Org(Expression),
+    Cpu(&'static [OpcodeInfo; 256]),
+    Byte(Vec<DataItem>),
+    Word(Vec<Expression>),
+    /// The mnemonic, the bit number of RMB, SMB, BBR and BBS (zero for
+    /// every other instruction) and the operand.
+    Instruction(Mnemonic, u8, Operand),
+}
+
+enum DataItem {
+    Value(Expression),
+    Text(Vec<u8>),
+}
+
+impl DataItem {
+    fn length(&self) -> i64 {
+        match self {
+            DataItem::Value(_) => 1,
+            DataItem::Text(text) => text.len() as i64,
+        }
+    }
+}
+
+/// The operand as written, before it is narrowed down to one addressing
+/// mode. `Address`, `IndexedX` and `IndexedY` can become zero page,
+/// absolute or relative addressing, and on the 65C02 `Indirect` and
+/// `IndirectX` can also be the zero page indirect and `JMP (abs,X)` modes.
+enum Operand {
+    None,
+    Accumulator,
+    Immediate(Expression),
+    Address(Expression),
+    IndexedX(Expression),
+    IndexedY(Expression),
+    Indirect(Expression),
+    IndirectX(Expression),
+    IndirectY(Expression),
+    /// The zero page address and branch target of BBR and BBS.
+    ZeroPageRelative(Expression, Expression),
+}
+
+impl Operand {
+    fn expression(&self) -> Option<&Expression> {
+        match self {
+            Operand::None | Operand::Accumulator | Operand::ZeroPageRelative(..) => None,
+            Operand::Immediate(expression)
+            | Operand::Address(expression)
+            | Operand::IndexedX(expression)
+            | Operand::IndexedY(expression)
+            | Operand::Indirect(expression)
+            | Operand::IndirectX(expression)
+            | Operand::IndirectY(expression) => Some(expression),
+        }
+    }
+}
+
+fn define(
+    symbols: &mut BTreeMap<String, i64>,
+    name: &str,
+    value: i64,
+) -> Result<(), AssemblyErrorKind> {
+    if symbols.insert(name.to_string(), value).is_some() {
+        return Err(AssemblyErrorKind::DuplicateSymbol(name.to_string()));
+    }
+    Ok(())
+}
+
+fn check_range(value: i64, min: i64, max: i64) -> Result<i64, AssemblyErrorKind> {
+    if (min..=max).contains(&value) {
+        Ok(value)
+    } else {
+        Err(AssemblyErrorKind::ValueOutOfRange(value))
+    }
+}
+
+fn select_addressing_mode(
+    mnemonic: Mnemonic,
+    operand: &Operand,
+    symbols: &BTreeMap<String, i64>,
+    address: i64,
+    opcodes: &[OpcodeInfo; 256],
+) -> Result<AddressingMode, AssemblyErrorKind> {
+    let supports = |addressing_mode| mnemonic.supports_in(opcodes, addressing_mode);
+    let fits_zero_page =
+        |expression: &Expression| matches!(expression.evaluate(symbols, address), Ok(0..=0xFF));
+    let zero_page_or_absolute = |expression: &Expression, zero_page, absolute| {
+        if fits_zero_page(expression) && supports(zero_page) {
+            zero_page
+        } else if supports(absolute) {
+            absolute
+        } else {
+            zero_page
+        }
+    };
+    let addressing_mode: AddressingMode = match operand {
+        Operand::None if supports(AddressingMode::Implied) => AddressingMode::Implied,
+        Operand::None | Operand::Accumulator => AddressingMode::Accumulator,
+        Operand::Immediate(_) => AddressingMode::Immediate,
+        Operand::Address(_) if supports(AddressingMode::Relative) => AddressingMode::Relative,
+        Operand::Address(expression) => zero_page_or_absolute(
+            expression,
+            AddressingMode::ZeroPage,
+            AddressingMode::Absolute,
+        ),
+        Operand::IndexedX(expression) => zero_page_or_absolute(
+            expression,
+            AddressingMode::ZeroPageX,
+            AddressingMode::AbsoluteX,
+        ),
+        Operand::IndexedY(expression) => zero_page_or_absolute(
+            expression,
+            AddressingMode::ZeroPageY,
+            AddressingMode::AbsoluteY,
+        ),
+        Operand::Indirect(_) if supports(AddressingMode::Indirect) => AddressingMode::Indirect,
+        Operand::Indirect(_) => AddressingMode::ZeroPageIndirect,
+        Operand::IndirectX(_) if supports(AddressingMode::AbsoluteIndexedIndirect) => {
+            AddressingMode::AbsoluteIndexedIndirect
+        }
+        Operand::IndirectX(_) => AddressingMode::IndirectX,
+        Operand::IndirectY(_) => AddressingMode::IndirectY,
+        Operand::ZeroPageRelative(..) => AddressingMode::ZeroPageRelative,
+    };
+    if supports(addressing_mode) {
+        Ok(addressing_mode)
+    } else {
+        Err(AssemblyErrorKind::InvalidAddressingMode(
+            mnemonic.to_string(),
+        ))
+    }
+}
+
+fn parse_line(text: &str, opcodes: &'static [OpcodeInfo; 256]) -> Result<Line, AssemblyErrorKind> {
+    let mut rest: &str = strip_comment(text).trim();
+    let mut label: Option<String> = None;
+    if let Some(colon) = rest.find(':') {
+        if is_identifier(rest[..colon].trim_end()) {
+            label = Some(rest[..colon].trim_end().to_string());
+            rest = rest[colon + 1..].trim_start();
+        }
+    }
+    let constant: Option<(&str, &str)> = rest
+        .split_once('=')
+        .filter(|(name, _)| is_identifier(name.trim()));
+    let statement: Statement = if rest.is_empty() {
+        Statement::Empty
+    } else if let Some((name, value)) = constant {
+        Statement::Constant(name.trim().to_string(), Expression::parse(value)?)
+    } else {
+        let (keyword, arguments) = match rest.find(char::is_whitespace) {
+            Some(index) => (&rest[..index], rest[index..].trim()),
+            None => (rest, ""),
+        };
+        if keyword.starts_with('.') {
+            parse_directive(keyword, arguments)?
+        } else {
+            let (mnemonic, bit): (Mnemonic, u8) = parse_mnemonic(keyword, opcodes)
+                .ok_or_else(|| AssemblyErrorKind::UnknownMnemonic(keyword.to_string()))?;
+            let operand: Operand = parse_operand(mnemonic, arguments, opcodes)?;
+            Statement::Instruction(mnemonic, bit, operand)
+        }
+    };
+    Ok(Line {
+        label,
+        statement,
+        opcodes,
+    })
+}
+
+/// Looks up a mnemonic, splitting the bit number off RMB, SMB, BBR and BBS.
+fn parse_mnemonic(name: &str, opcodes: &[OpcodeInfo; 256]) -> Option<(Mnemonic, u8)> {
+    let is_bit_instruction = |mnemonic: &Mnemonic| {
+        matches!(
+            mnemonic,
+            Mnemonic::RMB | Mnemonic::SMB | Mnemonic::BBR | Mnemonic::BBS
+        )
+    };
+    if let Some(mnemonic) = Mnemonic::from_name_in(opcodes, name) {
+        return (!is_bit_instruction(&mnemonic)).then_some((mnemonic, 0));
+    }
+    let (base, bit) = name.split_at(name.len().checked_sub(1)?);
+    let bit: u8 = bit.parse().ok().filter(|bit| *bit < 8)?;
+    Mnemonic::from_name_in(opcodes, base)
+        .filter(is_bit_instruction)
+        .map(|mnemonic| (mnemonic, bit))
+}
+
+fn parse_directive(directive: &str, arguments: &str) -> Result<Statement, AssemblyErrorKind> {
+    match directive.to_ascii_lowercase().as_str() {
+        ".org" => Ok(Statement::Org(Expression::parse(arguments)?)),
+        ".cpu" => match arguments.to_ascii_lowercase().as_str() {
+            "6502" => Ok(Statement::Cpu(&OPCODES)),
+            "65c02" => Ok(Statement::Cpu(&OPCODES_65C02)),
+            _ => Err(AssemblyErrorKind::UnknownCpu(arguments.to_string())),
+        },
+        ".byte" | ".text" => split_list(arguments)
+            .into_iter()
+            .map(|item| {
+                if item.starts_with('"') {
+                    parse_string(item).map(DataItem::Text)
+                } else {
+                    Expression::parse(item).map(DataItem::Value)
+                }
+            })
+            .collect::<Result<_, _>>()
+            .map(Statement::Byte),
+        ".word" => split_list(arguments)
+            .into_iter()
+            .map(Expression::parse)
+            .collect::<Result<_, _>>()
+            .map(Statement::Word),
+        _ => Err(AssemblyErrorKind::UnknownDirective(directive.to_string())),
+    }
+}
+
+fn parse_operand(
+    mnemonic: Mnemonic,
+    text: &str,
+    opcodes: &[OpcodeInfo; 256],
+) -> Result<Operand, AssemblyErrorKind> {
+    let supports = |addressing_mode| mnemonic.supports_in(opcodes, addressing_mode);
+    if text.is_empty() {
+        return Ok(Operand::None);
+    }
+    if text.eq_ignore_ascii_case("a") && supports(AddressingMode::Accumulator) {
+        return Ok(Operand::Accumulator);
+    }
+    if supports(AddressingMode::ZeroPageRelative) {
+        let [zero_page, target] = split_list(text)[..] else {
+            return Err(AssemblyErrorKind::InvalidAddressingMode(
+                mnemonic.to_string(),
+            ));
+        };
+        return Ok(Operand::ZeroPageRelative(
+            Expression::parse(zero_page)?,
+            Expression::parse(target)?,
+        ));
+    }
+    if let Some(value) = text.strip_prefix('#') {
+        return Ok(Operand::Immediate(Expression::parse(value)?));
+    }
+    let compact: String = text.split_whitespace().collect();
+    let upper: String = compact.to_ascii_uppercase();
+    if upper.starts_with('(') {
+        if let Some(inner) = upper.strip_suffix(",X)") {
+            return Ok(Operand::IndirectX(Expression::parse(
+                &compact[1..inner.len()],
+            )?));
+        }
+        if let Some(inner) = upper.strip_suffix("),Y") {
+            return Ok(Operand::IndirectY(Expression::parse(
+                &compact[1..inner.len()],
+            )?));
+        }
+        if upper.ends_with(')')
+            && matching_parenthesis(&compact) == Some(compact.len() - 1)
+            && (supports(AddressingMode::Indirect) || supports(AddressingMode::ZeroPageIndirect))
+        {
+            let inner: &str = &compact[1..compact.len() - 1];
+            return Ok(Operand::Indirect(Expression::parse(inner)?));
+        }
+    }
+    if let Some(base) = upper.strip_suffix(",X") {
+        return Ok(Operand::IndexedX(Expression::parse(
+            &compact[..base.len()],
+        )?));
+    }
+    if let Some(base) = upper.strip_suffix(",Y") {
+        return Ok(Operand::IndexedY(Expression::parse(
+            &compact[..base.len()],
+        )?));
+    }
+    Ok(Operand::Address(Expression::parse(text)?))
+}
+
+/// Finds the `)` that closes the `(` at the start of `text`.
+fn matching_parenthesis(text: &str) -> Option<usize> {
+    let mut depth: usize = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Cuts a `;` comment off the end of a line, ignoring any `;` inside a
+/// string or character literal.
+fn strip_comment(text: &str) -> &str {
+    let mut quote: Option<char> = None;
+    let mut escaped: bool = false;
+    for (index, c) in text.char_indices() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == ';' => return &text[..index],
+            None => {}
+        }
+    }
+    text
+}
+
+/// Splits a directive's arguments on the commas between them.
+fn split_list(text: &str) -> Vec<&str> {
+    let mut items: Vec<&str> = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut escaped: bool = false;
+    let mut start: usize = 0;
+    for (index, c) in text.char_indices() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == ',' => {
+                items.push(text[start..index].trim());
+                start = index + 1;
+            }
+            None => {}
+        }
+    }
+    items.push(text[start..].trim());
+    items
+}
+
+fn parse_string(text: &str) -> Result<Vec<u8>, AssemblyErrorKind> {
+    let inner: &str = text
+        .strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+        .ok_or_else(|| AssemblyErrorKind::Syntax(format!("unterminated string {}", text)))?;
+    let mut bytes: Vec<u8> = Vec::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        let c: char = if c == '\\' {
+            match chars.next() {
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('t') => '\t',
+                Some('0') => '\0',
+                Some(c @ ('\\' | '"')) => c,
+                _ => {
+                    return Err(AssemblyErrorKind::Syntax(format!(
+                        "invalid escape in {}",
+                        text
+                    )))
+                }
+            }
+        } else {
+            c
+        };
+        let mut buffer = [0; 4];
+        bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+    }
+    Ok(bytes)
+}
@@ -0,0 +1,66 @@
+use std::fmt;
+
+/// Why `assemble` rejected its source, along with the 1-based line number
+/// the problem was found on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssemblyError {
+    pub line: usize,
+    pub kind: AssemblyErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AssemblyErrorKind {
+    /// The line could not be parsed at all.
+    Syntax(String),
+    UnknownMnemonic(String),
+    UnknownDirective(String),
+    /// A `.cpu` directive names a chip the assembler does not know.
+    UnknownCpu(String),
+    /// The instruction exists, but not with the operand it was given.
+    InvalidAddressingMode(String),
+    UndefinedSymbol(String),
+    DuplicateSymbol(String),
+    /// A value does not fit in the operand or data field it is used for.
+    ValueOutOfRange(i64),
+    /// A branch target is more than 128 bytes away.
+    BranchOutOfRange(i64),
+}
+
+impl AssemblyError {
+    pub fn new(line: usize, kind: AssemblyErrorKind) -> Self {
+        Self { line, kind }
+    }
+}
+
+impl fmt::Display for AssemblyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            AssemblyErrorKind::Syntax(message) => write!(f, "{}", message),
+            AssemblyErrorKind::UnknownMnemonic(mnemonic) => {
+                write!(f, "unknown mnemonic '{}'", mnemonic)
+            }
+            AssemblyErrorKind::UnknownDirective(directive) => {
+                write!(f, "unknown directive '{}'", directive)
+            }
+            AssemblyErrorKind::UnknownCpu(cpu) => write!(f, "unknown CPU '{}'", cpu),
+            AssemblyErrorKind::InvalidAddressingMode(mnemonic) => {
+                write!(f, "invalid addressing mode for {}", mnemonic)
+            }
+            AssemblyErrorKind::UndefinedSymbol(symbol) => {
+                write!(f, "undefined symbol '{}'", symbol)
+            }
+            AssemblyErrorKind::DuplicateSymbol(symbol) => {
+                write!(f, "symbol '{}' is already defined", symbol)
+            }
+            AssemblyErrorKind::ValueOutOfRange(value) => {
+                write!(f, "value {} is out of range", value)
+            }
+            AssemblyErrorKind::BranchOutOfRange(offset) => {
+                write!(f, "branch offset {} is out of range", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssemblyError {}
@@ -0,0 +1,257 @@
+use std::collections::BTreeMap;
+
+use crate::error::AssemblyErrorKind;
+
+/// A constant expression, such as `table+2` or `>(start-1)`.
+///
+/// Numbers can be written as decimal, `$FF`/`0xFF` hex, `%1010`/`0b1010`
+/// binary or `'A'` characters, and `*` stands for the address of the
+/// current line. `<` and `>` in front of a value take its low and high
+/// byte.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expression {
+    Number(i64),
+    Symbol(String),
+    CurrentAddress,
+    Unary(UnaryOperator, Box<Expression>),
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnaryOperator {
+    Negate,
+    Not,
+    LowByte,
+    HighByte,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    And,
+    Or,
+    Xor,
+    ShiftLeft,
+    ShiftRight,
+}
+
+impl Expression {
+    pub fn parse(text: &str) -> Result<Expression, AssemblyErrorKind> {
+        let tokens: Vec<Token> = tokenize(text)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+        };
+        let expression: Expression = parser.parse_expression()?;
+        match parser.peek() {
+            None => Ok(expression),
+            Some(token) => Err(AssemblyErrorKind::Syntax(format!(
+                "unexpected {:?} in expression '{}'",
+                token, text
+            ))),
+        }
+    }
+
+    pub fn evaluate(
+        &self,
+        symbols: &BTreeMap<String, i64>,
+        current_address: i64,
+    ) -> Result<i64, AssemblyErrorKind> {
+        match self {
+            Expression::Number(value) => Ok(*value),
+            Expression::Symbol(name) => symbols
+                .get(name)
+                .copied()
+                .ok_or_else(|| AssemblyErrorKind::UndefinedSymbol(name.clone())),
+            Expression::CurrentAddress => Ok(current_address),
+            Expression::Unary(operator, operand) => {
+                let value: i64 = operand.evaluate(symbols, current_address)?;
+                Ok(match operator {
+                    UnaryOperator::Negate => value.wrapping_neg(),
+                    UnaryOperator::Not => !value,
+                    UnaryOperator::LowByte => value & 0xFF,
+                    UnaryOperator::HighByte => (value >> 8) & 0xFF,
+                })
+            }
+            Expression::Binary(operator, left, right) => {
+                let left: i64 = left.evaluate(symbols, current_address)?;
+                let right: i64 = right.evaluate(symbols, current_address)?;
+                match operator {
+                    BinaryOperator::Add => Ok(left.wrapping_add(right)),
+                    BinaryOperator::Subtract => Ok(left.wrapping_sub(right)),
+                    BinaryOperator::Multiply => Ok(left.wrapping_mul(right)),
+                    BinaryOperator::Divide | BinaryOperator::Modulo if right == 0 => {
+                        Err(AssemblyErrorKind::Syntax(String::from("division by zero")))
+                    }
+                    BinaryOperator::Divide => Ok(left.wrapping_div(right)),
+                    BinaryOperator::Modulo => Ok(left.wrapping_rem(right)),
+                    BinaryOperator::And => Ok(left & right),
+                    BinaryOperator::Or => Ok(left | right),
+                    BinaryOperator::Xor => Ok(left ^ right),
+                    BinaryOperator::ShiftLeft => Ok(left.wrapping_shl(right as u32)),
+                    BinaryOperator::ShiftRight => Ok(left.wrapping_shr(right as u32)),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Number(i64),
+    Identifier(String),
+    Operator(&'static str),
+}
+
+const OPERATORS: [&str; 15] = [
+    "<<", ">>", "+", "-", "*", "/", "%", "&", "|", "^", "~", "<", ">", "(", ")",
+];
+
+fn tokenize(text: &str) -> Result<Vec<Token>, AssemblyErrorKind> {
+    let syntax_error = || AssemblyErrorKind::Syntax(format!("invalid expression '{}'", text));
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut rest: &str = text.trim_start();
+    while let Some(c) = rest.chars().next() {
+        // `%` is only a binary prefix where a value is expected, otherwise
+        // it is the modulo operator
+        let expects_value: bool = !matches!(
+            tokens.last(),
+            Some(Token::Number(_) | Token::Identifier(_) | Token::Operator(")"))
+        );
+        let binary_prefix: bool = c == '%' && expects_value && rest[1..].starts_with(['0', '1']);
+        if c == '\'' {
+            let mut chars = rest[1..].chars();
+            let value: char = chars.next().ok_or_else(syntax_error)?;
+            if chars.next() != Some('\'') {
+                return Err(syntax_error());
+            }
+            tokens.push(Token::Number(value as i64));
+            rest = &rest[2 + value.len_utf8()..];
+        } else if c == '$' || binary_prefix || c.is_ascii_digit() {
+            let (radix, digits_start) = match c {
+                '$' => (16, 1),
+                '%' => (2, 1),
+                _ if rest.starts_with("0x") || rest.starts_with("0X") => (16, 2),
+                _ if rest.starts_with("0b") || rest.starts_with("0B") => (2, 2),
+                _ => (10, 0),
+            };
+            let digits: &str = &rest[digits_start..];
+            let length: usize = digits
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(digits.len());
+            let value: i64 =
+                i64::from_str_radix(&digits[..length], radix).map_err(|_| syntax_error())?;
+            tokens.push(Token::Number(value));
+            rest = &digits[length..];
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let length: usize = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Identifier(rest[..length].to_string()));
+            rest = &rest[length..];
+        } else {
+            let operator: &'static str = OPERATORS
+                .iter()
+                .find(|operator| rest.starts_with(*operator))
+                .ok_or_else(syntax_error)?;
+            tokens.push(Token::Operator(operator));
+            rest = &rest[operator.len()..];
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+/// Binary operators from lowest to highest precedence.
+const PRECEDENCE: [&[(&str, BinaryOperator)]; 6] = [
+    &[("|", BinaryOperator::Or)],
+    &[("^", BinaryOperator::Xor)],
+    &[("&", BinaryOperator::And)],
+    &[
+        ("<<", BinaryOperator::ShiftLeft),
+        (">>", BinaryOperator::ShiftRight),
+    ],
+    &[("+", BinaryOperator::Add), ("-", BinaryOperator::Subtract)],
+    &[
+        ("*", BinaryOperator::Multiply),
+        ("/", BinaryOperator::Divide),
+        ("%", BinaryOperator::Modulo),
+    ],
+];
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        token
+    }
+
+    fn parse_expression(&mut self) -> Result<Expression, AssemblyErrorKind> {
+        self.parse_binary(0)
+    }
+
+    fn parse_binary(&mut self, level: usize) -> Result<Expression, AssemblyErrorKind> {
+        if level == PRECEDENCE.len() {
+            return self.parse_unary();
+        }
+        let mut left: Expression = self.parse_binary(level + 1)?;
+        while let Some(Token::Operator(symbol)) = self.peek() {
+            let Some((_, operator)) = PRECEDENCE[level].iter().find(|(s, _)| s == symbol) else {
+                break;
+            };
+            let operator: BinaryOperator = *operator;
+            self.position += 1;
+            let right: Expression = self.parse_binary(level + 1)?;
+            left = Expression::Binary(operator, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expression, AssemblyErrorKind> {
+        let operator: UnaryOperator = match self.peek() {
+            Some(Token::Operator("-")) => UnaryOperator::Negate,
+            Some(Token::Operator("~")) => UnaryOperator::Not,
+            Some(Token::Operator("<")) => UnaryOperator::LowByte,
+            Some(Token::Operator(">")) => UnaryOperator::HighByte,
+            _ => return self.parse_primary(),
+        };
+        self.position += 1;
+        let operand: Expression = self.parse_unary()?;
+        Ok(Expression::Unary(operator, Box::new(operand)))
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression, AssemblyErrorKind> {
+        match self.next().cloned() {
+            Some(Token::Number(value)) => Ok(Expression::Number(value)),
+            Some(Token::Identifier(name)) => Ok(Expression::Symbol(name)),
+            Some(Token::Operator("*")) => Ok(Expression::CurrentAddress),
+            Some(Token::Operator("(")) => {
+                let expression: Expression = self.parse_expression()?;
+                match self.next() {
+                    Some(Token::Operator(")")) => Ok(expression),
+                    _ => Err(AssemblyErrorKind::Syntax(String::from("missing ')'"))),
+                }
+            }
+            Some(token) => Err(AssemblyErrorKind::Syntax(format!(
+                "unexpected {:?} in expression",
+                token
+            ))),
+            None => Err(AssemblyErrorKind::Syntax(String::from(
+                "expected an expression",
+            ))),
+        }
+    }
+}
@@ -0,0 +1,25 @@
+/// A program assembled at compile time by `asm_macros::asm!`, flattened
+/// into a single image that starts at `origin`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProgramImage<const N: usize> {
+    pub origin: u16,
+    pub bytes: [u8; N],
+    /// Every label and constant, sorted by name.
+    pub symbols: &'static [(&'static str, u16)],
+}
+
+impl<const N: usize> ProgramImage<N> {
+    pub fn symbol(&self, name: &str) -> Option<u16> {
+        self.symbols
+            .iter()
+            .find(|(symbol, _)| *symbol == name)
+            .map(|(_, value)| *value)
+    }
+
+    /// Copies the image into a 64KB address space, e.g. `Memory::data`.
+    pub fn load_into(&self, memory: &mut [u8]) {
+        for (offset, byte) in self.bytes.iter().enumerate() {
+            memory[(self.origin as usize + offset) & 0xFFFF] = *byte;
+        }
+    }
+}
@@ -0,0 +1,822 @@
+use std::fmt;
+
+//
+// Decoding
+//
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mnemonic {
+    ADC,
+    AND,
+    ASL,
+    BCC,
+    BCS,
+    BEQ,
+    BIT,
+    BMI,
+    BNE,
+    BPL,
+    BRK,
+    BVC,
+    BVS,
+    CLC,
+    CLD,
+    CLI,
+    CLV,
+    CMP,
+    CPX,
+    CPY,
+    DEC,
+    DEX,
+    DEY,
+    EOR,
+    INC,
+    INX,
+    INY,
+    JMP,
+    JSR,
+    LDA,
+    LDX,
+    LDY,
+    LSR,
+    NOP,
+    ORA,
+    PHA,
+    PHP,
+    PLA,
+    PLP,
+    ROL,
+    ROR,
+    RTI,
+    RTS,
+    SBC,
+    SEC,
+    SED,
+    SEI,
+    STA,
+    STX,
+    STY,
+    TAX,
+    TAY,
+    TSX,
+    TXA,
+    TXS,
+    TYA,
+    // Undocumented
+    ALR,
+    ANC,
+    ARR,
+    DCP,
+    ISC,
+    JAM,
+    LAS,
+    LAX,
+    LXA,
+    RLA,
+    RRA,
+    SAX,
+    SBX,
+    SHA,
+    SHX,
+    SHY,
+    SLO,
+    SRE,
+    TAS,
+    XAA,
+    // 65C02
+    BBR,
+    BBS,
+    BRA,
+    PHX,
+    PHY,
+    PLX,
+    PLY,
+    RMB,
+    SMB,
+    STP,
+    STZ,
+    TRB,
+    TSB,
+    WAI,
+    // 65816
+    BRL,
+    COP,
+    JML,
+    JSL,
+    MVN,
+    MVP,
+    PEA,
+    PEI,
+    PER,
+    PHB,
+    PHD,
+    PHK,
+    PLB,
+    PLD,
+    REP,
+    RTL,
+    SEP,
+    TCD,
+    TCS,
+    TDC,
+    TSC,
+    TXY,
+    TYX,
+    WDM,
+    XBA,
+    XCE,
+}
+
+impl fmt::Display for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddressingMode {
+    Implied,
+    Accumulator,
+    Immediate,
+    ZeroPage,
+    ZeroPageX,
+    ZeroPageY,
+    Relative,
+    Absolute,
+    AbsoluteX,
+    AbsoluteY,
+    Indirect,
+    IndirectX,
+    IndirectY,
+    // 65C02
+    /// `($12)`
+    ZeroPageIndirect,
+    /// `($1234,X)`, only used by JMP.
+    AbsoluteIndexedIndirect,
+    /// `$12,label`: a zero page address then a branch offset, used by BBR
+    /// and BBS.
+    ZeroPageRelative,
+    // 65816, where the zero page modes address the relocatable direct page
+    /// `$123456`
+    AbsoluteLong,
+    /// `$123456,X`
+    AbsoluteLongX,
+    /// `[$1234]`, only used by JML.
+    AbsoluteIndirectLong,
+    /// `[$12]`
+    ZeroPageIndirectLong,
+    /// `[$12],Y`
+    ZeroPageIndirectLongY,
+    /// `$12,S`
+    StackRelative,
+    /// `($12,S),Y`
+    StackRelativeIndirectY,
+    /// A 16-bit branch offset, used by BRL and PER.
+    RelativeLong,
+    /// The destination bank then the source bank, used by MVN and MVP.
+    BlockMove,
+}
+
+impl AddressingMode {
+    /// The number of operand bytes that follow the opcode.
+    pub const fn operand_length(self) -> u8 {
+        match self {
+            AddressingMode::Implied | AddressingMode::Accumulator => 0,
+            AddressingMode::Immediate
+            | AddressingMode::ZeroPage
+            | AddressingMode::ZeroPageX
+            | AddressingMode::ZeroPageY
+            | AddressingMode::Relative
+            | AddressingMode::IndirectX
+            | AddressingMode::IndirectY
+            | AddressingMode::ZeroPageIndirect
+            | AddressingMode::ZeroPageIndirectLong
+            | AddressingMode::ZeroPageIndirectLongY
+            | AddressingMode::StackRelative
+            | AddressingMode::StackRelativeIndirectY => 1,
+            AddressingMode::Absolute
+            | AddressingMode::AbsoluteX
+            | AddressingMode::AbsoluteY
+            | AddressingMode::Indirect
+            | AddressingMode::AbsoluteIndexedIndirect
+            | AddressingMode::ZeroPageRelative
+            | AddressingMode::AbsoluteIndirectLong
+            | AddressingMode::RelativeLong
+            | AddressingMode::BlockMove => 2,
+            AddressingMode::AbsoluteLong | AddressingMode::AbsoluteLongX => 3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    pub mnemonic: Mnemonic,
+    pub addressing_mode: AddressingMode,
+}
+
+//
+// Opcode Table
+//
+
+pub const FLAG_CARRY: u8 = 0b00000001;
+pub const FLAG_ZERO: u8 = 0b00000010;
+pub const FLAG_INTERRUPT: u8 = 0b00000100;
+pub const FLAG_DECIMAL: u8 = 0b00001000;
+pub const FLAG_OVERFLOW: u8 = 0b01000000;
+pub const FLAG_NEGATIVE: u8 = 0b10000000;
+
+/// Everything known about one opcode. Branches take one extra cycle when
+/// taken and `page_cross_penalty` covers the second extra cycle when the
+/// target is on another page.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OpcodeInfo {
+    pub opcode: u8,
+    pub mnemonic: Mnemonic,
+    pub addressing_mode: AddressingMode,
+    pub length: u8,
+    pub cycles: u8,
+    pub page_cross_penalty: bool,
+    /// The `FLAG_*` bits the instruction can change.
+    pub affected_flags: u8,
+    pub documented: bool,
+}
+
+impl OpcodeInfo {
+    pub fn instruction(&self) -> Instruction {
+        Instruction {
+            mnemonic: self.mnemonic,
+            addressing_mode: self.addressing_mode,
+        }
+    }
+}
+
+pub fn opcode_info(opcode: u8) -> &'static OpcodeInfo {
+    &OPCODES[opcode as usize]
+}
+
+/// Metadata for all 256 NMOS 6502 opcodes, indexed by opcode.
+pub static OPCODES: [OpcodeInfo; 256] = NMOS_OPCODES;
+
+const NMOS_OPCODES: [OpcodeInfo; 256] = {
+    use AddressingMode::*;
+    use Mnemonic::*;
+    [
+        documented(0x00, BRK, Implied, 7, false),
+        documented(0x01, ORA, IndirectX, 6, false),
+        undocumented(0x02, JAM, Implied, 2, false),
+        undocumented(0x03, SLO, IndirectX, 8, false),
+        undocumented(0x04, NOP, ZeroPage, 3, false),
+        documented(0x05, ORA, ZeroPage, 3, false),
+        documented(0x06, ASL, ZeroPage, 5, false),
+        undocumented(0x07, SLO, ZeroPage, 5, false),
+        documented(0x08, PHP, Implied, 3, false),
+        documented(0x09, ORA, Immediate, 2, false),
+        documented(0x0A, ASL, Accumulator, 2, false),
+        undocumented(0x0B, ANC, Immediate, 2, false),
+        undocumented(0x0C, NOP, Absolute, 4, false),
+        documented(0x0D, ORA, Absolute, 4, false),
+        documented(0x0E, ASL, Absolute, 6, false),
+        undocumented(0x0F, SLO, Absolute, 6, false),
+        documented(0x10, BPL, Relative, 2, true),
+        documented(0x11, ORA, IndirectY, 5, true),
+        undocumented(0x12, JAM, Implied, 2, false),
+        undocumented(0x13, SLO, IndirectY, 8, false),
+        undocumented(0x14, NOP, ZeroPageX, 4, false),
+        documented(0x15, ORA, ZeroPageX, 4, false),
+        documented(0x16, ASL, ZeroPageX, 6, false),
+        undocumented(0x17, SLO, ZeroPageX, 6, false),
+        documented(0x18, CLC, Implied, 2, false),
+        documented(0x19, ORA, AbsoluteY, 4, true),
+        undocumented(0x1A, NOP, Implied, 2, false),
+        undocumented(0x1B, SLO, AbsoluteY, 7, false),
+        undocumented(0x1C, NOP, AbsoluteX, 4, true),
+        documented(0x1D, ORA, AbsoluteX, 4, true),
+        documented(0x1E, ASL, AbsoluteX, 7, false),
+        undocumented(0x1F, SLO, AbsoluteX, 7, false),
+        documented(0x20, JSR, Absolute, 6, false),
+        documented(0x21, AND, IndirectX, 6, false),
+        undocumented(0x22, JAM, Implied, 2, false),
+        undocumented(0x23, RLA, IndirectX, 8, false),
+        documented(0x24, BIT, ZeroPage, 3, false),
+        documented(0x25, AND, ZeroPage, 3, false),
+        documented(0x26, ROL, ZeroPage, 5, false),
+        undocumented(0x27, RLA, ZeroPage, 5, false),
+        documented(0x28, PLP, Implied, 4, false),
+        documented(0x29, AND, Immediate, 2, false),
+        documented(0x2A, ROL, Accumulator, 2, false),
+        undocumented(0x2B, ANC, Immediate, 2, false),
+        documented(0x2C, BIT, Absolute, 4, false),
+        documented(0x2D, AND, Absolute, 4, false),
+        documented(0x2E, ROL, Absolute, 6, false),
+        undocumented(0x2F, RLA, Absolute, 6, false),
+        documented(0x30, BMI, Relative, 2, true),
+        documented(0x31, AND, IndirectY, 5, true),
+        undocumented(0x32, JAM, Implied, 2, false),
+        undocumented(0x33, RLA, IndirectY, 8, false),
+        undocumented(0x34, NOP, ZeroPageX, 4, false),
+        documented(0x35, AND, ZeroPageX, 4, false),
+        documented(0x36, ROL, ZeroPageX, 6, false),
+        undocumented(0x37, RLA, ZeroPageX, 6, false),
+        documented(0x38, SEC, Implied, 2, false),
+        documented(0x39, AND, AbsoluteY, 4, true),
+        undocumented(0x3A, NOP, Implied, 2, false),
+        undocumented(0x3B, RLA, AbsoluteY, 7, false),
+        undocumented(0x3C, NOP, AbsoluteX, 4, true),
+        documented(0x3D, AND, AbsoluteX, 4, true),
+        documented(0x3E, ROL, AbsoluteX, 7, false),
+        undocumented(0x3F, RLA, AbsoluteX, 7, false),
+        documented(0x40, RTI, Implied, 6, false),
+        documented(0x41, EOR, IndirectX, 6, false),
+        undocumented(0x42, JAM, Implied, 2, false),
+        undocumented(0x43, SRE, IndirectX, 8, false),
+        undocumented(0x44, NOP, ZeroPage, 3, false),
+        documented(0x45, EOR, ZeroPage, 3, false),
+        documented(0x46, LSR, ZeroPage, 5, false),
+        undocumented(0x47, SRE, ZeroPage, 5, false),
+        documented(0x48, PHA, Implied, 3, false),
+        documented(0x49, EOR, Immediate, 2, false),
+        documented(0x4A, LSR, Accumulator, 2, false),
+        undocumented(0x4B, ALR, Immediate, 2, false),
+        documented(0x4C, JMP, Absolute, 3, false),
+        documented(0x4D, EOR, Absolute, 4, false),
+        documented(0x4E, LSR, Absolute, 6, false),
+        undocumented(0x4F, SRE, Absolute, 6, false),
+        documented(0x50, BVC, Relative, 2, true),
+        documented(0x51, EOR, IndirectY, 5, true),
+        undocumented(0x52, JAM, Implied, 2, false),
+        undocumented(0x53, SRE, IndirectY, 8, false),
+        undocumented(0x54, NOP, ZeroPageX, 4, false),
+        documented(0x55, EOR, ZeroPageX, 4, false),
+        documented(0x56, LSR, ZeroPageX, 6, false),
+        undocumented(0x57, SRE, ZeroPageX, 6, false),
+        documented(0x58, CLI, Implied, 2, false),
+        documented(0x59, EOR, AbsoluteY, 4, true),
+        undocumented(0x5A, NOP, Implied, 2, false),
+        undocumented(0x5B, SRE, AbsoluteY, 7, false),
+        undocumented(0x5C, NOP, AbsoluteX, 4, true),
+        documented(0x5D, EOR, AbsoluteX, 4, true),
+        documented(0x5E, LSR, AbsoluteX, 7, false),
+        undocumented(0x5F, SRE, AbsoluteX, 7, false),
+        documented(0x60, RTS, Implied, 6, false),
+        documented(0x61, ADC, IndirectX, 6, false),
+        undocumented(0x62, JAM, Implied, 2, false),
+        undocumented(0x63, RRA, IndirectX, 8, false),
+        undocumented(0x64, NOP, ZeroPage, 3, false),
+        documented(0x65, ADC, ZeroPage, 3, false),
+        documented(0x66, ROR, ZeroPage, 5, false),
+        undocumented(0x67, RRA, ZeroPage, 5, false),
+        documented(0x68, PLA, Implied, 4, false),
+        documented(0x69, ADC, Immediate, 2, false),
+        documented(0x6A, ROR, Accumulator, 2, false),
+        undocumented(0x6B, ARR, Immediate, 2, false),
+        documented(0x6C, JMP, Indirect, 5, false),
+        documented(0x6D, ADC, Absolute, 4, false),
+        documented(0x6E, ROR, Absolute, 6, false),
+        undocumented(0x6F, RRA, Absolute, 6, false),
+        documented(0x70, BVS, Relative, 2, true),
+        documented(0x71, ADC, IndirectY, 5, true),
+        undocumented(0x72, JAM, Implied, 2, false),
+        undocumented(0x73, RRA, IndirectY, 8, false),
+        undocumented(0x74, NOP, ZeroPageX, 4, false),
+        documented(0x75, ADC, ZeroPageX, 4, false),
+        documented(0x76, ROR, ZeroPageX, 6, false),
+        undocumented(0x77, RRA, ZeroPageX, 6, false),
+        documented(0x78, SEI, Implied, 2, false),
+        documented(0x79, ADC, AbsoluteY, 4, true),
+        undocumented(0x7A, NOP, Implied, 2, false),
+        undocumented(0x7B, RRA, AbsoluteY, 7, false),
+        undocumented(0x7C, NOP, AbsoluteX, 4, true),
+        documented(0x7D, ADC, AbsoluteX, 4, true),
+        documented(0x7E, ROR, AbsoluteX, 7, false),
+        undocumented(0x7F, RRA, AbsoluteX, 7, false),
+        undocumented(0x80, NOP, Immediate, 2, false),
+        documented(0x81, STA, IndirectX, 6, false),
+        undocumented(0x82, NOP, Immediate, 2, false),
+        undocumented(0x83, SAX, IndirectX, 6, false),
+        documented(0x84, STY, ZeroPage, 3, false),
+        documented(0x85, STA, ZeroPage, 3, false),
+        documented(0x86, STX, ZeroPage, 3, false),
+        undocumented(0x87, SAX, ZeroPage, 3, false),
+        documented(0x88, DEY, Implied, 2, false),
+        undocumented(0x89, NOP, Immediate, 2, false),
+        documented(0x8A, TXA, Implied, 2, false),
+        undocumented(0x8B, XAA, Immediate, 2, false),
+        documented(0x8C, STY, Absolute, 4, false),
+        documented(0x8D, STA, Absolute, 4, false),
+        documented(0x8E, STX, Absolute, 4, false),
+        undocumented(0x8F, SAX, Absolute, 4, false),
+        documented(0x90, BCC, Relative, 2, true),
+        documented(0x91, STA, IndirectY, 6, false),
+        undocumented(0x92, JAM, Implied, 2, false),
+        undocumented(0x93, SHA, IndirectY, 6, false),
+        documented(0x94, STY, ZeroPageX, 4, false),
+        documented(0x95, STA, ZeroPageX, 4, false),
+        documented(0x96, STX, ZeroPageY, 4, false),
+        undocumented(0x97, SAX, ZeroPageY, 4, false),
+        documented(0x98, TYA, Implied, 2, false),
+        documented(0x99, STA, AbsoluteY, 5, false),
+        documented(0x9A, TXS, Implied, 2, false),
+        undocumented(0x9B, TAS, AbsoluteY, 5, false),
+        undocumented(0x9C, SHY, AbsoluteX, 5, false),
+        documented(0x9D, STA, AbsoluteX, 5, false),
+        undocumented(0x9E, SHX, AbsoluteY, 5, false),
+        undocumented(0x9F, SHA, AbsoluteY, 5, false),
+        documented(0xA0, LDY, Immediate, 2, false),
+        documented(0xA1, LDA, IndirectX, 6, false),
+        documented(0xA2, LDX, Immediate, 2, false),
+        undocumented(0xA3, LAX, IndirectX, 6, false),
+        documented(0xA4, LDY, ZeroPage, 3, false),
+        documented(0xA5, LDA, ZeroPage, 3, false),
+        documented(0xA6, LDX, ZeroPage, 3, false),
+        undocumented(0xA7, LAX, ZeroPage, 3, false),
+        documented(0xA8, TAY, Implied, 2, false),
+        documented(0xA9, LDA, Immediate, 2, false),
+        documented(0xAA, TAX, Implied, 2, false),
+        undocumented(0xAB, LXA, Immediate, 2, false),
+        documented(0xAC, LDY, Absolute, 4, false),
+        documented(0xAD, LDA, Absolute, 4, false),
+        documented(0xAE, LDX, Absolute, 4, false),
+        undocumented(0xAF, LAX, Absolute, 4, false),
+        documented(0xB0, BCS, Relative, 2, true),
+        documented(0xB1, LDA, IndirectY, 5, true),
+        undocumented(0xB2, JAM, Implied, 2, false),
+        undocumented(0xB3, LAX, IndirectY, 5, true),
+        documented(0xB4, LDY, ZeroPageX, 4, false),
+        documented(0xB5, LDA, ZeroPageX, 4, false),
+        documented(0xB6, LDX, ZeroPageY, 4, false),
+        undocumented(0xB7, LAX, ZeroPageY, 4, false),
+        documented(0xB8, CLV, Implied, 2, false),
+        documented(0xB9, LDA, AbsoluteY, 4, true),
+        documented(0xBA, TSX, Implied, 2, false),
+        undocumented(0xBB, LAS, AbsoluteY, 4, true),
+        documented(0xBC, LDY, AbsoluteX, 4, true),
+        documented(0xBD, LDA, AbsoluteX, 4, true),
+        documented(0xBE, LDX, AbsoluteY, 4, true),
+        undocumented(0xBF, LAX, AbsoluteY, 4, true),
+        documented(0xC0, CPY, Immediate, 2, false),
+        documented(0xC1, CMP, IndirectX, 6, false),
+        undocumented(0xC2, NOP, Immediate, 2, false),
+        undocumented(0xC3, DCP, IndirectX, 8, false),
+        documented(0xC4, CPY, ZeroPage, 3, false),
+        documented(0xC5, CMP, ZeroPage, 3, false),
+        documented(0xC6, DEC, ZeroPage, 5, false),
+        undocumented(0xC7, DCP, ZeroPage, 5, false),
+        documented(0xC8, INY, Implied, 2, false),
+        documented(0xC9, CMP, Immediate, 2, false),
+        documented(0xCA, DEX, Implied, 2, false),
+        undocumented(0xCB, SBX, Immediate, 2, false),
+        documented(0xCC, CPY, Absolute, 4, false),
+        documented(0xCD, CMP, Absolute, 4, false),
+        documented(0xCE, DEC, Absolute, 6, false),
+        undocumented(0xCF, DCP, Absolute, 6, false),
+        documented(0xD0, BNE, Relative, 2, true),
+        documented(0xD1, CMP, IndirectY, 5, true),
+        undocumented(0xD2, JAM, Implied, 2, false),
+        undocumented(0xD3, DCP, IndirectY, 8, false),
+        undocumented(0xD4, NOP, ZeroPageX, 4, false),
+        documented(0xD5, CMP, ZeroPageX, 4, false),
+        documented(0xD6, DEC, ZeroPageX, 6, false),
+        undocumented(0xD7, DCP, ZeroPageX, 6, false),
+        documented(0xD8, CLD, Implied, 2, false),
+        documented(0xD9, CMP, AbsoluteY, 4, true),
+        undocumented(0xDA, NOP, Implied, 2, false),
+        undocumented(0xDB, DCP, AbsoluteY, 7, false),
+        undocumented(0xDC, NOP, AbsoluteX, 4, true),
+        documented(0xDD, CMP, AbsoluteX, 4, true),
+        documented(0xDE, DEC, AbsoluteX, 7, false),
+        undocumented(0xDF, DCP, AbsoluteX, 7, false),
+        documented(0xE0, CPX, Immediate, 2, false),
+        documented(0xE1, SBC, IndirectX, 6, false),
+        undocumented(0xE2, NOP, Immediate, 2, false),
+        undocumented(0xE3, ISC, IndirectX, 8, false),
+        documented(0xE4, CPX, ZeroPage, 3, false),
+        documented(0xE5, SBC, ZeroPage, 3, false),
+        documented(0xE6, INC, ZeroPage, 5, false),
+        undocumented(0xE7, ISC, ZeroPage, 5, false),
+        documented(0xE8, INX, Implied, 2, false),
+        documented(0xE9, SBC, Immediate, 2, false),
+        documented(0xEA, NOP, Implied, 2, false),
+        undocumented(0xEB, SBC, Immediate, 2, false),
+        documented(0xEC, CPX, Absolute, 4, false),
+        documented(0xED, SBC, Absolute, 4, false),
+        documented(0xEE, INC, Absolute, 6, false),
+        undocumented(0xEF, ISC, Absolute, 6, false),
+        documented(0xF0, BEQ, Relative, 2, true),
+        documented(0xF1, SBC, IndirectY, 5, true),
+        undocumented(0xF2, JAM, Implied, 2, false),
+        undocumented(0xF3, ISC, IndirectY, 8, false),
+        undocumented(0xF4, NOP, ZeroPageX, 4, false),
+        documented(0xF5, SBC, ZeroPageX, 4, false),
+        documented(0xF6, INC, ZeroPageX, 6, false),
+        undocumented(0xF7, ISC, ZeroPageX, 6, false),
+        documented(0xF8, SED, Implied, 2, false),
+        documented(0xF9, SBC, AbsoluteY, 4, true),
+        undocumented(0xFA, NOP, Implied, 2, false),
+        undocumented(0xFB, ISC, AbsoluteY, 7, false),
+        undocumented(0xFC, NOP, AbsoluteX, 4, true),
+        documented(0xFD, SBC, AbsoluteX, 4, true),
+        documented(0xFE, INC, AbsoluteX, 7, false),
+        undocumented(0xFF, ISC, AbsoluteX, 7, false),
+    ]
+};
+
+/// Metadata for all 256 WDC 65C02 opcodes, indexed by opcode. The opcodes
+/// the NMOS chip leaves undocumented are either new instructions or NOPs of
+/// various lengths, listed as undocumented NOPs. ADC and SBC take one more
+/// cycle than listed in decimal mode.
+pub static OPCODES_65C02: [OpcodeInfo; 256] = {
+    use AddressingMode::*;
+    use Mnemonic::*;
+    let mut table: [OpcodeInfo; 256] = NMOS_OPCODES;
+    let mut opcode: usize = 0;
+    while opcode < 256 {
+        let info: OpcodeInfo = table[opcode];
+        if !info.documented {
+            // Whatever is not replaced below is a NOP that takes as many
+            // bytes and cycles as its column says
+            table[opcode] = match opcode & 0x0F {
+                0x02 => undocumented(opcode as u8, NOP, Immediate, 2, false),
+                0x03 | 0x0B => undocumented(opcode as u8, NOP, Implied, 1, false),
+                _ => undocumented(opcode as u8, NOP, info.addressing_mode, info.cycles, false),
+            };
+        }
+        // RMB, SMB, BBR and BBS take the bit number from bits 4 to 6
+        if opcode & 0x0F == 0x07 {
+            let mnemonic: Mnemonic = if opcode < 0x80 { RMB } else { SMB };
+            table[opcode] = documented(opcode as u8, mnemonic, ZeroPage, 5, false);
+        } else if opcode & 0x0F == 0x0F {
+            let mnemonic: Mnemonic = if opcode < 0x80 { BBR } else { BBS };
+            table[opcode] = documented(opcode as u8, mnemonic, ZeroPageRelative, 5, true);
+        }
+        opcode += 1;
+    }
+    let changes: [OpcodeInfo; 37] = [
+        documented(0x04, TSB, ZeroPage, 5, false),
+        documented(0x0C, TSB, Absolute, 6, false),
+        documented(0x12, ORA, ZeroPageIndirect, 5, false),
+        documented(0x14, TRB, ZeroPage, 5, false),
+        documented(0x1A, INC, Accumulator, 2, false),
+        documented(0x1C, TRB, Absolute, 6, false),
+        documented(0x1E, ASL, AbsoluteX, 6, true),
+        documented(0x32, AND, ZeroPageIndirect, 5, false),
+        documented(0x34, BIT, ZeroPageX, 4, false),
+        documented(0x3A, DEC, Accumulator, 2, false),
+        documented(0x3C, BIT, AbsoluteX, 4, true),
+        documented(0x3E, ROL, AbsoluteX, 6, true),
+        documented(0x52, EOR, ZeroPageIndirect, 5, false),
+        documented(0x5A, PHY, Implied, 3, false),
+        undocumented(0x5C, NOP, Absolute, 8, false),
+        documented(0x5E, LSR, AbsoluteX, 6, true),
+        documented(0x64, STZ, ZeroPage, 3, false),
+        documented(0x6C, JMP, Indirect, 6, false),
+        documented(0x72, ADC, ZeroPageIndirect, 5, false),
+        documented(0x74, STZ, ZeroPageX, 4, false),
+        documented(0x7A, PLY, Implied, 4, false),
+        documented(0x7C, JMP, AbsoluteIndexedIndirect, 6, false),
+        documented(0x7E, ROR, AbsoluteX, 6, true),
+        documented(0x80, BRA, Relative, 2, true),
+        // Without a memory operand there is nothing to copy N and V from
+        OpcodeInfo {
+            affected_flags: FLAG_ZERO,
+            ..documented(0x89, BIT, Immediate, 2, false)
+        },
+        documented(0x92, STA, ZeroPageIndirect, 5, false),
+        documented(0x9C, STZ, Absolute, 4, false),
+        documented(0x9E, STZ, AbsoluteX, 5, false),
+        documented(0xB2, LDA, ZeroPageIndirect, 5, false),
+        documented(0xCB, WAI, Implied, 3, false),
+        documented(0xD2, CMP, ZeroPageIndirect, 5, false),
+        documented(0xDA, PHX, Implied, 3, false),
+        documented(0xDB, STP, Implied, 3, false),
+        undocumented(0xDC, NOP, Absolute, 4, false),
+        documented(0xF2, SBC, ZeroPageIndirect, 5, false),
+        documented(0xFA, PLX, Implied, 4, false),
+        undocumented(0xFC, NOP, Absolute, 4, false),
+    ];
+    let mut index: usize = 0;
+    while index < changes.len() {
+        table[changes[index].opcode as usize] = changes[index];
+        index += 1;
+    }
+    table
+};
+
+/// Metadata for all 256 W65C816S opcodes, indexed by opcode. Lengths and
+/// cycles are for emulation mode, with 8-bit registers and the direct page
+/// on a page boundary: immediate operands are one byte longer when the
+/// register they load is 16 bits wide, and each 16-bit data access and an
+/// unaligned direct page add a cycle.
+pub static OPCODES_65816: [OpcodeInfo; 256] = {
+    use AddressingMode::*;
+    use Mnemonic::*;
+    let mut table: [OpcodeInfo; 256] = OPCODES_65C02;
+    let mut opcode: usize = 0;
+    while opcode < 256 {
+        // The ALU instructions fill the x3, x7 and xF columns with the new
+        // addressing modes
+        let mnemonic: Mnemonic = table[(opcode & 0xF0) | 0x01].mnemonic;
+        let odd_row: bool = opcode & 0x10 != 0;
+        table[opcode] = match (opcode & 0x0F, odd_row) {
+            (0x03, false) => documented(opcode as u8, mnemonic, StackRelative, 4, false),
+            (0x03, true) => documented(opcode as u8, mnemonic, StackRelativeIndirectY, 7, false),
+            (0x07, false) => documented(opcode as u8, mnemonic, ZeroPageIndirectLong, 6, false),
+            (0x07, true) => documented(opcode as u8, mnemonic, ZeroPageIndirectLongY, 6, false),
+            (0x0F, false) => documented(opcode as u8, mnemonic, AbsoluteLong, 5, false),
+            (0x0F, true) => documented(opcode as u8, mnemonic, AbsoluteLongX, 5, false),
+            _ => table[opcode],
+        };
+        opcode += 1;
+    }
+    let changes: [OpcodeInfo; 33] = [
+        documented(0x02, COP, Immediate, 7, false),
+        documented(0x0B, PHD, Implied, 4, false),
+        documented(0x1B, TCS, Implied, 2, false),
+        documented(0x1E, ASL, AbsoluteX, 7, false),
+        documented(0x22, JSL, AbsoluteLong, 8, false),
+        documented(0x2B, PLD, Implied, 5, false),
+        documented(0x3B, TSC, Implied, 2, false),
+        documented(0x3E, ROL, AbsoluteX, 7, false),
+        documented(0x42, WDM, Immediate, 2, false),
+        documented(0x44, MVP, BlockMove, 7, false),
+        documented(0x4B, PHK, Implied, 3, false),
+        documented(0x54, MVN, BlockMove, 7, false),
+        documented(0x5B, TCD, Implied, 2, false),
+        documented(0x5C, JML, AbsoluteLong, 4, false),
+        documented(0x5E, LSR, AbsoluteX, 7, false),
+        documented(0x62, PER, RelativeLong, 6, false),
+        documented(0x6B, RTL, Implied, 6, false),
+        documented(0x6C, JMP, Indirect, 5, false),
+        documented(0x7B, TDC, Implied, 2, false),
+        documented(0x7E, ROR, AbsoluteX, 7, false),
+        documented(0x82, BRL, RelativeLong, 4, false),
+        documented(0x8B, PHB, Implied, 3, false),
+        documented(0x9B, TXY, Implied, 2, false),
+        documented(0xAB, PLB, Implied, 4, false),
+        documented(0xBB, TYX, Implied, 2, false),
+        documented(0xC2, REP, Immediate, 3, false),
+        documented(0xD4, PEI, ZeroPageIndirect, 6, false),
+        documented(0xDC, JML, AbsoluteIndirectLong, 6, false),
+        documented(0xE2, SEP, Immediate, 3, false),
+        documented(0xEB, XBA, Implied, 3, false),
+        documented(0xF4, PEA, Absolute, 5, false),
+        documented(0xFB, XCE, Implied, 2, false),
+        documented(0xFC, JSR, AbsoluteIndexedIndirect, 8, false),
+    ];
+    let mut index: usize = 0;
+    while index < changes.len() {
+        table[changes[index].opcode as usize] = changes[index];
+        index += 1;
+    }
+    table
+};
+
+const fn documented(
+    opcode: u8,
+    mnemonic: Mnemonic,
+    addressing_mode: AddressingMode,
+    cycles: u8,
+    page_cross_penalty: bool,
+) -> OpcodeInfo {
+    OpcodeInfo {
+        opcode,
+        mnemonic,
+        addressing_mode,
+        length: 1 + addressing_mode.operand_length(),
+        cycles,
+        page_cross_penalty,
+        affected_flags: affected_flags(mnemonic),
+        documented: true,
+    }
+}
+
+const fn undocumented(
+    opcode: u8,
+    mnemonic: Mnemonic,
+    addressing_mode: AddressingMode,
+    cycles: u8,
+    page_cross_penalty: bool,
+) -> OpcodeInfo {
+    OpcodeInfo {
+        documented: false,
+        ..documented(
+            opcode,
+            mnemonic,
+            addressing_mode,
+            cycles,
+            page_cross_penalty,
+        )
+    }
+}
+
+const fn affected_flags(mnemonic: Mnemonic) -> u8 {
+    const NZ: u8 = FLAG_NEGATIVE | FLAG_ZERO;
+    const NZC: u8 = NZ | FLAG_CARRY;
+    const NVZC: u8 = NZC | FLAG_OVERFLOW;
+    match mnemonic {
+        Mnemonic::ADC | Mnemonic::SBC | Mnemonic::ARR | Mnemonic::ISC | Mnemonic::RRA => NVZC,
+        Mnemonic::ASL
+        | Mnemonic::LSR
+        | Mnemonic::ROL
+        | Mnemonic::ROR
+        | Mnemonic::CMP
+        | Mnemonic::CPX
+        | Mnemonic::CPY
+        | Mnemonic::ALR
+        | Mnemonic::ANC
+        | Mnemonic::DCP
+        | Mnemonic::RLA
+        | Mnemonic::SBX
+        | Mnemonic::SLO
+        | Mnemonic::SRE => NZC,
+        Mnemonic::AND
+        | Mnemonic::EOR
+        | Mnemonic::ORA
+        | Mnemonic::DEC
+        | Mnemonic::DEX
+        | Mnemonic::DEY
+        | Mnemonic::INC
+        | Mnemonic::INX
+        | Mnemonic::INY
+        | Mnemonic::LDA
+        | Mnemonic::LDX
+        | Mnemonic::LDY
+        | Mnemonic::PLA
+        | Mnemonic::PLX
+        | Mnemonic::PLY
+        | Mnemonic::TAX
+        | Mnemonic::TAY
+        | Mnemonic::TSX
+        | Mnemonic::TXA
+        | Mnemonic::TYA
+        | Mnemonic::LAS
+        | Mnemonic::LAX
+        | Mnemonic::LXA
+        | Mnemonic::XAA
+        | Mnemonic::PLB
+        | Mnemonic::PLD
+        | Mnemonic::TCD
+        | Mnemonic::TDC
+        | Mnemonic::TSC
+        | Mnemonic::TXY
+        | Mnemonic::TYX
+        | Mnemonic::XBA => NZ,
+        Mnemonic::TRB | Mnemonic::TSB => FLAG_ZERO,
+        Mnemonic::BIT => NZ | FLAG_OVERFLOW,
+        Mnemonic::PLP | Mnemonic::RTI | Mnemonic::REP | Mnemonic::SEP => {
+            NVZC | FLAG_INTERRUPT | FLAG_DECIMAL
+        }
+        Mnemonic::BRK | Mnemonic::COP | Mnemonic::CLI | Mnemonic::SEI => FLAG_INTERRUPT,
+        Mnemonic::CLC | Mnemonic::SEC | Mnemonic::XCE => FLAG_CARRY,
+        Mnemonic::CLD | Mnemonic::SED => FLAG_DECIMAL,
+        Mnemonic::CLV => FLAG_OVERFLOW,
+        _ => 0,
+    }
+}
+
+impl Mnemonic {
+    /// Looks up a mnemonic by name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Mnemonic> {
+        Mnemonic::from_name_in(&OPCODES, name)
+    }
+
+    /// Looks up a mnemonic by name among the instructions of an opcode
+    /// table, ignoring case.
+    pub fn from_name_in(opcodes: &[OpcodeInfo; 256], name: &str) -> Option<Mnemonic> {
+        opcodes
+            .iter()
+            .map(|info| info.mnemonic)
+            .find(|mnemonic| mnemonic.to_string().eq_ignore_ascii_case(name))
+    }
+
+    /// Whether any opcode implements the mnemonic in this addressing mode.
+    pub fn supports(self, addressing_mode: AddressingMode) -> bool {
+        self.supports_in(&OPCODES, addressing_mode)
+    }
+
+    /// Whether any opcode in the table implements the mnemonic in this
+    /// addressing mode.
+    pub fn supports_in(self, opcodes: &[OpcodeInfo; 256], addressing_mode: AddressingMode) -> bool {
+        encode_in(opcodes, self, addressing_mode).is_some()
+    }
+}
+
+/// Finds the opcode for an instruction, preferring the documented opcode
+/// when an undocumented one does the same thing (e.g. `SBC #` is both $E9
+/// and $EB).
+pub fn encode(mnemonic: Mnemonic, addressing_mode: AddressingMode) -> Option<u8> {
+    encode_in(&OPCODES, mnemonic, addressing_mode)
+}
+
+/// Like `encode`, but for the chip the opcode table describes. RMB, SMB,
+/// BBR and BBS encode to the opcode for bit 0.
+pub fn encode_in(
+    opcodes: &[OpcodeInfo; 256],
+    mnemonic: Mnemonic,
+    addressing_mode: AddressingMode,
+) -> Option<u8> {
+    opcodes
+        .iter()
+        .filter(|info| info.mnemonic == mnemonic && info.addressing_mode == addressing_mode)
+        .min_by_key(|info| !info.documented)
+        .map(|info| info.opcode)
+}
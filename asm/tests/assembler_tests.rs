@@ -0,0 +1,193 @@
+use asm::*;
+
+fn bytes(source: &str) -> Vec<u8> {
+    assemble(source).unwrap().image()
+}
+
+#[test]
+fn assembles_every_addressing_mode() {
+    let source = "
+        nop
+        asl a
+        lsr
+        lda #$42
+        lda $42
+        lda $42,x
+        ldx $42,y
+        lda $1234
+        lda $1234,X
+        lda $1234,Y
+        jmp ($1234)
+        lda ($42,x)
+        lda ($42),y
+    ";
+    assert_eq!(
+        bytes(source),
+        vec![
+            0xEA, 0x0A, 0x4A, 0xA9, 0x42, 0xA5, 0x42, 0xB5, 0x42, 0xB6, 0x42, 0xAD, 0x34, 0x12,
+            0xBD, 0x34, 0x12, 0xB9, 0x34, 0x12, 0x6C, 0x34, 0x12, 0xA1, 0x42, 0xB1, 0x42,
+        ]
+    );
+}
+
+#[test]
+fn writes_jsr_operand_low_byte_first() {
+    assert_eq!(
+        bytes("jsr $4242\njsr $1234"),
+        vec![0x20, 0x42, 0x42, 0x20, 0x34, 0x12]
+    );
+}
+
+#[test]
+fn zero_page_indexed_y_falls_back_to_absolute_when_unavailable() {
+    assert_eq!(bytes("lda $42,y"), vec![0xB9, 0x42, 0x00]);
+}
+
+#[test]
+fn resolves_forward_references() {
+    let program = assemble(
+        "
+        .org $8000
+start:  ldx #0
+loop:   inx
+        bne loop
+        jmp done
+        nop
+done:   rts
+    ",
+    )
+    .unwrap();
+    assert_eq!(program.origin(), 0x8000);
+    assert_eq!(
+        program.image(),
+        vec![0xA2, 0x00, 0xE8, 0xD0, 0xFD, 0x4C, 0x09, 0x80, 0xEA, 0x60]
+    );
+    assert_eq!(program.symbols["start"], 0x8000);
+    assert_eq!(program.symbols["loop"], 0x8002);
+    assert_eq!(program.symbols["done"], 0x8009);
+}
+
+#[test]
+fn forward_references_use_absolute_addressing() {
+    assert_eq!(
+        bytes("lda later\nlater = $10\nlda later"),
+        vec![0xAD, 0x10, 0x00, 0xA5, 0x10]
+    );
+}
+
+#[test]
+fn evaluates_constant_expressions() {
+    let source = "
+        base = $1200
+        count = 3 * (2 + 1)
+        lda #<base+$34
+        lda #>base
+        ldx #count
+        lda #%1010 | 0b0101
+        lda #'A'
+        lda #-1
+        lda base+count*2,x
+    ";
+    assert_eq!(
+        bytes(source),
+        vec![
+            0xA9, 0x34, 0xA9, 0x12, 0xA2, 0x09, 0xA9, 0x0F, 0xA9, 0x41, 0xA9, 0xFF, 0xBD, 0x12,
+            0x12
+        ]
+    );
+}
+
+#[test]
+fn current_address_can_be_used_in_expressions() {
+    assert_eq!(bytes(".org $0300\njmp *"), vec![0x4C, 0x00, 0x03]);
+}
+
+#[test]
+fn assembles_data_directives() {
+    let source = r#"
+        .byte 1, $02, 'c', "de"
+        .word $1234, label
+        .text "hi; there\n"
+label:
+    "#;
+    assert_eq!(
+        bytes(source),
+        vec![
+            0x01, 0x02, 0x63, 0x64, 0x65, 0x34, 0x12, 0x13, 0x00, b'h', b'i', b';', b' ', b't',
+            b'h', b'e', b'r', b'e', b'\n'
+        ]
+    );
+}
+
+#[test]
+fn org_starts_a_new_segment() {
+    let program = assemble(".org $0200\nnop\n.org $FFFC\n.word $0200").unwrap();
+    assert_eq!(
+        program.segments,
+        vec![
+            Segment {
+                address: 0x0200,
+                bytes: vec![0xEA],
+            },
+            Segment {
+                address: 0xFFFC,
+                bytes: vec![0x00, 0x02],
+            },
+        ]
+    );
+    let mut memory = [0x00; 0x10000];
+    program.load_into(&mut memory);
+    assert_eq!(memory[0x0200], 0xEA);
+    assert_eq!(memory[0xFFFD], 0x02);
+}
+
+#[test]
+fn prefers_documented_opcodes() {
+    assert_eq!(bytes("sbc #1\nnop"), vec![0xE9, 0x01, 0xEA]);
+}
+
+#[test]
+fn reports_errors_with_line_numbers() {
+    let error = |source| assemble(source).unwrap_err();
+    assert_eq!(
+        error("nop\nfoo #1"),
+        AssemblyError::new(2, AssemblyErrorKind::UnknownMnemonic(String::from("foo")))
+    );
+    assert_eq!(
+        error("lda missing"),
+        AssemblyError::new(
+            1,
+            AssemblyErrorKind::UndefinedSymbol(String::from("missing"))
+        )
+    );
+    assert_eq!(
+        error("x:\nx:"),
+        AssemblyError::new(2, AssemblyErrorKind::DuplicateSymbol(String::from("x")))
+    );
+    assert_eq!(
+        error("stx $1234,x"),
+        AssemblyError::new(
+            1,
+            AssemblyErrorKind::InvalidAddressingMode(String::from("STX"))
+        )
+    );
+    assert_eq!(
+        error("lda #$100"),
+        AssemblyError::new(1, AssemblyErrorKind::ValueOutOfRange(0x100))
+    );
+    assert_eq!(
+        error(".org $1000\nbeq $2000"),
+        AssemblyError::new(2, AssemblyErrorKind::BranchOutOfRange(0x0FFE))
+    );
+    assert_eq!(
+        error(".fill 3"),
+        AssemblyError::new(
+            1,
+            AssemblyErrorKind::UnknownDirective(String::from(".fill"))
+        )
+    );
+    assert_eq!(
+        error("lda #$100").to_string(),
+        "line 1: value 256 is out of range"
+    );
+}
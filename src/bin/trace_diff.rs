@@ -0,0 +1,32 @@
+//! Compares a trace written by `rust6502::Tracer` against a reference log in
+//! the nestest.log layout and prints the first line where they differ.
+//!
+//! Usage: trace_diff <trace> <reference>
+
+use std::process::ExitCode;
+
+use rust6502::trace;
+
+fn main() -> ExitCode {
+    let paths: Vec<String> = std::env::args().skip(1).collect();
+    let [actual, reference] = paths.as_slice() else {
+        eprintln!("usage: trace_diff <trace> <reference>");
+        return ExitCode::from(2);
+    };
+    let read = |path: &str| {
+        std::fs::read_to_string(path).unwrap_or_else(|error| {
+            eprintln!("{}: {}", path, error);
+            std::process::exit(2);
+        })
+    };
+    match trace::compare_traces(&read(actual), &read(reference)) {
+        None => {
+            println!("traces match");
+            ExitCode::SUCCESS
+        }
+        Some(divergence) => {
+            println!("{}", divergence);
+            ExitCode::FAILURE
+        }
+    }
+}
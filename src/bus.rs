@@ -0,0 +1,32 @@
+use crate::{Byte, LongAddress, Word};
+
+/// Everything the CPU can see on its address bus.
+///
+/// The CPU performs every memory access through this trait, so an
+/// implementation can map RAM, ROM and memory-mapped peripherals to any
+/// address. Reads take `&mut self` because reading a peripheral register
+/// (e.g. acknowledging an interrupt) may change its state.
+pub trait Bus {
+    fn read(&mut self, address: Word) -> Byte;
+    fn write(&mut self, address: Word, data: Byte);
+
+    /// Returns what a read of `address` would, without any of its side
+    /// effects. Debugging tools use this to inspect memory without
+    /// disturbing the machine. Buses with registers whose reads change
+    /// state must override it; the default simply calls `read`.
+    fn peek(&mut self, address: Word) -> Byte {
+        self.read(address)
+    }
+
+    /// Called by the 6510 with the level of every pin of its I/O port after
+    /// each write to $0000 or $0001 and after reset, so that a banking
+    /// controller wired to the port can remap the bus.
+    fn io_port_changed(&mut self, _pins: Byte) {}
+}
+
+/// The 24-bit address bus of the 65816, which reaches 16 MB in 256 banks
+/// of 64 KB. The CPU only passes addresses below $1000000.
+pub trait LongBus {
+    fn read(&mut self, address: LongAddress) -> Byte;
+    fn write(&mut self, address: LongAddress, data: Byte);
+}
@@ -0,0 +1,425 @@
+use std::ops::RangeInclusive;
+
+use crate::instructions::{INSTRUCTION_JSR, INSTRUCTION_RTI, INSTRUCTION_RTS};
+use crate::rewind::History;
+use crate::snapshot::SaveState;
+use crate::{Bus, Byte, ExecutionError, RegisterType, Step, Word, CPU};
+
+pub type BreakpointId = usize;
+pub type WatchpointId = usize;
+
+/// A status flag a conditional breakpoint can test.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flag {
+    Carry,
+    Zero,
+    Interrupt,
+    Decimal,
+    Overflow,
+    Negative,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// What must hold for a conditional breakpoint to stop the CPU.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Condition {
+    Register(RegisterType, Comparison, Byte),
+    Flag(Flag, bool),
+}
+
+impl Condition {
+    pub fn holds(&self, cpu: &CPU) -> bool {
+        match *self {
+            Condition::Register(register, comparison, value) => {
+                let register: Byte = match register {
+                    RegisterType::Accumulator => cpu.accumulator,
+                    RegisterType::RegisterX => cpu.register_x,
+                    RegisterType::RegisterY => cpu.register_y,
+                    RegisterType::StackPointer => cpu.stack_pointer,
+                };
+                match comparison {
+                    Comparison::Equal => register == value,
+                    Comparison::NotEqual => register != value,
+                    Comparison::Less => register < value,
+                    Comparison::LessOrEqual => register <= value,
+                    Comparison::Greater => register > value,
+                    Comparison::GreaterOrEqual => register >= value,
+                }
+            }
+            Condition::Flag(flag, set) => {
+                let status = cpu.processor_status;
+                let value: bool = match flag {
+                    Flag::Carry => status.carry(),
+                    Flag::Zero => status.zero(),
+                    Flag::Interrupt => status.interrupt(),
+                    Flag::Decimal => status.decimal(),
+                    Flag::Overflow => status.overflow(),
+                    Flag::Negative => status.negative(),
+                };
+                value == set
+            }
+        }
+    }
+}
+
+/// Stops the CPU before it executes the instruction at `address`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Breakpoint {
+    pub id: BreakpointId,
+    pub address: Word,
+    /// Only stop if this holds when the program counter reaches `address`.
+    pub condition: Option<Condition>,
+    /// Remove the breakpoint the first time it stops the CPU.
+    pub temporary: bool,
+}
+
+/// Which bus accesses a watchpoint stops on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    Access,
+}
+
+/// Stops the CPU after an instruction that accessed an address in `range`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub id: WatchpointId,
+    pub range: RangeInclusive<Word>,
+    pub kind: WatchKind,
+}
+
+/// The first watched bus access an instruction made.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WatchHit {
+    pub id: WatchpointId,
+    pub address: Word,
+    pub data: Byte,
+    pub write: bool,
+}
+
+/// Why a `Debugger` call returned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// The requested step finished.
+    Step,
+    /// The program counter reached a breakpoint.
+    Breakpoint(BreakpointId),
+    Watchpoint(WatchHit),
+    /// `instruction_limit` instructions ran without anything else stopping
+    /// the CPU.
+    InstructionLimit,
+}
+
+/// Runs a CPU against a bus under the control of breakpoints and
+/// watchpoints.
+///
+/// Every call executes at least one instruction before it looks at
+/// breakpoints, so running from a breakpoint moves past it. Watchpoints see
+/// every bus access the CPU makes, dummy reads included. The 6510 handles
+/// $0000 and $0001 in its own port without using the bus, so watchpoints
+/// never stop on those.
+pub struct Debugger<B: Bus> {
+    pub cpu: CPU,
+    pub bus: B,
+    /// The most instructions one call may run, or `None` for no limit.
+    pub instruction_limit: Option<u64>,
+    breakpoints: Vec<Breakpoint>,
+    watchpoints: Vec<Watchpoint>,
+    next_id: usize,
+    history: Option<History<B>>,
+}
+
+impl<B: Bus> Debugger<B> {
+    pub fn new(cpu: CPU, bus: B) -> Self {
+        Self {
+            cpu,
+            bus,
+            instruction_limit: None,
+            breakpoints: Vec::new(),
+            watchpoints: Vec::new(),
+            next_id: 1,
+            history: None,
+        }
+    }
+
+    /// The recorded execution, if rewind is enabled.
+    pub fn history(&self) -> Option<&History<B>> {
+        self.history.as_ref()
+    }
+
+    pub fn disable_rewind(&mut self) {
+        self.history = None;
+    }
+
+    /// Forgets the recorded execution, keeping rewind enabled. Call this
+    /// after changing the CPU or bus directly.
+    pub fn clear_history(&mut self) {
+        if let Some(history) = &mut self.history {
+            history.clear();
+        }
+    }
+
+    /// Goes back to the state before the last instruction. Returns false if
+    /// rewind is disabled or the history does not reach back that far.
+    pub fn step_back(&mut self) -> bool {
+        let Some(history) = &mut self.history else {
+            return false;
+        };
+        match history.instructions().checked_sub(1) {
+            Some(instruction) => history.restore(instruction, &mut self.cpu, &mut self.bus),
+            None => false,
+        }
+    }
+
+    /// Goes back to the state just before the most recent instruction that
+    /// wrote to `address`, so that stepping forward once repeats the write.
+    /// Returns false if no recorded instruction wrote there.
+    pub fn run_back_to_write(&mut self, address: Word) -> bool {
+        let Some(history) = &mut self.history else {
+            return false;
+        };
+        match history.last_write(address) {
+            Some(instruction) => history.restore(instruction, &mut self.cpu, &mut self.bus),
+            None => false,
+        }
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    pub fn add_breakpoint(&mut self, address: Word) -> BreakpointId {
+        self.insert_breakpoint(address, None, false)
+    }
+
+    pub fn add_conditional_breakpoint(
+        &mut self,
+        address: Word,
+        condition: Condition,
+    ) -> BreakpointId {
+        self.insert_breakpoint(address, Some(condition), false)
+    }
+
+    pub fn add_temporary_breakpoint(&mut self, address: Word) -> BreakpointId {
+        self.insert_breakpoint(address, None, true)
+    }
+
+    /// Returns whether a breakpoint with that id existed.
+    pub fn remove_breakpoint(&mut self, id: BreakpointId) -> bool {
+        let count: usize = self.breakpoints.len();
+        self.breakpoints.retain(|breakpoint| breakpoint.id != id);
+        self.breakpoints.len() != count
+    }
+
+    pub fn add_watchpoint(&mut self, range: RangeInclusive<Word>, kind: WatchKind) -> WatchpointId {
+        let id: WatchpointId = self.allocate_id();
+        self.watchpoints.push(Watchpoint { id, range, kind });
+        id
+    }
+
+    /// Returns whether a watchpoint with that id existed.
+    pub fn remove_watchpoint(&mut self, id: WatchpointId) -> bool {
+        let count: usize = self.watchpoints.len();
+        self.watchpoints.retain(|watchpoint| watchpoint.id != id);
+        self.watchpoints.len() != count
+    }
+
+    /// Executes one instruction, stopping early only for a watchpoint.
+    pub fn step(&mut self) -> Result<StopReason, ExecutionError> {
+        let (_, hit): (Step, Option<WatchHit>) = self.step_watched()?;
+        Ok(hit.map_or(StopReason::Step, StopReason::Watchpoint))
+    }
+
+    /// Runs until a breakpoint or watchpoint stops the CPU.
+    pub fn run(&mut self) -> Result<StopReason, ExecutionError> {
+        self.run_until(|_, _| false)
+    }
+
+    /// Executes one instruction, treating a JSR and the whole subroutine it
+    /// calls as a single step.
+    pub fn step_over(&mut self) -> Result<StopReason, ExecutionError> {
+        if self.cpu.peek(self.cpu.program_counter, &mut self.bus) != INSTRUCTION_JSR {
+            return self.step();
+        }
+        let return_address: Word = self.cpu.program_counter.wrapping_add(3);
+        let stack_pointer: Byte = self.cpu.stack_pointer;
+        self.run_until(|cpu, _| {
+            cpu.program_counter == return_address && cpu.stack_pointer == stack_pointer
+        })
+    }
+
+    /// Runs until the current subroutine returns, that is until an RTS or
+    /// RTI pulls the stack above where it is now.
+    pub fn step_out(&mut self) -> Result<StopReason, ExecutionError> {
+        let stack_pointer: Byte = self.cpu.stack_pointer;
+        self.run_until(|cpu, step| {
+            matches!(step.opcode, INSTRUCTION_RTS | INSTRUCTION_RTI)
+                && step.interrupt.is_none()
+                && (cpu.stack_pointer.wrapping_sub(stack_pointer) as i8) > 0
+        })
+    }
+
+    /// Runs instructions until `done` returns true after one of them, or a
+    /// breakpoint, watchpoint or the instruction limit stops the CPU.
+    fn run_until(
+        &mut self,
+        mut done: impl FnMut(&CPU, &Step) -> bool,
+    ) -> Result<StopReason, ExecutionError> {
+        let mut instructions: u64 = 0;
+        loop {
+            let (step, hit): (Step, Option<WatchHit>) = self.step_watched()?;
+            instructions += 1;
+            if let Some(hit) = hit {
+                return Ok(StopReason::Watchpoint(hit));
+            }
+            if done(&self.cpu, &step) {
+                return Ok(StopReason::Step);
+            }
+            if let Some(id) = self.check_breakpoints() {
+                return Ok(StopReason::Breakpoint(id));
+            }
+            if self
+                .instruction_limit
+                .is_some_and(|limit| instructions >= limit)
+            {
+                return Ok(StopReason::InstructionLimit);
+            }
+        }
+    }
+
+    fn step_watched(&mut self) -> Result<(Step, Option<WatchHit>), ExecutionError> {
+        let address: Word = self.cpu.program_counter;
+        if let Some(history) = &mut self.history {
+            history.before_instruction(&self.cpu, &self.bus);
+        }
+        let mut bus: WatchingBus<B> = WatchingBus {
+            bus: &mut self.bus,
+            watchpoints: &self.watchpoints,
+            hit: None,
+            writes: self.history.as_ref().map(|_| Vec::new()),
+        };
+        let step: Step = self.cpu.step(&mut bus)?;
+        let (hit, writes): (Option<WatchHit>, Option<Vec<(Word, Byte)>>) = (bus.hit, bus.writes);
+        if let (Some(history), Some(writes)) = (&mut self.history, writes) {
+            history.after_instruction(address, writes);
+        }
+        Ok((step, hit))
+    }
+
+    /// Returns the breakpoint at the program counter whose condition holds,
+    /// removing it if it is temporary.
+    fn check_breakpoints(&mut self) -> Option<BreakpointId> {
+        let cpu: &CPU = &self.cpu;
+        let index: usize = self.breakpoints.iter().position(|breakpoint| {
+            breakpoint.address == cpu.program_counter
+                && breakpoint
+                    .condition
+                    .is_none_or(|condition| condition.holds(cpu))
+        })?;
+        let breakpoint: Breakpoint = self.breakpoints[index];
+        if breakpoint.temporary {
+            self.breakpoints.remove(index);
+        }
+        Some(breakpoint.id)
+    }
+
+    fn insert_breakpoint(
+        &mut self,
+        address: Word,
+        condition: Option<Condition>,
+        temporary: bool,
+    ) -> BreakpointId {
+        let id: BreakpointId = self.allocate_id();
+        self.breakpoints.push(Breakpoint {
+            id,
+            address,
+            condition,
+            temporary,
+        });
+        id
+    }
+
+    fn allocate_id(&mut self) -> usize {
+        let id: usize = self.next_id;
+        self.next_id += 1;
+        id
+    }
+}
+
+impl<B: Bus + SaveState> Debugger<B> {
+    /// Starts recording execution so that it can be stepped backwards,
+    /// keeping a checkpoint every `interval` instructions and at most
+    /// `capacity` of them.
+    pub fn enable_rewind(&mut self, interval: u64, capacity: usize) {
+        self.history = Some(History::new(interval, capacity));
+    }
+}
+
+/// Passes accesses through to the debugged bus, noting the first one that
+/// a watchpoint covers and, while rewind is enabled, every write.
+struct WatchingBus<'a, B: Bus> {
+    bus: &'a mut B,
+    watchpoints: &'a [Watchpoint],
+    hit: Option<WatchHit>,
+    writes: Option<Vec<(Word, Byte)>>,
+}
+
+impl<B: Bus> WatchingBus<'_, B> {
+    fn check(&mut self, address: Word, data: Byte, write: bool) {
+        if self.hit.is_some() {
+            return;
+        }
+        let watchpoint: Option<&Watchpoint> = self.watchpoints.iter().find(|watchpoint| {
+            watchpoint.range.contains(&address)
+                && match watchpoint.kind {
+                    WatchKind::Read => !write,
+                    WatchKind::Write => write,
+                    WatchKind::Access => true,
+                }
+        });
+        self.hit = watchpoint.map(|watchpoint| WatchHit {
+            id: watchpoint.id,
+            address,
+            data,
+            write,
+        });
+    }
+}
+
+impl<B: Bus> Bus for WatchingBus<'_, B> {
+    fn read(&mut self, address: Word) -> Byte {
+        let data: Byte = self.bus.read(address);
+        self.check(address, data, false);
+        data
+    }
+
+    fn write(&mut self, address: Word, data: Byte) {
+        self.bus.write(address, data);
+        if let Some(writes) = &mut self.writes {
+            writes.push((address, data));
+        }
+        self.check(address, data, true);
+    }
+
+    fn peek(&mut self, address: Word) -> Byte {
+        self.bus.peek(address)
+    }
+
+    fn io_port_changed(&mut self, pins: Byte) {
+        self.bus.io_port_changed(pins);
+    }
+}
@@ -0,0 +1,196 @@
+use std::fmt;
+
+use crate::instructions::{AddressingMode, Mnemonic, OpcodeInfo};
+use crate::{Bus, Byte, Variant, Word};
+
+/// One decoded instruction, as found at `address`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Disassembly {
+    pub address: Word,
+    /// The opcode followed by its operand bytes.
+    pub bytes: Vec<Byte>,
+    pub mnemonic: Mnemonic,
+    pub addressing_mode: AddressingMode,
+    /// The operand bytes as a little-endian word.
+    pub operand: Option<Word>,
+    pub documented: bool,
+}
+
+impl Disassembly {
+    pub fn opcode(&self) -> Byte {
+        self.bytes[0]
+    }
+
+    pub fn length(&self) -> Word {
+        self.bytes.len() as Word
+    }
+
+    /// The absolute address a relative branch jumps to when taken.
+    pub fn branch_target(&self) -> Option<Word> {
+        let offset: Byte = match (self.addressing_mode, self.operand) {
+            (AddressingMode::Relative, Some(operand)) => operand as Byte,
+            (AddressingMode::ZeroPageRelative, Some(operand)) => (operand >> 8) as Byte,
+            (AddressingMode::RelativeLong, Some(operand)) => {
+                return Some(
+                    self.address
+                        .wrapping_add(self.length())
+                        .wrapping_add(operand),
+                );
+            }
+            _ => return None,
+        };
+        Some(
+            self.address
+                .wrapping_add(self.length())
+                .wrapping_add_signed(offset as i8 as i16),
+        )
+    }
+
+    /// The bit RMB, SMB, BBR and BBS work on, which is part of the opcode.
+    pub fn bit_number(&self) -> Option<Byte> {
+        match self.mnemonic {
+            Mnemonic::RMB | Mnemonic::SMB | Mnemonic::BBR | Mnemonic::BBS => {
+                Some((self.opcode() >> 4) & 0b111)
+            }
+            _ => None,
+        }
+    }
+
+    /// All three operand bytes of a 65816 long address.
+    fn long_operand(&self) -> u32 {
+        self.bytes[1..]
+            .iter()
+            .rev()
+            .fold(0, |address, byte| (address << 8) | *byte as u32)
+    }
+
+    /// Renders the instruction as `address  bytes  text`, with the hex
+    /// bytes column left out unless `show_bytes` is set.
+    pub fn format(&self, show_bytes: bool) -> String {
+        if show_bytes {
+            let bytes: Vec<String> = self.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+            format!("{:04X}  {:<8}  {}", self.address, bytes.join(" "), self)
+        } else {
+            format!("{:04X}  {}", self.address, self)
+        }
+    }
+}
+
+/// Standard assembler syntax, e.g. `LDA ($42),Y`. Undocumented opcodes are
+/// marked with a leading `*`, and the bit instructions get their bit number
+/// appended as in `BBR3 $42,$1234`.
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.documented {
+            write!(f, "*")?;
+        }
+        write!(f, "{}", self.mnemonic)?;
+        if let Some(bit) = self.bit_number() {
+            write!(f, "{}", bit)?;
+        }
+        let operand: Word = self.operand.unwrap_or(0);
+        match self.addressing_mode {
+            AddressingMode::Implied => Ok(()),
+            AddressingMode::Accumulator => write!(f, " A"),
+            AddressingMode::Immediate => write!(f, " #${:02X}", operand),
+            AddressingMode::ZeroPage => write!(f, " ${:02X}", operand),
+            AddressingMode::ZeroPageX => write!(f, " ${:02X},X", operand),
+            AddressingMode::ZeroPageY => write!(f, " ${:02X},Y", operand),
+            AddressingMode::Relative => write!(f, " ${:04X}", self.branch_target().unwrap_or(0)),
+            AddressingMode::Absolute => write!(f, " ${:04X}", operand),
+            AddressingMode::AbsoluteX => write!(f, " ${:04X},X", operand),
+            AddressingMode::AbsoluteY => write!(f, " ${:04X},Y", operand),
+            AddressingMode::Indirect => write!(f, " (${:04X})", operand),
+            AddressingMode::IndirectX => write!(f, " (${:02X},X)", operand),
+            AddressingMode::IndirectY => write!(f, " (${:02X}),Y", operand),
+            AddressingMode::ZeroPageIndirect => write!(f, " (${:02X})", operand),
+            AddressingMode::AbsoluteIndexedIndirect => write!(f, " (${:04X},X)", operand),
+            AddressingMode::ZeroPageRelative => write!(
+                f,
+                " ${:02X},${:04X}",
+                operand & 0xFF,
+                self.branch_target().unwrap_or(0)
+            ),
+            AddressingMode::AbsoluteLong => write!(f, " ${:06X}", self.long_operand()),
+            AddressingMode::AbsoluteLongX => write!(f, " ${:06X},X", self.long_operand()),
+            AddressingMode::AbsoluteIndirectLong => write!(f, " [${:04X}]", operand),
+            AddressingMode::ZeroPageIndirectLong => write!(f, " [${:02X}]", operand),
+            AddressingMode::ZeroPageIndirectLongY => write!(f, " [${:02X}],Y", operand),
+            AddressingMode::StackRelative => write!(f, " ${:02X},S", operand),
+            AddressingMode::StackRelativeIndirectY => write!(f, " (${:02X},S),Y", operand),
+            AddressingMode::RelativeLong => {
+                write!(f, " ${:04X}", self.branch_target().unwrap_or(0))
+            }
+            // The operand bytes are the destination bank then the source
+            // bank, but the source is written first
+            AddressingMode::BlockMove => {
+                write!(f, " ${:02X},${:02X}", operand >> 8, operand & 0xFF)
+            }
+        }
+    }
+}
+
+/// Decodes the NMOS instruction at `address`. Every opcode decodes to
+/// something, undocumented ones included.
+pub fn disassemble<B: Bus>(bus: &mut B, address: Word) -> Disassembly {
+    disassemble_variant(bus, address, Variant::Nmos6502)
+}
+
+/// Decodes the instruction at `address` using the opcode table of
+/// `variant`. The bytes are fetched with `Bus::peek`.
+pub fn disassemble_variant<B: Bus>(bus: &mut B, address: Word, variant: Variant) -> Disassembly {
+    let opcode: Byte = bus.peek(address);
+    let info: &OpcodeInfo = variant.opcode_info(opcode);
+    let mut bytes: Vec<Byte> = vec![opcode];
+    for offset in 1..info.length as Word {
+        bytes.push(bus.peek(address.wrapping_add(offset)));
+    }
+    let operand: Option<Word> = match bytes.len() {
+        2 => Some(bytes[1] as Word),
+        3 => Some(bytes[1] as Word | ((bytes[2] as Word) << 8)),
+        _ => None,
+    };
+    Disassembly {
+        address,
+        bytes,
+        mnemonic: info.mnemonic,
+        addressing_mode: info.addressing_mode,
+        operand,
+        documented: info.documented,
+    }
+}
+
+/// Decodes every NMOS instruction that starts between `start` and `end`
+/// inclusive.
+pub fn disassemble_range<B: Bus>(bus: &mut B, start: Word, end: Word) -> Vec<Disassembly> {
+    disassemble_range_variant(bus, start, end, Variant::Nmos6502)
+}
+
+pub fn disassemble_range_variant<B: Bus>(
+    bus: &mut B,
+    start: Word,
+    end: Word,
+    variant: Variant,
+) -> Vec<Disassembly> {
+    let mut instructions: Vec<Disassembly> = Vec::new();
+    let mut address: u32 = start as u32;
+    while address <= end as u32 {
+        let instruction: Disassembly = disassemble_variant(bus, address as Word, variant);
+        address += instruction.length() as u32;
+        instructions.push(instruction);
+    }
+    instructions
+}
+
+/// Renders `disassemble_range` as text, one instruction per line.
+pub fn disassemble_to_string<B: Bus>(
+    bus: &mut B,
+    start: Word,
+    end: Word,
+    show_bytes: bool,
+) -> String {
+    disassemble_range(bus, start, end)
+        .iter()
+        .map(|instruction| instruction.format(show_bytes) + "\n")
+        .collect()
+}
@@ -0,0 +1,221 @@
+use std::{fmt, io};
+
+use crate::{Byte, Word, CPU};
+
+/// Why `CPU::try_execute` stopped before using up its cycle budget.
+///
+/// Every variant carries the offending opcode, the address it was fetched
+/// from and a boxed copy of the CPU state at the time.
+#[derive(Clone, Debug)]
+pub enum ExecutionError {
+    /// An opcode that the CPU does not implement.
+    IllegalOpcode {
+        opcode: Byte,
+        address: Word,
+        cpu: Box<CPU>,
+    },
+    /// A JAM (KIL) opcode, which locks up an NMOS 6502 until it is reset.
+    Jam {
+        opcode: Byte,
+        address: Word,
+        cpu: Box<CPU>,
+    },
+}
+
+impl ExecutionError {
+    pub fn opcode(&self) -> Byte {
+        match self {
+            ExecutionError::IllegalOpcode { opcode, .. } | ExecutionError::Jam { opcode, .. } => {
+                *opcode
+            }
+        }
+    }
+
+    pub fn address(&self) -> Word {
+        match self {
+            ExecutionError::IllegalOpcode { address, .. } | ExecutionError::Jam { address, .. } => {
+                *address
+            }
+        }
+    }
+
+    pub fn cpu(&self) -> &CPU {
+        match self {
+            ExecutionError::IllegalOpcode { cpu, .. } | ExecutionError::Jam { cpu, .. } => cpu,
+        }
+    }
+}
+
+impl fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecutionError::IllegalOpcode {
+                opcode, address, ..
+            } => write!(
+                f,
+                "Unknown instruction: {:#04x} at address {:#06x}",
+                opcode, address
+            ),
+            ExecutionError::Jam {
+                opcode, address, ..
+            } => write!(
+                f,
+                "CPU jammed by instruction: {:#04x} at address {:#06x}",
+                opcode, address
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ExecutionError {}
+
+/// What the CPU does when it fetches an opcode it does not implement.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum UnknownOpcodePolicy {
+    /// Stop and return the error, leaving the program counter on the opcode.
+    #[default]
+    Halt,
+    /// Skip the opcode and its operands, reading the operand like a NOP
+    /// would but writing nothing. JAM opcodes still halt.
+    Nop,
+    /// Call a user function with the program counter just past the opcode.
+    /// It returns the number of cycles the instruction took after the opcode
+    /// fetch, or `None` to halt with the error.
+    Handler(fn(&mut CPU, &ExecutionError) -> Option<i32>),
+}
+
+/// Why `snapshot::load` rejected its data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The data does not start with the snapshot signature.
+    NotASnapshot,
+    /// The snapshot was written by a newer or older, incompatible format.
+    UnsupportedVersion(u16),
+    /// The variant byte does not name a variant this version knows.
+    UnknownVariant(Byte),
+    /// The data ends part way through the snapshot.
+    Truncated,
+    /// There is more data after the end of the snapshot.
+    TrailingData,
+    /// The bus did not accept its saved state.
+    InvalidBusState,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::NotASnapshot => write!(f, "not a snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {}", version)
+            }
+            SnapshotError::UnknownVariant(variant) => {
+                write!(f, "unknown CPU variant {} in snapshot", variant)
+            }
+            SnapshotError::Truncated => write!(f, "snapshot is truncated"),
+            SnapshotError::TrailingData => write!(f, "unexpected data after snapshot"),
+            SnapshotError::InvalidBusState => write!(f, "invalid bus state in snapshot"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+/// Why `Tracer::step` failed.
+#[derive(Debug)]
+pub enum TraceError {
+    /// Writing the trace line failed.
+    Io(io::Error),
+    /// The CPU halted on an unknown opcode.
+    Execution(ExecutionError),
+}
+
+impl From<io::Error> for TraceError {
+    fn from(error: io::Error) -> Self {
+        TraceError::Io(error)
+    }
+}
+
+impl From<ExecutionError> for TraceError {
+    fn from(error: ExecutionError) -> Self {
+        TraceError::Execution(error)
+    }
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceError::Io(error) => write!(f, "{}", error),
+            TraceError::Execution(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for TraceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TraceError::Io(error) => Some(error),
+            TraceError::Execution(error) => Some(error),
+        }
+    }
+}
+
+/// Why a `loader` function rejected a program file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadError {
+    /// A line of a text format that is not a well-formed record. Lines are
+    /// numbered from 1.
+    InvalidRecord { line: usize },
+    /// A record whose checksum does not match its contents.
+    BadChecksum { line: usize },
+    /// A record type the loader does not know, or one the format reserves
+    /// such as S4.
+    UnsupportedRecord { line: usize },
+    /// An S-record count record that disagrees with the number of data
+    /// records before it.
+    RecordCountMismatch { line: usize },
+    /// A text file without its end-of-file or termination record.
+    MissingEndRecord,
+    /// A binary file that ends part way through a header or segment.
+    Truncated,
+    /// An Atari executable that does not start with $FFFF.
+    MissingHeader,
+    /// An XEX segment whose end address is before its start address.
+    InvalidSegment { start: Word, end: Word },
+    /// Data that would run past the end of the address space.
+    OutOfRange { address: u32 },
+    /// Two segments that write to the same address.
+    Overlap { address: Word },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::InvalidRecord { line } => write!(f, "line {}: invalid record", line),
+            LoadError::BadChecksum { line } => write!(f, "line {}: bad checksum", line),
+            LoadError::UnsupportedRecord { line } => {
+                write!(f, "line {}: unsupported record", line)
+            }
+            LoadError::RecordCountMismatch { line } => {
+                write!(f, "line {}: record count does not match", line)
+            }
+            LoadError::MissingEndRecord => write!(f, "missing end record"),
+            LoadError::Truncated => write!(f, "file is truncated"),
+            LoadError::MissingHeader => write!(f, "missing $FFFF header"),
+            LoadError::InvalidSegment { start, end } => {
+                write!(
+                    f,
+                    "segment ends at {:04X} before it starts at {:04X}",
+                    end, start
+                )
+            }
+            LoadError::OutOfRange { address } => {
+                write!(f, "address {:X} is outside the address space", address)
+            }
+            LoadError::Overlap { address } => {
+                write!(f, "segments overlap at {:04X}", address)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
@@ -0,0 +1,452 @@
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+use crate::debugger::{Debugger, StopReason, WatchKind};
+use crate::{Bus, Byte, ExecutionError, ProcessorStatus, Word};
+
+/// How many instructions `c` runs between checks for an interrupt request
+/// from the client.
+const CONTINUE_CHUNK: u64 = 100_000;
+
+/// The register layout reported to GDB. The 6502 has no standard GDB
+/// target, so the client is told about it through `qXfer:features:read`.
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.rust6502.cpu">
+    <reg name="a" bitsize="8" type="uint8" regnum="0"/>
+    <reg name="x" bitsize="8" type="uint8"/>
+    <reg name="y" bitsize="8" type="uint8"/>
+    <reg name="p" bitsize="8" type="uint8"/>
+    <reg name="sp" bitsize="8" type="uint8"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+  </feature>
+</target>
+"#;
+
+const SIGINT: u8 = 2;
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+
+/// A breakpoint or watchpoint the client inserted with a `Z` packet.
+struct InsertedPoint {
+    kind: u8,
+    address: Word,
+    length: Word,
+    id: usize,
+}
+
+/// A GDB Remote Serial Protocol stub serving a `Debugger`.
+///
+/// Registers are numbered a, x, y, p, sp, pc, with pc 16 bits wide and the
+/// rest 8 bits. Breakpoints (`Z0`/`Z1`) and write, read and access
+/// watchpoints (`Z2`-`Z4`) map onto the debugger's own.
+pub struct GdbStub<'a, B: Bus> {
+    debugger: &'a mut Debugger<B>,
+    points: Vec<InsertedPoint>,
+    no_ack: bool,
+}
+
+impl<'a, B: Bus> GdbStub<'a, B> {
+    pub fn new(debugger: &'a mut Debugger<B>) -> Self {
+        Self {
+            debugger,
+            points: Vec::new(),
+            no_ack: false,
+        }
+    }
+
+    /// Waits for one client on `address` and serves it until it detaches or
+    /// disconnects.
+    pub fn listen<A: ToSocketAddrs>(&mut self, address: A) -> io::Result<()> {
+        let listener: TcpListener = TcpListener::bind(address)?;
+        let (stream, _) = listener.accept()?;
+        self.serve(stream)
+    }
+
+    /// Serves a connected client until it detaches, kills the target or
+    /// disconnects. Breakpoints and watchpoints the client inserted are
+    /// removed afterwards.
+    pub fn serve(&mut self, mut stream: TcpStream) -> io::Result<()> {
+        let result: io::Result<()> = self.serve_packets(&mut stream);
+        for point in std::mem::take(&mut self.points) {
+            self.remove_point(&point);
+        }
+        result
+    }
+
+    fn serve_packets(&mut self, stream: &mut TcpStream) -> io::Result<()> {
+        loop {
+            let packet: String = match read_packet(stream, self.no_ack)? {
+                Some(Incoming::Packet(packet)) => packet,
+                Some(Incoming::Interrupt) => {
+                    write_packet(stream, &stop_signal(SIGINT))?;
+                    continue;
+                }
+                None => return Ok(()),
+            };
+            let reply: String = match packet.as_bytes().first() {
+                Some(b'c') => self.resume(stream, &packet[1..])?,
+                Some(b'D') => {
+                    write_packet(stream, "OK")?;
+                    return Ok(());
+                }
+                Some(b'k') => return Ok(()),
+                _ => self.handle_packet(&packet),
+            };
+            write_packet(stream, &reply)?;
+            if packet == "QStartNoAckMode" {
+                self.no_ack = true;
+            }
+        }
+    }
+
+    /// Answers every packet except `c`, `D` and `k`, which need the
+    /// connection. Unsupported packets get the empty reply the protocol
+    /// asks for.
+    pub fn handle_packet(&mut self, packet: &str) -> String {
+        // A command is one ASCII byte, so anything else is unsupported
+        let (command, arguments): (Byte, &str) = match (packet.as_bytes().first(), packet.get(1..))
+        {
+            (Some(&command), Some(arguments)) => (command, arguments),
+            _ => return String::new(),
+        };
+        let reply: Option<String> = match command {
+            b'?' => Some(stop_signal(SIGTRAP)),
+            b'g' => Some(self.read_registers()),
+            b'G' => self.write_registers(arguments),
+            b'p' => self.read_register(arguments),
+            b'P' => self.write_register(arguments),
+            b'm' => self.read_memory(arguments),
+            b'M' => self.write_memory(arguments),
+            b's' => self.step(arguments),
+            b'Z' => self.insert_point(arguments),
+            b'z' => self.delete_point(arguments),
+            b'H' => Some(String::from("OK")),
+            b'q' | b'Q' => Some(self.query(packet)),
+            _ => Some(String::new()),
+        };
+        reply.unwrap_or_else(|| String::from("E01"))
+    }
+
+    fn query(&self, packet: &str) -> String {
+        if packet.starts_with("qSupported") {
+            return String::from("PacketSize=4000;qXfer:features:read+;QStartNoAckMode+");
+        }
+        if let Some(range) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            let Some((offset, length)) = range.split_once(',') else {
+                return String::from("E01");
+            };
+            let (Ok(offset), Ok(length)) = (
+                usize::from_str_radix(offset, 16),
+                usize::from_str_radix(length, 16),
+            ) else {
+                return String::from("E01");
+            };
+            let start: usize = offset.min(TARGET_XML.len());
+            let end: usize = (start + length).min(TARGET_XML.len());
+            let marker: char = if end == TARGET_XML.len() { 'l' } else { 'm' };
+            return format!("{}{}", marker, &TARGET_XML[start..end]);
+        }
+        match packet {
+            "QStartNoAckMode" => String::from("OK"),
+            "qAttached" => String::from("1"),
+            "qC" => String::from("QC1"),
+            "qfThreadInfo" => String::from("m1"),
+            "qsThreadInfo" => String::from("l"),
+            _ => String::new(),
+        }
+    }
+
+    fn registers(&self) -> [Byte; 7] {
+        let cpu = &self.debugger.cpu;
+        let [pc_lo, pc_hi] = cpu.program_counter.to_le_bytes();
+        [
+            cpu.accumulator,
+            cpu.register_x,
+            cpu.register_y,
+            cpu.processor_status.0,
+            cpu.stack_pointer,
+            pc_lo,
+            pc_hi,
+        ]
+    }
+
+    fn read_registers(&self) -> String {
+        to_hex(&self.registers())
+    }
+
+    fn write_registers(&mut self, arguments: &str) -> Option<String> {
+        let bytes: Vec<Byte> = from_hex(arguments)?;
+        let [a, x, y, p, sp, pc_lo, pc_hi] = bytes[..] else {
+            return None;
+        };
+        let cpu = &mut self.debugger.cpu;
+        cpu.accumulator = a;
+        cpu.register_x = x;
+        cpu.register_y = y;
+        cpu.processor_status = ProcessorStatus(p);
+        cpu.stack_pointer = sp;
+        cpu.program_counter = Word::from_le_bytes([pc_lo, pc_hi]);
+        self.debugger.clear_history();
+        Some(String::from("OK"))
+    }
+
+    fn read_register(&self, arguments: &str) -> Option<String> {
+        let registers: [Byte; 7] = self.registers();
+        match usize::from_str_radix(arguments, 16).ok()? {
+            number @ 0..=4 => Some(to_hex(&registers[number..=number])),
+            5 => Some(to_hex(&registers[5..])),
+            _ => None,
+        }
+    }
+
+    fn write_register(&mut self, arguments: &str) -> Option<String> {
+        let (number, value) = arguments.split_once('=')?;
+        let bytes: Vec<Byte> = from_hex(value)?;
+        let cpu = &mut self.debugger.cpu;
+        match (usize::from_str_radix(number, 16).ok()?, &bytes[..]) {
+            (0, &[a]) => cpu.accumulator = a,
+            (1, &[x]) => cpu.register_x = x,
+            (2, &[y]) => cpu.register_y = y,
+            (3, &[p]) => cpu.processor_status = ProcessorStatus(p),
+            (4, &[sp]) => cpu.stack_pointer = sp,
+            (5, &[lo, hi]) => cpu.program_counter = Word::from_le_bytes([lo, hi]),
+            _ => return None,
+        }
+        self.debugger.clear_history();
+        Some(String::from("OK"))
+    }
+
+    fn read_memory(&mut self, arguments: &str) -> Option<String> {
+        let (address, length) = parse_address_length(arguments)?;
+        let bytes: Vec<Byte> = (0..length)
+            .map(|offset| {
+                let address: Word = address.wrapping_add(offset);
+                self.debugger.cpu.peek(address, &mut self.debugger.bus)
+            })
+            .collect();
+        Some(to_hex(&bytes))
+    }
+
+    fn write_memory(&mut self, arguments: &str) -> Option<String> {
+        let (range, data) = arguments.split_once(':')?;
+        let (address, length) = parse_address_length(range)?;
+        let bytes: Vec<Byte> = from_hex(data)?;
+        if bytes.len() != length as usize {
+            return None;
+        }
+        for (offset, byte) in bytes.into_iter().enumerate() {
+            self.debugger
+                .bus
+                .write(address.wrapping_add(offset as Word), byte);
+        }
+        self.debugger.clear_history();
+        Some(String::from("OK"))
+    }
+
+    /// `s [addr]`: executes one instruction, from `addr` if given.
+    fn step(&mut self, arguments: &str) -> Option<String> {
+        self.set_resume_address(arguments)?;
+        let result = self.debugger.step();
+        Some(self.stop_reply(result))
+    }
+
+    /// `c [addr]`: runs until something stops the CPU or the client sends
+    /// an interrupt.
+    fn resume(&mut self, stream: &mut TcpStream, arguments: &str) -> io::Result<String> {
+        if self.set_resume_address(arguments).is_none() {
+            return Ok(String::from("E01"));
+        }
+        let instruction_limit: Option<u64> = self.debugger.instruction_limit;
+        self.debugger.instruction_limit = Some(CONTINUE_CHUNK);
+        let reply: io::Result<String> = loop {
+            match self.debugger.run() {
+                Ok(StopReason::InstructionLimit) => match interrupt_requested(stream) {
+                    Ok(false) => continue,
+                    Ok(true) => break Ok(stop_signal(SIGINT)),
+                    Err(error) => break Err(error),
+                },
+                result => break Ok(self.stop_reply(result)),
+            }
+        };
+        self.debugger.instruction_limit = instruction_limit;
+        reply
+    }
+
+    fn set_resume_address(&mut self, arguments: &str) -> Option<()> {
+        if !arguments.is_empty() {
+            self.debugger.cpu.program_counter = Word::from_str_radix(arguments, 16).ok()?;
+            self.debugger.clear_history();
+        }
+        Some(())
+    }
+
+    fn stop_reply(&self, result: Result<StopReason, ExecutionError>) -> String {
+        match result {
+            Ok(StopReason::Watchpoint(hit)) => {
+                let kind: &str = match self.points.iter().find(|point| point.id == hit.id) {
+                    Some(InsertedPoint { kind: 3, .. }) => "rwatch",
+                    Some(InsertedPoint { kind: 4, .. }) => "awatch",
+                    _ => "watch",
+                };
+                format!("T{:02x}{}:{:04x};", SIGTRAP, kind, hit.address)
+            }
+            Ok(_) => stop_signal(SIGTRAP),
+            Err(_) => stop_signal(SIGILL),
+        }
+    }
+
+    /// `Z type,addr,kind`. Software and hardware breakpoints are the same
+    /// thing here.
+    fn insert_point(&mut self, arguments: &str) -> Option<String> {
+        let (kind, address, length) = parse_point(arguments)?;
+        let id: usize = match kind {
+            0 | 1 => self.debugger.add_breakpoint(address),
+            2..=4 => {
+                let watch_kind: WatchKind = match kind {
+                    2 => WatchKind::Write,
+                    3 => WatchKind::Read,
+                    _ => WatchKind::Access,
+                };
+                let end: Word = address.wrapping_add(length.max(1) - 1);
+                self.debugger.add_watchpoint(address..=end, watch_kind)
+            }
+            _ => return Some(String::new()),
+        };
+        self.points.push(InsertedPoint {
+            kind,
+            address,
+            length,
+            id,
+        });
+        Some(String::from("OK"))
+    }
+
+    fn delete_point(&mut self, arguments: &str) -> Option<String> {
+        let (kind, address, length) = parse_point(arguments)?;
+        if kind > 4 {
+            return Some(String::new());
+        }
+        let index: usize = self.points.iter().position(|point| {
+            point.kind == kind && point.address == address && point.length == length
+        })?;
+        let point: InsertedPoint = self.points.remove(index);
+        self.remove_point(&point);
+        Some(String::from("OK"))
+    }
+
+    fn remove_point(&mut self, point: &InsertedPoint) {
+        if point.kind <= 1 {
+            self.debugger.remove_breakpoint(point.id);
+        } else {
+            self.debugger.remove_watchpoint(point.id);
+        }
+    }
+}
+
+fn stop_signal(signal: u8) -> String {
+    format!("S{:02x}", signal)
+}
+
+fn parse_address_length(text: &str) -> Option<(Word, Word)> {
+    let (address, length) = text.split_once(',')?;
+    Some((
+        Word::from_str_radix(address, 16).ok()?,
+        Word::from_str_radix(length, 16).ok()?,
+    ))
+}
+
+fn parse_point(text: &str) -> Option<(u8, Word, Word)> {
+    let (kind, rest) = text.split_once(',')?;
+    let (address, length) = parse_address_length(rest)?;
+    Some((kind.parse().ok()?, address, length))
+}
+
+fn to_hex(bytes: &[Byte]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<Byte>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|index| Byte::from_str_radix(text.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+/// `$data#checksum`, where the checksum is the sum of the data bytes modulo
+/// 256.
+pub fn encode_packet(data: &str) -> String {
+    let checksum: Byte = data.bytes().fold(0, |sum, byte| sum.wrapping_add(byte));
+    format!("${}#{:02x}", data, checksum)
+}
+
+fn write_packet(stream: &mut TcpStream, data: &str) -> io::Result<()> {
+    stream.write_all(encode_packet(data).as_bytes())
+}
+
+enum Incoming {
+    Packet(String),
+    Interrupt,
+}
+
+/// Reads the next packet, acknowledging it unless no-ack mode is on.
+/// Returns `None` when the client disconnects.
+fn read_packet(stream: &mut TcpStream, no_ack: bool) -> io::Result<Option<Incoming>> {
+    let mut byte: [u8; 1] = [0];
+    loop {
+        if stream.read(&mut byte)? == 0 {
+            return Ok(None);
+        }
+        match byte[0] {
+            0x03 => return Ok(Some(Incoming::Interrupt)),
+            b'$' => {}
+            _ => continue,
+        }
+        let mut data: Vec<u8> = Vec::new();
+        loop {
+            if stream.read(&mut byte)? == 0 {
+                return Ok(None);
+            }
+            if byte[0] == b'#' {
+                break;
+            }
+            data.push(byte[0]);
+        }
+        let mut checksum: [u8; 2] = [0; 2];
+        stream.read_exact(&mut checksum)?;
+        let expected: Option<Byte> = std::str::from_utf8(&checksum)
+            .ok()
+            .and_then(|text| Byte::from_str_radix(text, 16).ok());
+        let actual: Byte = data.iter().fold(0, |sum, byte| sum.wrapping_add(*byte));
+        if no_ack {
+            return Ok(Some(Incoming::Packet(
+                String::from_utf8_lossy(&data).into(),
+            )));
+        }
+        if expected == Some(actual) {
+            stream.write_all(b"+")?;
+            return Ok(Some(Incoming::Packet(
+                String::from_utf8_lossy(&data).into(),
+            )));
+        }
+        stream.write_all(b"-")?;
+    }
+}
+
+/// Checks, without blocking, whether the client has sent an interrupt.
+fn interrupt_requested(stream: &mut TcpStream) -> io::Result<bool> {
+    stream.set_nonblocking(true)?;
+    let mut byte: [u8; 1] = [0];
+    let result: io::Result<usize> = stream.read(&mut byte);
+    stream.set_nonblocking(false)?;
+    match result {
+        Ok(1) => Ok(byte[0] == 0x03),
+        Ok(_) => Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+        Err(error) if error.kind() == io::ErrorKind::WouldBlock => Ok(false),
+        Err(error) => Err(error),
+    }
+}
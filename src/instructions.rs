@@ -1,5 +1,15 @@
+pub use asm::opcodes::{
+    opcode_info, AddressingMode, Instruction, Mnemonic, OpcodeInfo, FLAG_CARRY, FLAG_DECIMAL,
+    FLAG_INTERRUPT, FLAG_NEGATIVE, FLAG_OVERFLOW, FLAG_ZERO, OPCODES, OPCODES_65816, OPCODES_65C02,
+};
+
+use crate::variant::Variant;
 use crate::Byte;
 
+//
+// Load/Store Operations
+//
+
 // STA
 pub const INSTRUCTION_STA_ZERO: Byte = 0x85;
 pub const INSTRUCTION_STA_ZERO_X: Byte = 0x95;
@@ -43,5 +53,265 @@ pub const INSTRUCTION_LDY_ZERO_X: Byte = 0xB4;
 pub const INSTRUCTION_LDY_ABS: Byte = 0xAC;
 pub const INSTRUCTION_LDY_ABS_X: Byte = 0xBC;
 
+//
+// Register Transfers
+//
+
+pub const INSTRUCTION_TAX: Byte = 0xAA;
+pub const INSTRUCTION_TAY: Byte = 0xA8;
+pub const INSTRUCTION_TXA: Byte = 0x8A;
+pub const INSTRUCTION_TYA: Byte = 0x98;
+
+//
+// Stack Operations
+//
+
+pub const INSTRUCTION_TSX: Byte = 0xBA;
+pub const INSTRUCTION_TXS: Byte = 0x9A;
+pub const INSTRUCTION_PHA: Byte = 0x48;
+pub const INSTRUCTION_PHP: Byte = 0x08;
+pub const INSTRUCTION_PLA: Byte = 0x68;
+pub const INSTRUCTION_PLP: Byte = 0x28;
+
+//
+// Logical
+//
+
+// AND
+pub const INSTRUCTION_AND_IMM: Byte = 0x29;
+pub const INSTRUCTION_AND_ZERO: Byte = 0x25;
+pub const INSTRUCTION_AND_ZERO_X: Byte = 0x35;
+pub const INSTRUCTION_AND_ABS: Byte = 0x2D;
+pub const INSTRUCTION_AND_ABS_X: Byte = 0x3D;
+pub const INSTRUCTION_AND_ABS_Y: Byte = 0x39;
+pub const INSTRUCTION_AND_INDR_X: Byte = 0x21;
+pub const INSTRUCTION_AND_INDR_Y: Byte = 0x31;
+
+// EOR
+pub const INSTRUCTION_EOR_IMM: Byte = 0x49;
+pub const INSTRUCTION_EOR_ZERO: Byte = 0x45;
+pub const INSTRUCTION_EOR_ZERO_X: Byte = 0x55;
+pub const INSTRUCTION_EOR_ABS: Byte = 0x4D;
+pub const INSTRUCTION_EOR_ABS_X: Byte = 0x5D;
+pub const INSTRUCTION_EOR_ABS_Y: Byte = 0x59;
+pub const INSTRUCTION_EOR_INDR_X: Byte = 0x41;
+pub const INSTRUCTION_EOR_INDR_Y: Byte = 0x51;
+
+// ORA
+pub const INSTRUCTION_ORA_IMM: Byte = 0x09;
+pub const INSTRUCTION_ORA_ZERO: Byte = 0x05;
+pub const INSTRUCTION_ORA_ZERO_X: Byte = 0x15;
+pub const INSTRUCTION_ORA_ABS: Byte = 0x0D;
+pub const INSTRUCTION_ORA_ABS_X: Byte = 0x1D;
+pub const INSTRUCTION_ORA_ABS_Y: Byte = 0x19;
+pub const INSTRUCTION_ORA_INDR_X: Byte = 0x01;
+pub const INSTRUCTION_ORA_INDR_Y: Byte = 0x11;
+
+// BIT
+pub const INSTRUCTION_BIT_ZERO: Byte = 0x24;
+pub const INSTRUCTION_BIT_ABS: Byte = 0x2C;
+
+//
+// Arithmetic
+//
+
+// ADC
+pub const INSTRUCTION_ADC_IMM: Byte = 0x69;
+pub const INSTRUCTION_ADC_ZERO: Byte = 0x65;
+pub const INSTRUCTION_ADC_ZERO_X: Byte = 0x75;
+pub const INSTRUCTION_ADC_ABS: Byte = 0x6D;
+pub const INSTRUCTION_ADC_ABS_X: Byte = 0x7D;
+pub const INSTRUCTION_ADC_ABS_Y: Byte = 0x79;
+pub const INSTRUCTION_ADC_INDR_X: Byte = 0x61;
+pub const INSTRUCTION_ADC_INDR_Y: Byte = 0x71;
+
+// SBC
+pub const INSTRUCTION_SBC_IMM: Byte = 0xE9;
+pub const INSTRUCTION_SBC_ZERO: Byte = 0xE5;
+pub const INSTRUCTION_SBC_ZERO_X: Byte = 0xF5;
+pub const INSTRUCTION_SBC_ABS: Byte = 0xED;
+pub const INSTRUCTION_SBC_ABS_X: Byte = 0xFD;
+pub const INSTRUCTION_SBC_ABS_Y: Byte = 0xF9;
+pub const INSTRUCTION_SBC_INDR_X: Byte = 0xE1;
+pub const INSTRUCTION_SBC_INDR_Y: Byte = 0xF1;
+
+// CMP
+pub const INSTRUCTION_CMP_IMM: Byte = 0xC9;
+pub const INSTRUCTION_CMP_ZERO: Byte = 0xC5;
+pub const INSTRUCTION_CMP_ZERO_X: Byte = 0xD5;
+pub const INSTRUCTION_CMP_ABS: Byte = 0xCD;
+pub const INSTRUCTION_CMP_ABS_X: Byte = 0xDD;
+pub const INSTRUCTION_CMP_ABS_Y: Byte = 0xD9;
+pub const INSTRUCTION_CMP_INDR_X: Byte = 0xC1;
+pub const INSTRUCTION_CMP_INDR_Y: Byte = 0xD1;
+
+// CPX
+pub const INSTRUCTION_CPX_IMM: Byte = 0xE0;
+pub const INSTRUCTION_CPX_ZERO: Byte = 0xE4;
+pub const INSTRUCTION_CPX_ABS: Byte = 0xEC;
+
+// CPY
+pub const INSTRUCTION_CPY_IMM: Byte = 0xC0;
+pub const INSTRUCTION_CPY_ZERO: Byte = 0xC4;
+pub const INSTRUCTION_CPY_ABS: Byte = 0xCC;
+
+//
+// Increments & Decrements
+//
+
+// INC
+pub const INSTRUCTION_INC_ZERO: Byte = 0xE6;
+pub const INSTRUCTION_INC_ZERO_X: Byte = 0xF6;
+pub const INSTRUCTION_INC_ABS: Byte = 0xEE;
+pub const INSTRUCTION_INC_ABS_X: Byte = 0xFE;
+
+pub const INSTRUCTION_INX: Byte = 0xE8;
+pub const INSTRUCTION_INY: Byte = 0xC8;
+
+// DEC
+pub const INSTRUCTION_DEC_ZERO: Byte = 0xC6;
+pub const INSTRUCTION_DEC_ZERO_X: Byte = 0xD6;
+pub const INSTRUCTION_DEC_ABS: Byte = 0xCE;
+pub const INSTRUCTION_DEC_ABS_X: Byte = 0xDE;
+
+pub const INSTRUCTION_DEX: Byte = 0xCA;
+pub const INSTRUCTION_DEY: Byte = 0x88;
+
+//
+// Shifts
+//
+
+// ASL
+pub const INSTRUCTION_ASL_ACC: Byte = 0x0A;
+pub const INSTRUCTION_ASL_ZERO: Byte = 0x06;
+pub const INSTRUCTION_ASL_ZERO_X: Byte = 0x16;
+pub const INSTRUCTION_ASL_ABS: Byte = 0x0E;
+pub const INSTRUCTION_ASL_ABS_X: Byte = 0x1E;
+
+// LSR
+pub const INSTRUCTION_LSR_ACC: Byte = 0x4A;
+pub const INSTRUCTION_LSR_ZERO: Byte = 0x46;
+pub const INSTRUCTION_LSR_ZERO_X: Byte = 0x56;
+pub const INSTRUCTION_LSR_ABS: Byte = 0x4E;
+pub const INSTRUCTION_LSR_ABS_X: Byte = 0x5E;
+
+// ROL
+pub const INSTRUCTION_ROL_ACC: Byte = 0x2A;
+pub const INSTRUCTION_ROL_ZERO: Byte = 0x26;
+pub const INSTRUCTION_ROL_ZERO_X: Byte = 0x36;
+pub const INSTRUCTION_ROL_ABS: Byte = 0x2E;
+pub const INSTRUCTION_ROL_ABS_X: Byte = 0x3E;
+
+// ROR
+pub const INSTRUCTION_ROR_ACC: Byte = 0x6A;
+pub const INSTRUCTION_ROR_ZERO: Byte = 0x66;
+pub const INSTRUCTION_ROR_ZERO_X: Byte = 0x76;
+pub const INSTRUCTION_ROR_ABS: Byte = 0x6E;
+pub const INSTRUCTION_ROR_ABS_X: Byte = 0x7E;
+
+//
+// Jumps & Calls
+//
+
+pub const INSTRUCTION_JMP_ABS: Byte = 0x4C;
+pub const INSTRUCTION_JMP_INDR: Byte = 0x6C;
 pub const INSTRUCTION_JSR: Byte = 0x20;
 pub const INSTRUCTION_RTS: Byte = 0x60;
+
+//
+// Branches
+//
+
+pub const INSTRUCTION_BCC: Byte = 0x90;
+pub const INSTRUCTION_BCS: Byte = 0xB0;
+pub const INSTRUCTION_BEQ: Byte = 0xF0;
+pub const INSTRUCTION_BMI: Byte = 0x30;
+pub const INSTRUCTION_BNE: Byte = 0xD0;
+pub const INSTRUCTION_BPL: Byte = 0x10;
+pub const INSTRUCTION_BVC: Byte = 0x50;
+pub const INSTRUCTION_BVS: Byte = 0x70;
+
+//
+// Status Flag Changes
+//
+
+pub const INSTRUCTION_CLC: Byte = 0x18;
+pub const INSTRUCTION_CLD: Byte = 0xD8;
+pub const INSTRUCTION_CLI: Byte = 0x58;
+pub const INSTRUCTION_CLV: Byte = 0xB8;
+pub const INSTRUCTION_SEC: Byte = 0x38;
+pub const INSTRUCTION_SED: Byte = 0xF8;
+pub const INSTRUCTION_SEI: Byte = 0x78;
+
+//
+// System Functions
+//
+
+pub const INSTRUCTION_BRK: Byte = 0x00;
+pub const INSTRUCTION_NOP: Byte = 0xEA;
+pub const INSTRUCTION_RTI: Byte = 0x40;
+
+//
+// 65C02
+//
+
+pub const INSTRUCTION_BRA: Byte = 0x80;
+pub const INSTRUCTION_PHX: Byte = 0xDA;
+pub const INSTRUCTION_PHY: Byte = 0x5A;
+pub const INSTRUCTION_PLX: Byte = 0xFA;
+pub const INSTRUCTION_PLY: Byte = 0x7A;
+pub const INSTRUCTION_INC_A: Byte = 0x1A;
+pub const INSTRUCTION_DEC_A: Byte = 0x3A;
+pub const INSTRUCTION_BIT_IMM: Byte = 0x89;
+pub const INSTRUCTION_BIT_ZERO_X: Byte = 0x34;
+pub const INSTRUCTION_BIT_ABS_X: Byte = 0x3C;
+pub const INSTRUCTION_JMP_INDR_X: Byte = 0x7C;
+pub const INSTRUCTION_WAI: Byte = 0xCB;
+pub const INSTRUCTION_STP: Byte = 0xDB;
+
+// STZ
+pub const INSTRUCTION_STZ_ZERO: Byte = 0x64;
+pub const INSTRUCTION_STZ_ZERO_X: Byte = 0x74;
+pub const INSTRUCTION_STZ_ABS: Byte = 0x9C;
+pub const INSTRUCTION_STZ_ABS_X: Byte = 0x9E;
+
+// TRB & TSB
+pub const INSTRUCTION_TRB_ZERO: Byte = 0x14;
+pub const INSTRUCTION_TRB_ABS: Byte = 0x1C;
+pub const INSTRUCTION_TSB_ZERO: Byte = 0x04;
+pub const INSTRUCTION_TSB_ABS: Byte = 0x0C;
+
+// (zp)
+pub const INSTRUCTION_ORA_INDR_ZERO: Byte = 0x12;
+pub const INSTRUCTION_AND_INDR_ZERO: Byte = 0x32;
+pub const INSTRUCTION_EOR_INDR_ZERO: Byte = 0x52;
+pub const INSTRUCTION_ADC_INDR_ZERO: Byte = 0x72;
+pub const INSTRUCTION_STA_INDR_ZERO: Byte = 0x92;
+pub const INSTRUCTION_LDA_INDR_ZERO: Byte = 0xB2;
+pub const INSTRUCTION_CMP_INDR_ZERO: Byte = 0xD2;
+pub const INSTRUCTION_SBC_INDR_ZERO: Byte = 0xF2;
+
+// RMB, SMB, BBR and BBS hold the bit number in bits 4 to 6
+pub const INSTRUCTION_RMB0: Byte = 0x07;
+pub const INSTRUCTION_SMB0: Byte = 0x87;
+pub const INSTRUCTION_BBR0: Byte = 0x0F;
+pub const INSTRUCTION_BBS0: Byte = 0x8F;
+
+//
+// Decoding
+//
+
+/// Decodes a documented NMOS opcode, returning `None` for undocumented
+/// ones. See `Variant::decode` for the other chips.
+pub fn decode(opcode: Byte) -> Option<Instruction> {
+    Variant::Nmos6502.decode(opcode)
+}
+
+//
+// Undocumented Opcodes
+//
+
+/// Returns true for the twelve NMOS opcodes that lock up the CPU.
+pub fn is_jam(opcode: Byte) -> bool {
+    opcode_info(opcode).mnemonic == Mnemonic::JAM
+}
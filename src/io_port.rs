@@ -0,0 +1,104 @@
+use crate::{Byte, Word};
+
+/// The data direction register of the 6510 port.
+pub const DIRECTION_ADDRESS: Word = 0x0000;
+/// The data register of the 6510 port.
+pub const DATA_ADDRESS: Word = 0x0001;
+
+/// How long a C64's 6510 keeps reading a 1 from an unconnected pin after
+/// it stops driving it, according to VICE.
+pub const C64_FADE_CYCLES: u64 = 350_000;
+
+/// The 8-bit I/O port built into the 6510, at $0000 (data direction, 1 for
+/// output) and $0001 (data).
+///
+/// Pins set as inputs read whatever outside hardware drives onto them.
+/// Pins nothing drives keep the charge of the last level they were driven
+/// to, so a 1 written to them reads back as 1 for `fade_cycles` after the
+/// pin stops being an output and then fades to 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IoPort {
+    pub direction: Byte,
+    pub data: Byte,
+    /// The pins outside hardware drives when they are inputs.
+    pub driven_inputs: Byte,
+    /// The levels outside hardware drives onto `driven_inputs`, such as
+    /// pull-up resistors.
+    pub input_levels: Byte,
+    pub fade_cycles: u64,
+    /// The last level each undriven pin was driven to.
+    pub(crate) charge: Byte,
+    /// The cycle at which each charged pin fades to 0.
+    pub(crate) fade_at: [u64; 8],
+}
+
+impl IoPort {
+    /// A port wired like the C64's: the six pins of the 6510 package are
+    /// pulled up, except for the cassette motor and write lines (bits 5 and
+    /// 3), and bits 6 and 7 are not connected at all.
+    pub fn c64() -> Self {
+        Self {
+            direction: 0x00,
+            data: 0x00,
+            driven_inputs: 0b0011_1111,
+            input_levels: 0b0001_0111,
+            fade_cycles: C64_FADE_CYCLES,
+            charge: 0x00,
+            fade_at: [0; 8],
+        }
+    }
+
+    pub fn is_port_address(address: Word) -> bool {
+        address <= DATA_ADDRESS
+    }
+
+    /// The level of every pin: the data register where the pin is an
+    /// output and the outside hardware or the fading charge elsewhere.
+    pub fn pins(&self, cycle: u64) -> Byte {
+        let floating: Byte = (0..8)
+            .filter(|bit| cycle < self.fade_at[*bit])
+            .fold(0, |bits, bit| bits | (1 << bit))
+            & self.charge;
+        let inputs: Byte =
+            (self.input_levels & self.driven_inputs) | (floating & !self.driven_inputs);
+        (self.data & self.direction) | (inputs & !self.direction)
+    }
+
+    pub fn read(&self, address: Word, cycle: u64) -> Byte {
+        if address == DIRECTION_ADDRESS {
+            self.direction
+        } else {
+            self.pins(cycle)
+        }
+    }
+
+    pub fn write(&mut self, address: Word, data: Byte, cycle: u64) {
+        // Outputs charge their pins right up until the write changes them
+        self.charge_outputs(cycle);
+        if address == DIRECTION_ADDRESS {
+            self.direction = data;
+        } else {
+            self.data = data;
+        }
+        self.charge_outputs(cycle);
+    }
+
+    /// Called on reset, which turns every pin into an input.
+    pub fn reset(&mut self, cycle: u64) {
+        self.write(DIRECTION_ADDRESS, 0x00, cycle);
+    }
+
+    fn charge_outputs(&mut self, cycle: u64) {
+        let charging: Byte = self.direction & !self.driven_inputs;
+        self.charge = (self.charge & !charging) | (self.data & charging);
+        for bit in (0..8).filter(|bit| charging & (1 << bit) != 0) {
+            self.fade_at[bit] = cycle + self.fade_cycles;
+        }
+    }
+}
+
+impl Default for IoPort {
+    fn default() -> Self {
+        IoPort::c64()
+    }
+}
@@ -1,388 +1,1311 @@
 use bitfield::bitfield;
+use instructions::{AddressingMode, Mnemonic, OpcodeInfo};
 
+pub mod bus;
+pub mod debugger;
+pub mod disassembler;
+pub mod error;
+pub mod gdb;
 pub mod instructions;
+pub mod io_port;
+pub mod loader;
+pub mod monitor;
+pub mod rewind;
+pub mod snapshot;
+pub mod step;
+pub mod trace;
+pub mod undocumented;
+pub mod variant;
+pub mod wdc65816;
+mod wdc65c02;
+
+pub use bus::{Bus, LongBus};
+pub use debugger::Debugger;
+pub use disassembler::{disassemble, Disassembly};
+pub use error::{ExecutionError, LoadError, SnapshotError, TraceError, UnknownOpcodePolicy};
+pub use io_port::IoPort;
+pub use step::{Interrupt, Step};
+pub use trace::Tracer;
+pub use undocumented::UndocumentedOpcodes;
+pub use variant::Variant;
+pub use wdc65816::CPU65816;
 
 pub type Byte = u8;
 pub type Word = u16;
+/// A 65816 address: the bank in bits 16 to 23, then the address within
+/// the bank.
+pub type LongAddress = u32;
 
 const MAX_MEM: u32 = 1024 * 64;
+const MAX_LONG_MEM: u32 = 1024 * 1024 * 16;
+
+const STACK_PAGE: Word = 0x0100;
+const NMI_VECTOR: Word = 0xFFFA;
+const RESET_VECTOR: Word = 0xFFFC;
+const IRQ_VECTOR: Word = 0xFFFE;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub struct CPU {
+    /// The chip being emulated. `CPU::new` and `CPU::reset` give an NMOS
+    /// 6502; use `CPU::with_variant` for the others.
+    pub variant: Variant,
     pub processor_status: ProcessorStatus,
     pub program_counter: Word,
     pub stack_pointer: Byte,
     pub accumulator: Byte,
     pub register_x: Byte,
     pub register_y: Byte,
+    pub unknown_opcode_policy: UnknownOpcodePolicy,
+    /// Executes the undocumented NMOS opcodes instead of treating them as
+    /// unknown. Off by default. The 65C02 always runs its undocumented
+    /// opcodes as NOPs.
+    pub undocumented_opcodes: Option<UndocumentedOpcodes>,
+    /// Cycles used by `step` since the CPU was created.
+    pub cycles: u64,
+    /// The on-chip port of the 6510. The other variants ignore it.
+    pub io_port: IoPort,
+    irq_line: bool,
+    nmi_line: bool,
+    nmi_pending: bool,
+    reset_pending: bool,
+    delayed_interrupt_flag: Option<bool>,
+    /// Set by WAI until an interrupt arrives.
+    waiting: bool,
+    /// Set by STP until the next reset.
+    stopped: bool,
+    step_state: StepState,
+}
+
+/// What the current instruction has decoded so far, for `CPU::step`.
+#[derive(Clone, Copy, Debug, Default)]
+struct StepState {
+    operand: Option<Word>,
+    effective_address: Option<Word>,
+    page_crossed: bool,
 }
 
 impl CPU {
-    pub fn execute(&mut self, mut cycles: i32, memory: &mut Memory) -> i32 {
-        let cycles_requested: i32 = cycles.clone();
+    /// Runs instructions until at least `cycles` cycles have been used and
+    /// returns the number of cycles actually used.
+    ///
+    /// Panics if an unknown opcode halts the CPU; use `try_execute` to
+    /// handle that case instead.
+    pub fn execute<B: Bus>(&mut self, cycles: i32, bus: &mut B) -> i32 {
+        match self.try_execute(cycles, bus) {
+            Ok(cycles_used) => cycles_used,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    pub fn try_execute<B: Bus>(
+        &mut self,
+        mut cycles: i32,
+        bus: &mut B,
+    ) -> Result<i32, ExecutionError> {
+        let cycles_requested: i32 = cycles;
         while cycles > 0 {
-            let instruction: Byte = self.fetch_byte(&mut cycles, &memory);
-            match instruction {
-                //
-                // Load Registers
-                //
-
-                // LDA
-                instructions::INSTRUCTION_LDA_IMM => {
-                    self.accumulator = self.fetch_byte(&mut cycles, &memory);
-                    self.load_register_set_status(&RegisterType::Accumulator);
-                }
-                instructions::INSTRUCTION_LDA_ZERO => {
-                    let address: Word = self.get_zero_page_addr(&mut cycles, &memory);
-                    self.load_register(&mut cycles, address, &RegisterType::Accumulator, &memory);
-                }
-                instructions::INSTRUCTION_LDA_ZERO_X => {
-                    let address: Word = self.get_zero_page_addr_x(&mut cycles, &memory);
-                    self.load_register(&mut cycles, address, &RegisterType::Accumulator, &memory);
-                }
-                instructions::INSTRUCTION_LDA_ABS => {
-                    let address: Word = self.get_absolute_addr(&mut cycles, &memory);
-                    self.load_register(&mut cycles, address, &RegisterType::Accumulator, &memory);
-                }
-                instructions::INSTRUCTION_LDA_ABS_X => {
-                    let address: Word = self.get_absolute_addr_x(&mut cycles, &memory);
-                    self.load_register(&mut cycles, address, &RegisterType::Accumulator, &memory);
-                }
-                instructions::INSTRUCTION_LDA_ABS_Y => {
-                    let address: Word = self.get_absolute_addr_y(&mut cycles, &memory);
-                    self.load_register(&mut cycles, address, &RegisterType::Accumulator, &memory);
-                }
-                instructions::INSTRUCTION_LDA_INDR_X => {
-                    let address = self.get_indr_addr_x(&mut cycles, &memory);
-                    self.load_register(&mut cycles, address, &RegisterType::Accumulator, &memory);
-                }
-                instructions::INSTRUCTION_LDA_INDR_Y => {
-                    let address = self.get_indr_addr_y(&mut cycles, &memory);
-                    self.load_register(&mut cycles, address, &RegisterType::Accumulator, &memory);
-                }
+            cycles -= self.step(bus)?.cycles;
+        }
+        Ok(cycles_requested - cycles)
+    }
 
-                // LDX
-                instructions::INSTRUCTION_LDX_IMM => {
-                    self.register_x = self.fetch_byte(&mut cycles, &memory);
-                    self.load_register_set_status(&RegisterType::RegisterX);
-                }
-                instructions::INSTRUCTION_LDX_ZERO => {
-                    let address: Word = self.get_zero_page_addr(&mut cycles, &memory);
-                    self.load_register(&mut cycles, address, &RegisterType::RegisterX, &memory);
-                }
-                instructions::INSTRUCTION_LDX_ZERO_Y => {
-                    let address: Word = self.get_zero_page_addr_y(&mut cycles, &memory);
-                    self.load_register(&mut cycles, address, &RegisterType::RegisterX, &memory);
-                }
-                instructions::INSTRUCTION_LDX_ABS => {
-                    let address: Word = self.get_absolute_addr(&mut cycles, &memory);
-                    self.load_register(&mut cycles, address, &RegisterType::RegisterX, &memory);
-                }
-                instructions::INSTRUCTION_LDX_ABS_Y => {
-                    let address: Word = self.get_absolute_addr_y(&mut cycles, &memory);
-                    self.load_register(&mut cycles, address, &RegisterType::RegisterX, &memory);
-                }
+    /// Executes exactly one instruction, or runs one pending interrupt
+    /// sequence, and returns a record of what it did.
+    pub fn step<B: Bus>(&mut self, bus: &mut B) -> Result<Step, ExecutionError> {
+        // The helpers count cycles down, so this ends up negative
+        let mut cycles: i32 = 0;
+        let address: Word = self.program_counter;
+        if let Some(interrupt) = self.poll_interrupts(&mut cycles, bus) {
+            self.cycles += -cycles as u64;
+            return Ok(Step {
+                address,
+                opcode: instructions::INSTRUCTION_BRK,
+                instruction: instructions::decode(instructions::INSTRUCTION_BRK),
+                operand: None,
+                effective_address: None,
+                cycles: -cycles,
+                page_crossed: false,
+                interrupt: Some(interrupt),
+            });
+        }
+        if self.waiting || self.stopped {
+            // The halted CPU keeps reading the byte after WAI or STP
+            self.dummy_read_program_counter(&mut cycles, bus);
+            self.cycles += 1;
+            let opcode: Byte = if self.stopped {
+                instructions::INSTRUCTION_STP
+            } else {
+                instructions::INSTRUCTION_WAI
+            };
+            return Ok(Step {
+                address,
+                opcode,
+                instruction: self.variant.decode(opcode),
+                operand: None,
+                effective_address: None,
+                cycles: 1,
+                page_crossed: false,
+                interrupt: None,
+            });
+        }
+        let opcode: Byte = self.fetch_byte(&mut cycles, bus);
+        self.step_state = StepState::default();
+        self.execute_instruction(&mut cycles, opcode, bus)?;
+        self.cycles += -cycles as u64;
+        Ok(Step {
+            address,
+            opcode,
+            instruction: self.variant.decode(opcode),
+            operand: self.step_state.operand,
+            effective_address: self.step_state.effective_address,
+            cycles: -cycles,
+            page_crossed: self.step_state.page_crossed,
+            interrupt: None,
+        })
+    }
 
-                // LDY
-                instructions::INSTRUCTION_LDY_IMM => {
-                    self.register_y = self.fetch_byte(&mut cycles, &memory);
-                    self.load_register_set_status(&RegisterType::RegisterY);
-                }
-                instructions::INSTRUCTION_LDY_ZERO => {
-                    let address: Word = self.get_zero_page_addr(&mut cycles, &memory);
-                    self.load_register(&mut cycles, address, &RegisterType::RegisterY, &memory);
-                }
-                instructions::INSTRUCTION_LDY_ZERO_X => {
-                    let address: Word = self.get_zero_page_addr_x(&mut cycles, &memory);
-                    self.load_register(&mut cycles, address, &RegisterType::RegisterY, &memory);
-                }
-                instructions::INSTRUCTION_LDY_ABS => {
-                    let address: Word = self.get_absolute_addr(&mut cycles, &memory);
-                    self.load_register(&mut cycles, address, &RegisterType::RegisterY, &memory);
-                }
-                instructions::INSTRUCTION_LDY_ABS_X => {
-                    let address: Word = self.get_absolute_addr_x(&mut cycles, &memory);
-                    self.load_register(&mut cycles, address, &RegisterType::RegisterY, &memory);
+    fn execute_instruction<B: Bus>(
+        &mut self,
+        cycles: &mut i32,
+        opcode: Byte,
+        bus: &mut B,
+    ) -> Result<(), ExecutionError> {
+        let info: &OpcodeInfo = self.variant.opcode_info(opcode);
+        if !info.documented && self.variant.is_cmos() {
+            self.execute_reserved_nop(cycles, info, bus);
+            return Ok(());
+        }
+        if !info.documented {
+            return match self.undocumented_opcodes {
+                Some(settings) if info.mnemonic != Mnemonic::JAM => {
+                    self.execute_undocumented(cycles, info, settings, bus);
+                    Ok(())
                 }
+                _ => self.handle_unknown_opcode(cycles, opcode, bus),
+            };
+        }
+        let interrupt_flag: bool = self.processor_status.interrupt();
+        match info.mnemonic {
+            //
+            // Load/Store Operations
+            //
+            Mnemonic::LDA => {
+                self.load_register(cycles, info, &RegisterType::Accumulator, bus);
+            }
+            Mnemonic::LDX => {
+                self.load_register(cycles, info, &RegisterType::RegisterX, bus);
+            }
+            Mnemonic::LDY => {
+                self.load_register(cycles, info, &RegisterType::RegisterY, bus);
+            }
+            Mnemonic::STA => {
+                let address: Word = self.operand_address(cycles, info, bus);
+                self.write_byte(cycles, address, self.accumulator, bus);
+            }
+            Mnemonic::STX => {
+                let address: Word = self.operand_address(cycles, info, bus);
+                self.write_byte(cycles, address, self.register_x, bus);
+            }
+            Mnemonic::STY => {
+                let address: Word = self.operand_address(cycles, info, bus);
+                self.write_byte(cycles, address, self.register_y, bus);
+            }
 
-                //
-                // Store Registers
-                //
+            //
+            // Register Transfers
+            //
+            Mnemonic::TAX => {
+                self.transfer_register(
+                    cycles,
+                    &RegisterType::Accumulator,
+                    &RegisterType::RegisterX,
+                    bus,
+                );
+            }
+            Mnemonic::TAY => {
+                self.transfer_register(
+                    cycles,
+                    &RegisterType::Accumulator,
+                    &RegisterType::RegisterY,
+                    bus,
+                );
+            }
+            Mnemonic::TXA => {
+                self.transfer_register(
+                    cycles,
+                    &RegisterType::RegisterX,
+                    &RegisterType::Accumulator,
+                    bus,
+                );
+            }
+            Mnemonic::TYA => {
+                self.transfer_register(
+                    cycles,
+                    &RegisterType::RegisterY,
+                    &RegisterType::Accumulator,
+                    bus,
+                );
+            }
 
-                // STA
-                instructions::INSTRUCTION_STA_ZERO => {
-                    let address: Word = self.get_zero_page_addr(&mut cycles, &memory);
-                    self.write_byte(&mut cycles, address, self.accumulator, memory);
-                }
-                instructions::INSTRUCTION_STA_ZERO_X => {
-                    let address: Word = self.get_zero_page_addr_x(&mut cycles, &memory);
-                    self.write_byte(&mut cycles, address, self.accumulator, memory);
-                }
-                instructions::INSTRUCTION_STA_ABS => {
-                    let address: Word = self.get_absolute_addr(&mut cycles, &memory);
-                    self.write_byte(&mut cycles, address, self.accumulator, memory);
-                }
-                instructions::INSTRUCTION_STA_ABS_X => {
-                    let address: Word = self.get_absolute_addr_x_5(&mut cycles, &memory);
-                    self.write_byte(&mut cycles, address, self.accumulator, memory);
-                }
-                instructions::INSTRUCTION_STA_ABS_Y => {
-                    let address: Word = self.get_absolute_addr_y_5(&mut cycles, &memory);
-                    self.write_byte(&mut cycles, address, self.accumulator, memory);
-                }
-                instructions::INSTRUCTION_STA_INDR_X => {
-                    let address = self.get_indr_addr_x(&mut cycles, &memory);
-                    self.write_byte(&mut cycles, address, self.accumulator, memory);
-                }
-                instructions::INSTRUCTION_STA_INDR_Y => {
-                    let address = self.get_indr_addr_y_6(&mut cycles, &memory);
-                    self.write_byte(&mut cycles, address, self.accumulator, memory);
-                }
+            //
+            // Stack Operations
+            //
+            Mnemonic::TSX => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.register_x = self.stack_pointer;
+                self.load_register_set_status(&RegisterType::RegisterX);
+            }
+            Mnemonic::TXS => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.stack_pointer = self.register_x;
+            }
+            Mnemonic::PHA => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.push_byte_to_stack(cycles, self.accumulator, bus);
+            }
+            Mnemonic::PHP => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.push_processor_status_to_stack(cycles, true, bus);
+            }
+            Mnemonic::PLA => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.dummy_read_stack(cycles, bus);
+                self.accumulator = self.pop_byte_from_stack(cycles, bus);
+                self.load_register_set_status(&RegisterType::Accumulator);
+            }
+            Mnemonic::PLP => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.dummy_read_stack(cycles, bus);
+                self.pop_processor_status_from_stack(cycles, bus);
+                self.delayed_interrupt_flag = Some(interrupt_flag);
+            }
 
-                // STX
-                instructions::INSTRUCTION_STX_ZERO => {
-                    let address: Word = self.get_zero_page_addr(&mut cycles, &memory);
-                    self.write_byte(&mut cycles, address, self.register_x, memory);
-                }
-                instructions::INSTRUCTION_STX_ABS => {
-                    let address: Word = self.get_absolute_addr(&mut cycles, &memory);
-                    self.write_byte(&mut cycles, address, self.register_x, memory);
-                }
-                instructions::INSTRUCTION_STX_ZERO_Y => {
-                    let address: Word = self.get_zero_page_addr_y(&mut cycles, &memory);
-                    self.write_byte(&mut cycles, address, self.register_x, memory);
-                }
+            //
+            // Logical
+            //
+            Mnemonic::AND => {
+                let operand: Byte = self.read_operand(cycles, info, bus);
+                self.logical_and(operand);
+            }
+            Mnemonic::EOR => {
+                let operand: Byte = self.read_operand(cycles, info, bus);
+                self.exclusive_or(operand);
+            }
+            Mnemonic::ORA => {
+                let operand: Byte = self.read_operand(cycles, info, bus);
+                self.inclusive_or(operand);
+            }
+            Mnemonic::BIT if info.addressing_mode == AddressingMode::Immediate => {
+                let operand: Byte = self.read_operand(cycles, info, bus);
+                self.processor_status
+                    .set_zero(self.accumulator & operand == 0x00);
+            }
+            Mnemonic::BIT => {
+                let operand: Byte = self.read_operand(cycles, info, bus);
+                self.bit_test(operand);
+            }
 
-                // STY
-                instructions::INSTRUCTION_STY_ZERO => {
-                    let address: Word = self.get_zero_page_addr(&mut cycles, &memory);
-                    self.write_byte(&mut cycles, address, self.register_y, memory);
-                }
-                instructions::INSTRUCTION_STY_ABS => {
-                    let address: Word = self.get_absolute_addr(&mut cycles, &memory);
-                    self.write_byte(&mut cycles, address, self.register_y, memory);
-                }
-                instructions::INSTRUCTION_STY_ZERO_X => {
-                    let address: Word = self.get_zero_page_addr_x(&mut cycles, &memory);
-                    self.write_byte(&mut cycles, address, self.register_y, memory);
-                }
+            //
+            // Arithmetic
+            //
+            Mnemonic::ADC => {
+                let operand: Byte = self.read_operand(cycles, info, bus);
+                self.add_with_carry(operand);
+                self.decimal_adjust_cycle(cycles, bus);
+            }
+            Mnemonic::SBC => {
+                let operand: Byte = self.read_operand(cycles, info, bus);
+                self.subtract_with_carry(operand);
+                self.decimal_adjust_cycle(cycles, bus);
+            }
+            Mnemonic::CMP => {
+                let operand: Byte = self.read_operand(cycles, info, bus);
+                self.compare(&RegisterType::Accumulator, operand);
+            }
+            Mnemonic::CPX => {
+                let operand: Byte = self.read_operand(cycles, info, bus);
+                self.compare(&RegisterType::RegisterX, operand);
+            }
+            Mnemonic::CPY => {
+                let operand: Byte = self.read_operand(cycles, info, bus);
+                self.compare(&RegisterType::RegisterY, operand);
+            }
 
-                //
-                instructions::INSTRUCTION_JSR => {
-                    let subroutine_addr: Word = self.fetch_word(&mut cycles, &memory);
-                    self.push_program_counter_to_stack(&mut cycles, memory);
-                    self.program_counter = subroutine_addr;
-                    cycles -= 1;
-                }
-                instructions::INSTRUCTION_RTS => {
-                    let return_addr: Word =
-                        self.read_word(&mut cycles, self.stack_pointer_as_word(), memory);
-                    self.pop_word_from_stack(&mut cycles, &memory);
-                    self.program_counter = return_addr + 1;
-                    cycles -= 2;
-                }
-                _ => {
-                    panic!(
-                        "Unknown instruction: {:#010x} at address {:#010x}",
-                        instruction,
-                        self.program_counter.wrapping_sub(1)
-                    );
+            //
+            // Increments & Decrements
+            //
+            Mnemonic::INC => self.modify_operand(cycles, info, CPU::increment, bus),
+            Mnemonic::INX => {
+                self.modify_register(cycles, &RegisterType::RegisterX, CPU::increment, bus);
+            }
+            Mnemonic::INY => {
+                self.modify_register(cycles, &RegisterType::RegisterY, CPU::increment, bus);
+            }
+            Mnemonic::DEC => self.modify_operand(cycles, info, CPU::decrement, bus),
+            Mnemonic::DEX => {
+                self.modify_register(cycles, &RegisterType::RegisterX, CPU::decrement, bus);
+            }
+            Mnemonic::DEY => {
+                self.modify_register(cycles, &RegisterType::RegisterY, CPU::decrement, bus);
+            }
+
+            //
+            // Shifts
+            //
+            Mnemonic::ASL => self.modify_operand(cycles, info, CPU::shift_left, bus),
+            Mnemonic::LSR => self.modify_operand(cycles, info, CPU::shift_right, bus),
+            Mnemonic::ROL => self.modify_operand(cycles, info, CPU::rotate_left, bus),
+            Mnemonic::ROR => self.modify_operand(cycles, info, CPU::rotate_right, bus),
+
+            //
+            // Jumps & Calls
+            //
+            Mnemonic::JMP => {
+                self.program_counter = self.operand_address(cycles, info, bus);
+            }
+            Mnemonic::JSR => {
+                // The high byte of the target is only fetched after the
+                // return address (which points at it) has been pushed
+                let low: Byte = self.fetch_byte(cycles, bus);
+                self.dummy_read_stack(cycles, bus);
+                self.push_word_to_stack(cycles, self.program_counter, bus);
+                let high: Byte = self.fetch_byte(cycles, bus);
+                let subroutine_addr: Word = low as Word | ((high as Word) << 8);
+                self.step_state.operand = Some(subroutine_addr);
+                self.program_counter = self.record_effective_address(subroutine_addr);
+            }
+            Mnemonic::RTS => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.dummy_read_stack(cycles, bus);
+                self.program_counter = self.pop_word_from_stack(cycles, bus);
+                self.dummy_read_program_counter(cycles, bus);
+                self.program_counter = self.program_counter.wrapping_add(1);
+            }
+
+            //
+            // Branches
+            //
+            Mnemonic::BCC => {
+                let condition: bool = !self.processor_status.carry();
+                self.branch_if(cycles, condition, bus);
+            }
+            Mnemonic::BCS => {
+                let condition: bool = self.processor_status.carry();
+                self.branch_if(cycles, condition, bus);
+            }
+            Mnemonic::BEQ => {
+                let condition: bool = self.processor_status.zero();
+                self.branch_if(cycles, condition, bus);
+            }
+            Mnemonic::BMI => {
+                let condition: bool = self.processor_status.negative();
+                self.branch_if(cycles, condition, bus);
+            }
+            Mnemonic::BNE => {
+                let condition: bool = !self.processor_status.zero();
+                self.branch_if(cycles, condition, bus);
+            }
+            Mnemonic::BPL => {
+                let condition: bool = !self.processor_status.negative();
+                self.branch_if(cycles, condition, bus);
+            }
+            Mnemonic::BVC => {
+                let condition: bool = !self.processor_status.overflow();
+                self.branch_if(cycles, condition, bus);
+            }
+            Mnemonic::BVS => {
+                let condition: bool = self.processor_status.overflow();
+                self.branch_if(cycles, condition, bus);
+            }
+
+            //
+            // Status Flag Changes
+            //
+            Mnemonic::CLC => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.processor_status.set_carry(false);
+            }
+            Mnemonic::CLD => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.processor_status.set_decimal(false);
+            }
+            Mnemonic::CLI => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.processor_status.set_interrupt(false);
+                self.delayed_interrupt_flag = Some(interrupt_flag);
+            }
+            Mnemonic::CLV => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.processor_status.set_overflow(false);
+            }
+            Mnemonic::SEC => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.processor_status.set_carry(true);
+            }
+            Mnemonic::SED => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.processor_status.set_decimal(true);
+            }
+            Mnemonic::SEI => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.processor_status.set_interrupt(true);
+                self.delayed_interrupt_flag = Some(interrupt_flag);
+            }
+
+            //
+            // System Functions
+            //
+            Mnemonic::BRK => {
+                // BRK is followed by a padding byte which the return address skips over
+                self.fetch_byte(cycles, bus);
+                self.push_word_to_stack(cycles, self.program_counter, bus);
+                self.push_processor_status_to_stack(cycles, true, bus);
+                self.processor_status.set_interrupt(true);
+                if self.variant.is_cmos() {
+                    self.processor_status.set_decimal(false);
                 }
+                self.program_counter = self.read_word(cycles, IRQ_VECTOR, bus);
+            }
+            Mnemonic::NOP => {
+                self.dummy_read_program_counter(cycles, bus);
+            }
+            Mnemonic::RTI => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.dummy_read_stack(cycles, bus);
+                self.pop_processor_status_from_stack(cycles, bus);
+                self.program_counter = self.pop_word_from_stack(cycles, bus);
+            }
+
+            //
+            // 65C02
+            //
+            Mnemonic::BRA
+            | Mnemonic::PHX
+            | Mnemonic::PHY
+            | Mnemonic::PLX
+            | Mnemonic::PLY
+            | Mnemonic::STZ
+            | Mnemonic::TRB
+            | Mnemonic::TSB
+            | Mnemonic::RMB
+            | Mnemonic::SMB
+            | Mnemonic::BBR
+            | Mnemonic::BBS
+            | Mnemonic::WAI
+            | Mnemonic::STP => self.execute_65c02(cycles, info, bus),
+            _ => {
+                self.handle_unknown_opcode(cycles, opcode, bus)?;
             }
         }
-        cycles_requested - cycles
+        Ok(())
     }
 
-    pub fn reset() -> Self {
+    /// Creates a CPU as it is at power on: the first call to `execute` runs
+    /// the RESET sequence, which loads the program counter from $FFFC.
+    pub fn new() -> Self {
+        let mut cpu: CPU = CPU::at_reset_vector_unsequenced();
+        cpu.trigger_reset();
+        cpu
+    }
+
+    /// Like `CPU::new`, but emulating the given chip.
+    pub fn with_variant(variant: Variant) -> Self {
         Self {
+            variant,
+            ..CPU::new()
+        }
+    }
+
+    /// Creates a CPU that starts executing directly at $FFFC, without going
+    /// through the RESET sequence, so the vector bytes themselves run as
+    /// code. Useful in tests that place an instruction at $FFFC; use
+    /// `CPU::new` to start from the reset vector.
+    pub fn at_reset_vector_unsequenced() -> Self {
+        Self {
+            variant: Variant::Nmos6502,
             program_counter: 0xFFFC,
             stack_pointer: 0xFF,
             accumulator: 0x00,
             register_x: 0x00,
             register_y: 0x00,
             processor_status: ProcessorStatus(0x00000000),
+            unknown_opcode_policy: UnknownOpcodePolicy::Halt,
+            undocumented_opcodes: None,
+            cycles: 0,
+            io_port: IoPort::default(),
+            irq_line: false,
+            nmi_line: false,
+            nmi_pending: false,
+            reset_pending: false,
+            delayed_interrupt_flag: None,
+            waiting: false,
+            stopped: false,
+            step_state: StepState::default(),
+        }
+    }
+
+    #[deprecated(note = "runs the reset vector bytes as code; use `CPU::new`, or \
+                `CPU::at_reset_vector_unsequenced` for the old behaviour")]
+    pub fn reset() -> Self {
+        CPU::at_reset_vector_unsequenced()
+    }
+
+    fn handle_unknown_opcode<B: Bus>(
+        &mut self,
+        cycles: &mut i32,
+        opcode: Byte,
+        bus: &mut B,
+    ) -> Result<(), ExecutionError> {
+        let address: Word = self.program_counter.wrapping_sub(1);
+        let jam: bool = instructions::is_jam(opcode);
+        if matches!(self.unknown_opcode_policy, UnknownOpcodePolicy::Nop) && !jam {
+            let info: &OpcodeInfo = self.variant.opcode_info(opcode);
+            match info.addressing_mode {
+                AddressingMode::Implied | AddressingMode::Accumulator => {
+                    self.dummy_read_program_counter(cycles, bus);
+                }
+                _ => {
+                    self.read_operand(cycles, info, bus);
+                }
+            }
+            return Ok(());
+        }
+        let error: ExecutionError = if jam {
+            ExecutionError::Jam {
+                opcode,
+                address,
+                cpu: Box::new(*self),
+            }
+        } else {
+            ExecutionError::IllegalOpcode {
+                opcode,
+                address,
+                cpu: Box::new(*self),
+            }
+        };
+        if let UnknownOpcodePolicy::Handler(handler) = self.unknown_opcode_policy {
+            if let Some(handler_cycles) = handler(self, &error) {
+                *cycles -= handler_cycles;
+                return Ok(());
+            }
+        }
+        self.program_counter = address;
+        Err(error)
+    }
+
+    /// Drives the level-triggered IRQ input. An IRQ is taken before every
+    /// instruction for as long as the line stays asserted and the interrupt
+    /// disable flag is clear.
+    pub fn set_irq(&mut self, asserted: bool) {
+        self.irq_line = asserted;
+    }
+
+    /// Drives the edge-triggered NMI input. Only a transition from released
+    /// to asserted latches an NMI, which is taken regardless of the
+    /// interrupt disable flag.
+    pub fn set_nmi(&mut self, asserted: bool) {
+        if asserted && !self.nmi_line {
+            self.nmi_pending = true;
         }
+        self.nmi_line = asserted;
+    }
+
+    /// Pulses the RESET input. The RESET sequence runs before the next
+    /// instruction and takes priority over any pending interrupt.
+    pub fn trigger_reset(&mut self) {
+        self.reset_pending = true;
+    }
+
+    /// Whether a WAI instruction is waiting for an interrupt.
+    pub fn is_waiting(&self) -> bool {
+        self.waiting
+    }
+
+    /// Whether an STP instruction has stopped the clock until the next
+    /// reset.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Returns the byte the CPU would read at `address`, including the 6510
+    /// port registers, without side effects on the bus.
+    pub fn peek<B: Bus>(&self, address: Word, bus: &mut B) -> Byte {
+        if self.variant.has_io_port() && IoPort::is_port_address(address) {
+            self.io_port.read(address, self.cycles)
+        } else {
+            bus.peek(address)
+        }
+    }
+
+    /// Interrupts are polled between instructions. CLI, SEI and PLP change
+    /// the interrupt disable flag too late for the following poll, so the
+    /// flag from before those instructions is used instead.
+    fn poll_interrupts<B: Bus>(&mut self, cycles: &mut i32, bus: &mut B) -> Option<Interrupt> {
+        let interrupt_disabled: bool = self
+            .delayed_interrupt_flag
+            .take()
+            .unwrap_or(self.processor_status.interrupt());
+        if self.reset_pending {
+            self.reset_pending = false;
+            self.nmi_pending = false;
+            self.waiting = false;
+            self.stopped = false;
+            self.service_reset(cycles, bus);
+            Some(Interrupt::Reset)
+        } else if self.stopped {
+            None
+        } else if self.nmi_pending {
+            self.nmi_pending = false;
+            self.waiting = false;
+            self.service_interrupt(cycles, NMI_VECTOR, bus);
+            Some(Interrupt::Nmi)
+        } else if self.irq_line && !interrupt_disabled {
+            self.waiting = false;
+            self.service_interrupt(cycles, IRQ_VECTOR, bus);
+            Some(Interrupt::Irq)
+        } else {
+            // An IRQ ends WAI even when it is masked, and execution just
+            // carries on after the WAI
+            self.waiting &= !self.irq_line;
+            None
+        }
+    }
+
+    /// Reset runs the interrupt sequence with the bus held in read mode, so
+    /// the three stack "pushes" become reads.
+    fn service_reset<B: Bus>(&mut self, cycles: &mut i32, bus: &mut B) {
+        self.dummy_read_program_counter(cycles, bus);
+        self.dummy_read_program_counter(cycles, bus);
+        for _ in 0..3 {
+            self.dummy_read_stack(cycles, bus);
+            self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+        }
+        self.processor_status.set_interrupt(true);
+        if self.variant.is_cmos() {
+            self.processor_status.set_decimal(false);
+        }
+        if self.variant.has_io_port() {
+            self.io_port.reset(self.current_cycle(cycles));
+            bus.io_port_changed(self.io_port.pins(self.current_cycle(cycles)));
+        }
+        self.program_counter = self.read_word(cycles, RESET_VECTOR, bus);
+    }
+
+    fn service_interrupt<B: Bus>(&mut self, cycles: &mut i32, vector: Word, bus: &mut B) {
+        self.dummy_read_program_counter(cycles, bus);
+        self.dummy_read_program_counter(cycles, bus);
+        self.push_word_to_stack(cycles, self.program_counter, bus);
+        self.push_processor_status_to_stack(cycles, false, bus);
+        self.processor_status.set_interrupt(true);
+        if self.variant.is_cmos() {
+            self.processor_status.set_decimal(false);
+        }
+        self.program_counter = self.read_word(cycles, vector, bus);
     }
 
     fn stack_pointer_as_word(&self) -> Word {
-        self.stack_pointer as Word | 0x100
+        self.stack_pointer as Word | STACK_PAGE
     }
 
-    fn push_program_counter_to_stack(&mut self, cycles: &mut i32, memory: &mut Memory) {
-        self.write_word(
-            cycles,
-            self.stack_pointer_as_word() - 1,
-            self.program_counter.wrapping_sub(1),
-            memory,
-        );
-        self.stack_pointer -= 2;
+    fn push_byte_to_stack<B: Bus>(&mut self, cycles: &mut i32, data: Byte, bus: &mut B) {
+        self.write_byte(cycles, self.stack_pointer_as_word(), data, bus);
+        self.stack_pointer = self.stack_pointer.wrapping_sub(1);
     }
 
-    fn pop_word_from_stack(&mut self, cycles: &mut i32, memory: &Memory) -> Word {
-        let value: Word = self.read_word(cycles, self.stack_pointer_as_word() + 1, &memory);
-        self.stack_pointer += 2;
-        *cycles -= 1;
-        value
+    fn pop_byte_from_stack<B: Bus>(&mut self, cycles: &mut i32, bus: &mut B) -> Byte {
+        self.stack_pointer = self.stack_pointer.wrapping_add(1);
+        self.read_byte(cycles, self.stack_pointer_as_word(), bus)
     }
 
-    fn load_register(
+    fn push_word_to_stack<B: Bus>(&mut self, cycles: &mut i32, word: Word, bus: &mut B) {
+        self.push_byte_to_stack(cycles, (word >> 8) as Byte, bus);
+        self.push_byte_to_stack(cycles, (word & 0xFF) as Byte, bus);
+    }
+
+    fn pop_word_from_stack<B: Bus>(&mut self, cycles: &mut i32, bus: &mut B) -> Word {
+        let low: Byte = self.pop_byte_from_stack(cycles, bus);
+        let high: Byte = self.pop_byte_from_stack(cycles, bus);
+        low as Word | ((high as Word) << 8)
+    }
+
+    /// The break and unused bits only exist on the stack copy of the status
+    /// register: they are always pushed as 1, except that the break bit is
+    /// pushed as 0 when a hardware interrupt is being serviced.
+    fn push_processor_status_to_stack<B: Bus>(
         &mut self,
         cycles: &mut i32,
-        address: Word,
+        r#break: bool,
+        bus: &mut B,
+    ) {
+        let mut status: ProcessorStatus = self.processor_status;
+        status.set_break(r#break);
+        status.set_unused(true);
+        self.push_byte_to_stack(cycles, status.0, bus);
+    }
+
+    fn pop_processor_status_from_stack<B: Bus>(&mut self, cycles: &mut i32, bus: &mut B) {
+        let mut status: ProcessorStatus = ProcessorStatus(self.pop_byte_from_stack(cycles, bus));
+        status.set_break(self.processor_status.r#break());
+        status.set_unused(self.processor_status.unused());
+        self.processor_status = status;
+    }
+
+    /// Works out the address an instruction operates on, making the bus
+    /// accesses its addressing mode requires. Indexed read instructions only
+    /// spend a cycle fixing up the high byte when a page boundary is crossed,
+    /// while stores and read-modify-write instructions always do.
+    fn operand_address<B: Bus>(
+        &mut self,
+        cycles: &mut i32,
+        info: &OpcodeInfo,
+        bus: &mut B,
+    ) -> Word {
+        match info.addressing_mode {
+            AddressingMode::ZeroPage => self.get_zero_page_addr(cycles, bus),
+            AddressingMode::ZeroPageX => self.get_zero_page_addr_x(cycles, bus),
+            AddressingMode::ZeroPageY => self.get_zero_page_addr_y(cycles, bus),
+            AddressingMode::Absolute => self.get_absolute_addr(cycles, bus),
+            AddressingMode::AbsoluteX if info.page_cross_penalty => {
+                self.get_absolute_addr_x(cycles, bus)
+            }
+            AddressingMode::AbsoluteX => self.get_absolute_addr_x_5(cycles, bus),
+            AddressingMode::AbsoluteY if info.page_cross_penalty => {
+                self.get_absolute_addr_y(cycles, bus)
+            }
+            AddressingMode::AbsoluteY => self.get_absolute_addr_y_5(cycles, bus),
+            AddressingMode::Indirect if self.variant.is_cmos() => {
+                let indirect_addr: Word = self.get_absolute_addr(cycles, bus);
+                self.dummy_read_unfixed_address(cycles, indirect_addr, indirect_addr, bus);
+                let jump_addr: Word = self.read_word(cycles, indirect_addr, bus);
+                self.record_effective_address(jump_addr)
+            }
+            AddressingMode::Indirect => {
+                let indirect_addr: Word = self.get_absolute_addr(cycles, bus);
+                let jump_addr: Word = self.read_word_within_page(cycles, indirect_addr, bus);
+                self.record_effective_address(jump_addr)
+            }
+            AddressingMode::AbsoluteIndexedIndirect => {
+                let base_addr: Word = self.fetch_word(cycles, bus);
+                self.dummy_read_unfixed_address(cycles, base_addr, base_addr, bus);
+                let indirect_addr: Word = base_addr.wrapping_add(self.register_x as Word);
+                let jump_addr: Word = self.read_word(cycles, indirect_addr, bus);
+                self.record_effective_address(jump_addr)
+            }
+            AddressingMode::ZeroPageIndirect => {
+                let zero_page_addr: Byte = self.fetch_byte(cycles, bus);
+                let effective_address: Word =
+                    self.read_word_within_page(cycles, zero_page_addr as Word, bus);
+                self.record_effective_address(effective_address)
+            }
+            AddressingMode::IndirectX => self.get_indr_addr_x(cycles, bus),
+            AddressingMode::IndirectY if info.page_cross_penalty => {
+                self.get_indr_addr_y(cycles, bus)
+            }
+            AddressingMode::IndirectY => self.get_indr_addr_y_6(cycles, bus),
+            AddressingMode::Implied
+            | AddressingMode::Accumulator
+            | AddressingMode::Immediate
+            | AddressingMode::Relative
+            | AddressingMode::ZeroPageRelative => {
+                unreachable!("{:?} has no operand address", info.addressing_mode)
+            }
+            _ => {
+                unreachable!("{:?} is only used by the 65816", info.addressing_mode)
+            }
+        }
+    }
+
+    fn read_operand<B: Bus>(&mut self, cycles: &mut i32, info: &OpcodeInfo, bus: &mut B) -> Byte {
+        if info.addressing_mode == AddressingMode::Immediate {
+            return self.fetch_byte(cycles, bus);
+        }
+        let address: Word = self.operand_address(cycles, info, bus);
+        self.read_byte(cycles, address, bus)
+    }
+
+    fn modify_operand<B: Bus>(
+        &mut self,
+        cycles: &mut i32,
+        info: &OpcodeInfo,
+        operation: fn(&mut CPU, Byte) -> Byte,
+        bus: &mut B,
+    ) {
+        if info.addressing_mode == AddressingMode::Accumulator {
+            self.modify_register(cycles, &RegisterType::Accumulator, operation, bus);
+        } else {
+            let address: Word = self.operand_address(cycles, info, bus);
+            self.modify_memory(cycles, address, operation, bus);
+        }
+    }
+
+    fn load_register<B: Bus>(
+        &mut self,
+        cycles: &mut i32,
+        info: &OpcodeInfo,
         register_type: &RegisterType,
-        memory: &Memory,
+        bus: &mut B,
     ) {
-        let data: Byte = self.read_byte(cycles, address, &memory);
+        let data: Byte = self.read_operand(cycles, info, bus);
         *self.get_register(register_type) = data;
         self.load_register_set_status(register_type);
     }
 
+    fn transfer_register<B: Bus>(
+        &mut self,
+        cycles: &mut i32,
+        from: &RegisterType,
+        to: &RegisterType,
+        bus: &mut B,
+    ) {
+        self.dummy_read_program_counter(cycles, bus);
+        *self.get_register(to) = *self.get_register(from);
+        self.load_register_set_status(to);
+    }
+
+    fn modify_register<B: Bus>(
+        &mut self,
+        cycles: &mut i32,
+        register_type: &RegisterType,
+        operation: fn(&mut CPU, Byte) -> Byte,
+        bus: &mut B,
+    ) {
+        self.dummy_read_program_counter(cycles, bus);
+        let data: Byte = *self.get_register(register_type);
+        *self.get_register(register_type) = operation(self, data);
+    }
+
+    /// Read-modify-write instructions spend a cycle writing the unmodified
+    /// value back before the result is written. The CMOS chips read it
+    /// again instead.
+    fn modify_memory<B: Bus>(
+        &mut self,
+        cycles: &mut i32,
+        address: Word,
+        operation: fn(&mut CPU, Byte) -> Byte,
+        bus: &mut B,
+    ) {
+        let data: Byte = self.read_byte(cycles, address, bus);
+        if self.variant.is_cmos() {
+            self.read_byte(cycles, address, bus);
+        } else {
+            self.write_byte(cycles, address, data, bus);
+        }
+        let result: Byte = operation(self, data);
+        self.write_byte(cycles, address, result, bus);
+    }
+
+    fn branch_if<B: Bus>(&mut self, cycles: &mut i32, condition: bool, bus: &mut B) {
+        let offset: Byte = self.fetch_byte(cycles, bus);
+        let target: Word = self
+            .program_counter
+            .wrapping_add_signed(offset as i8 as i16);
+        self.record_effective_address(target);
+        if condition {
+            self.dummy_read_program_counter(cycles, bus);
+            if (target & 0xFF00) != (self.program_counter & 0xFF00) {
+                self.step_state.page_crossed = true;
+                // The low byte is added first, so the fixup cycle reads
+                // from the target offset within the old page
+                let unfixed: Word = (self.program_counter & 0xFF00) | (target & 0x00FF);
+                self.read_byte(cycles, unfixed, bus);
+            }
+            self.program_counter = target;
+        }
+    }
+
+    fn logical_and(&mut self, operand: Byte) {
+        self.accumulator &= operand;
+        self.load_register_set_status(&RegisterType::Accumulator);
+    }
+
+    fn exclusive_or(&mut self, operand: Byte) {
+        self.accumulator ^= operand;
+        self.load_register_set_status(&RegisterType::Accumulator);
+    }
+
+    fn inclusive_or(&mut self, operand: Byte) {
+        self.accumulator |= operand;
+        self.load_register_set_status(&RegisterType::Accumulator);
+    }
+
+    fn bit_test(&mut self, operand: Byte) {
+        self.processor_status
+            .set_zero(self.accumulator & operand == 0x00);
+        self.processor_status.set_overflow(operand & 0b01000000 > 0);
+        self.processor_status.set_negative(operand & 0b10000000 > 0);
+    }
+
+    fn add_with_carry(&mut self, operand: Byte) {
+        if self.decimal_mode() {
+            self.add_with_carry_decimal(operand);
+            return;
+        }
+        let sum: Word =
+            self.accumulator as Word + operand as Word + self.processor_status.carry() as Word;
+        let result: Byte = (sum & 0xFF) as Byte;
+        self.processor_status.set_carry(sum > 0xFF);
+        self.processor_status
+            .set_overflow((self.accumulator ^ result) & (operand ^ result) & 0b10000000 > 0);
+        self.accumulator = result;
+        self.load_register_set_status(&RegisterType::Accumulator);
+    }
+
+    fn subtract_with_carry(&mut self, operand: Byte) {
+        if self.decimal_mode() {
+            self.subtract_with_carry_decimal(operand);
+            return;
+        }
+        self.add_with_carry(!operand);
+    }
+
+    /// Whether ADC and SBC should do decimal arithmetic.
+    fn decimal_mode(&self) -> bool {
+        self.processor_status.decimal() && self.variant.has_decimal_mode()
+    }
+
+    /// NMOS decimal addition, following Bruce Clark's "Decimal Mode" notes.
+    /// Z reflects the binary sum, N and V are taken from the intermediate
+    /// result before the high nibble is adjusted, and C from the adjusted one.
+    /// The CMOS chips take N and Z from the final result instead.
+    fn add_with_carry_decimal(&mut self, operand: Byte) {
+        let carry: i16 = self.processor_status.carry() as i16;
+        let binary_result: Byte = self
+            .accumulator
+            .wrapping_add(operand)
+            .wrapping_add(carry as Byte);
+
+        let mut low: i16 = (self.accumulator & 0x0F) as i16 + (operand & 0x0F) as i16 + carry;
+        if low >= 0x0A {
+            low = ((low + 0x06) & 0x0F) + 0x10;
+        }
+        let mut sum: i16 = (self.accumulator & 0xF0) as i16 + (operand & 0xF0) as i16 + low;
+        let signed_sum: i16 =
+            (self.accumulator & 0xF0) as i8 as i16 + (operand & 0xF0) as i8 as i16 + low;
+
+        self.processor_status.set_negative(sum & 0x80 > 0);
+        self.processor_status
+            .set_overflow(!(-128..=127).contains(&signed_sum));
+        self.processor_status.set_zero(binary_result == 0x00);
+        if sum >= 0xA0 {
+            sum += 0x60;
+        }
+        self.processor_status.set_carry(sum >= 0x100);
+        self.accumulator = (sum & 0xFF) as Byte;
+        if self.variant.is_cmos() {
+            self.load_register_set_status(&RegisterType::Accumulator);
+        }
+    }
+
+    /// NMOS decimal subtraction. The flags are identical to a binary
+    /// subtraction; only the accumulator result is decimal adjusted. The
+    /// CMOS chips adjust the result differently (sequence 4 in Bruce Clark's
+    /// notes) and take N and Z from it.
+    fn subtract_with_carry_decimal(&mut self, operand: Byte) {
+        let carry: i16 = self.processor_status.carry() as i16;
+        let accumulator: Byte = self.accumulator;
+
+        self.processor_status.set_decimal(false);
+        self.add_with_carry(!operand);
+        self.processor_status.set_decimal(true);
+
+        let mut low: i16 = (accumulator & 0x0F) as i16 - (operand & 0x0F) as i16 + carry - 1;
+        if self.variant.is_cmos() {
+            let mut result: i16 = accumulator as i16 - operand as i16 + carry - 1;
+            if result < 0 {
+                result -= 0x60;
+            }
+            if low < 0 {
+                result -= 0x06;
+            }
+            self.accumulator = (result & 0xFF) as Byte;
+            self.load_register_set_status(&RegisterType::Accumulator);
+            return;
+        }
+        if low < 0 {
+            low = ((low - 0x06) & 0x0F) - 0x10;
+        }
+        let mut result: i16 = (accumulator & 0xF0) as i16 - (operand & 0xF0) as i16 + low;
+        if result < 0 {
+            result -= 0x60;
+        }
+        self.accumulator = (result & 0xFF) as Byte;
+    }
+
+    fn compare(&mut self, register_type: &RegisterType, operand: Byte) {
+        let register: Byte = *self.get_register(register_type);
+        self.processor_status.set_carry(register >= operand);
+        self.set_zero_and_negative(register.wrapping_sub(operand));
+    }
+
+    fn increment(&mut self, data: Byte) -> Byte {
+        let result: Byte = data.wrapping_add(1);
+        self.set_zero_and_negative(result);
+        result
+    }
+
+    fn decrement(&mut self, data: Byte) -> Byte {
+        let result: Byte = data.wrapping_sub(1);
+        self.set_zero_and_negative(result);
+        result
+    }
+
+    fn shift_left(&mut self, data: Byte) -> Byte {
+        let result: Byte = data << 1;
+        self.processor_status.set_carry(data & 0b10000000 > 0);
+        self.set_zero_and_negative(result);
+        result
+    }
+
+    fn shift_right(&mut self, data: Byte) -> Byte {
+        let result: Byte = data >> 1;
+        self.processor_status.set_carry(data & 0b00000001 > 0);
+        self.set_zero_and_negative(result);
+        result
+    }
+
+    fn rotate_left(&mut self, data: Byte) -> Byte {
+        let result: Byte = (data << 1) | self.processor_status.carry() as Byte;
+        self.processor_status.set_carry(data & 0b10000000 > 0);
+        self.set_zero_and_negative(result);
+        result
+    }
+
+    fn rotate_right(&mut self, data: Byte) -> Byte {
+        let result: Byte = (data >> 1) | ((self.processor_status.carry() as Byte) << 7);
+        self.processor_status.set_carry(data & 0b00000001 > 0);
+        self.set_zero_and_negative(result);
+        result
+    }
+
     pub fn get_register(&mut self, register_type: &RegisterType) -> &mut Byte {
         match register_type {
             RegisterType::Accumulator => &mut self.accumulator,
             RegisterType::RegisterX => &mut self.register_x,
             RegisterType::RegisterY => &mut self.register_y,
+            RegisterType::StackPointer => &mut self.stack_pointer,
         }
     }
 
-    fn read_byte(&self, cycles: &mut i32, address: Word, memory: &Memory) -> Byte {
-        let data: Byte = memory.data[address as usize];
+    fn read_byte<B: Bus>(&self, cycles: &mut i32, address: Word, bus: &mut B) -> Byte {
+        let data: Byte = if self.variant.has_io_port() && IoPort::is_port_address(address) {
+            self.io_port.read(address, self.current_cycle(cycles))
+        } else {
+            bus.read(address)
+        };
         *cycles -= 1;
         data
     }
 
-    fn write_byte(&self, cycles: &mut i32, address: Word, data: Byte, memory: &mut Memory) {
-        memory.data[address as usize] = data;
+    fn write_byte<B: Bus>(&mut self, cycles: &mut i32, address: Word, data: Byte, bus: &mut B) {
+        if self.variant.has_io_port() && IoPort::is_port_address(address) {
+            let cycle: u64 = self.current_cycle(cycles);
+            self.io_port.write(address, data, cycle);
+            bus.io_port_changed(self.io_port.pins(cycle));
+        } else {
+            bus.write(address, data);
+        }
         *cycles -= 1;
     }
 
-    fn read_word(&self, cycles: &mut i32, address: Word, memory: &Memory) -> Word {
-        let low: Byte = self.read_byte(cycles, address, &memory);
-        let high: Byte = self.read_byte(cycles, address + 1, &memory);
+    /// The number of the bus cycle about to run, given the cycles the
+    /// current step has used so far.
+    fn current_cycle(&self, cycles: &i32) -> u64 {
+        self.cycles + (-*cycles) as u64
+    }
+
+    fn read_word<B: Bus>(&self, cycles: &mut i32, address: Word, bus: &mut B) -> Word {
+        let low: Byte = self.read_byte(cycles, address, bus);
+        let high: Byte = self.read_byte(cycles, address.wrapping_add(1), bus);
+        low as Word | ((high as Word) << 8)
+    }
+
+    /// Reads a word without carrying into the high byte of the address, so a
+    /// pointer at $xxFF takes its high byte from $xx00. This reproduces the
+    /// NMOS indirect JMP bug and zero page pointer wrap-around.
+    fn read_word_within_page<B: Bus>(&self, cycles: &mut i32, address: Word, bus: &mut B) -> Word {
+        let low: Byte = self.read_byte(cycles, address, bus);
+        let high_address: Word = (address & 0xFF00) | (address.wrapping_add(1) & 0x00FF);
+        let high: Byte = self.read_byte(cycles, high_address, bus);
         low as Word | ((high as Word) << 8)
     }
 
-    pub fn write_word(&self, cycles: &mut i32, word: Word, address: Word, memory: &mut Memory) {
-        memory.data[address as usize] = (word & 0xFF) as Byte;
-        memory.data[(address + 1) as usize] = (word >> 8) as Byte;
-        *cycles -= 2;
+    pub fn write_word<B: Bus>(&mut self, cycles: &mut i32, word: Word, address: Word, bus: &mut B) {
+        self.write_byte(cycles, address, (word & 0xFF) as Byte, bus);
+        self.write_byte(cycles, address.wrapping_add(1), (word >> 8) as Byte, bus);
+    }
+
+    /// Reads the byte at the program counter without advancing it. Single
+    /// byte instructions and internal operations spend their idle cycles
+    /// doing this.
+    fn dummy_read_program_counter<B: Bus>(&self, cycles: &mut i32, bus: &mut B) {
+        self.read_byte(cycles, self.program_counter, bus);
+    }
+
+    fn dummy_read_stack<B: Bus>(&self, cycles: &mut i32, bus: &mut B) {
+        self.read_byte(cycles, self.stack_pointer_as_word(), bus);
     }
 
-    fn fetch_byte(&mut self, cycles: &mut i32, memory: &Memory) -> Byte {
-        let data: Byte = memory.data[self.program_counter as usize];
+    /// Indexing adds to the low byte first, so the cycle spent fixing up
+    /// the high byte reads from the indexed offset within the base page.
+    /// The CMOS chips read the last operand byte again instead.
+    fn dummy_read_unfixed_address<B: Bus>(
+        &self,
+        cycles: &mut i32,
+        base_address: Word,
+        indexed_address: Word,
+        bus: &mut B,
+    ) {
+        let unfixed_address: Word = if self.variant.is_cmos() {
+            self.program_counter.wrapping_sub(1)
+        } else {
+            (base_address & 0xFF00) | (indexed_address & 0x00FF)
+        };
+        self.read_byte(cycles, unfixed_address, bus);
+    }
+
+    fn fetch_byte<B: Bus>(&mut self, cycles: &mut i32, bus: &mut B) -> Byte {
+        let data: Byte = self.read_byte(cycles, self.program_counter, bus);
         self.program_counter = self.program_counter.wrapping_add(1);
-        *cycles -= 1;
+        self.step_state.operand = Some(data as Word);
         data
     }
 
-    fn fetch_word(&mut self, cycles: &mut i32, memory: &Memory) -> Word {
-        let mut data: Word = memory.data[self.program_counter as usize] as u16;
+    fn fetch_word<B: Bus>(&mut self, cycles: &mut i32, bus: &mut B) -> Word {
+        let mut data: Word = self.read_byte(cycles, self.program_counter, bus) as Word;
         self.program_counter = self.program_counter.wrapping_add(1);
-        data |= (memory.data[self.program_counter as usize] as Word) << 8;
+        data |= (self.read_byte(cycles, self.program_counter, bus) as Word) << 8;
         self.program_counter = self.program_counter.wrapping_add(1);
-        *cycles -= 2;
+        self.step_state.operand = Some(data);
         data
     }
 
     fn load_register_set_status(&mut self, register_type: &RegisterType) {
-        // println!("Acc: {:#010x}", self.accumulator);
-        // println!("ReX: {:#010x}", self.register_x);
-        // println!("ReY: {:#010x}", self.register_y);
         let register = *self.get_register(register_type);
-        self.processor_status.set_zero(register == 0x00000000);
-        self.processor_status
-            .set_negative(register & 0b10000000 > 0);
+        self.set_zero_and_negative(register);
+    }
+
+    fn set_zero_and_negative(&mut self, value: Byte) {
+        self.processor_status.set_zero(value == 0x00000000);
+        self.processor_status.set_negative(value & 0b10000000 > 0);
     }
 
-    fn get_zero_page_addr(&mut self, cycles: &mut i32, memory: &Memory) -> Word {
-        self.fetch_byte(cycles, &memory) as Word
+    fn get_zero_page_addr<B: Bus>(&mut self, cycles: &mut i32, bus: &mut B) -> Word {
+        let zero_page_address: Byte = self.fetch_byte(cycles, bus);
+        self.record_effective_address(zero_page_address as Word)
     }
 
-    fn get_zero_page_addr_x(&mut self, cycles: &mut i32, memory: &Memory) -> Word {
-        let mut zero_page_address: Byte = self.fetch_byte(cycles, &memory);
+    fn get_zero_page_addr_x<B: Bus>(&mut self, cycles: &mut i32, bus: &mut B) -> Word {
+        let mut zero_page_address: Byte = self.fetch_byte(cycles, bus);
+        self.read_byte(cycles, zero_page_address as Word, bus);
         zero_page_address = zero_page_address.wrapping_add(self.register_x);
-        *cycles -= 1;
-        zero_page_address as Word
+        self.record_effective_address(zero_page_address as Word)
     }
 
-    fn get_zero_page_addr_y(&mut self, cycles: &mut i32, memory: &Memory) -> Word {
-        let mut zero_page_address: Byte = self.fetch_byte(cycles, &memory);
+    fn get_zero_page_addr_y<B: Bus>(&mut self, cycles: &mut i32, bus: &mut B) -> Word {
+        let mut zero_page_address: Byte = self.fetch_byte(cycles, bus);
+        self.read_byte(cycles, zero_page_address as Word, bus);
         zero_page_address = zero_page_address.wrapping_add(self.register_y);
-        *cycles -= 1;
-        zero_page_address as Word
+        self.record_effective_address(zero_page_address as Word)
     }
 
-    fn get_indr_addr_x(&mut self, cycles: &mut i32, memory: &Memory) -> Word {
-        let mut address: Byte = self.fetch_byte(cycles, memory);
-        address += self.register_x;
-        *cycles -= 1;
-        self.read_word(cycles, address as Word, memory)
+    fn get_indr_addr_x<B: Bus>(&mut self, cycles: &mut i32, bus: &mut B) -> Word {
+        let mut address: Byte = self.fetch_byte(cycles, bus);
+        self.read_byte(cycles, address as Word, bus);
+        address = address.wrapping_add(self.register_x);
+        let effective_address: Word = self.read_word_within_page(cycles, address as Word, bus);
+        self.record_effective_address(effective_address)
     }
 
-    fn get_indr_addr_y(&mut self, cycles: &mut i32, memory: &Memory) -> Word {
-        let zero_page_addr: Byte = self.fetch_byte(cycles, memory);
-        let effective_address: Word = self.read_word(cycles, zero_page_addr as Word, memory);
-        let effective_address_plus_y = effective_address + self.register_y as Word;
-        if (effective_address_plus_y & 0xFF00) != (effective_address & 0xFF00) {
-            *cycles -= 1;
+    fn get_indr_addr_y<B: Bus>(&mut self, cycles: &mut i32, bus: &mut B) -> Word {
+        let zero_page_addr: Byte = self.fetch_byte(cycles, bus);
+        let effective_address: Word =
+            self.read_word_within_page(cycles, zero_page_addr as Word, bus);
+        let effective_address_plus_y = self.index_address(effective_address, self.register_y);
+        if self.step_state.page_crossed {
+            self.dummy_read_unfixed_address(
+                cycles,
+                effective_address,
+                effective_address_plus_y,
+                bus,
+            );
         }
         effective_address_plus_y
     }
 
-    fn get_indr_addr_y_6(&mut self, cycles: &mut i32, memory: &Memory) -> Word {
-        let zero_page_addr: Byte = self.fetch_byte(cycles, memory);
-        let effective_address: Word = self.read_word(cycles, zero_page_addr as Word, memory);
-        let effective_address_plus_y = effective_address + self.register_y as Word;
-        *cycles -= 1;
+    fn get_indr_addr_y_6<B: Bus>(&mut self, cycles: &mut i32, bus: &mut B) -> Word {
+        let zero_page_addr: Byte = self.fetch_byte(cycles, bus);
+        let effective_address: Word =
+            self.read_word_within_page(cycles, zero_page_addr as Word, bus);
+        let effective_address_plus_y = self.index_address(effective_address, self.register_y);
+        self.dummy_read_unfixed_address(cycles, effective_address, effective_address_plus_y, bus);
         effective_address_plus_y
     }
 
-    fn get_absolute_addr(&mut self, cycles: &mut i32, memory: &Memory) -> Word {
-        self.fetch_word(cycles, &memory)
+    fn get_absolute_addr<B: Bus>(&mut self, cycles: &mut i32, bus: &mut B) -> Word {
+        let absolute_addr: Word = self.fetch_word(cycles, bus);
+        self.record_effective_address(absolute_addr)
     }
 
-    fn get_absolute_addr_x(&mut self, cycles: &mut i32, memory: &Memory) -> Word {
-        let absolute_addr: Word = self.fetch_word(cycles, &memory);
-        let absolute_addr_plus_x = absolute_addr + self.register_x as Word;
-        if (absolute_addr_plus_x & 0xFF00) != (absolute_addr & 0xFF00) {
-            *cycles -= 1;
+    fn get_absolute_addr_x<B: Bus>(&mut self, cycles: &mut i32, bus: &mut B) -> Word {
+        let absolute_addr: Word = self.fetch_word(cycles, bus);
+        let absolute_addr_plus_x = self.index_address(absolute_addr, self.register_x);
+        if self.step_state.page_crossed {
+            self.dummy_read_unfixed_address(cycles, absolute_addr, absolute_addr_plus_x, bus);
         }
         absolute_addr_plus_x
     }
 
-    fn get_absolute_addr_y(&mut self, cycles: &mut i32, memory: &Memory) -> Word {
-        let absolute_addr: Word = self.fetch_word(cycles, &memory);
-        let absolute_addr_plus_y = absolute_addr + self.register_y as Word;
-        if (absolute_addr_plus_y & 0xFF00) != (absolute_addr & 0xFF00) {
-            *cycles -= 1;
+    fn get_absolute_addr_y<B: Bus>(&mut self, cycles: &mut i32, bus: &mut B) -> Word {
+        let absolute_addr: Word = self.fetch_word(cycles, bus);
+        let absolute_addr_plus_y = self.index_address(absolute_addr, self.register_y);
+        if self.step_state.page_crossed {
+            self.dummy_read_unfixed_address(cycles, absolute_addr, absolute_addr_plus_y, bus);
         }
         absolute_addr_plus_y
     }
 
-    fn get_absolute_addr_x_5(&mut self, cycles: &mut i32, memory: &Memory) -> Word {
-        let absolute_addr: Word = self.fetch_word(cycles, &memory);
-        let absolute_addr_plus_x = absolute_addr + self.register_x as Word;
-        *cycles -= 1;
+    fn get_absolute_addr_x_5<B: Bus>(&mut self, cycles: &mut i32, bus: &mut B) -> Word {
+        let absolute_addr: Word = self.fetch_word(cycles, bus);
+        let absolute_addr_plus_x = self.index_address(absolute_addr, self.register_x);
+        self.dummy_read_unfixed_address(cycles, absolute_addr, absolute_addr_plus_x, bus);
         absolute_addr_plus_x
     }
 
-    fn get_absolute_addr_y_5(&mut self, cycles: &mut i32, memory: &Memory) -> Word {
-        let absolute_addr: Word = self.fetch_word(cycles, &memory);
-        let absolute_addr_plus_y = absolute_addr + self.register_y as Word;
-        *cycles -= 1;
+    fn get_absolute_addr_y_5<B: Bus>(&mut self, cycles: &mut i32, bus: &mut B) -> Word {
+        let absolute_addr: Word = self.fetch_word(cycles, bus);
+        let absolute_addr_plus_y = self.index_address(absolute_addr, self.register_y);
+        self.dummy_read_unfixed_address(cycles, absolute_addr, absolute_addr_plus_y, bus);
         absolute_addr_plus_y
     }
+
+    fn index_address(&mut self, base_address: Word, index: Byte) -> Word {
+        let indexed_address: Word = base_address.wrapping_add(index as Word);
+        self.step_state.page_crossed = (indexed_address & 0xFF00) != (base_address & 0xFF00);
+        self.record_effective_address(indexed_address)
+    }
+
+    fn record_effective_address(&mut self, address: Word) -> Word {
+        self.step_state.effective_address = Some(address);
+        address
+    }
+}
+
+impl Default for CPU {
+    fn default() -> Self {
+        CPU::new()
+    }
 }
 
 bitfield! {
     #[derive(Clone, Copy, Debug)]
     pub struct ProcessorStatus(Byte);
     Byte;
-    pub carry, set_carry: 1;
-    pub zero, set_zero: 2;
-    pub interrupt, set_interrupt: 3;
-    pub decimal, set_decimal: 4;
-    pub r#break, set_break: 5;
+    pub carry, set_carry: 0;
+    pub zero, set_zero: 1;
+    pub interrupt, set_interrupt: 2;
+    pub decimal, set_decimal: 3;
+    pub r#break, set_break: 4;
+    pub unused, set_unused: 5;
     pub overflow, set_overflow: 6;
     pub negative, set_negative: 7;
 }
@@ -399,6 +1322,7 @@ impl PartialEq for ProcessorStatus {
     }
 }
 
+#[derive(Clone)]
 pub struct Memory {
     pub data: [Byte; MAX_MEM as usize],
 }
@@ -411,8 +1335,45 @@ impl Memory {
     }
 }
 
+impl Bus for Memory {
+    fn read(&mut self, address: Word) -> Byte {
+        self.data[address as usize]
+    }
+
+    fn write(&mut self, address: Word, data: Byte) {
+        self.data[address as usize] = data;
+    }
+}
+
+/// The full 16 MB the 65816 can address. It lives on the heap, unlike
+/// `Memory`.
+#[derive(Clone)]
+pub struct LongMemory {
+    pub data: Vec<Byte>,
+}
+
+impl LongMemory {
+    pub fn reset() -> Self {
+        Self {
+            data: vec![0x00; MAX_LONG_MEM as usize],
+        }
+    }
+}
+
+impl LongBus for LongMemory {
+    fn read(&mut self, address: LongAddress) -> Byte {
+        self.data[address as usize]
+    }
+
+    fn write(&mut self, address: LongAddress, data: Byte) {
+        self.data[address as usize] = data;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum RegisterType {
     Accumulator,
     RegisterX,
     RegisterY,
+    StackPointer,
 }
@@ -0,0 +1,323 @@
+use std::path::Path;
+
+use crate::{Bus, Byte, LoadError, Word};
+
+/// Where Atari DOS looks for the run address of an executable.
+const RUNAD: Word = 0x02E0;
+/// Where Atari DOS looks for the address of an init routine, which it
+/// calls as soon as the segment setting it has loaded.
+const INITAD: Word = 0x02E2;
+
+const ADDRESS_SPACE: u32 = 0x10000;
+
+/// Bytes to be written to consecutive addresses.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Segment {
+    pub address: Word,
+    pub data: Vec<Byte>,
+}
+
+impl Segment {
+    /// The last address the segment writes to.
+    pub fn end(&self) -> Word {
+        self.address + (self.data.len() - 1) as Word
+    }
+}
+
+/// A program read from a file, ready to be written to a bus.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Image {
+    /// Every non-empty segment in file order. No two of them overlap.
+    pub segments: Vec<Segment>,
+    /// Where execution should start, if the file says.
+    pub start: Option<Word>,
+    /// The init routines of an Atari executable, in the order the loader
+    /// would call them.
+    pub init: Vec<Word>,
+}
+
+impl Image {
+    /// Checks that every segment fits in the address space and that none
+    /// of them overlap.
+    fn new(
+        segments: Vec<(u32, Vec<Byte>)>,
+        start: Option<Word>,
+        init: Vec<Word>,
+    ) -> Result<Self, LoadError> {
+        let mut ranges: Vec<(u64, u64)> = Vec::new();
+        for (address, data) in &segments {
+            let end: u64 = *address as u64 + data.len() as u64;
+            if !data.is_empty() && end > ADDRESS_SPACE as u64 {
+                return Err(LoadError::OutOfRange {
+                    address: (*address).max(ADDRESS_SPACE),
+                });
+            }
+            ranges.push((*address as u64, end));
+        }
+        ranges.sort_unstable();
+        let mut covered: u64 = 0;
+        for (address, end) in ranges.into_iter().filter(|(address, end)| end > address) {
+            if address < covered {
+                return Err(LoadError::Overlap {
+                    address: address as Word,
+                });
+            }
+            covered = end;
+        }
+        let segments: Vec<Segment> = segments
+            .into_iter()
+            .filter(|(_, data)| !data.is_empty())
+            .map(|(address, data)| Segment {
+                address: address as Word,
+                data,
+            })
+            .collect();
+        Ok(Self {
+            segments,
+            start,
+            init,
+        })
+    }
+
+    pub fn load_into<B: Bus>(&self, bus: &mut B) {
+        for segment in &self.segments {
+            for (offset, byte) in segment.data.iter().enumerate() {
+                bus.write(segment.address + offset as Word, *byte);
+            }
+        }
+    }
+
+    /// The start address, or failing that where the first segment loads.
+    pub fn entry(&self) -> Option<Word> {
+        self.start
+            .or_else(|| self.segments.first().map(|segment| segment.address))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// A plain binary, loaded at the given address.
+    Raw(Word),
+    IntelHex,
+    SRecord,
+    /// A Commodore program file, whose first two bytes are the load address.
+    Prg,
+    /// An Atari DOS executable made of `$FFFF`-headed segments.
+    Xex,
+}
+
+impl Format {
+    /// Picks a format from the file extension, treating anything unknown
+    /// as a raw binary at `raw_address`.
+    pub fn from_path(path: &Path, raw_address: Word) -> Format {
+        let extension: String = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        match extension.as_str() {
+            "hex" | "ihx" | "ihex" => Format::IntelHex,
+            "srec" | "s19" | "s28" | "s37" | "mot" => Format::SRecord,
+            "prg" => Format::Prg,
+            "xex" | "com" | "exe" => Format::Xex,
+            _ => Format::Raw(raw_address),
+        }
+    }
+}
+
+pub fn parse(format: Format, data: &[Byte]) -> Result<Image, LoadError> {
+    match format {
+        Format::Raw(address) => parse_raw(data, address),
+        Format::IntelHex => parse_intel_hex(data),
+        Format::SRecord => parse_srecord(data),
+        Format::Prg => parse_prg(data),
+        Format::Xex => parse_xex(data),
+    }
+}
+
+/// Parses `data` and writes it to the bus, returning the image so that the
+/// caller can find its start address.
+pub fn load<B: Bus>(format: Format, data: &[Byte], bus: &mut B) -> Result<Image, LoadError> {
+    let image: Image = parse(format, data)?;
+    image.load_into(bus);
+    Ok(image)
+}
+
+pub fn parse_raw(data: &[Byte], address: Word) -> Result<Image, LoadError> {
+    Image::new(vec![(address as u32, data.to_vec())], None, Vec::new())
+}
+
+/// Reads Intel HEX data (type 00), end of file (01), extended segment and
+/// linear address (02, 04) and start address (03, 05) records.
+pub fn parse_intel_hex(data: &[Byte]) -> Result<Image, LoadError> {
+    let mut segments: Vec<(u32, Vec<Byte>)> = Vec::new();
+    let mut start: Option<Word> = None;
+    let mut base: u32 = 0;
+    for (line, text) in lines(data) {
+        let Some(hex) = text.strip_prefix(b":") else {
+            return Err(LoadError::InvalidRecord { line });
+        };
+        let record: Vec<Byte> = decode_hex(hex, line)?;
+        if record.len() < 5 || record.len() != record[0] as usize + 5 {
+            return Err(LoadError::InvalidRecord { line });
+        }
+        if record
+            .iter()
+            .fold(0, |sum: Byte, byte| sum.wrapping_add(*byte))
+            != 0
+        {
+            return Err(LoadError::BadChecksum { line });
+        }
+        let offset: u32 = u16::from_be_bytes([record[1], record[2]]) as u32;
+        let payload: &[Byte] = &record[4..record.len() - 1];
+        match (record[3], payload.len()) {
+            (0x00, _) => segments.push((base + offset, payload.to_vec())),
+            (0x01, 0) => return Image::new(segments, start, Vec::new()),
+            (0x02, 2) => base = (u16::from_be_bytes([payload[0], payload[1]]) as u32) << 4,
+            (0x04, 2) => base = (u16::from_be_bytes([payload[0], payload[1]]) as u32) << 16,
+            (0x03, 4) => {
+                let segment: u32 = u16::from_be_bytes([payload[0], payload[1]]) as u32;
+                let pointer: u32 = u16::from_be_bytes([payload[2], payload[3]]) as u32;
+                start = Some(start_address((segment << 4) + pointer)?);
+            }
+            (0x05, 4) => {
+                let address: u32 =
+                    u32::from_be_bytes([payload[0], payload[1], payload[2], payload[3]]);
+                start = Some(start_address(address)?);
+            }
+            (0x00..=0x05, _) => return Err(LoadError::InvalidRecord { line }),
+            _ => return Err(LoadError::UnsupportedRecord { line }),
+        }
+    }
+    Err(LoadError::MissingEndRecord)
+}
+
+/// Reads Motorola S-records: an optional S0 header, S1/S2/S3 data, S5/S6
+/// counts and an S7/S8/S9 termination record holding the start address.
+pub fn parse_srecord(data: &[Byte]) -> Result<Image, LoadError> {
+    let mut segments: Vec<(u32, Vec<Byte>)> = Vec::new();
+    for (line, text) in lines(data) {
+        let [b'S' | b's', kind, hex @ ..] = text else {
+            return Err(LoadError::InvalidRecord { line });
+        };
+        let record: Vec<Byte> = decode_hex(hex, line)?;
+        if record.len() < 3 || record.len() != record[0] as usize + 1 {
+            return Err(LoadError::InvalidRecord { line });
+        }
+        if record
+            .iter()
+            .fold(0, |sum: Byte, byte| sum.wrapping_add(*byte))
+            != 0xFF
+        {
+            return Err(LoadError::BadChecksum { line });
+        }
+        let address_length: usize = match kind {
+            b'0' | b'1' | b'5' | b'9' => 2,
+            b'2' | b'6' | b'8' => 3,
+            b'3' | b'7' => 4,
+            b'4' => return Err(LoadError::UnsupportedRecord { line }),
+            _ => return Err(LoadError::InvalidRecord { line }),
+        };
+        if record.len() < address_length + 2 {
+            return Err(LoadError::InvalidRecord { line });
+        }
+        let address: u32 = record[1..=address_length]
+            .iter()
+            .fold(0, |address, byte| (address << 8) | *byte as u32);
+        let payload: &[Byte] = &record[address_length + 1..record.len() - 1];
+        match kind {
+            b'0' => {}
+            b'1' | b'2' | b'3' => segments.push((address, payload.to_vec())),
+            b'5' | b'6' => {
+                if address as usize != segments.len() {
+                    return Err(LoadError::RecordCountMismatch { line });
+                }
+            }
+            _ => return Image::new(segments, Some(start_address(address)?), Vec::new()),
+        }
+    }
+    Err(LoadError::MissingEndRecord)
+}
+
+pub fn parse_prg(data: &[Byte]) -> Result<Image, LoadError> {
+    let [lo, hi, program @ ..] = data else {
+        return Err(LoadError::Truncated);
+    };
+    Image::new(
+        vec![(u16::from_le_bytes([*lo, *hi]) as u32, program.to_vec())],
+        None,
+        Vec::new(),
+    )
+}
+
+/// Reads an Atari DOS executable. Segments that only set the run or init
+/// address (`$02E0` and `$02E2`) go into `Image::start` and `Image::init`
+/// instead of memory, since a program may set the init address many times.
+pub fn parse_xex(data: &[Byte]) -> Result<Image, LoadError> {
+    let mut segments: Vec<(u32, Vec<Byte>)> = Vec::new();
+    let mut start: Option<Word> = None;
+    let mut init: Vec<Word> = Vec::new();
+    let mut remaining: &[Byte] = match data {
+        [0xFF, 0xFF, rest @ ..] => rest,
+        [_, _, ..] => return Err(LoadError::MissingHeader),
+        _ => return Err(LoadError::Truncated),
+    };
+    while !remaining.is_empty() {
+        // Any segment may repeat the header
+        if read_word(remaining, 0)? == 0xFFFF {
+            remaining = &remaining[2..];
+        }
+        let first: Word = read_word(remaining, 0)?;
+        let last: Word = read_word(remaining, 2)?;
+        if last < first {
+            return Err(LoadError::InvalidSegment {
+                start: first,
+                end: last,
+            });
+        }
+        let length: usize = (last - first) as usize + 1;
+        let segment: &[Byte] = remaining.get(4..4 + length).ok_or(LoadError::Truncated)?;
+        remaining = &remaining[4 + length..];
+        match (first, last) {
+            (RUNAD, 0x02E1) => start = Some(read_word(segment, 0)?),
+            (INITAD, 0x02E3) => init.push(read_word(segment, 0)?),
+            (RUNAD, 0x02E3) => {
+                start = Some(read_word(segment, 0)?);
+                init.push(read_word(segment, 2)?);
+            }
+            _ => segments.push((first as u32, segment.to_vec())),
+        }
+    }
+    Image::new(segments, start, init)
+}
+
+/// The non-blank lines of a text file, numbered from 1 and trimmed.
+fn lines(data: &[Byte]) -> impl Iterator<Item = (usize, &[Byte])> {
+    data.split(|byte| *byte == b'\n')
+        .map(<[Byte]>::trim_ascii)
+        .enumerate()
+        .map(|(index, text)| (index + 1, text))
+        .filter(|(_, text)| !text.is_empty())
+}
+
+fn decode_hex(text: &[Byte], line: usize) -> Result<Vec<Byte>, LoadError> {
+    let digit = |character: &Byte| (*character as char).to_digit(16);
+    text.chunks(2)
+        .map(|pair| match pair {
+            [hi, lo] => Some((digit(hi)? << 4 | digit(lo)?) as Byte),
+            _ => None,
+        })
+        .collect::<Option<Vec<Byte>>>()
+        .ok_or(LoadError::InvalidRecord { line })
+}
+
+fn read_word(data: &[Byte], offset: usize) -> Result<Word, LoadError> {
+    match data.get(offset..offset + 2) {
+        Some([lo, hi]) => Ok(u16::from_le_bytes([*lo, *hi])),
+        _ => Err(LoadError::Truncated),
+    }
+}
+
+fn start_address(address: u32) -> Result<Word, LoadError> {
+    Word::try_from(address).map_err(|_| LoadError::OutOfRange { address })
+}
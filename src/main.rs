@@ -1,8 +1,57 @@
-use rust6502::{instructions::*, *};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
 
+use rust6502::loader::Format;
+use rust6502::monitor::Monitor;
+use rust6502::*;
+
+/// With these, `zb` and `bw` can reach back 100,000 instructions.
+const REWIND_INTERVAL: u64 = 1_000;
+const REWIND_CHECKPOINTS: usize = 100;
+
+/// Starts the monitor on 64KB of RAM, optionally loading a program first:
+/// `rust6502 [file [address]]`. See the monitor's `l` command for the file
+/// formats it understands.
 fn main() {
-    asm::asm! {
-        lda #0x84;
-        jsr 0x4242;
-    };
+    let mut cpu: CPU = CPU::new();
+    let mut memory: Memory = Memory::reset();
+    // Run the RESET sequence up front, so that a PC set by loading a file
+    // or by `r pc=` is not overwritten by a reset still pending
+    cpu.step(&mut memory).unwrap();
+    let mut debugger: Debugger<Memory> = Debugger::new(cpu, memory);
+    debugger.enable_rewind(REWIND_INTERVAL, REWIND_CHECKPOINTS);
+    let mut monitor: Monitor<Memory> = Monitor::new(debugger);
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    if let Some((file, address)) = arguments.split_first() {
+        let mut commands: Vec<String> = vec![format!("l {}", arguments.join(" "))];
+        // Files with their own load address set PC themselves if they can
+        let raw: bool = matches!(Format::from_path(Path::new(file), 0), Format::Raw(_));
+        if raw || !address.is_empty() {
+            let address: &str = address.first().map_or("0", String::as_str);
+            commands.push(format!("r pc={}", address));
+        }
+        for command in commands {
+            match monitor.execute(&command) {
+                Ok(output) => print!("{}", output),
+                Err(error) => {
+                    eprintln!("{}", error);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    while !monitor.is_finished() {
+        print!("{}", monitor.prompt());
+        io::stdout().flush().unwrap();
+        let Some(Ok(line)) = lines.next() else {
+            break;
+        };
+        match monitor.execute(&line) {
+            Ok(output) => print!("{}", output),
+            Err(error) => println!("?{}", error),
+        }
+    }
 }
@@ -0,0 +1,598 @@
+use std::path::Path;
+
+use crate::debugger::{
+    Breakpoint, Comparison, Condition, Debugger, Flag, StopReason, WatchKind, Watchpoint,
+};
+use crate::disassembler::{self, Disassembly};
+use crate::gdb::GdbStub;
+use crate::instructions::OpcodeInfo;
+use crate::loader::{self, Format, Image};
+use crate::{Bus, Byte, ExecutionError, ProcessorStatus, RegisterType, Word};
+
+const HELP: &str = "\
+m [start [end]]           show memory
+> addr byte...            deposit bytes
+d [start [end]]           disassemble
+a addr instruction        assemble one instruction in place
+r [reg=value...]          show or set registers (pc, a, x, y, sp, p)
+break [addr [if cond]]    list or add breakpoints, e.g. break c000 if x == 10
+until addr                run to a temporary breakpoint
+watch [r|w|rw] start [end]  list or add watchpoints
+del id                    delete a breakpoint or watchpoint
+z [count]                 step instructions
+zb                        step back one instruction
+bw addr                   go back to the last write to addr
+n                         step over a subroutine call
+ret                       run until the current subroutine returns
+g [addr]                  go until a breakpoint or watchpoint
+reset                     run the RESET sequence
+l file [addr]             load a file; .hex, .s19, .prg and .xex by format,
+                          anything else as a raw binary at addr
+s file start end          save memory to a binary file
+gdb port                  serve one GDB client on 127.0.0.1:port (decimal)
+x                         quit
+Numbers are hex, with an optional $ prefix.";
+
+/// The most instructions a single `g`, `n`, `ret` or `until` runs before
+/// handing control back.
+pub const INSTRUCTION_LIMIT: u64 = 10_000_000;
+
+/// A line-oriented machine-language monitor in the style of the VICE and
+/// Apple II monitors, driving a `Debugger`.
+pub struct Monitor<B: Bus> {
+    pub debugger: Debugger<B>,
+    next_memory: Word,
+    next_disassembly: Word,
+    finished: bool,
+}
+
+impl<B: Bus> Monitor<B> {
+    pub fn new(mut debugger: Debugger<B>) -> Self {
+        debugger.instruction_limit = Some(INSTRUCTION_LIMIT);
+        let program_counter: Word = debugger.cpu.program_counter;
+        Self {
+            debugger,
+            next_memory: 0x0000,
+            next_disassembly: program_counter,
+            finished: false,
+        }
+    }
+
+    /// Whether the user has asked to quit.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn prompt(&self) -> String {
+        format!("{:04X}> ", self.debugger.cpu.program_counter)
+    }
+
+    /// Runs one command line and returns what it printed, or a message
+    /// saying why the command was rejected.
+    pub fn execute(&mut self, line: &str) -> Result<String, String> {
+        let arguments: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, arguments)) = arguments.split_first() else {
+            return Ok(String::new());
+        };
+        match command.to_ascii_lowercase().as_str() {
+            "m" => self.memory(arguments),
+            ">" => self.deposit(arguments),
+            "d" => self.disassemble(arguments),
+            "a" => self.assemble(arguments),
+            "r" => self.registers(arguments),
+            "break" | "bk" => self.breakpoint(arguments),
+            "until" | "un" => {
+                let address: Word = parse_address(single(arguments)?)?;
+                self.debugger.add_temporary_breakpoint(address);
+                self.run(|debugger| debugger.run())
+            }
+            "watch" | "w" => self.watchpoint(arguments),
+            "del" => {
+                let id: usize = single(arguments)?
+                    .parse()
+                    .map_err(|_| String::from("expected a breakpoint number"))?;
+                if self.debugger.remove_breakpoint(id) || self.debugger.remove_watchpoint(id) {
+                    Ok(String::new())
+                } else {
+                    Err(format!("no breakpoint or watchpoint {}", id))
+                }
+            }
+            "z" => {
+                let count: u64 = match arguments {
+                    [] => 1,
+                    [count] => parse_number(count)?.into(),
+                    _ => return Err(String::from("expected a count")),
+                };
+                self.run(|debugger| {
+                    for _ in 1..count {
+                        let reason: StopReason = debugger.step()?;
+                        if reason != StopReason::Step {
+                            return Ok(reason);
+                        }
+                    }
+                    debugger.step()
+                })
+            }
+            "zb" => {
+                if !arguments.is_empty() {
+                    return Err(String::from("expected no arguments"));
+                }
+                if !self.debugger.step_back() {
+                    return Err(String::from("no earlier instruction recorded"));
+                }
+                self.next_disassembly = self.debugger.cpu.program_counter;
+                Ok(self.status())
+            }
+            "bw" => {
+                let address: Word = parse_address(single(arguments)?)?;
+                if !self.debugger.run_back_to_write(address) {
+                    return Err(format!("no recorded write to {:04X}", address));
+                }
+                self.next_disassembly = self.debugger.cpu.program_counter;
+                Ok(self.status())
+            }
+            "n" => self.run(|debugger| debugger.step_over()),
+            "ret" => self.run(|debugger| debugger.step_out()),
+            "g" => {
+                if let [address] = arguments {
+                    self.debugger.cpu.program_counter = parse_address(address)?;
+                    self.debugger.clear_history();
+                }
+                self.run(|debugger| debugger.run())
+            }
+            "reset" => {
+                self.debugger.cpu.trigger_reset();
+                self.debugger.clear_history();
+                self.run(|debugger| debugger.step())
+            }
+            "l" => self.load(arguments),
+            "s" => self.save(arguments),
+            "gdb" => {
+                let port: u16 = single(arguments)?
+                    .parse()
+                    .map_err(|_| String::from("expected a port number"))?;
+                GdbStub::new(&mut self.debugger)
+                    .listen(("127.0.0.1", port))
+                    .map_err(|error| error.to_string())?;
+                self.next_disassembly = self.debugger.cpu.program_counter;
+                Ok(format!("gdb detached\n{}", self.status()))
+            }
+            "x" | "q" | "quit" => {
+                self.finished = true;
+                Ok(String::new())
+            }
+            "?" | "help" => Ok(format!("{}\n", HELP)),
+            _ => Err(format!("unknown command '{}', try 'help'", command)),
+        }
+    }
+
+    fn memory(&mut self, arguments: &[&str]) -> Result<String, String> {
+        let (start, end): (Word, Word) = self.range(arguments, self.next_memory, 0x7F)?;
+        let mut output: String = String::new();
+        let mut address: u32 = start as u32;
+        while address <= end as u32 {
+            let row_end: u32 = (address + 15).min(end as u32);
+            let bytes: Vec<Byte> = (address..=row_end)
+                .map(|address| {
+                    self.debugger
+                        .cpu
+                        .peek(address as Word, &mut self.debugger.bus)
+                })
+                .collect();
+            let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+            let text: String = bytes
+                .iter()
+                .map(|&byte| {
+                    if byte.is_ascii_graphic() || byte == b' ' {
+                        byte as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            output += &format!("{:04X}  {:<47}  {}\n", address, hex.join(" "), text);
+            address = row_end + 1;
+        }
+        self.next_memory = address as Word;
+        Ok(output)
+    }
+
+    fn deposit(&mut self, arguments: &[&str]) -> Result<String, String> {
+        let [address, bytes @ ..] = arguments else {
+            return Err(String::from("expected an address and bytes"));
+        };
+        if bytes.is_empty() {
+            return Err(String::from("expected an address and bytes"));
+        }
+        let address: Word = parse_address(address)?;
+        for (offset, byte) in bytes.iter().enumerate() {
+            let byte: Byte = parse_byte(byte)?;
+            self.debugger
+                .bus
+                .write(address.wrapping_add(offset as Word), byte);
+        }
+        self.debugger.clear_history();
+        Ok(String::new())
+    }
+
+    fn disassemble(&mut self, arguments: &[&str]) -> Result<String, String> {
+        let instructions: Vec<Disassembly> = match arguments {
+            [] | [_] => {
+                let mut address: Word = match arguments {
+                    [start] => parse_address(start)?,
+                    _ => self.next_disassembly,
+                };
+                (0..10)
+                    .map(|_| {
+                        let instruction = disassembler::disassemble_variant(
+                            &mut self.debugger.bus,
+                            address,
+                            self.debugger.cpu.variant,
+                        );
+                        address = address.wrapping_add(instruction.length());
+                        instruction
+                    })
+                    .collect()
+            }
+            [start, end] => disassembler::disassemble_range_variant(
+                &mut self.debugger.bus,
+                parse_address(start)?,
+                parse_address(end)?,
+                self.debugger.cpu.variant,
+            ),
+            _ => return Err(String::from("expected a start and end address")),
+        };
+        if let Some(last) = instructions.last() {
+            self.next_disassembly = last.address.wrapping_add(last.length());
+        }
+        Ok(instructions
+            .iter()
+            .map(|instruction| instruction.format(true) + "\n")
+            .collect())
+    }
+
+    fn assemble(&mut self, arguments: &[&str]) -> Result<String, String> {
+        let [address, instruction @ ..] = arguments else {
+            return Err(String::from("expected an address and an instruction"));
+        };
+        let address: Word = parse_address(address)?;
+        let source: String = format!(".org ${:04X}\n{}", address, instruction.join(" "));
+        let opcodes: &'static [OpcodeInfo; 256] = self.debugger.cpu.variant.opcodes();
+        let program: asm::Program =
+            asm::assemble_for(&source, opcodes).map_err(|error| match error.kind {
+                asm::AssemblyErrorKind::Syntax(message) => message,
+                _ => error.to_string().replacen("line 2: ", "", 1),
+            })?;
+        for segment in &program.segments {
+            for (offset, byte) in segment.bytes.iter().enumerate() {
+                self.debugger
+                    .bus
+                    .write(segment.address.wrapping_add(offset as Word), *byte);
+            }
+        }
+        self.debugger.clear_history();
+        let instruction: Disassembly = disassembler::disassemble_variant(
+            &mut self.debugger.bus,
+            address,
+            self.debugger.cpu.variant,
+        );
+        self.next_disassembly = address.wrapping_add(instruction.length());
+        Ok(instruction.format(true) + "\n")
+    }
+
+    fn registers(&mut self, arguments: &[&str]) -> Result<String, String> {
+        for argument in arguments {
+            let (name, value) = argument
+                .split_once('=')
+                .ok_or_else(|| format!("expected register=value, got '{}'", argument))?;
+            let cpu = &mut self.debugger.cpu;
+            match name.to_ascii_lowercase().as_str() {
+                "pc" => cpu.program_counter = parse_address(value)?,
+                "a" => cpu.accumulator = parse_byte(value)?,
+                "x" => cpu.register_x = parse_byte(value)?,
+                "y" => cpu.register_y = parse_byte(value)?,
+                "sp" => cpu.stack_pointer = parse_byte(value)?,
+                "p" => cpu.processor_status = ProcessorStatus(parse_byte(value)?),
+                _ => return Err(format!("unknown register '{}'", name)),
+            }
+            self.debugger.clear_history();
+        }
+        self.next_disassembly = self.debugger.cpu.program_counter;
+        Ok(self.status())
+    }
+
+    fn breakpoint(&mut self, arguments: &[&str]) -> Result<String, String> {
+        match arguments {
+            [] => Ok(self
+                .debugger
+                .breakpoints()
+                .iter()
+                .map(|breakpoint| format_breakpoint(breakpoint) + "\n")
+                .collect()),
+            [address] => {
+                let id = self.debugger.add_breakpoint(parse_address(address)?);
+                Ok(format!("breakpoint {}\n", id))
+            }
+            [address, "if", condition @ ..] => {
+                let address: Word = parse_address(address)?;
+                let condition: Condition = parse_condition(&condition.join(" "))?;
+                let id = self.debugger.add_conditional_breakpoint(address, condition);
+                Ok(format!("breakpoint {}\n", id))
+            }
+            _ => Err(String::from(
+                "expected an address and an optional 'if' condition",
+            )),
+        }
+    }
+
+    fn watchpoint(&mut self, arguments: &[&str]) -> Result<String, String> {
+        let (kind, range): (WatchKind, &[&str]) = match arguments {
+            [] => {
+                return Ok(self
+                    .debugger
+                    .watchpoints()
+                    .iter()
+                    .map(|watchpoint| format_watchpoint(watchpoint) + "\n")
+                    .collect())
+            }
+            ["r", range @ ..] => (WatchKind::Read, range),
+            ["w", range @ ..] => (WatchKind::Write, range),
+            ["rw", range @ ..] => (WatchKind::Access, range),
+            range => (WatchKind::Access, range),
+        };
+        let (start, end): (Word, Word) = match range {
+            [start] => (parse_address(start)?, parse_address(start)?),
+            [start, end] => (parse_address(start)?, parse_address(end)?),
+            _ => return Err(String::from("expected a start and optional end address")),
+        };
+        let id = self.debugger.add_watchpoint(start..=end, kind);
+        Ok(format!("watchpoint {}\n", id))
+    }
+
+    /// Loads a file in the format its extension names, or a raw binary at
+    /// the given address. A file that gives a start address sets PC.
+    fn load(&mut self, arguments: &[&str]) -> Result<String, String> {
+        let (path, address): (&str, Option<Word>) = match arguments {
+            [path] => (path, None),
+            [path, address] => (path, Some(parse_address(address)?)),
+            _ => return Err(String::from("expected a file name and an optional address")),
+        };
+        let path: &str = path.trim_matches('"');
+        let format: Format = Format::from_path(Path::new(path), address.unwrap_or(0x0000));
+        if address.is_some() && !matches!(format, Format::Raw(_)) {
+            return Err(format!("{} gives its own load address", path));
+        }
+        let bytes: Vec<Byte> =
+            std::fs::read(path).map_err(|error| format!("{}: {}", path, error))?;
+        let image: Image = loader::load(format, &bytes, &mut self.debugger.bus)
+            .map_err(|error| format!("{}: {}", path, error))?;
+        self.debugger.clear_history();
+        let mut output: String = String::new();
+        for segment in &image.segments {
+            output += &format!("loaded {:04X}-{:04X}\n", segment.address, segment.end());
+        }
+        if image.segments.is_empty() {
+            output += "loaded nothing\n";
+        }
+        if let Some(start) = image.start {
+            self.debugger.cpu.program_counter = start;
+            self.next_disassembly = start;
+            output += &format!("start {:04X}\n", start);
+        }
+        Ok(output)
+    }
+
+    fn save(&mut self, arguments: &[&str]) -> Result<String, String> {
+        let [path, start, end] = arguments else {
+            return Err(String::from("expected a file name, start and end address"));
+        };
+        let path: &str = path.trim_matches('"');
+        let (start, end): (Word, Word) = (parse_address(start)?, parse_address(end)?);
+        if end < start {
+            return Err(String::from("end address is before start address"));
+        }
+        let bytes: Vec<Byte> = (start..=end)
+            .map(|address| self.debugger.cpu.peek(address, &mut self.debugger.bus))
+            .collect();
+        std::fs::write(path, bytes).map_err(|error| format!("{}: {}", path, error))?;
+        Ok(format!("saved {:04X}-{:04X}\n", start, end))
+    }
+
+    /// Runs the CPU and reports why it stopped, followed by the registers
+    /// and the next instruction.
+    fn run(
+        &mut self,
+        run: impl FnOnce(&mut Debugger<B>) -> Result<StopReason, ExecutionError>,
+    ) -> Result<String, String> {
+        let mut output: String = match run(&mut self.debugger) {
+            Ok(StopReason::Step) => String::new(),
+            Ok(StopReason::Breakpoint(id)) => format!("breakpoint {}\n", id),
+            Ok(StopReason::Watchpoint(hit)) => format!(
+                "watchpoint {}: {} ${:02X} at {:04X}\n",
+                hit.id,
+                if hit.write { "write" } else { "read" },
+                hit.data,
+                hit.address
+            ),
+            Ok(StopReason::InstructionLimit) => {
+                format!("stopped after {} instructions\n", INSTRUCTION_LIMIT)
+            }
+            Err(error) => format!("{}\n", error),
+        };
+        let program_counter: Word = self.debugger.cpu.program_counter;
+        let next: Disassembly = disassembler::disassemble_variant(
+            &mut self.debugger.bus,
+            program_counter,
+            self.debugger.cpu.variant,
+        );
+        self.next_disassembly = program_counter.wrapping_add(next.length());
+        output += &self.status();
+        output += &next.format(true);
+        output.push('\n');
+        Ok(output)
+    }
+
+    /// The registers, with set flags in upper case: `nv-bdIZc`.
+    fn status(&self) -> String {
+        let cpu = &self.debugger.cpu;
+        let status: Byte = cpu.processor_status.0;
+        let flags: String = "NV-BDIZC"
+            .chars()
+            .enumerate()
+            .map(|(bit, flag)| {
+                if status & (0x80 >> bit) != 0 {
+                    flag
+                } else {
+                    flag.to_ascii_lowercase()
+                }
+            })
+            .collect();
+        format!(
+            "PC:{:04X} A:{:02X} X:{:02X} Y:{:02X} SP:{:02X} P:{:02X} {}\n",
+            cpu.program_counter,
+            cpu.accumulator,
+            cpu.register_x,
+            cpu.register_y,
+            cpu.stack_pointer,
+            status,
+            flags
+        )
+    }
+
+    /// An optional `start [end]` range, defaulting to `default_start` and
+    /// `length` bytes after the start.
+    fn range(
+        &self,
+        arguments: &[&str],
+        default_start: Word,
+        length: Word,
+    ) -> Result<(Word, Word), String> {
+        match arguments {
+            [] => Ok((default_start, default_start.saturating_add(length))),
+            [start] => {
+                let start: Word = parse_address(start)?;
+                Ok((start, start.saturating_add(length)))
+            }
+            [start, end] => Ok((parse_address(start)?, parse_address(end)?)),
+            _ => Err(String::from("expected a start and end address")),
+        }
+    }
+}
+
+fn single<'a>(arguments: &[&'a str]) -> Result<&'a str, String> {
+    match arguments {
+        [argument] => Ok(argument),
+        _ => Err(String::from("expected one argument")),
+    }
+}
+
+fn parse_number(text: &str) -> Result<u32, String> {
+    let digits: &str = text
+        .strip_prefix('$')
+        .or_else(|| text.strip_prefix("0x"))
+        .unwrap_or(text);
+    u32::from_str_radix(digits, 16).map_err(|_| format!("invalid number '{}'", text))
+}
+
+fn parse_address(text: &str) -> Result<Word, String> {
+    Word::try_from(parse_number(text)?).map_err(|_| format!("address '{}' is out of range", text))
+}
+
+fn parse_byte(text: &str) -> Result<Byte, String> {
+    Byte::try_from(parse_number(text)?).map_err(|_| format!("byte '{}' is out of range", text))
+}
+
+const COMPARISONS: [(&str, Comparison); 6] = [
+    ("==", Comparison::Equal),
+    ("!=", Comparison::NotEqual),
+    ("<=", Comparison::LessOrEqual),
+    (">=", Comparison::GreaterOrEqual),
+    ("<", Comparison::Less),
+    (">", Comparison::Greater),
+];
+
+const FLAGS: [(&str, Flag); 6] = [
+    ("c", Flag::Carry),
+    ("z", Flag::Zero),
+    ("i", Flag::Interrupt),
+    ("d", Flag::Decimal),
+    ("v", Flag::Overflow),
+    ("n", Flag::Negative),
+];
+
+const REGISTERS: [(&str, RegisterType); 4] = [
+    ("a", RegisterType::Accumulator),
+    ("x", RegisterType::RegisterX),
+    ("y", RegisterType::RegisterY),
+    ("sp", RegisterType::StackPointer),
+];
+
+/// Parses `register op value` or `flag == 0|1`, e.g. `x >= $10` or `z == 1`.
+fn parse_condition(text: &str) -> Result<Condition, String> {
+    let (position, symbol, comparison) = COMPARISONS
+        .iter()
+        .find_map(|&(symbol, comparison)| Some((text.find(symbol)?, symbol, comparison)))
+        .ok_or_else(|| format!("expected a comparison in '{}'", text))?;
+    let name: String = text[..position].trim().to_ascii_lowercase();
+    let value: &str = text[position + symbol.len()..].trim();
+    if let Some(&(_, register)) = REGISTERS.iter().find(|(n, _)| *n == name) {
+        return Ok(Condition::Register(
+            register,
+            comparison,
+            parse_byte(value)?,
+        ));
+    }
+    let &(_, flag) = FLAGS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .ok_or_else(|| format!("unknown register or flag '{}'", name))?;
+    let set: bool = match value {
+        "0" => false,
+        "1" => true,
+        _ => return Err(String::from("flags can only be compared with 0 or 1")),
+    };
+    match comparison {
+        Comparison::Equal => Ok(Condition::Flag(flag, set)),
+        Comparison::NotEqual => Ok(Condition::Flag(flag, !set)),
+        _ => Err(String::from("flags can only be compared with == or !=")),
+    }
+}
+
+fn format_breakpoint(breakpoint: &Breakpoint) -> String {
+    let mut text: String = format!("{}: {:04X}", breakpoint.id, breakpoint.address);
+    match breakpoint.condition {
+        Some(Condition::Register(register, comparison, value)) => {
+            let name: &str = REGISTERS.iter().find(|(_, r)| *r == register).unwrap().0;
+            let symbol: &str = COMPARISONS
+                .iter()
+                .find(|(_, c)| *c == comparison)
+                .unwrap()
+                .0;
+            text += &format!(" if {} {} ${:02X}", name, symbol, value);
+        }
+        Some(Condition::Flag(flag, set)) => {
+            let name: &str = FLAGS.iter().find(|(_, f)| *f == flag).unwrap().0;
+            text += &format!(" if {} == {}", name, set as u8);
+        }
+        None => {}
+    }
+    if breakpoint.temporary {
+        text += " (temporary)";
+    }
+    text
+}
+
+fn format_watchpoint(watchpoint: &Watchpoint) -> String {
+    let kind: &str = match watchpoint.kind {
+        WatchKind::Read => "r",
+        WatchKind::Write => "w",
+        WatchKind::Access => "rw",
+    };
+    format!(
+        "{}: {} {:04X}-{:04X}",
+        watchpoint.id,
+        kind,
+        watchpoint.range.start(),
+        watchpoint.range.end()
+    )
+}
@@ -0,0 +1,162 @@
+use std::collections::VecDeque;
+
+use crate::snapshot::{self, SaveState};
+use crate::{Bus, Byte, SnapshotError, Word, CPU};
+
+/// A snapshot taken just before instruction number `instruction` ran.
+struct Checkpoint {
+    instruction: u64,
+    snapshot: Vec<Byte>,
+}
+
+/// The memory writes one instruction made.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JournalEntry {
+    /// How many instructions had run before this one.
+    pub instruction: u64,
+    /// Where the instruction was fetched from.
+    pub address: Word,
+    /// Every write in bus order, as (address, data).
+    pub writes: Vec<(Word, Byte)>,
+}
+
+/// Recorded execution that lets a `Debugger` go backwards.
+///
+/// A snapshot is taken every `interval` instructions and kept in a ring of
+/// `capacity` checkpoints, alongside a journal of the writes made by every
+/// instruction since the oldest one. Going back restores the nearest
+/// earlier checkpoint and replays forward from it, so the bus must behave
+/// the same way when replayed, which holds for anything whose state is
+/// covered by `SaveState`.
+pub struct History<B> {
+    interval: u64,
+    capacity: usize,
+    checkpoints: VecDeque<Checkpoint>,
+    journal: VecDeque<JournalEntry>,
+    /// Instructions run since recording started.
+    instructions: u64,
+    // Stored so that `Debugger` can record without requiring `SaveState`
+    // for every bus
+    save: fn(&CPU, &B) -> Vec<Byte>,
+    load: fn(&[Byte], &mut CPU, &mut B) -> Result<(), SnapshotError>,
+}
+
+impl<B: Bus + SaveState> History<B> {
+    /// Panics if `interval` or `capacity` is zero.
+    pub fn new(interval: u64, capacity: usize) -> Self {
+        assert!(interval > 0 && capacity > 0, "rewind needs a checkpoint");
+        Self {
+            interval,
+            capacity,
+            checkpoints: VecDeque::new(),
+            journal: VecDeque::new(),
+            instructions: 0,
+            save: snapshot::save::<B>,
+            load: snapshot::load::<B>,
+        }
+    }
+}
+
+impl<B: Bus> History<B> {
+    /// How many instructions back the history reaches.
+    pub fn depth(&self) -> u64 {
+        self.checkpoints
+            .front()
+            .map_or(0, |checkpoint| self.instructions - checkpoint.instruction)
+    }
+
+    /// Instructions recorded since rewind was enabled.
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    pub fn journal(&self) -> impl DoubleEndedIterator<Item = &JournalEntry> {
+        self.journal.iter()
+    }
+
+    /// Drops every checkpoint, so that recording starts again from the
+    /// next instruction.
+    pub(crate) fn clear(&mut self) {
+        self.checkpoints.clear();
+        self.journal.clear();
+        self.instructions = 0;
+    }
+
+    /// Called before each instruction runs, to take a checkpoint when one
+    /// is due.
+    pub(crate) fn before_instruction(&mut self, cpu: &CPU, bus: &B) {
+        // An instruction that failed is attempted again without a second
+        // checkpoint
+        if !self.instructions.is_multiple_of(self.interval)
+            || self
+                .checkpoints
+                .back()
+                .is_some_and(|checkpoint| checkpoint.instruction == self.instructions)
+        {
+            return;
+        }
+        if self.checkpoints.len() == self.capacity {
+            self.checkpoints.pop_front();
+            let oldest: u64 = self.checkpoints.front().map_or(0, |c| c.instruction);
+            while self
+                .journal
+                .front()
+                .is_some_and(|entry| entry.instruction < oldest)
+            {
+                self.journal.pop_front();
+            }
+        }
+        self.checkpoints.push_back(Checkpoint {
+            instruction: self.instructions,
+            snapshot: (self.save)(cpu, bus),
+        });
+    }
+
+    /// Called after an instruction ran, with the writes it made.
+    pub(crate) fn after_instruction(&mut self, address: Word, writes: Vec<(Word, Byte)>) {
+        self.journal.push_back(JournalEntry {
+            instruction: self.instructions,
+            address,
+            writes,
+        });
+        self.instructions += 1;
+    }
+
+    /// The number of the most recent instruction that wrote to `address`.
+    pub(crate) fn last_write(&self, address: Word) -> Option<u64> {
+        self.journal
+            .iter()
+            .rev()
+            .find(|entry| entry.writes.iter().any(|&(written, _)| written == address))
+            .map(|entry| entry.instruction)
+    }
+
+    /// Puts the CPU and bus back to just before instruction number
+    /// `instruction` ran, forgetting everything recorded after that.
+    /// Returns false, changing nothing, if the history does not reach that
+    /// far back.
+    pub(crate) fn restore(&mut self, instruction: u64, cpu: &mut CPU, bus: &mut B) -> bool {
+        if instruction > self.instructions {
+            return false;
+        }
+        let Some(index) = self
+            .checkpoints
+            .iter()
+            .rposition(|checkpoint| checkpoint.instruction <= instruction)
+        else {
+            return false;
+        };
+        let checkpoint: &Checkpoint = &self.checkpoints[index];
+        (self.load)(&checkpoint.snapshot, cpu, bus)
+            .expect("a checkpoint taken by this history loads");
+        for _ in checkpoint.instruction..instruction {
+            cpu.step(bus)
+                .expect("replaying recorded instructions succeeds");
+        }
+        self.checkpoints
+            .retain(|checkpoint| checkpoint.instruction <= instruction);
+        self.journal.retain(|entry| entry.instruction < instruction);
+        self.instructions = instruction;
+        true
+    }
+}
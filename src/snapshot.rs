@@ -0,0 +1,164 @@
+use crate::{Byte, IoPort, Memory, ProcessorStatus, SnapshotError, StepState, Variant, Word, CPU};
+
+/// Every snapshot starts with these bytes.
+pub const SIGNATURE: &[Byte; 8] = b"6502SNAP";
+/// Bumped whenever the layout changes. `load` only reads this version.
+pub const VERSION: u16 = 2;
+
+/// The variants in the order of the variant byte that identifies them.
+const VARIANTS: [Variant; 4] = [
+    Variant::Nmos6502,
+    Variant::Wdc65C02,
+    Variant::Ricoh2A03,
+    Variant::Mos6510,
+];
+
+/// A bus whose state, including any peripherals on it, can be stored in a
+/// snapshot.
+pub trait SaveState {
+    fn save_state(&self, output: &mut Vec<Byte>);
+    /// Restores the state written by `save_state`. Must leave the bus
+    /// unchanged if it returns an error.
+    fn load_state(&mut self, data: &[Byte]) -> Result<(), SnapshotError>;
+}
+
+impl SaveState for Memory {
+    fn save_state(&self, output: &mut Vec<Byte>) {
+        output.extend_from_slice(&self.data);
+    }
+
+    fn load_state(&mut self, data: &[Byte]) -> Result<(), SnapshotError> {
+        if data.len() != self.data.len() {
+            return Err(SnapshotError::InvalidBusState);
+        }
+        self.data.copy_from_slice(data);
+        Ok(())
+    }
+}
+
+/// Serializes the CPU and bus into a snapshot.
+///
+/// The layout, with every number little-endian, is the signature, the
+/// version, a byte for the variant, PC, SP, A, X, Y, P, the cycle counter, a
+/// byte of interrupt line, pending interrupt and WAI/STP bits, the 6510
+/// port's direction, data and charge bytes followed by the cycle at which
+/// each pin fades, and finally the length of the bus state followed by the
+/// bus state itself.
+///
+/// Not saved are the unknown opcode policy, since it may hold a function
+/// pointer, the `undocumented_opcodes` settings and the wiring of the port;
+/// `load` keeps whatever the target CPU has.
+pub fn save<B: SaveState>(cpu: &CPU, bus: &B) -> Vec<Byte> {
+    let mut output: Vec<Byte> = Vec::new();
+    output.extend_from_slice(SIGNATURE);
+    output.extend_from_slice(&VERSION.to_le_bytes());
+    let variant: usize = VARIANTS.iter().position(|&v| v == cpu.variant).unwrap();
+    output.push(variant as Byte);
+    output.extend_from_slice(&cpu.program_counter.to_le_bytes());
+    output.extend_from_slice(&[
+        cpu.stack_pointer,
+        cpu.accumulator,
+        cpu.register_x,
+        cpu.register_y,
+        cpu.processor_status.0,
+    ]);
+    output.extend_from_slice(&cpu.cycles.to_le_bytes());
+    let interrupts: [bool; 8] = [
+        cpu.irq_line,
+        cpu.nmi_line,
+        cpu.nmi_pending,
+        cpu.reset_pending,
+        cpu.delayed_interrupt_flag.is_some(),
+        cpu.delayed_interrupt_flag.unwrap_or(false),
+        cpu.waiting,
+        cpu.stopped,
+    ];
+    output.push(
+        interrupts
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (bit, &set)| bits | ((set as Byte) << bit)),
+    );
+    let port: &IoPort = &cpu.io_port;
+    output.extend_from_slice(&[port.direction, port.data, port.charge]);
+    for fade_at in port.fade_at {
+        output.extend_from_slice(&fade_at.to_le_bytes());
+    }
+    let mut bus_state: Vec<Byte> = Vec::new();
+    bus.save_state(&mut bus_state);
+    output.extend_from_slice(&(bus_state.len() as u32).to_le_bytes());
+    output.extend_from_slice(&bus_state);
+    output
+}
+
+/// Restores a snapshot written by `save`. On error the CPU and bus are left
+/// as they were. The variant is restored from the snapshot, but the CPU
+/// keeps its current unknown opcode policy, undocumented opcode settings
+/// and port wiring.
+pub fn load<B: SaveState>(data: &[Byte], cpu: &mut CPU, bus: &mut B) -> Result<(), SnapshotError> {
+    let mut reader: Reader = Reader { data };
+    if reader.take(SIGNATURE.len()) != Ok(SIGNATURE) {
+        return Err(SnapshotError::NotASnapshot);
+    }
+    let version: u16 = u16::from_le_bytes(reader.array()?);
+    if version != VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+    let mut loaded: CPU = *cpu;
+    let [variant] = reader.array()?;
+    loaded.variant = *VARIANTS
+        .get(variant as usize)
+        .ok_or(SnapshotError::UnknownVariant(variant))?;
+    loaded.program_counter = Word::from_le_bytes(reader.array()?);
+    let [sp, a, x, y, p] = reader.array()?;
+    loaded.stack_pointer = sp;
+    loaded.accumulator = a;
+    loaded.register_x = x;
+    loaded.register_y = y;
+    loaded.processor_status = ProcessorStatus(p);
+    loaded.cycles = u64::from_le_bytes(reader.array()?);
+    let [interrupts] = reader.array()?;
+    let bit = |bit: u8| interrupts & (1 << bit) != 0;
+    loaded.irq_line = bit(0);
+    loaded.nmi_line = bit(1);
+    loaded.nmi_pending = bit(2);
+    loaded.reset_pending = bit(3);
+    loaded.delayed_interrupt_flag = bit(4).then(|| bit(5));
+    loaded.waiting = bit(6);
+    loaded.stopped = bit(7);
+    let [direction, data, charge] = reader.array()?;
+    loaded.io_port.direction = direction;
+    loaded.io_port.data = data;
+    loaded.io_port.charge = charge;
+    for fade_at in &mut loaded.io_port.fade_at {
+        *fade_at = u64::from_le_bytes(reader.array()?);
+    }
+    loaded.step_state = StepState::default();
+    let length: u32 = u32::from_le_bytes(reader.array()?);
+    let bus_state: &[Byte] = reader.take(length as usize)?;
+    if !reader.data.is_empty() {
+        return Err(SnapshotError::TrailingData);
+    }
+    bus.load_state(bus_state)?;
+    *cpu = loaded;
+    Ok(())
+}
+
+struct Reader<'a> {
+    data: &'a [Byte],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [Byte], SnapshotError> {
+        if self.data.len() < length {
+            return Err(SnapshotError::Truncated);
+        }
+        let (taken, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[Byte; N], SnapshotError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+}
@@ -0,0 +1,32 @@
+use crate::instructions::Instruction;
+use crate::{Byte, Word};
+
+/// The interrupt sequences the CPU can run in place of an instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interrupt {
+    Reset,
+    Nmi,
+    Irq,
+}
+
+/// A record of one call to `CPU::step`.
+///
+/// Interrupt sequences are reported as a step of their own. The hardware
+/// runs them by forcing a BRK opcode into the instruction register, so they
+/// are recorded with the BRK opcode and `interrupt` set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Step {
+    /// Address the opcode was fetched from.
+    pub address: Word,
+    pub opcode: Byte,
+    /// The decoded instruction, or `None` for an undocumented opcode.
+    pub instruction: Option<Instruction>,
+    /// The operand bytes following the opcode, as a little-endian word.
+    pub operand: Option<Word>,
+    /// The memory address the instruction read, wrote or jumped to.
+    pub effective_address: Option<Word>,
+    pub cycles: i32,
+    /// Whether indexing or a taken branch crossed into another page.
+    pub page_crossed: bool,
+    pub interrupt: Option<Interrupt>,
+}
@@ -0,0 +1,221 @@
+use std::fmt;
+use std::io::Write;
+
+use crate::disassembler::{self, Disassembly};
+use crate::instructions::{AddressingMode, Mnemonic};
+use crate::{Bus, Byte, Step, TraceError, Word, CPU};
+
+/// Runs the CPU one instruction at a time, writing a line per instruction
+/// in the layout of nestest.log:
+///
+/// ```text
+/// C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD CYC:7
+/// ```
+///
+/// Each line shows the state before the instruction runs. The PPU column of
+/// the original log is left out.
+pub struct Tracer<W: Write> {
+    output: W,
+    /// Total cycles run so far. nestest.log starts counting at 7, the length
+    /// of the RESET sequence.
+    pub cycles: u64,
+}
+
+impl<W: Write> Tracer<W> {
+    pub fn new(output: W) -> Self {
+        Self { output, cycles: 0 }
+    }
+
+    /// Traces and executes one instruction. Interrupt sequences are run
+    /// without writing a line, but their cycles are still counted.
+    pub fn step<B: Bus>(&mut self, cpu: &mut CPU, bus: &mut B) -> Result<Step, TraceError> {
+        let line: String = trace_line(cpu, bus, self.cycles);
+        let step: Step = cpu.step(bus)?;
+        if step.interrupt.is_none() {
+            writeln!(self.output, "{}", line)?;
+        }
+        self.cycles += step.cycles as u64;
+        Ok(step)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+}
+
+/// Formats the instruction at the program counter and the CPU registers as
+/// one nestest.log line. Memory is inspected with `CPU::peek`, so tracing
+/// does not disturb peripherals. `P` is shown with bit 5 set and the B bit clear,
+/// as nestest.log does.
+pub fn trace_line<B: Bus>(cpu: &CPU, bus: &mut B, cycles: u64) -> String {
+    let instruction: Disassembly =
+        disassembler::disassemble_variant(bus, cpu.program_counter, cpu.variant);
+    let bytes: Vec<String> = instruction
+        .bytes
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect();
+    let marker: &str = if instruction.documented { " " } else { "" };
+    let text: String = format!(
+        "{}{}{}",
+        marker,
+        instruction,
+        operand_values(&instruction, cpu, bus)
+    );
+    format!(
+        "{:04X}  {:<8} {:<33}A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} CYC:{}",
+        cpu.program_counter,
+        bytes.join(" "),
+        text,
+        cpu.accumulator,
+        cpu.register_x,
+        cpu.register_y,
+        (cpu.processor_status.0 | 0x20) & !0x10,
+        cpu.stack_pointer,
+        cycles
+    )
+}
+
+/// The addresses and memory contents nestest.log shows after an operand,
+/// such as ` @ 0300 = 89` for `LDA $0300,X`.
+fn operand_values<B: Bus>(instruction: &Disassembly, cpu: &CPU, bus: &mut B) -> String {
+    let operand: Word = instruction.operand.unwrap_or(0);
+    let zero_page_word = |bus: &mut B, pointer: Byte| -> Word {
+        let lo: Byte = cpu.peek(pointer as Word, bus);
+        let hi: Byte = cpu.peek(pointer.wrapping_add(1) as Word, bus);
+        lo as Word | ((hi as Word) << 8)
+    };
+    match instruction.addressing_mode {
+        AddressingMode::ZeroPage => format!(" = {:02X}", cpu.peek(operand, bus)),
+        AddressingMode::ZeroPageX | AddressingMode::ZeroPageY => {
+            let index: Byte = if instruction.addressing_mode == AddressingMode::ZeroPageX {
+                cpu.register_x
+            } else {
+                cpu.register_y
+            };
+            let address: Word = (operand as Byte).wrapping_add(index) as Word;
+            format!(" @ {:02X} = {:02X}", address, cpu.peek(address, bus))
+        }
+        AddressingMode::Absolute => match instruction.mnemonic {
+            Mnemonic::JMP | Mnemonic::JSR => String::new(),
+            _ => format!(" = {:02X}", cpu.peek(operand, bus)),
+        },
+        AddressingMode::AbsoluteX | AddressingMode::AbsoluteY => {
+            let index: Byte = if instruction.addressing_mode == AddressingMode::AbsoluteX {
+                cpu.register_x
+            } else {
+                cpu.register_y
+            };
+            let address: Word = operand.wrapping_add(index as Word);
+            format!(" @ {:04X} = {:02X}", address, cpu.peek(address, bus))
+        }
+        AddressingMode::Indirect => {
+            // The NMOS chips read the high byte without carrying into the
+            // next page
+            let lo: Byte = cpu.peek(operand, bus);
+            let hi_address: Word = if cpu.variant.is_cmos() {
+                operand.wrapping_add(1)
+            } else {
+                (operand & 0xFF00) | (operand.wrapping_add(1) & 0x00FF)
+            };
+            let hi: Byte = cpu.peek(hi_address, bus);
+            format!(" = {:04X}", lo as Word | ((hi as Word) << 8))
+        }
+        AddressingMode::ZeroPageIndirect => {
+            let address: Word = zero_page_word(bus, operand as Byte);
+            format!(" = {:04X} = {:02X}", address, cpu.peek(address, bus))
+        }
+        AddressingMode::IndirectX => {
+            let pointer: Byte = (operand as Byte).wrapping_add(cpu.register_x);
+            let address: Word = zero_page_word(bus, pointer);
+            format!(
+                " @ {:02X} = {:04X} = {:02X}",
+                pointer,
+                address,
+                cpu.peek(address, bus)
+            )
+        }
+        AddressingMode::IndirectY => {
+            let base: Word = zero_page_word(bus, operand as Byte);
+            let address: Word = base.wrapping_add(cpu.register_y as Word);
+            format!(
+                " = {:04X} @ {:04X} = {:02X}",
+                base,
+                address,
+                cpu.peek(address, bus)
+            )
+        }
+        _ => String::new(),
+    }
+}
+
+/// The first line where two traces disagree. `None` means that trace ended
+/// before the other one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    /// 1-based line number.
+    pub line: usize,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "traces diverge at line {}", self.line)?;
+        writeln!(
+            f,
+            "expected: {}",
+            self.expected.as_deref().unwrap_or("<end of trace>")
+        )?;
+        write!(
+            f,
+            "actual:   {}",
+            self.actual.as_deref().unwrap_or("<end of trace>")
+        )
+    }
+}
+
+/// Compares a trace against a reference log and returns the first line
+/// where they differ.
+///
+/// Lines are compared on the address, the instruction bytes and the
+/// `A`, `X`, `Y`, `P`, `SP` and `CYC` fields. The disassembly column is
+/// ignored, since logs disagree on how to spell undocumented opcodes, and
+/// so are any fields this tracer does not write, such as `PPU`.
+pub fn compare_traces(actual: &str, reference: &str) -> Option<Divergence> {
+    let mut actual_lines = actual.lines();
+    let mut reference_lines = reference.lines();
+    let mut line: usize = 0;
+    loop {
+        line += 1;
+        let (expected, actual): (Option<&str>, Option<&str>) =
+            (reference_lines.next(), actual_lines.next());
+        let matches: bool = match (expected, actual) {
+            (None, None) => return None,
+            (Some(expected), Some(actual)) => trace_fields(expected) == trace_fields(actual),
+            _ => false,
+        };
+        if !matches {
+            return Some(Divergence {
+                line,
+                expected: expected.map(String::from),
+                actual: actual.map(String::from),
+            });
+        }
+    }
+}
+
+const COMPARED_REGISTERS: [&str; 6] = ["A", "X", "Y", "P", "SP", "CYC"];
+
+fn trace_fields(line: &str) -> (&str, Vec<&str>) {
+    let prefix: &str = line.get(..14).unwrap_or(line).trim_end();
+    let registers: Vec<&str> = line
+        .split_whitespace()
+        .filter(|field| {
+            field
+                .split_once(':')
+                .is_some_and(|(name, _)| COMPARED_REGISTERS.contains(&name))
+        })
+        .collect();
+    (prefix, registers)
+}
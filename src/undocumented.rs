@@ -0,0 +1,223 @@
+use crate::instructions::{AddressingMode, Mnemonic, OpcodeInfo};
+use crate::{Bus, Byte, RegisterType, Word, CPU};
+
+/// Turns on the undocumented NMOS opcodes, which otherwise go to the
+/// unknown opcode policy. JAM opcodes still halt the CPU with
+/// `ExecutionError::Jam`.
+///
+/// XAA ($8B) and LXA ($AB) are unstable: the accumulator is ORed with a
+/// constant that depends on the chip and its temperature before it is
+/// ANDed with the operand. SHA, SHX, SHY and TAS store a register ANDed
+/// with the high byte of the base address plus one, and when indexing
+/// crosses a page that value also replaces the high byte of the address
+/// written to. This is what most chips do, but not all.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UndocumentedOpcodes {
+    /// The constant XAA and LXA OR into the accumulator. Common values are
+    /// $EE, $EF, $FE and $FF.
+    pub magic: Byte,
+}
+
+impl Default for UndocumentedOpcodes {
+    fn default() -> Self {
+        Self { magic: 0xEE }
+    }
+}
+
+impl CPU {
+    pub(crate) fn execute_undocumented<B: Bus>(
+        &mut self,
+        cycles: &mut i32,
+        info: &OpcodeInfo,
+        settings: UndocumentedOpcodes,
+        bus: &mut B,
+    ) {
+        match info.mnemonic {
+            Mnemonic::NOP if info.addressing_mode == AddressingMode::Implied => {
+                self.dummy_read_program_counter(cycles, bus);
+            }
+            Mnemonic::NOP => {
+                self.read_operand(cycles, info, bus);
+            }
+            Mnemonic::SBC => {
+                let operand: Byte = self.read_operand(cycles, info, bus);
+                self.subtract_with_carry(operand);
+            }
+
+            //
+            // Combined read-modify-write and accumulator operations
+            //
+            Mnemonic::SLO => self.modify_operand(cycles, info, CPU::shift_left_or, bus),
+            Mnemonic::RLA => self.modify_operand(cycles, info, CPU::rotate_left_and, bus),
+            Mnemonic::SRE => self.modify_operand(cycles, info, CPU::shift_right_eor, bus),
+            Mnemonic::RRA => self.modify_operand(cycles, info, CPU::rotate_right_adc, bus),
+            Mnemonic::DCP => self.modify_operand(cycles, info, CPU::decrement_cmp, bus),
+            Mnemonic::ISC => self.modify_operand(cycles, info, CPU::increment_sbc, bus),
+
+            //
+            // Loads & Stores
+            //
+            Mnemonic::LAX => {
+                self.load_register(cycles, info, &RegisterType::Accumulator, bus);
+                self.register_x = self.accumulator;
+            }
+            Mnemonic::LAS => {
+                let operand: Byte = self.read_operand(cycles, info, bus);
+                let result: Byte = operand & self.stack_pointer;
+                self.accumulator = result;
+                self.register_x = result;
+                self.stack_pointer = result;
+                self.set_zero_and_negative(result);
+            }
+            Mnemonic::SAX => {
+                let address: Word = self.operand_address(cycles, info, bus);
+                self.write_byte(cycles, address, self.accumulator & self.register_x, bus);
+            }
+            Mnemonic::SHA => {
+                let data: Byte = self.accumulator & self.register_x;
+                self.store_and_high_byte(cycles, info, data, bus);
+            }
+            Mnemonic::SHX => {
+                let data: Byte = self.register_x;
+                self.store_and_high_byte(cycles, info, data, bus);
+            }
+            Mnemonic::SHY => {
+                let data: Byte = self.register_y;
+                self.store_and_high_byte(cycles, info, data, bus);
+            }
+            Mnemonic::TAS => {
+                self.stack_pointer = self.accumulator & self.register_x;
+                let data: Byte = self.stack_pointer;
+                self.store_and_high_byte(cycles, info, data, bus);
+            }
+
+            //
+            // Immediate
+            //
+            Mnemonic::ANC => {
+                let operand: Byte = self.read_operand(cycles, info, bus);
+                self.logical_and(operand);
+                self.processor_status
+                    .set_carry(self.processor_status.negative());
+            }
+            Mnemonic::ALR => {
+                let operand: Byte = self.read_operand(cycles, info, bus);
+                let data: Byte = self.accumulator & operand;
+                self.accumulator = self.shift_right(data);
+            }
+            Mnemonic::ARR => {
+                let operand: Byte = self.read_operand(cycles, info, bus);
+                self.and_rotate_right(operand);
+            }
+            Mnemonic::SBX => {
+                let operand: Byte = self.read_operand(cycles, info, bus);
+                let data: Byte = self.accumulator & self.register_x;
+                self.processor_status.set_carry(data >= operand);
+                self.register_x = data.wrapping_sub(operand);
+                self.load_register_set_status(&RegisterType::RegisterX);
+            }
+            Mnemonic::XAA => {
+                let operand: Byte = self.read_operand(cycles, info, bus);
+                self.accumulator = (self.accumulator | settings.magic) & self.register_x & operand;
+                self.load_register_set_status(&RegisterType::Accumulator);
+            }
+            Mnemonic::LXA => {
+                let operand: Byte = self.read_operand(cycles, info, bus);
+                self.accumulator = (self.accumulator | settings.magic) & operand;
+                self.register_x = self.accumulator;
+                self.load_register_set_status(&RegisterType::Accumulator);
+            }
+            mnemonic => unreachable!("{} is not an undocumented instruction", mnemonic),
+        }
+    }
+
+    /// The store half of SHA, SHX, SHY and TAS. Only the indexed addressing
+    /// modes exist, and they always spend the cycle fixing up the address.
+    fn store_and_high_byte<B: Bus>(
+        &mut self,
+        cycles: &mut i32,
+        info: &OpcodeInfo,
+        data: Byte,
+        bus: &mut B,
+    ) {
+        let address: Word = self.operand_address(cycles, info, bus);
+        let index: Byte = match info.addressing_mode {
+            AddressingMode::AbsoluteX => self.register_x,
+            _ => self.register_y,
+        };
+        let base: Word = address.wrapping_sub(index as Word);
+        let data: Byte = data & ((base >> 8) as Byte).wrapping_add(1);
+        let address: Word = if self.step_state.page_crossed {
+            self.record_effective_address(((data as Word) << 8) | (address & 0x00FF))
+        } else {
+            address
+        };
+        self.write_byte(cycles, address, data, bus);
+    }
+
+    fn shift_left_or(&mut self, data: Byte) -> Byte {
+        let result: Byte = self.shift_left(data);
+        self.inclusive_or(result);
+        result
+    }
+
+    fn rotate_left_and(&mut self, data: Byte) -> Byte {
+        let result: Byte = self.rotate_left(data);
+        self.logical_and(result);
+        result
+    }
+
+    fn shift_right_eor(&mut self, data: Byte) -> Byte {
+        let result: Byte = self.shift_right(data);
+        self.exclusive_or(result);
+        result
+    }
+
+    fn rotate_right_adc(&mut self, data: Byte) -> Byte {
+        let result: Byte = self.rotate_right(data);
+        self.add_with_carry(result);
+        result
+    }
+
+    fn decrement_cmp(&mut self, data: Byte) -> Byte {
+        let result: Byte = data.wrapping_sub(1);
+        self.compare(&RegisterType::Accumulator, result);
+        result
+    }
+
+    fn increment_sbc(&mut self, data: Byte) -> Byte {
+        let result: Byte = data.wrapping_add(1);
+        self.subtract_with_carry(result);
+        result
+    }
+
+    /// ARR ANDs and rotates right, but takes its flags from the adder, so
+    /// in decimal mode it also applies a decimal correction as described in
+    /// 64doc.
+    fn and_rotate_right(&mut self, operand: Byte) {
+        let data: Byte = self.accumulator & operand;
+        let carry: bool = self.processor_status.carry();
+        let mut result: Byte = (data >> 1) | ((carry as Byte) << 7);
+        if !self.decimal_mode() {
+            self.set_zero_and_negative(result);
+            self.processor_status.set_carry(result & 0b01000000 > 0);
+            self.processor_status
+                .set_overflow(((result >> 6) ^ (result >> 5)) & 1 > 0);
+            self.accumulator = result;
+            return;
+        }
+        self.processor_status.set_negative(carry);
+        self.processor_status.set_zero(result == 0x00);
+        self.processor_status
+            .set_overflow((data ^ result) & 0b01000000 > 0);
+        if (data & 0x0F) + (data & 0x01) > 0x05 {
+            result = (result & 0xF0) | (result.wrapping_add(0x06) & 0x0F);
+        }
+        let high_carry: bool = (data & 0xF0) as Word + (data & 0x10) as Word > 0x50;
+        if high_carry {
+            result = result.wrapping_add(0x60);
+        }
+        self.processor_status.set_carry(high_carry);
+        self.accumulator = result;
+    }
+}
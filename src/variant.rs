@@ -0,0 +1,62 @@
+use crate::instructions::{Instruction, OpcodeInfo, OPCODES, OPCODES_65C02};
+use crate::Byte;
+
+/// The members of the 6502 family the CPU can emulate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Variant {
+    /// The original NMOS 6502.
+    #[default]
+    Nmos6502,
+    /// The WDC 65C02, with the extra instructions and addressing modes of
+    /// the CMOS chips, the Rockwell bit instructions and WAI/STP. It also
+    /// fixes the indirect JMP page wrap, sets N and Z properly in decimal
+    /// mode and clears D when taking an interrupt.
+    Wdc65C02,
+    /// The Ricoh 2A03 and 2A07 in the NES and its PAL version: an NMOS core,
+    /// with the same undocumented opcodes, whose decimal mode has been cut
+    /// off. D can still be set and cleared, but ADC and SBC ignore it.
+    Ricoh2A03,
+    /// The MOS 6510 used in the C64: an NMOS 6502 with an I/O port at $0000
+    /// and $0001, see `IoPort`.
+    Mos6510,
+}
+
+impl Variant {
+    /// The opcode table for the chip, indexed by opcode.
+    pub fn opcodes(self) -> &'static [OpcodeInfo; 256] {
+        match self {
+            Variant::Nmos6502 | Variant::Ricoh2A03 | Variant::Mos6510 => &OPCODES,
+            Variant::Wdc65C02 => &OPCODES_65C02,
+        }
+    }
+
+    pub fn opcode_info(self, opcode: Byte) -> &'static OpcodeInfo {
+        &self.opcodes()[opcode as usize]
+    }
+
+    /// Decodes a documented opcode, returning `None` for undocumented ones.
+    pub fn decode(self, opcode: Byte) -> Option<Instruction> {
+        let info: &OpcodeInfo = self.opcode_info(opcode);
+        if info.documented {
+            Some(info.instruction())
+        } else {
+            None
+        }
+    }
+
+    /// Whether the chip has the CMOS behaviour changes.
+    pub fn is_cmos(self) -> bool {
+        self == Variant::Wdc65C02
+    }
+
+    /// Whether reads and writes of $0000 and $0001 go to an on-chip I/O
+    /// port instead of the bus.
+    pub fn has_io_port(self) -> bool {
+        self == Variant::Mos6510
+    }
+
+    /// Whether ADC and SBC do decimal arithmetic when D is set.
+    pub fn has_decimal_mode(self) -> bool {
+        self != Variant::Ricoh2A03
+    }
+}
@@ -0,0 +1,1196 @@
+use bitfield::bitfield;
+
+use crate::instructions::{AddressingMode, Mnemonic, OpcodeInfo, OPCODES_65816};
+use crate::{Byte, LongAddress, LongBus, Word, IRQ_VECTOR, NMI_VECTOR, RESET_VECTOR};
+
+const ADDRESS_MASK: LongAddress = 0xFF_FFFF;
+
+const COP_VECTOR: Word = 0xFFF4;
+const NATIVE_COP_VECTOR: Word = 0xFFE4;
+const NATIVE_BRK_VECTOR: Word = 0xFFE6;
+const NATIVE_NMI_VECTOR: Word = 0xFFEA;
+const NATIVE_IRQ_VECTOR: Word = 0xFFEE;
+
+/// A WDC W65C816S, the 16-bit member of the family, on a 24-bit bus.
+///
+/// It starts out in emulation mode, where it runs 6502 code with 8-bit
+/// registers and the stack in page 1, until XCE switches it to native
+/// mode. There the M and X flags choose 8- or 16-bit accumulator and index
+/// registers.
+///
+/// The accumulator and index registers are always stored 16 bits wide.
+/// With an 8-bit accumulator only its low byte is used, and the high byte
+/// (B) keeps its value; with 8-bit index registers their high bytes are 0.
+///
+/// Every cycle either reads or writes the bus or, for internal operations,
+/// leaves it idle.
+#[derive(Clone, Copy, Debug)]
+pub struct CPU65816 {
+    pub processor_status: StatusRegister,
+    /// Set in emulation mode. XCE swaps it with the carry flag.
+    pub emulation: bool,
+    pub program_counter: Word,
+    pub program_bank: Byte,
+    /// The bank used by absolute and indirect addressing.
+    pub data_bank: Byte,
+    /// The bank 0 address the zero page addressing modes are relative to.
+    pub direct_page: Word,
+    pub stack_pointer: Word,
+    pub accumulator: Word,
+    pub register_x: Word,
+    pub register_y: Word,
+    /// Cycles used by `step` since the CPU was created.
+    pub cycles: u64,
+    irq_line: bool,
+    nmi_line: bool,
+    nmi_pending: bool,
+    reset_pending: bool,
+    /// Set by WAI until an interrupt arrives.
+    waiting: bool,
+    /// Set by STP until the next reset.
+    stopped: bool,
+}
+
+bitfield! {
+    /// The 65816 status register. In emulation mode M and X are always
+    /// set, and X is pushed as the break flag.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct StatusRegister(Byte);
+    Byte;
+    pub carry, set_carry: 0;
+    pub zero, set_zero: 1;
+    pub interrupt, set_interrupt: 2;
+    pub decimal, set_decimal: 3;
+    pub index_8bit, set_index_8bit: 4;
+    pub memory_8bit, set_memory_8bit: 5;
+    pub overflow, set_overflow: 6;
+    pub negative, set_negative: 7;
+}
+
+impl CPU65816 {
+    /// Runs instructions until at least `cycles` cycles have been used and
+    /// returns the number of cycles actually used.
+    pub fn execute<B: LongBus>(&mut self, mut cycles: i32, bus: &mut B) -> i32 {
+        let cycles_requested: i32 = cycles;
+        while cycles > 0 {
+            cycles -= self.step(bus);
+        }
+        cycles_requested - cycles
+    }
+
+    /// Executes exactly one instruction, or runs one pending interrupt
+    /// sequence, and returns the number of cycles it took. Block moves
+    /// copy one byte per step.
+    pub fn step<B: LongBus>(&mut self, bus: &mut B) -> i32 {
+        // The helpers count cycles down, so this ends up negative
+        let mut cycles: i32 = 0;
+        if !self.poll_interrupts(&mut cycles, bus) {
+            if self.waiting || self.stopped {
+                self.idle(&mut cycles);
+            } else {
+                let opcode: Byte = self.fetch_byte(&mut cycles, bus);
+                self.execute_instruction(&mut cycles, opcode, bus);
+            }
+        }
+        self.cycles += -cycles as u64;
+        -cycles
+    }
+
+    fn execute_instruction<B: LongBus>(&mut self, cycles: &mut i32, opcode: Byte, bus: &mut B) {
+        let info: &OpcodeInfo = &OPCODES_65816[opcode as usize];
+        let wide_memory: bool = !self.processor_status.memory_8bit();
+        let wide_index: bool = !self.processor_status.index_8bit();
+        match info.mnemonic {
+            //
+            // Load/Store Operations
+            //
+            Mnemonic::LDA => {
+                let data: Word = self.read_operand(cycles, info, wide_memory, bus);
+                self.set_accumulator(data);
+            }
+            Mnemonic::LDX => {
+                self.register_x = self.read_operand(cycles, info, wide_index, bus);
+                self.set_zero_and_negative(self.register_x, wide_index);
+            }
+            Mnemonic::LDY => {
+                self.register_y = self.read_operand(cycles, info, wide_index, bus);
+                self.set_zero_and_negative(self.register_y, wide_index);
+            }
+            Mnemonic::STA => self.store(cycles, info, self.accumulator, wide_memory, bus),
+            Mnemonic::STX => self.store(cycles, info, self.register_x, wide_index, bus),
+            Mnemonic::STY => self.store(cycles, info, self.register_y, wide_index, bus),
+            Mnemonic::STZ => self.store(cycles, info, 0x0000, wide_memory, bus),
+
+            //
+            // Register Transfers
+            //
+            Mnemonic::TAX => {
+                self.idle(cycles);
+                self.register_x = self.index_value(self.accumulator);
+                self.set_zero_and_negative(self.register_x, wide_index);
+            }
+            Mnemonic::TAY => {
+                self.idle(cycles);
+                self.register_y = self.index_value(self.accumulator);
+                self.set_zero_and_negative(self.register_y, wide_index);
+            }
+            Mnemonic::TXA => {
+                self.idle(cycles);
+                self.set_accumulator(self.register_x);
+            }
+            Mnemonic::TYA => {
+                self.idle(cycles);
+                self.set_accumulator(self.register_y);
+            }
+            Mnemonic::TXY => {
+                self.idle(cycles);
+                self.register_y = self.register_x;
+                self.set_zero_and_negative(self.register_y, wide_index);
+            }
+            Mnemonic::TYX => {
+                self.idle(cycles);
+                self.register_x = self.register_y;
+                self.set_zero_and_negative(self.register_x, wide_index);
+            }
+            Mnemonic::TCD => {
+                self.idle(cycles);
+                self.direct_page = self.accumulator;
+                self.set_zero_and_negative(self.direct_page, true);
+            }
+            Mnemonic::TDC => {
+                self.idle(cycles);
+                self.accumulator = self.direct_page;
+                self.set_zero_and_negative(self.accumulator, true);
+            }
+            Mnemonic::XBA => {
+                self.idle(cycles);
+                self.idle(cycles);
+                self.accumulator = self.accumulator.swap_bytes();
+                self.set_zero_and_negative(self.accumulator, false);
+            }
+            Mnemonic::XCE => {
+                self.idle(cycles);
+                let carry: bool = self.processor_status.carry();
+                self.processor_status.set_carry(self.emulation);
+                self.emulation = carry;
+                self.set_processor_status(self.processor_status.0);
+            }
+
+            //
+            // Stack Operations
+            //
+            Mnemonic::TSX => {
+                self.idle(cycles);
+                self.register_x = self.index_value(self.stack_pointer);
+                self.set_zero_and_negative(self.register_x, wide_index);
+            }
+            Mnemonic::TXS => {
+                self.idle(cycles);
+                self.set_stack_pointer(self.register_x);
+            }
+            Mnemonic::TCS => {
+                self.idle(cycles);
+                self.set_stack_pointer(self.accumulator);
+            }
+            Mnemonic::TSC => {
+                self.idle(cycles);
+                self.accumulator = self.stack_pointer;
+                self.set_zero_and_negative(self.accumulator, true);
+            }
+            Mnemonic::PHA => self.push_register(cycles, self.accumulator, wide_memory, bus),
+            Mnemonic::PHX => self.push_register(cycles, self.register_x, wide_index, bus),
+            Mnemonic::PHY => self.push_register(cycles, self.register_y, wide_index, bus),
+            Mnemonic::PHD => self.push_register(cycles, self.direct_page, true, bus),
+            Mnemonic::PHB => self.push_register(cycles, self.data_bank as Word, false, bus),
+            Mnemonic::PHK => self.push_register(cycles, self.program_bank as Word, false, bus),
+            Mnemonic::PHP => {
+                self.push_register(cycles, self.processor_status.0 as Word, false, bus);
+            }
+            Mnemonic::PLA => {
+                let data: Word = self.pop_register(cycles, wide_memory, bus);
+                self.set_accumulator(data);
+            }
+            Mnemonic::PLX => {
+                self.register_x = self.pop_register(cycles, wide_index, bus);
+                self.set_zero_and_negative(self.register_x, wide_index);
+            }
+            Mnemonic::PLY => {
+                self.register_y = self.pop_register(cycles, wide_index, bus);
+                self.set_zero_and_negative(self.register_y, wide_index);
+            }
+            Mnemonic::PLD => {
+                self.direct_page = self.pop_register(cycles, true, bus);
+                self.set_zero_and_negative(self.direct_page, true);
+            }
+            Mnemonic::PLB => {
+                self.data_bank = self.pop_register(cycles, false, bus) as Byte;
+                self.set_zero_and_negative(self.data_bank as Word, false);
+            }
+            Mnemonic::PLP => {
+                let status: Word = self.pop_register(cycles, false, bus);
+                self.set_processor_status(status as Byte);
+            }
+            Mnemonic::PEA => {
+                let data: Word = self.fetch_word(cycles, bus);
+                self.push_word_to_stack(cycles, data, bus);
+            }
+            Mnemonic::PEI => {
+                let offset: Byte = self.fetch_direct_offset(cycles, bus);
+                let data: Word = self.read_direct_word(cycles, offset as Word, bus);
+                self.push_word_to_stack(cycles, data, bus);
+            }
+            Mnemonic::PER => {
+                let offset: Word = self.fetch_word(cycles, bus);
+                self.idle(cycles);
+                self.push_word_to_stack(cycles, self.program_counter.wrapping_add(offset), bus);
+            }
+
+            //
+            // Logical
+            //
+            Mnemonic::AND => {
+                let operand: Word = self.read_operand(cycles, info, wide_memory, bus);
+                self.set_accumulator(self.accumulator & operand);
+            }
+            Mnemonic::EOR => {
+                let operand: Word = self.read_operand(cycles, info, wide_memory, bus);
+                self.set_accumulator(self.accumulator ^ operand);
+            }
+            Mnemonic::ORA => {
+                let operand: Word = self.read_operand(cycles, info, wide_memory, bus);
+                self.set_accumulator(self.accumulator | operand);
+            }
+            Mnemonic::BIT => {
+                let operand: Word = self.read_operand(cycles, info, wide_memory, bus);
+                let mask: Word = width_mask(wide_memory);
+                self.processor_status
+                    .set_zero(self.accumulator & operand & mask == 0x0000);
+                if info.addressing_mode != AddressingMode::Immediate {
+                    let sign: Word = sign_bit(wide_memory);
+                    self.processor_status.set_negative(operand & sign != 0);
+                    self.processor_status
+                        .set_overflow(operand & (sign >> 1) != 0);
+                }
+            }
+            Mnemonic::TRB => self.modify_operand(cycles, info, CPU65816::test_and_reset_bits, bus),
+            Mnemonic::TSB => self.modify_operand(cycles, info, CPU65816::test_and_set_bits, bus),
+
+            //
+            // Arithmetic
+            //
+            Mnemonic::ADC => {
+                let operand: Word = self.read_operand(cycles, info, wide_memory, bus);
+                self.add_with_carry(operand, false);
+            }
+            Mnemonic::SBC => {
+                let operand: Word = self.read_operand(cycles, info, wide_memory, bus);
+                self.add_with_carry(operand, true);
+            }
+            Mnemonic::CMP => {
+                let operand: Word = self.read_operand(cycles, info, wide_memory, bus);
+                self.compare(self.accumulator, operand, wide_memory);
+            }
+            Mnemonic::CPX => {
+                let operand: Word = self.read_operand(cycles, info, wide_index, bus);
+                self.compare(self.register_x, operand, wide_index);
+            }
+            Mnemonic::CPY => {
+                let operand: Word = self.read_operand(cycles, info, wide_index, bus);
+                self.compare(self.register_y, operand, wide_index);
+            }
+
+            //
+            // Increments, Decrements & Shifts
+            //
+            Mnemonic::INC => self.modify_operand(cycles, info, CPU65816::increment, bus),
+            Mnemonic::DEC => self.modify_operand(cycles, info, CPU65816::decrement, bus),
+            Mnemonic::ASL => self.modify_operand(cycles, info, CPU65816::shift_left, bus),
+            Mnemonic::LSR => self.modify_operand(cycles, info, CPU65816::shift_right, bus),
+            Mnemonic::ROL => self.modify_operand(cycles, info, CPU65816::rotate_left, bus),
+            Mnemonic::ROR => self.modify_operand(cycles, info, CPU65816::rotate_right, bus),
+            Mnemonic::INX => {
+                self.idle(cycles);
+                self.register_x = self.increment(self.register_x, wide_index);
+            }
+            Mnemonic::INY => {
+                self.idle(cycles);
+                self.register_y = self.increment(self.register_y, wide_index);
+            }
+            Mnemonic::DEX => {
+                self.idle(cycles);
+                self.register_x = self.decrement(self.register_x, wide_index);
+            }
+            Mnemonic::DEY => {
+                self.idle(cycles);
+                self.register_y = self.decrement(self.register_y, wide_index);
+            }
+
+            //
+            // Jumps & Calls
+            //
+            Mnemonic::JMP => self.program_counter = self.jump_address(cycles, info, bus),
+            Mnemonic::JML => {
+                let address: LongAddress = if info.addressing_mode == AddressingMode::AbsoluteLong {
+                    self.fetch_long(cycles, bus)
+                } else {
+                    let pointer: Word = self.fetch_word(cycles, bus);
+                    self.read_long_pointer(cycles, pointer, bus)
+                };
+                self.jump_long(address);
+            }
+            Mnemonic::JSR if info.addressing_mode == AddressingMode::AbsoluteIndexedIndirect => {
+                // The return address is pushed between the two halves of
+                // the table address
+                let low: Byte = self.fetch_byte(cycles, bus);
+                self.push_word_to_stack(cycles, self.program_counter, bus);
+                let high: Byte = self.fetch_byte(cycles, bus);
+                self.idle(cycles);
+                let table: Word = Word::from_le_bytes([low, high]).wrapping_add(self.register_x);
+                self.program_counter = self.read_bank_word(cycles, self.program_bank, table, bus);
+            }
+            Mnemonic::JSR => {
+                let subroutine_addr: Word = self.fetch_word(cycles, bus);
+                self.idle(cycles);
+                self.push_word_to_stack(cycles, self.program_counter.wrapping_sub(1), bus);
+                self.program_counter = subroutine_addr;
+            }
+            Mnemonic::JSL => {
+                let subroutine_addr: Word = self.fetch_word(cycles, bus);
+                self.push_byte_to_stack(cycles, self.program_bank, bus);
+                self.idle(cycles);
+                let bank: Byte = self.fetch_byte(cycles, bus);
+                self.push_word_to_stack(cycles, self.program_counter.wrapping_sub(1), bus);
+                self.program_bank = bank;
+                self.program_counter = subroutine_addr;
+            }
+            Mnemonic::RTS => {
+                self.idle(cycles);
+                self.idle(cycles);
+                self.program_counter = self.pop_word_from_stack(cycles, bus);
+                self.idle(cycles);
+                self.program_counter = self.program_counter.wrapping_add(1);
+            }
+            Mnemonic::RTL => {
+                self.idle(cycles);
+                self.idle(cycles);
+                self.program_counter = self.pop_word_from_stack(cycles, bus).wrapping_add(1);
+                self.program_bank = self.pop_byte_from_stack(cycles, bus);
+            }
+
+            //
+            // Branches
+            //
+            Mnemonic::BCC => self.branch_if(cycles, !self.processor_status.carry(), bus),
+            Mnemonic::BCS => self.branch_if(cycles, self.processor_status.carry(), bus),
+            Mnemonic::BEQ => self.branch_if(cycles, self.processor_status.zero(), bus),
+            Mnemonic::BMI => self.branch_if(cycles, self.processor_status.negative(), bus),
+            Mnemonic::BNE => self.branch_if(cycles, !self.processor_status.zero(), bus),
+            Mnemonic::BPL => self.branch_if(cycles, !self.processor_status.negative(), bus),
+            Mnemonic::BVC => self.branch_if(cycles, !self.processor_status.overflow(), bus),
+            Mnemonic::BVS => self.branch_if(cycles, self.processor_status.overflow(), bus),
+            Mnemonic::BRA => self.branch_if(cycles, true, bus),
+            Mnemonic::BRL => {
+                let offset: Word = self.fetch_word(cycles, bus);
+                self.idle(cycles);
+                self.program_counter = self.program_counter.wrapping_add(offset);
+            }
+
+            //
+            // Status Flag Changes
+            //
+            Mnemonic::CLC | Mnemonic::CLD | Mnemonic::CLI | Mnemonic::CLV => {
+                self.idle(cycles);
+                let status: Byte = self.processor_status.0 & !info.affected_flags;
+                self.set_processor_status(status);
+            }
+            Mnemonic::SEC | Mnemonic::SED | Mnemonic::SEI => {
+                self.idle(cycles);
+                let status: Byte = self.processor_status.0 | info.affected_flags;
+                self.set_processor_status(status);
+            }
+            Mnemonic::REP => {
+                let mask: Byte = self.fetch_byte(cycles, bus);
+                self.idle(cycles);
+                self.set_processor_status(self.processor_status.0 & !mask);
+            }
+            Mnemonic::SEP => {
+                let mask: Byte = self.fetch_byte(cycles, bus);
+                self.idle(cycles);
+                self.set_processor_status(self.processor_status.0 | mask);
+            }
+
+            //
+            // Block Moves
+            //
+            Mnemonic::MVN | Mnemonic::MVP => {
+                let destination_bank: Byte = self.fetch_byte(cycles, bus);
+                let source_bank: Byte = self.fetch_byte(cycles, bus);
+                self.data_bank = destination_bank;
+                let source: LongAddress = long_address(source_bank, self.register_x);
+                let data: Byte = self.read_byte(cycles, source, bus);
+                let destination: LongAddress = long_address(destination_bank, self.register_y);
+                self.write_byte(cycles, destination, data, bus);
+                self.idle(cycles);
+                self.idle(cycles);
+                let step: Word = if info.mnemonic == Mnemonic::MVN {
+                    0x0001
+                } else {
+                    0xFFFF
+                };
+                self.register_x = self.index_value(self.register_x.wrapping_add(step));
+                self.register_y = self.index_value(self.register_y.wrapping_add(step));
+                // The instruction runs again until the count in C, which
+                // is always 16 bits wide, wraps below zero
+                self.accumulator = self.accumulator.wrapping_sub(1);
+                if self.accumulator != 0xFFFF {
+                    self.program_counter = self.program_counter.wrapping_sub(3);
+                }
+            }
+
+            //
+            // System Functions
+            //
+            Mnemonic::BRK => {
+                // The signature byte after BRK and COP is skipped by the
+                // return address
+                self.fetch_byte(cycles, bus);
+                self.service_interrupt(cycles, NATIVE_BRK_VECTOR, IRQ_VECTOR, true, bus);
+            }
+            Mnemonic::COP => {
+                self.fetch_byte(cycles, bus);
+                self.service_interrupt(cycles, NATIVE_COP_VECTOR, COP_VECTOR, true, bus);
+            }
+            Mnemonic::RTI => {
+                self.idle(cycles);
+                self.idle(cycles);
+                let status: Byte = self.pop_byte_from_stack(cycles, bus);
+                self.set_processor_status(status);
+                self.program_counter = self.pop_word_from_stack(cycles, bus);
+                if !self.emulation {
+                    self.program_bank = self.pop_byte_from_stack(cycles, bus);
+                }
+            }
+            Mnemonic::NOP => self.idle(cycles),
+            Mnemonic::WDM => {
+                self.fetch_byte(cycles, bus);
+            }
+            Mnemonic::WAI => {
+                self.idle(cycles);
+                self.idle(cycles);
+                self.waiting = true;
+            }
+            Mnemonic::STP => {
+                self.idle(cycles);
+                self.idle(cycles);
+                self.stopped = true;
+            }
+            _ => unreachable!("{} is not a 65816 instruction", info.mnemonic),
+        }
+    }
+
+    /// Creates a CPU as it is at power on: the first call to `execute` runs
+    /// the RESET sequence, which loads the program counter from $00FFFC.
+    pub fn new() -> Self {
+        let mut cpu: CPU65816 = CPU65816::reset();
+        cpu.trigger_reset();
+        cpu
+    }
+
+    /// Creates a CPU in emulation mode that starts executing directly at
+    /// $00FFFC, without going through the RESET sequence.
+    pub fn reset() -> Self {
+        Self {
+            processor_status: StatusRegister(0b0011_0000),
+            emulation: true,
+            program_counter: 0xFFFC,
+            program_bank: 0x00,
+            data_bank: 0x00,
+            direct_page: 0x0000,
+            stack_pointer: 0x01FF,
+            accumulator: 0x0000,
+            register_x: 0x0000,
+            register_y: 0x0000,
+            cycles: 0,
+            irq_line: false,
+            nmi_line: false,
+            nmi_pending: false,
+            reset_pending: false,
+            waiting: false,
+            stopped: false,
+        }
+    }
+
+    /// The full address of the next instruction.
+    pub fn program_address(&self) -> LongAddress {
+        long_address(self.program_bank, self.program_counter)
+    }
+
+    /// Drives the level-triggered IRQ input.
+    pub fn set_irq(&mut self, asserted: bool) {
+        self.irq_line = asserted;
+    }
+
+    /// Drives the edge-triggered NMI input.
+    pub fn set_nmi(&mut self, asserted: bool) {
+        if asserted && !self.nmi_line {
+            self.nmi_pending = true;
+        }
+        self.nmi_line = asserted;
+    }
+
+    /// Pulses the RESET input, which also returns the CPU to emulation
+    /// mode.
+    pub fn trigger_reset(&mut self) {
+        self.reset_pending = true;
+    }
+
+    /// Whether a WAI instruction is waiting for an interrupt.
+    pub fn is_waiting(&self) -> bool {
+        self.waiting
+    }
+
+    /// Whether an STP instruction has stopped the clock until the next
+    /// reset.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    fn poll_interrupts<B: LongBus>(&mut self, cycles: &mut i32, bus: &mut B) -> bool {
+        if self.reset_pending {
+            self.reset_pending = false;
+            self.nmi_pending = false;
+            self.waiting = false;
+            self.stopped = false;
+            self.service_reset(cycles, bus);
+            true
+        } else if self.stopped {
+            false
+        } else if self.nmi_pending {
+            self.nmi_pending = false;
+            self.waiting = false;
+            self.idle(cycles);
+            self.idle(cycles);
+            self.service_interrupt(cycles, NATIVE_NMI_VECTOR, NMI_VECTOR, false, bus);
+            true
+        } else if self.irq_line && !self.processor_status.interrupt() {
+            self.waiting = false;
+            self.idle(cycles);
+            self.idle(cycles);
+            self.service_interrupt(cycles, NATIVE_IRQ_VECTOR, IRQ_VECTOR, false, bus);
+            true
+        } else {
+            // A masked IRQ still ends WAI
+            self.waiting &= !self.irq_line;
+            false
+        }
+    }
+
+    /// Reset runs the emulation mode interrupt sequence with the stack
+    /// writes turned into reads, and leaves the registers as a 6502 expects.
+    fn service_reset<B: LongBus>(&mut self, cycles: &mut i32, bus: &mut B) {
+        self.emulation = true;
+        self.direct_page = 0x0000;
+        self.data_bank = 0x00;
+        self.program_bank = 0x00;
+        self.set_stack_pointer(self.stack_pointer);
+        self.idle(cycles);
+        self.idle(cycles);
+        for _ in 0..3 {
+            self.read_byte(cycles, self.stack_pointer as LongAddress, bus);
+            self.set_stack_pointer(self.stack_pointer.wrapping_sub(1));
+        }
+        self.processor_status.set_interrupt(true);
+        self.processor_status.set_decimal(false);
+        self.set_processor_status(self.processor_status.0);
+        self.program_counter = self.read_bank_word(cycles, 0x00, RESET_VECTOR, bus);
+    }
+
+    /// Native mode interrupts also push the program bank and have vectors
+    /// of their own. In emulation mode the X bit of the pushed status
+    /// tells BRK apart from IRQ, as the break flag does on the 6502.
+    fn service_interrupt<B: LongBus>(
+        &mut self,
+        cycles: &mut i32,
+        native_vector: Word,
+        emulation_vector: Word,
+        r#break: bool,
+        bus: &mut B,
+    ) {
+        let mut status: StatusRegister = self.processor_status;
+        let vector: Word = if self.emulation {
+            status.set_index_8bit(r#break);
+            emulation_vector
+        } else {
+            self.push_byte_to_stack(cycles, self.program_bank, bus);
+            native_vector
+        };
+        self.push_word_to_stack(cycles, self.program_counter, bus);
+        self.push_byte_to_stack(cycles, status.0, bus);
+        self.processor_status.set_interrupt(true);
+        self.processor_status.set_decimal(false);
+        self.program_bank = 0x00;
+        self.program_counter = self.read_bank_word(cycles, 0x00, vector, bus);
+    }
+
+    /// Loads the status register, keeping M and X set in emulation mode
+    /// and clearing the high bytes of the index registers when X is set.
+    fn set_processor_status(&mut self, status: Byte) {
+        self.processor_status = StatusRegister(status);
+        if self.emulation {
+            self.processor_status.set_memory_8bit(true);
+            self.processor_status.set_index_8bit(true);
+            self.set_stack_pointer(self.stack_pointer);
+        }
+        if self.processor_status.index_8bit() {
+            self.register_x &= 0x00FF;
+            self.register_y &= 0x00FF;
+        }
+    }
+
+    /// In emulation mode the stack stays in page 1.
+    fn set_stack_pointer(&mut self, stack_pointer: Word) {
+        self.stack_pointer = if self.emulation {
+            0x0100 | (stack_pointer & 0x00FF)
+        } else {
+            stack_pointer
+        };
+    }
+
+    /// Loads the accumulator at its current width and sets N and Z.
+    fn set_accumulator(&mut self, data: Word) {
+        let wide: bool = !self.processor_status.memory_8bit();
+        let mask: Word = width_mask(wide);
+        self.accumulator = (self.accumulator & !mask) | (data & mask);
+        self.set_zero_and_negative(self.accumulator, wide);
+    }
+
+    fn index_value(&self, data: Word) -> Word {
+        data & width_mask(!self.processor_status.index_8bit())
+    }
+
+    fn push_byte_to_stack<B: LongBus>(&mut self, cycles: &mut i32, data: Byte, bus: &mut B) {
+        self.write_byte(cycles, self.stack_pointer as LongAddress, data, bus);
+        self.set_stack_pointer(self.stack_pointer.wrapping_sub(1));
+    }
+
+    fn pop_byte_from_stack<B: LongBus>(&mut self, cycles: &mut i32, bus: &mut B) -> Byte {
+        self.set_stack_pointer(self.stack_pointer.wrapping_add(1));
+        self.read_byte(cycles, self.stack_pointer as LongAddress, bus)
+    }
+
+    fn push_word_to_stack<B: LongBus>(&mut self, cycles: &mut i32, word: Word, bus: &mut B) {
+        self.push_byte_to_stack(cycles, (word >> 8) as Byte, bus);
+        self.push_byte_to_stack(cycles, (word & 0xFF) as Byte, bus);
+    }
+
+    fn pop_word_from_stack<B: LongBus>(&mut self, cycles: &mut i32, bus: &mut B) -> Word {
+        let low: Byte = self.pop_byte_from_stack(cycles, bus);
+        let high: Byte = self.pop_byte_from_stack(cycles, bus);
+        Word::from_le_bytes([low, high])
+    }
+
+    fn push_register<B: LongBus>(&mut self, cycles: &mut i32, data: Word, wide: bool, bus: &mut B) {
+        self.idle(cycles);
+        if wide {
+            self.push_word_to_stack(cycles, data, bus);
+        } else {
+            self.push_byte_to_stack(cycles, data as Byte, bus);
+        }
+    }
+
+    fn pop_register<B: LongBus>(&mut self, cycles: &mut i32, wide: bool, bus: &mut B) -> Word {
+        self.idle(cycles);
+        self.idle(cycles);
+        if wide {
+            self.pop_word_from_stack(cycles, bus)
+        } else {
+            self.pop_byte_from_stack(cycles, bus) as Word
+        }
+    }
+
+    /// Works out the address a data instruction operates on, making the
+    /// bus accesses its addressing mode requires.
+    fn operand_address<B: LongBus>(
+        &mut self,
+        cycles: &mut i32,
+        info: &OpcodeInfo,
+        bus: &mut B,
+    ) -> LongAddress {
+        match info.addressing_mode {
+            AddressingMode::ZeroPage => {
+                let offset: Byte = self.fetch_direct_offset(cycles, bus);
+                self.direct_address(offset as Word)
+            }
+            AddressingMode::ZeroPageX | AddressingMode::ZeroPageY => {
+                let offset: Byte = self.fetch_direct_offset(cycles, bus);
+                self.idle(cycles);
+                let index: Word = if info.addressing_mode == AddressingMode::ZeroPageX {
+                    self.register_x
+                } else {
+                    self.register_y
+                };
+                self.direct_address((offset as Word).wrapping_add(index))
+            }
+            AddressingMode::Absolute => {
+                let address: Word = self.fetch_word(cycles, bus);
+                long_address(self.data_bank, address)
+            }
+            AddressingMode::AbsoluteX => {
+                let base_addr: Word = self.fetch_word(cycles, bus);
+                self.index_address(cycles, info, base_addr, self.register_x)
+            }
+            AddressingMode::AbsoluteY => {
+                let base_addr: Word = self.fetch_word(cycles, bus);
+                self.index_address(cycles, info, base_addr, self.register_y)
+            }
+            AddressingMode::AbsoluteLong => self.fetch_long(cycles, bus),
+            AddressingMode::AbsoluteLongX => {
+                let base_addr: LongAddress = self.fetch_long(cycles, bus);
+                (base_addr + self.register_x as LongAddress) & ADDRESS_MASK
+            }
+            AddressingMode::ZeroPageIndirect => {
+                let offset: Byte = self.fetch_direct_offset(cycles, bus);
+                let pointer: Word = self.read_direct_word(cycles, offset as Word, bus);
+                long_address(self.data_bank, pointer)
+            }
+            AddressingMode::IndirectX => {
+                let offset: Byte = self.fetch_direct_offset(cycles, bus);
+                self.idle(cycles);
+                let offset: Word = (offset as Word).wrapping_add(self.register_x);
+                let pointer: Word = self.read_direct_word(cycles, offset, bus);
+                long_address(self.data_bank, pointer)
+            }
+            AddressingMode::IndirectY => {
+                let offset: Byte = self.fetch_direct_offset(cycles, bus);
+                let pointer: Word = self.read_direct_word(cycles, offset as Word, bus);
+                self.index_address(cycles, info, pointer, self.register_y)
+            }
+            AddressingMode::ZeroPageIndirectLong | AddressingMode::ZeroPageIndirectLongY => {
+                let offset: Byte = self.fetch_direct_offset(cycles, bus);
+                let low: Byte = self.read_byte(cycles, self.direct_address(offset as Word), bus);
+                let high: Byte =
+                    self.read_byte(cycles, self.direct_address(offset as Word + 1), bus);
+                let bank: Byte =
+                    self.read_byte(cycles, self.direct_address(offset as Word + 2), bus);
+                let pointer: LongAddress = long_address(bank, Word::from_le_bytes([low, high]));
+                if info.addressing_mode == AddressingMode::ZeroPageIndirectLongY {
+                    (pointer + self.register_y as LongAddress) & ADDRESS_MASK
+                } else {
+                    pointer
+                }
+            }
+            AddressingMode::StackRelative => {
+                let offset: Byte = self.fetch_byte(cycles, bus);
+                self.idle(cycles);
+                self.stack_pointer.wrapping_add(offset as Word) as LongAddress
+            }
+            AddressingMode::StackRelativeIndirectY => {
+                let offset: Byte = self.fetch_byte(cycles, bus);
+                self.idle(cycles);
+                let address: Word = self.stack_pointer.wrapping_add(offset as Word);
+                let pointer: Word = self.read_bank_word(cycles, 0x00, address, bus);
+                self.idle(cycles);
+                (long_address(self.data_bank, pointer) + self.register_y as LongAddress)
+                    & ADDRESS_MASK
+            }
+            _ => unreachable!("{:?} has no operand address", info.addressing_mode),
+        }
+    }
+
+    /// Where JMP goes: indirect pointers are read from bank 0, while the
+    /// indexed jump table is in the program bank.
+    fn jump_address<B: LongBus>(
+        &mut self,
+        cycles: &mut i32,
+        info: &OpcodeInfo,
+        bus: &mut B,
+    ) -> Word {
+        let address: Word = self.fetch_word(cycles, bus);
+        match info.addressing_mode {
+            AddressingMode::Indirect => self.read_bank_word(cycles, 0x00, address, bus),
+            AddressingMode::AbsoluteIndexedIndirect => {
+                self.idle(cycles);
+                let table: Word = address.wrapping_add(self.register_x);
+                self.read_bank_word(cycles, self.program_bank, table, bus)
+            }
+            _ => address,
+        }
+    }
+
+    fn jump_long(&mut self, address: LongAddress) {
+        self.program_bank = (address >> 16) as Byte;
+        self.program_counter = address as Word;
+    }
+
+    /// Indexed read instructions only spend a cycle fixing up the address
+    /// when indexing crosses a page or the index registers are 16 bits
+    /// wide, while stores and read-modify-write instructions always do.
+    fn index_address(
+        &mut self,
+        cycles: &mut i32,
+        info: &OpcodeInfo,
+        base_addr: Word,
+        index: Word,
+    ) -> LongAddress {
+        let base_addr: LongAddress = long_address(self.data_bank, base_addr);
+        let address: LongAddress = (base_addr + index as LongAddress) & ADDRESS_MASK;
+        let page_crossed: bool = (address >> 8) != (base_addr >> 8);
+        if !info.page_cross_penalty || page_crossed || !self.processor_status.index_8bit() {
+            self.idle(cycles);
+        }
+        address
+    }
+
+    /// Direct page addresses are in bank 0. In emulation mode with the
+    /// direct page on a page boundary, indexing wraps within the page like
+    /// it does in the 6502 zero page.
+    fn direct_address(&self, offset: Word) -> LongAddress {
+        if self.emulation && self.direct_page & 0x00FF == 0 {
+            (self.direct_page | (offset & 0x00FF)) as LongAddress
+        } else {
+            self.direct_page.wrapping_add(offset) as LongAddress
+        }
+    }
+
+    /// Fetches a direct page offset, which costs a cycle more when the
+    /// direct page is not on a page boundary.
+    fn fetch_direct_offset<B: LongBus>(&mut self, cycles: &mut i32, bus: &mut B) -> Byte {
+        let offset: Byte = self.fetch_byte(cycles, bus);
+        if self.direct_page & 0x00FF != 0 {
+            self.idle(cycles);
+        }
+        offset
+    }
+
+    fn read_direct_word<B: LongBus>(&self, cycles: &mut i32, offset: Word, bus: &mut B) -> Word {
+        let low: Byte = self.read_byte(cycles, self.direct_address(offset), bus);
+        let high: Byte = self.read_byte(cycles, self.direct_address(offset.wrapping_add(1)), bus);
+        Word::from_le_bytes([low, high])
+    }
+
+    fn read_operand<B: LongBus>(
+        &mut self,
+        cycles: &mut i32,
+        info: &OpcodeInfo,
+        wide: bool,
+        bus: &mut B,
+    ) -> Word {
+        if info.addressing_mode == AddressingMode::Immediate {
+            return if wide {
+                self.fetch_word(cycles, bus)
+            } else {
+                self.fetch_byte(cycles, bus) as Word
+            };
+        }
+        let address: LongAddress = self.operand_address(cycles, info, bus);
+        let low: Byte = self.read_byte(cycles, address, bus);
+        if !wide {
+            return low as Word;
+        }
+        let high: Byte = self.read_byte(cycles, next_address(info, address), bus);
+        Word::from_le_bytes([low, high])
+    }
+
+    fn store<B: LongBus>(
+        &mut self,
+        cycles: &mut i32,
+        info: &OpcodeInfo,
+        data: Word,
+        wide: bool,
+        bus: &mut B,
+    ) {
+        let address: LongAddress = self.operand_address(cycles, info, bus);
+        self.write_byte(cycles, address, data as Byte, bus);
+        if wide {
+            self.write_byte(
+                cycles,
+                next_address(info, address),
+                (data >> 8) as Byte,
+                bus,
+            );
+        }
+    }
+
+    /// Read-modify-write instructions spend a cycle between the read and
+    /// the write, which emulation mode uses to write the unmodified value
+    /// back like the NMOS 6502. 16-bit results are written high byte first.
+    fn modify_operand<B: LongBus>(
+        &mut self,
+        cycles: &mut i32,
+        info: &OpcodeInfo,
+        operation: fn(&mut CPU65816, Word, bool) -> Word,
+        bus: &mut B,
+    ) {
+        let wide: bool = !self.processor_status.memory_8bit();
+        if info.addressing_mode == AddressingMode::Accumulator {
+            self.idle(cycles);
+            let mask: Word = width_mask(wide);
+            let result: Word = operation(self, self.accumulator & mask, wide);
+            self.accumulator = (self.accumulator & !mask) | (result & mask);
+            return;
+        }
+        let address: LongAddress = self.operand_address(cycles, info, bus);
+        let low: Byte = self.read_byte(cycles, address, bus);
+        let data: Word = if wide {
+            let high: Byte = self.read_byte(cycles, next_address(info, address), bus);
+            Word::from_le_bytes([low, high])
+        } else {
+            low as Word
+        };
+        if self.emulation {
+            self.write_byte(cycles, address, low, bus);
+        } else {
+            self.idle(cycles);
+        }
+        let result: Word = operation(self, data, wide);
+        if wide {
+            self.write_byte(
+                cycles,
+                next_address(info, address),
+                (result >> 8) as Byte,
+                bus,
+            );
+        }
+        self.write_byte(cycles, address, result as Byte, bus);
+    }
+
+    /// Taking a branch costs a cycle, and crossing a page costs another
+    /// one in emulation mode only.
+    fn branch_if<B: LongBus>(&mut self, cycles: &mut i32, condition: bool, bus: &mut B) {
+        let offset: Byte = self.fetch_byte(cycles, bus);
+        if condition {
+            let target: Word = self
+                .program_counter
+                .wrapping_add_signed(offset as i8 as i16);
+            self.idle(cycles);
+            if self.emulation && (target & 0xFF00) != (self.program_counter & 0xFF00) {
+                self.idle(cycles);
+            }
+            self.program_counter = target;
+        }
+    }
+
+    /// ADC, and SBC with `subtract` set, at the width of the accumulator.
+    /// Decimal mode works a digit at a time with valid N and Z, and takes V
+    /// from the sum before the top digit is adjusted.
+    fn add_with_carry(&mut self, operand: Word, subtract: bool) {
+        let wide: bool = !self.processor_status.memory_8bit();
+        let mask: u32 = width_mask(wide) as u32;
+        let accumulator: u32 = self.accumulator as u32 & mask;
+        let operand: u32 = if subtract {
+            !operand as u32 & mask
+        } else {
+            operand as u32 & mask
+        };
+        let sign: u32 = sign_bit(wide) as u32;
+        let mut carry: u32 = self.processor_status.carry() as u32;
+        let result: u32 = if self.processor_status.decimal() {
+            let digits: u32 = if wide { 4 } else { 2 };
+            let mut result: u32 = 0;
+            for digit in 0..digits {
+                let shift: u32 = digit * 4;
+                let mut sum: i32 = ((accumulator >> shift) & 0xF) as i32
+                    + ((operand >> shift) & 0xF) as i32
+                    + carry as i32;
+                if digit == digits - 1 {
+                    let unadjusted: u32 = result | ((sum as u32) << shift);
+                    self.processor_status.set_overflow(
+                        !(accumulator ^ operand) & (accumulator ^ unadjusted) & sign != 0,
+                    );
+                }
+                if subtract && sum <= 0xF {
+                    sum -= 6;
+                } else if !subtract && sum > 9 {
+                    sum += 6;
+                }
+                carry = (sum > 0xF) as u32;
+                result |= ((sum as u32) & 0xF) << shift;
+            }
+            result
+        } else {
+            let sum: u32 = accumulator + operand + carry;
+            carry = (sum > mask) as u32;
+            self.processor_status
+                .set_overflow(!(accumulator ^ operand) & (accumulator ^ sum) & sign != 0);
+            sum & mask
+        };
+        self.processor_status.set_carry(carry != 0);
+        self.set_accumulator(result as Word);
+    }
+
+    fn compare(&mut self, register: Word, operand: Word, wide: bool) {
+        let mask: Word = width_mask(wide);
+        let register: Word = register & mask;
+        let operand: Word = operand & mask;
+        self.processor_status.set_carry(register >= operand);
+        self.set_zero_and_negative(register.wrapping_sub(operand), wide);
+    }
+
+    fn increment(&mut self, data: Word, wide: bool) -> Word {
+        let result: Word = data.wrapping_add(1) & width_mask(wide);
+        self.set_zero_and_negative(result, wide);
+        result
+    }
+
+    fn decrement(&mut self, data: Word, wide: bool) -> Word {
+        let result: Word = data.wrapping_sub(1) & width_mask(wide);
+        self.set_zero_and_negative(result, wide);
+        result
+    }
+
+    fn shift_left(&mut self, data: Word, wide: bool) -> Word {
+        let result: Word = (data << 1) & width_mask(wide);
+        self.processor_status.set_carry(data & sign_bit(wide) != 0);
+        self.set_zero_and_negative(result, wide);
+        result
+    }
+
+    fn shift_right(&mut self, data: Word, wide: bool) -> Word {
+        let result: Word = data >> 1;
+        self.processor_status.set_carry(data & 0x0001 != 0);
+        self.set_zero_and_negative(result, wide);
+        result
+    }
+
+    fn rotate_left(&mut self, data: Word, wide: bool) -> Word {
+        let result: Word = ((data << 1) | self.processor_status.carry() as Word) & width_mask(wide);
+        self.processor_status.set_carry(data & sign_bit(wide) != 0);
+        self.set_zero_and_negative(result, wide);
+        result
+    }
+
+    fn rotate_right(&mut self, data: Word, wide: bool) -> Word {
+        let carry: Word = if self.processor_status.carry() {
+            sign_bit(wide)
+        } else {
+            0x0000
+        };
+        let result: Word = (data >> 1) | carry;
+        self.processor_status.set_carry(data & 0x0001 != 0);
+        self.set_zero_and_negative(result, wide);
+        result
+    }
+
+    fn test_and_reset_bits(&mut self, data: Word, wide: bool) -> Word {
+        let accumulator: Word = self.accumulator & width_mask(wide);
+        self.processor_status.set_zero(accumulator & data == 0x0000);
+        data & !accumulator
+    }
+
+    fn test_and_set_bits(&mut self, data: Word, wide: bool) -> Word {
+        let accumulator: Word = self.accumulator & width_mask(wide);
+        self.processor_status.set_zero(accumulator & data == 0x0000);
+        data | accumulator
+    }
+
+    fn set_zero_and_negative(&mut self, value: Word, wide: bool) {
+        self.processor_status
+            .set_zero(value & width_mask(wide) == 0x0000);
+        self.processor_status
+            .set_negative(value & sign_bit(wide) != 0);
+    }
+
+    fn read_byte<B: LongBus>(&self, cycles: &mut i32, address: LongAddress, bus: &mut B) -> Byte {
+        *cycles -= 1;
+        bus.read(address & ADDRESS_MASK)
+    }
+
+    fn write_byte<B: LongBus>(
+        &self,
+        cycles: &mut i32,
+        address: LongAddress,
+        data: Byte,
+        bus: &mut B,
+    ) {
+        *cycles -= 1;
+        bus.write(address & ADDRESS_MASK, data);
+    }
+
+    /// An internal operation, which takes a cycle without using the bus.
+    fn idle(&self, cycles: &mut i32) {
+        *cycles -= 1;
+    }
+
+    /// Reads a pointer that wraps around within `bank`.
+    fn read_bank_word<B: LongBus>(
+        &self,
+        cycles: &mut i32,
+        bank: Byte,
+        address: Word,
+        bus: &mut B,
+    ) -> Word {
+        let low: Byte = self.read_byte(cycles, long_address(bank, address), bus);
+        let high: Byte = self.read_byte(cycles, long_address(bank, address.wrapping_add(1)), bus);
+        Word::from_le_bytes([low, high])
+    }
+
+    /// Reads a long pointer from bank 0, as used by JML.
+    fn read_long_pointer<B: LongBus>(
+        &self,
+        cycles: &mut i32,
+        address: Word,
+        bus: &mut B,
+    ) -> LongAddress {
+        let pointer: Word = self.read_bank_word(cycles, 0x00, address, bus);
+        let bank: Byte = self.read_byte(cycles, address.wrapping_add(2) as LongAddress, bus);
+        long_address(bank, pointer)
+    }
+
+    /// Instructions are fetched from the program bank, and the program
+    /// counter wraps around within it.
+    fn fetch_byte<B: LongBus>(&mut self, cycles: &mut i32, bus: &mut B) -> Byte {
+        let data: Byte = self.read_byte(cycles, self.program_address(), bus);
+        self.program_counter = self.program_counter.wrapping_add(1);
+        data
+    }
+
+    fn fetch_word<B: LongBus>(&mut self, cycles: &mut i32, bus: &mut B) -> Word {
+        let low: Byte = self.fetch_byte(cycles, bus);
+        let high: Byte = self.fetch_byte(cycles, bus);
+        Word::from_le_bytes([low, high])
+    }
+
+    fn fetch_long<B: LongBus>(&mut self, cycles: &mut i32, bus: &mut B) -> LongAddress {
+        let address: Word = self.fetch_word(cycles, bus);
+        let bank: Byte = self.fetch_byte(cycles, bus);
+        long_address(bank, address)
+    }
+}
+
+impl Default for CPU65816 {
+    fn default() -> Self {
+        CPU65816::new()
+    }
+}
+
+pub fn long_address(bank: Byte, address: Word) -> LongAddress {
+    ((bank as LongAddress) << 16) | address as LongAddress
+}
+
+/// The second byte of a 16-bit operand. Direct page and stack operands
+/// wrap around within bank 0, while the others carry into the next bank.
+fn next_address(info: &OpcodeInfo, address: LongAddress) -> LongAddress {
+    match info.addressing_mode {
+        AddressingMode::ZeroPage
+        | AddressingMode::ZeroPageX
+        | AddressingMode::ZeroPageY
+        | AddressingMode::StackRelative => (address + 1) & 0xFFFF,
+        _ => (address + 1) & ADDRESS_MASK,
+    }
+}
+
+fn width_mask(wide: bool) -> Word {
+    if wide {
+        0xFFFF
+    } else {
+        0x00FF
+    }
+}
+
+fn sign_bit(wide: bool) -> Word {
+    if wide {
+        0x8000
+    } else {
+        0x0080
+    }
+}
@@ -0,0 +1,126 @@
+use crate::instructions::{AddressingMode, Mnemonic, OpcodeInfo};
+use crate::{Bus, Byte, RegisterType, Word, CPU};
+
+impl CPU {
+    pub(crate) fn execute_65c02<B: Bus>(
+        &mut self,
+        cycles: &mut i32,
+        info: &OpcodeInfo,
+        bus: &mut B,
+    ) {
+        match info.mnemonic {
+            Mnemonic::BRA => self.branch_if(cycles, true, bus),
+            Mnemonic::PHX => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.push_byte_to_stack(cycles, self.register_x, bus);
+            }
+            Mnemonic::PHY => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.push_byte_to_stack(cycles, self.register_y, bus);
+            }
+            Mnemonic::PLX => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.dummy_read_stack(cycles, bus);
+                self.register_x = self.pop_byte_from_stack(cycles, bus);
+                self.load_register_set_status(&RegisterType::RegisterX);
+            }
+            Mnemonic::PLY => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.dummy_read_stack(cycles, bus);
+                self.register_y = self.pop_byte_from_stack(cycles, bus);
+                self.load_register_set_status(&RegisterType::RegisterY);
+            }
+            Mnemonic::STZ => {
+                let address: Word = self.operand_address(cycles, info, bus);
+                self.write_byte(cycles, address, 0x00, bus);
+            }
+            Mnemonic::TRB => self.modify_operand(cycles, info, CPU::test_and_reset_bits, bus),
+            Mnemonic::TSB => self.modify_operand(cycles, info, CPU::test_and_set_bits, bus),
+
+            //
+            // Bit Instructions
+            //
+            Mnemonic::RMB | Mnemonic::SMB => {
+                let address: Word = self.operand_address(cycles, info, bus);
+                let mask: Byte = 1 << bit_number(info);
+                let data: Byte = self.read_byte(cycles, address, bus);
+                self.read_byte(cycles, address, bus);
+                let result: Byte = if info.mnemonic == Mnemonic::SMB {
+                    data | mask
+                } else {
+                    data & !mask
+                };
+                self.write_byte(cycles, address, result, bus);
+            }
+            Mnemonic::BBR | Mnemonic::BBS => {
+                let address: Word = self.get_zero_page_addr(cycles, bus);
+                let data: Byte = self.read_byte(cycles, address, bus);
+                self.read_byte(cycles, address, bus);
+                let set: bool = data & (1 << bit_number(info)) != 0;
+                self.branch_if(cycles, set == (info.mnemonic == Mnemonic::BBS), bus);
+                let offset: Word = self.step_state.operand.unwrap_or(0);
+                self.step_state.operand = Some(address | (offset << 8));
+            }
+
+            //
+            // System Functions
+            //
+            Mnemonic::WAI => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.dummy_read_program_counter(cycles, bus);
+                self.waiting = true;
+            }
+            Mnemonic::STP => {
+                self.dummy_read_program_counter(cycles, bus);
+                self.dummy_read_program_counter(cycles, bus);
+                self.stopped = true;
+            }
+            mnemonic => unreachable!("{} is not a 65C02 instruction", mnemonic),
+        }
+    }
+
+    /// The 65C02 turns every opcode the NMOS chip left undocumented into a
+    /// NOP. They read their operand like any other instruction in the same
+    /// addressing mode and then idle for the rest of their cycles.
+    pub(crate) fn execute_reserved_nop<B: Bus>(
+        &mut self,
+        cycles: &mut i32,
+        info: &OpcodeInfo,
+        bus: &mut B,
+    ) {
+        // The opcode fetch has already been counted
+        let end: i32 = *cycles - (info.cycles as i32 - 1);
+        if info.addressing_mode != AddressingMode::Implied {
+            self.read_operand(cycles, info, bus);
+        }
+        while *cycles > end {
+            self.dummy_read_program_counter(cycles, bus);
+        }
+    }
+
+    /// ADC and SBC take an extra cycle in decimal mode on the CMOS chips,
+    /// which is what lets them set N and Z from the adjusted result.
+    pub(crate) fn decimal_adjust_cycle<B: Bus>(&mut self, cycles: &mut i32, bus: &mut B) {
+        if self.variant.is_cmos() && self.processor_status.decimal() {
+            self.dummy_read_program_counter(cycles, bus);
+        }
+    }
+
+    fn test_and_reset_bits(&mut self, data: Byte) -> Byte {
+        self.processor_status
+            .set_zero(self.accumulator & data == 0x00);
+        data & !self.accumulator
+    }
+
+    fn test_and_set_bits(&mut self, data: Byte) -> Byte {
+        self.processor_status
+            .set_zero(self.accumulator & data == 0x00);
+        data | self.accumulator
+    }
+}
+
+/// RMB, SMB, BBR and BBS hold the bit they work on in bits 4 to 6 of the
+/// opcode.
+fn bit_number(info: &OpcodeInfo) -> u8 {
+    (info.opcode >> 4) & 0b111
+}
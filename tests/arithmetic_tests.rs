@@ -0,0 +1,206 @@
+use rust6502::{instructions::*, *};
+
+mod common;
+
+//
+// ADC Tests
+//
+
+#[test]
+fn adc_immediate_can_add_to_accumulator() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0x10;
+    memory.data[0xFFFC] = INSTRUCTION_ADC_IMM;
+    memory.data[0xFFFD] = 0x22;
+    let cycles_used = cpu.execute(2, &mut memory);
+    assert_eq!(cycles_used, 2);
+    assert_eq!(cpu.accumulator, 0x32);
+    assert!(!cpu.processor_status.carry());
+    assert!(!cpu.processor_status.overflow());
+    assert!(!cpu.processor_status.zero());
+    assert!(!cpu.processor_status.negative());
+}
+
+#[test]
+fn adc_immediate_adds_carry_in() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0x10;
+    cpu.processor_status.set_carry(true);
+    memory.data[0xFFFC] = INSTRUCTION_ADC_IMM;
+    memory.data[0xFFFD] = 0x22;
+    cpu.execute(2, &mut memory);
+    assert_eq!(cpu.accumulator, 0x33);
+    assert!(!cpu.processor_status.carry());
+}
+
+#[test]
+fn adc_immediate_can_set_carry_and_zero_flags() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0xFF;
+    memory.data[0xFFFC] = INSTRUCTION_ADC_IMM;
+    memory.data[0xFFFD] = 0x01;
+    cpu.execute(2, &mut memory);
+    assert_eq!(cpu.accumulator, 0x00);
+    assert!(cpu.processor_status.carry());
+    assert!(cpu.processor_status.zero());
+    assert!(!cpu.processor_status.overflow());
+}
+
+#[test]
+fn adc_immediate_can_set_overflow_flag_on_signed_overflow() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0x7F;
+    memory.data[0xFFFC] = INSTRUCTION_ADC_IMM;
+    memory.data[0xFFFD] = 0x01;
+    cpu.execute(2, &mut memory);
+    assert_eq!(cpu.accumulator, 0x80);
+    assert!(cpu.processor_status.overflow());
+    assert!(cpu.processor_status.negative());
+    assert!(!cpu.processor_status.carry());
+}
+
+#[test]
+fn adc_absolute_with_offset_y_takes_extra_cycle_when_crossing_page() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0x01;
+    cpu.register_y = 0x01;
+    memory.data[0xFFFC] = INSTRUCTION_ADC_ABS_Y;
+    memory.data[0xFFFD] = 0xFF;
+    memory.data[0xFFFE] = 0x44;
+    memory.data[0x4500] = 0x02;
+    let cycles_used = cpu.execute(5, &mut memory);
+    assert_eq!(cycles_used, 5);
+    assert_eq!(cpu.accumulator, 0x03);
+}
+
+#[test]
+fn adc_indirect_y_can_add_to_accumulator() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0x01;
+    cpu.register_y = 0x04;
+    memory.data[0xFFFC] = INSTRUCTION_ADC_INDR_Y;
+    memory.data[0xFFFD] = 0x02;
+    memory.data[0x0002] = 0x00;
+    memory.data[0x0003] = 0x80;
+    memory.data[0x8004] = 0x41;
+    let cycles_used = cpu.execute(5, &mut memory);
+    assert_eq!(cycles_used, 5);
+    assert_eq!(cpu.accumulator, 0x42);
+}
+
+//
+// SBC Tests
+//
+
+#[test]
+fn sbc_immediate_can_subtract_from_accumulator() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0x50;
+    cpu.processor_status.set_carry(true);
+    memory.data[0xFFFC] = INSTRUCTION_SBC_IMM;
+    memory.data[0xFFFD] = 0x10;
+    let cycles_used = cpu.execute(2, &mut memory);
+    assert_eq!(cycles_used, 2);
+    assert_eq!(cpu.accumulator, 0x40);
+    assert!(cpu.processor_status.carry());
+    assert!(!cpu.processor_status.overflow());
+}
+
+#[test]
+fn sbc_immediate_subtracts_borrow_when_carry_is_clear() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0x50;
+    memory.data[0xFFFC] = INSTRUCTION_SBC_IMM;
+    memory.data[0xFFFD] = 0x10;
+    cpu.execute(2, &mut memory);
+    assert_eq!(cpu.accumulator, 0x3F);
+    assert!(cpu.processor_status.carry());
+}
+
+#[test]
+fn sbc_immediate_can_borrow_and_overflow() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0x80;
+    cpu.processor_status.set_carry(true);
+    memory.data[0xFFFC] = INSTRUCTION_SBC_IMM;
+    memory.data[0xFFFD] = 0x01;
+    cpu.execute(2, &mut memory);
+    assert_eq!(cpu.accumulator, 0x7F);
+    assert!(cpu.processor_status.carry());
+    assert!(cpu.processor_status.overflow());
+    assert!(!cpu.processor_status.negative());
+}
+
+#[test]
+fn sbc_zeropage_clears_carry_on_borrow() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0x01;
+    cpu.processor_status.set_carry(true);
+    memory.data[0xFFFC] = INSTRUCTION_SBC_ZERO;
+    memory.data[0xFFFD] = 0x42;
+    memory.data[0x0042] = 0x02;
+    let cycles_used = cpu.execute(3, &mut memory);
+    assert_eq!(cycles_used, 3);
+    assert_eq!(cpu.accumulator, 0xFF);
+    assert!(!cpu.processor_status.carry());
+    assert!(cpu.processor_status.negative());
+}
+
+//
+// Compare Tests
+//
+
+#[test]
+fn cmp_immediate_sets_carry_and_zero_when_equal() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0x42;
+    memory.data[0xFFFC] = INSTRUCTION_CMP_IMM;
+    memory.data[0xFFFD] = 0x42;
+    let cycles_used = cpu.execute(2, &mut memory);
+    assert_eq!(cycles_used, 2);
+    assert_eq!(cpu.accumulator, 0x42);
+    assert!(cpu.processor_status.carry());
+    assert!(cpu.processor_status.zero());
+    assert!(!cpu.processor_status.negative());
+}
+
+#[test]
+fn cmp_absolute_clears_carry_when_register_is_smaller() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0x10;
+    memory.data[0xFFFC] = INSTRUCTION_CMP_ABS;
+    memory.data[0xFFFD] = 0x00;
+    memory.data[0xFFFE] = 0x80;
+    memory.data[0x8000] = 0x20;
+    let cycles_used = cpu.execute(4, &mut memory);
+    assert_eq!(cycles_used, 4);
+    assert!(!cpu.processor_status.carry());
+    assert!(!cpu.processor_status.zero());
+    assert!(cpu.processor_status.negative());
+}
+
+#[test]
+fn cpx_zeropage_can_compare_register_x() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.register_x = 0x30;
+    memory.data[0xFFFC] = INSTRUCTION_CPX_ZERO;
+    memory.data[0xFFFD] = 0x42;
+    memory.data[0x0042] = 0x20;
+    let cycles_used = cpu.execute(3, &mut memory);
+    assert_eq!(cycles_used, 3);
+    assert!(cpu.processor_status.carry());
+    assert!(!cpu.processor_status.zero());
+    assert!(!cpu.processor_status.negative());
+}
+
+#[test]
+fn cpy_immediate_can_compare_register_y() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.register_y = 0x05;
+    memory.data[0xFFFC] = INSTRUCTION_CPY_IMM;
+    memory.data[0xFFFD] = 0x05;
+    let cycles_used = cpu.execute(2, &mut memory);
+    assert_eq!(cycles_used, 2);
+    assert!(cpu.processor_status.carry());
+    assert!(cpu.processor_status.zero());
+}
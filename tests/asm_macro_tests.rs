@@ -0,0 +1,132 @@
+use asm_macros::asm;
+use rust6502::*;
+mod common;
+
+#[test]
+fn asm_macro_assembles_at_compile_time() {
+    let program = asm! {
+        .org $8000
+    start:
+        ldx #$FF
+        lda ($42),Y
+        sta $12AB,x
+        jmp ($FFFC)
+        lda ($10,x); bne start
+    };
+    assert_eq!(program.origin, 0x8000);
+    assert_eq!(
+        program.bytes,
+        [0xA2, 0xFF, 0xB1, 0x42, 0x9D, 0xAB, 0x12, 0x6C, 0xFC, 0xFF, 0xA1, 0x10, 0xD0, 0xF2]
+    );
+    assert_eq!(program.symbol("start"), Some(0x8000));
+}
+
+#[test]
+fn asm_macro_supports_expressions_and_data() {
+    let program = asm! {
+        table = $1234
+        size = 10 % 4
+        lda #<table
+        ldx #>table
+        ldy #%0110 | size
+        .byte 'A', "bc"
+        .word table + 1, end
+    end:
+    };
+    assert_eq!(
+        program.bytes,
+        [0xA9, 0x34, 0xA2, 0x12, 0xA0, 0x06, 0x41, 0x62, 0x63, 0x35, 0x12, 0x0D, 0x00]
+    );
+    assert_eq!(program.symbol("size"), Some(2));
+    assert_eq!(program.symbol("missing"), None);
+}
+
+#[test]
+fn asm_macro_can_initialise_a_const() {
+    const PROGRAM: asm::ProgramImage<3> = asm! { lda #1; rts };
+    assert_eq!(PROGRAM.bytes, [0xA9, 0x01, 0x60]);
+}
+
+#[test]
+fn asm_macro_programs_run_on_the_cpu() {
+    let program = asm! {
+        .org $0200
+        ldx #5
+        lda #0
+    loop:
+        clc
+        adc #3
+        dex
+        bne loop
+    done:
+        nop
+    };
+    let (mut cpu, mut memory) = common::setup();
+    program.load_into(&mut memory.data);
+    cpu.program_counter = program.origin;
+    while cpu.program_counter != program.symbol("done").unwrap() {
+        cpu.step(&mut memory).unwrap();
+    }
+    assert_eq!(cpu.accumulator, 15);
+}
+
+#[test]
+fn asm_macro_accepts_every_hex_shape_in_a_string() {
+    let program = asm!(
+        "
+        .org $0300
+        lda #$E     ; a lone letter
+        lda #$0E    ; a digit then E
+        lda #$1E
+        lda #$2E
+        lda #$EE
+        lda $FF
+        lda $1E5    ; also a valid Rust float
+        lda $10E
+        lda $E0E
+        lda $1E00
+        lda $FFFF
+    "
+    );
+    assert_eq!(program.origin, 0x0300);
+    assert_eq!(
+        program.bytes,
+        [
+            0xA9, 0x0E, 0xA9, 0x0E, 0xA9, 0x1E, 0xA9, 0x2E, 0xA9, 0xEE, 0xA5, 0xFF, 0xAD, 0xE5,
+            0x01, 0xAD, 0x0E, 0x01, 0xAD, 0x0E, 0x0E, 0xAD, 0x00, 0x1E, 0xAD, 0xFF, 0xFF
+        ]
+    );
+}
+
+#[test]
+fn asm_macro_tokens_accept_0x_for_hex_that_does_not_lex_with_a_dollar() {
+    let tokens = asm! {
+        lda #0x1E
+        lda 0x10E
+        lda $E0E
+        lda $1E5
+    };
+    let string = asm!("lda #$1E\n lda $10E\n lda $E0E\n lda $1E5");
+    assert_eq!(tokens.bytes, string.bytes);
+}
+
+#[test]
+fn asm_macro_string_form_supports_raw_strings_and_escapes() {
+    let raw = asm!(r#"loop: jmp loop"#);
+    let escaped = asm!("\tloop: jmp loop\n");
+    assert_eq!(raw.bytes, [0x4C, 0x00, 0x00]);
+    assert_eq!(escaped.bytes, raw.bytes);
+    assert_eq!(escaped.symbol("loop"), Some(0x0000));
+}
+
+#[test]
+fn asm_macro_assembles_65c02_instructions() {
+    let program = asm! {
+        .cpu 65c02
+    loop:
+        stz $12
+        lda ($12)
+        bbr0 $12, loop
+    };
+    assert_eq!(program.bytes, [0x64, 0x12, 0xB2, 0x12, 0x0F, 0x12, 0xF9]);
+}
@@ -0,0 +1,97 @@
+use rust6502::{disassembler::*, instructions::*, *};
+mod common;
+
+#[test]
+fn assembler_round_trips_every_opcode_through_the_disassembler() {
+    for info in OPCODES.iter().filter(|info| info.mnemonic != Mnemonic::JAM) {
+        let (_, mut memory) = common::setup();
+        memory.data[0x0200] = info.opcode;
+        memory.data[0x0201] = 0x10;
+        memory.data[0x0202] = 0x20;
+        let instruction = disassemble(&mut memory, 0x0200);
+        let source = format!(
+            ".org $0200\n{}",
+            instruction.to_string().trim_start_matches('*')
+        );
+        let program = asm::assemble(&source).unwrap();
+        let expected_opcode = asm::opcodes::encode(info.mnemonic, info.addressing_mode).unwrap();
+        assert_eq!(program.image()[0], expected_opcode, "{}", source);
+        assert_eq!(program.image()[1..], instruction.bytes[1..], "{}", source);
+    }
+}
+
+#[test]
+fn assembler_round_trips_every_65c02_opcode_through_the_disassembler() {
+    for info in OPCODES_65C02.iter().filter(|info| info.documented) {
+        let (_, mut memory) = common::setup();
+        memory.data[0x0200] = info.opcode;
+        memory.data[0x0201] = 0x10;
+        memory.data[0x0202] = 0x20;
+        let instruction = disassemble_variant(&mut memory, 0x0200, Variant::Wdc65C02);
+        let source = format!(".cpu 65c02\n.org $0200\n{}", instruction);
+        let program = asm::assemble(&source).unwrap();
+        assert_eq!(program.image(), instruction.bytes, "{}", source);
+    }
+}
+
+#[test]
+fn cpu_directive_selects_the_instruction_set() {
+    let program = asm::assemble(
+        "
+        .org $0300
+        .cpu 65c02
+loop:   stz $12
+        lda ($12)
+        jmp ($1234,x)
+        bbs7 $12,loop
+        smb2 $34
+        .cpu 6502
+        lax $12
+    ",
+    )
+    .unwrap();
+    assert_eq!(
+        program.image(),
+        [0x64, 0x12, 0xB2, 0x12, 0x7C, 0x34, 0x12, 0xFF, 0x12, 0xF6, 0xA7, 0x34, 0xA7, 0x12]
+    );
+    let error = asm::assemble("stz $12").unwrap_err();
+    assert_eq!(
+        error.kind,
+        asm::AssemblyErrorKind::UnknownMnemonic(String::from("stz"))
+    );
+    let error = asm::assemble(".cpu 65c02\nlax $12").unwrap_err();
+    assert_eq!(error.line, 2);
+    assert!(asm::assemble(".cpu 65c02\nrmb8 $12").is_err());
+    assert_eq!(
+        asm::assemble(".cpu 68000").unwrap_err().kind,
+        asm::AssemblyErrorKind::UnknownCpu(String::from("68000"))
+    );
+}
+
+#[test]
+fn assembled_programs_run_on_the_cpu() {
+    let program = asm::assemble(
+        "
+        .org $8000
+reset:  ldx #5
+        lda #0
+        clc
+loop:   adc #3
+        dex
+        bne loop
+        sta result
+done:   nop
+result = $0200
+        .org $FFFC
+        .word reset
+    ",
+    )
+    .unwrap();
+    let mut cpu: CPU = CPU::new();
+    let (_, mut memory) = common::setup();
+    program.load_into(&mut memory.data);
+    while cpu.program_counter != program.symbols["done"] {
+        cpu.step(&mut memory).unwrap();
+    }
+    assert_eq!(memory.data[0x0200], 15);
+}
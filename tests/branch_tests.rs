@@ -0,0 +1,77 @@
+use rust6502::{instructions::*, *};
+
+mod common;
+
+type SetFlag = fn(&mut ProcessorStatus, bool);
+
+#[test]
+fn beq_does_not_branch_when_zero_is_clear() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.program_counter = 0xFF00;
+    memory.data[0xFF00] = INSTRUCTION_BEQ;
+    memory.data[0xFF01] = 0x10;
+    let cycles_used = cpu.execute(2, &mut memory);
+    assert_eq!(cycles_used, 2);
+    assert_eq!(cpu.program_counter, 0xFF02);
+}
+
+#[test]
+fn beq_can_branch_forwards_when_zero_is_set() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.program_counter = 0xFF00;
+    cpu.processor_status.set_zero(true);
+    memory.data[0xFF00] = INSTRUCTION_BEQ;
+    memory.data[0xFF01] = 0x10;
+    let cycles_used = cpu.execute(3, &mut memory);
+    assert_eq!(cycles_used, 3);
+    assert_eq!(cpu.program_counter, 0xFF12);
+}
+
+#[test]
+fn bne_can_branch_backwards() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.program_counter = 0xFF80;
+    memory.data[0xFF80] = INSTRUCTION_BNE;
+    memory.data[0xFF81] = 0xFC;
+    let cycles_used = cpu.execute(3, &mut memory);
+    assert_eq!(cycles_used, 3);
+    assert_eq!(cpu.program_counter, 0xFF7E);
+}
+
+#[test]
+fn bcs_takes_extra_cycle_when_crossing_page() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.program_counter = 0xFEFD;
+    cpu.processor_status.set_carry(true);
+    memory.data[0xFEFD] = INSTRUCTION_BCS;
+    memory.data[0xFEFE] = 0x01;
+    let cycles_used = cpu.execute(4, &mut memory);
+    assert_eq!(cycles_used, 4);
+    assert_eq!(cpu.program_counter, 0xFF00);
+}
+
+#[test]
+fn branches_test_their_flags() {
+    let cases: [(Byte, SetFlag, bool); 8] = [
+        (INSTRUCTION_BCC, ProcessorStatus::set_carry, false),
+        (INSTRUCTION_BCS, ProcessorStatus::set_carry, true),
+        (INSTRUCTION_BEQ, ProcessorStatus::set_zero, true),
+        (INSTRUCTION_BNE, ProcessorStatus::set_zero, false),
+        (INSTRUCTION_BMI, ProcessorStatus::set_negative, true),
+        (INSTRUCTION_BPL, ProcessorStatus::set_negative, false),
+        (INSTRUCTION_BVS, ProcessorStatus::set_overflow, true),
+        (INSTRUCTION_BVC, ProcessorStatus::set_overflow, false),
+    ];
+    for (opcode, set_flag, taken_when) in cases {
+        for flag in [false, true] {
+            let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+            cpu.program_counter = 0xFF00;
+            set_flag(&mut cpu.processor_status, flag);
+            memory.data[0xFF00] = opcode;
+            memory.data[0xFF01] = 0x04;
+            cpu.execute(1, &mut memory);
+            let expected: Word = if flag == taken_when { 0xFF06 } else { 0xFF02 };
+            assert_eq!(cpu.program_counter, expected, "opcode {:#04x}", opcode);
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use rust6502::{instructions::*, *};
+
+mod common;
+
+use common::recording_bus::{Access, RecordingBus};
+
+fn setup(program: &[Byte]) -> (CPU, RecordingBus) {
+    let mut cpu: CPU = CPU::at_reset_vector_unsequenced();
+    cpu.program_counter = 0x0200;
+    let mut bus = RecordingBus::new();
+    bus.memory.data[0x0200..0x0200 + program.len()].copy_from_slice(program);
+    (cpu, bus)
+}
+
+#[test]
+fn every_documented_opcode_accesses_the_bus_once_per_cycle() {
+    for opcode in 0x00..=0xFF {
+        if decode(opcode).is_none() {
+            continue;
+        }
+        let (mut cpu, mut bus) = setup(&[opcode, 0xFF, 0x12]);
+        cpu.register_x = 0x80;
+        cpu.register_y = 0x80;
+        let step = cpu.step(&mut bus).unwrap();
+        assert_eq!(
+            bus.accesses.len() as i32,
+            step.cycles,
+            "opcode {:#04x}",
+            opcode
+        );
+    }
+}
+
+#[test]
+fn implied_instructions_read_the_next_byte() {
+    let (mut cpu, mut bus) = setup(&[INSTRUCTION_INX, 0xAB]);
+    cpu.step(&mut bus).unwrap();
+    assert_eq!(
+        bus.accesses,
+        vec![
+            Access::Read(0x0200, INSTRUCTION_INX),
+            Access::Read(0x0201, 0xAB),
+        ]
+    );
+}
+
+#[test]
+fn absolute_x_read_makes_a_dummy_read_when_crossing_a_page() {
+    let (mut cpu, mut bus) = setup(&[INSTRUCTION_LDA_ABS_X, 0xF0, 0x12]);
+    cpu.register_x = 0x20;
+    cpu.step(&mut bus).unwrap();
+    assert_eq!(
+        bus.accesses,
+        vec![
+            Access::Read(0x0200, INSTRUCTION_LDA_ABS_X),
+            Access::Read(0x0201, 0xF0),
+            Access::Read(0x0202, 0x12),
+            Access::Read(0x1210, 0x00),
+            Access::Read(0x1310, 0x00),
+        ]
+    );
+}
+
+#[test]
+fn absolute_x_store_always_makes_a_dummy_read() {
+    let (mut cpu, mut bus) = setup(&[INSTRUCTION_STA_ABS_X, 0x00, 0x12]);
+    cpu.register_x = 0x05;
+    cpu.accumulator = 0x42;
+    cpu.step(&mut bus).unwrap();
+    assert_eq!(
+        bus.accesses,
+        vec![
+            Access::Read(0x0200, INSTRUCTION_STA_ABS_X),
+            Access::Read(0x0201, 0x00),
+            Access::Read(0x0202, 0x12),
+            Access::Read(0x1205, 0x00),
+            Access::Write(0x1205, 0x42),
+        ]
+    );
+}
+
+#[test]
+fn indirect_y_read_makes_a_dummy_read_when_crossing_a_page() {
+    let (mut cpu, mut bus) = setup(&[INSTRUCTION_LDA_INDR_Y, 0x10]);
+    bus.memory.data[0x0010] = 0xFF;
+    bus.memory.data[0x0011] = 0x30;
+    cpu.register_y = 0x02;
+    cpu.step(&mut bus).unwrap();
+    assert_eq!(
+        bus.accesses,
+        vec![
+            Access::Read(0x0200, INSTRUCTION_LDA_INDR_Y),
+            Access::Read(0x0201, 0x10),
+            Access::Read(0x0010, 0xFF),
+            Access::Read(0x0011, 0x30),
+            Access::Read(0x3001, 0x00),
+            Access::Read(0x3101, 0x00),
+        ]
+    );
+}
+
+#[test]
+fn indirect_x_reads_the_pointer_before_indexing() {
+    let (mut cpu, mut bus) = setup(&[INSTRUCTION_LDA_INDR_X, 0x10]);
+    bus.memory.data[0x0014] = 0x00;
+    bus.memory.data[0x0015] = 0x30;
+    cpu.register_x = 0x04;
+    cpu.step(&mut bus).unwrap();
+    assert_eq!(
+        bus.accesses,
+        vec![
+            Access::Read(0x0200, INSTRUCTION_LDA_INDR_X),
+            Access::Read(0x0201, 0x10),
+            Access::Read(0x0010, 0x00),
+            Access::Read(0x0014, 0x00),
+            Access::Read(0x0015, 0x30),
+            Access::Read(0x3000, 0x00),
+        ]
+    );
+}
+
+#[test]
+fn read_modify_write_writes_the_original_value_first() {
+    let (mut cpu, mut bus) = setup(&[INSTRUCTION_INC_ZERO, 0x40]);
+    bus.memory.data[0x0040] = 0x7F;
+    cpu.step(&mut bus).unwrap();
+    assert_eq!(
+        bus.accesses,
+        vec![
+            Access::Read(0x0200, INSTRUCTION_INC_ZERO),
+            Access::Read(0x0201, 0x40),
+            Access::Read(0x0040, 0x7F),
+            Access::Write(0x0040, 0x7F),
+            Access::Write(0x0040, 0x80),
+        ]
+    );
+}
+
+#[test]
+fn jsr_pushes_the_return_address_before_fetching_the_high_byte() {
+    let (mut cpu, mut bus) = setup(&[INSTRUCTION_JSR, 0x00, 0x30]);
+    cpu.step(&mut bus).unwrap();
+    assert_eq!(
+        bus.accesses,
+        vec![
+            Access::Read(0x0200, INSTRUCTION_JSR),
+            Access::Read(0x0201, 0x00),
+            Access::Read(0x01FF, 0x00),
+            Access::Write(0x01FF, 0x02),
+            Access::Write(0x01FE, 0x02),
+            Access::Read(0x0202, 0x30),
+        ]
+    );
+    assert_eq!(cpu.program_counter, 0x3000);
+}
+
+#[test]
+fn rts_reads_the_stack_and_the_return_address() {
+    let (mut cpu, mut bus) = setup(&[INSTRUCTION_RTS, 0xEA]);
+    cpu.stack_pointer = 0xFD;
+    bus.memory.data[0x01FE] = 0x02;
+    bus.memory.data[0x01FF] = 0x30;
+    cpu.step(&mut bus).unwrap();
+    assert_eq!(
+        bus.accesses,
+        vec![
+            Access::Read(0x0200, INSTRUCTION_RTS),
+            Access::Read(0x0201, 0xEA),
+            Access::Read(0x01FD, 0x00),
+            Access::Read(0x01FE, 0x02),
+            Access::Read(0x01FF, 0x30),
+            Access::Read(0x3002, 0x00),
+        ]
+    );
+    assert_eq!(cpu.program_counter, 0x3003);
+}
+
+#[test]
+fn taken_branch_across_a_page_reads_the_unfixed_target() {
+    let (mut cpu, mut bus) = setup(&[]);
+    cpu.program_counter = 0x02F0;
+    bus.memory.data[0x02F0] = INSTRUCTION_BNE;
+    bus.memory.data[0x02F1] = 0x10;
+    cpu.step(&mut bus).unwrap();
+    assert_eq!(
+        bus.accesses,
+        vec![
+            Access::Read(0x02F0, INSTRUCTION_BNE),
+            Access::Read(0x02F1, 0x10),
+            Access::Read(0x02F2, 0x00),
+            Access::Read(0x0202, 0x00),
+        ]
+    );
+    assert_eq!(cpu.program_counter, 0x0302);
+}
+
+#[test]
+fn reset_reads_the_stack_instead_of_writing_it() {
+    let mut cpu: CPU = CPU::new();
+    let mut bus = RecordingBus::new();
+    cpu.program_counter = 0x0200;
+    bus.memory.data[0xFFFC] = 0x00;
+    bus.memory.data[0xFFFD] = 0x80;
+    let step = cpu.step(&mut bus).unwrap();
+    assert_eq!(step.cycles, 7);
+    assert!(bus
+        .accesses
+        .iter()
+        .all(|access| matches!(access, Access::Read(..))));
+    assert_eq!(cpu.program_counter, 0x8000);
+}
+
+#[test]
+fn nop_policy_reads_the_operand_of_an_unknown_opcode() {
+    // SLO zp: read like a NOP zp, without the read-modify-write
+    let (mut cpu, mut bus) = setup(&[0x07, 0x10]);
+    cpu.unknown_opcode_policy = UnknownOpcodePolicy::Nop;
+    bus.memory.data[0x0010] = 0x81;
+    cpu.step(&mut bus).unwrap();
+    assert_eq!(
+        bus.accesses,
+        vec![
+            Access::Read(0x0200, 0x07),
+            Access::Read(0x0201, 0x10),
+            Access::Read(0x0010, 0x81),
+        ]
+    );
+    assert_eq!(cpu.program_counter, 0x0202);
+}
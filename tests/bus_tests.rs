@@ -0,0 +1,80 @@
+use rust6502::{instructions::*, *};
+
+/// 2KB of RAM mirrored across $0000-$1FFF, an output port at $6000 and a
+/// read-only ROM in the top 32KB.
+struct TestBoard {
+    ram: [Byte; 0x0800],
+    rom: [Byte; 0x8000],
+    port_writes: Vec<Byte>,
+}
+
+impl TestBoard {
+    fn new() -> Self {
+        Self {
+            ram: [0x00; 0x0800],
+            rom: [0x00; 0x8000],
+            port_writes: Vec::new(),
+        }
+    }
+}
+
+impl Bus for TestBoard {
+    fn read(&mut self, address: Word) -> Byte {
+        match address {
+            0x0000..=0x1FFF => self.ram[(address & 0x07FF) as usize],
+            0x8000..=0xFFFF => self.rom[(address - 0x8000) as usize],
+            _ => 0x00,
+        }
+    }
+
+    fn write(&mut self, address: Word, data: Byte) {
+        match address {
+            0x0000..=0x1FFF => self.ram[(address & 0x07FF) as usize] = data,
+            0x6000 => self.port_writes.push(data),
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn cpu_can_execute_from_a_custom_bus() {
+    let mut cpu: CPU = CPU::at_reset_vector_unsequenced();
+    let mut board: TestBoard = TestBoard::new();
+    board.rom[0x7FFC] = INSTRUCTION_LDA_IMM;
+    board.rom[0x7FFD] = 0x42;
+    board.rom[0x7FFE] = INSTRUCTION_NOP;
+    let cycles_used = cpu.execute(4, &mut board);
+    assert_eq!(cycles_used, 4);
+    assert_eq!(cpu.accumulator, 0x42);
+}
+
+#[test]
+fn writes_reach_memory_mapped_peripherals() {
+    let mut cpu: CPU = CPU::at_reset_vector_unsequenced();
+    let mut board: TestBoard = TestBoard::new();
+    cpu.program_counter = 0x8000;
+    cpu.accumulator = 0x37;
+    board.rom[0x0000] = INSTRUCTION_STA_ABS;
+    board.rom[0x0001] = 0x00;
+    board.rom[0x0002] = 0x60;
+    cpu.execute(4, &mut board);
+    assert_eq!(board.port_writes, vec![0x37]);
+}
+
+#[test]
+fn writes_to_rom_are_ignored_and_ram_is_mirrored() {
+    let mut cpu: CPU = CPU::at_reset_vector_unsequenced();
+    let mut board: TestBoard = TestBoard::new();
+    cpu.program_counter = 0x8000;
+    cpu.accumulator = 0x37;
+    board.rom[0x0000] = INSTRUCTION_STA_ABS;
+    board.rom[0x0001] = 0x10;
+    board.rom[0x0002] = 0x08;
+    board.rom[0x0003] = INSTRUCTION_STA_ABS;
+    board.rom[0x0004] = 0x00;
+    board.rom[0x0005] = 0x90;
+    cpu.execute(8, &mut board);
+    assert_eq!(board.read(0x0010), 0x37);
+    assert_eq!(board.read(0x1810), 0x37);
+    assert_eq!(board.read(0x9000), 0x00);
+}
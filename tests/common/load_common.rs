@@ -1,9 +1,8 @@
-#[allow(dead_code)]
 use crate::*;
 
 pub fn load_register_immediate(opcode: Byte, register_type: RegisterType) {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy: CPU = cpu.clone();
+    let cpu_copy: CPU = cpu;
     memory.data[0xFFFC] = opcode;
     memory.data[0xFFFD] = 0x84;
     let cycles_used = cpu.execute(2, &mut memory);
@@ -16,7 +15,7 @@ pub fn load_register_immediate(opcode: Byte, register_type: RegisterType) {
 
 pub fn load_register_zeropage(opcode: Byte, register_type: RegisterType) {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy: CPU = cpu.clone();
+    let cpu_copy: CPU = cpu;
     memory.data[0xFFFC] = opcode;
     memory.data[0xFFFD] = 0x42;
     memory.data[0x0042] = 0x37;
@@ -30,7 +29,7 @@ pub fn load_register_zeropage(opcode: Byte, register_type: RegisterType) {
 
 pub fn load_register_zeropage_x(opcode: Byte, register_type: RegisterType) {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy: CPU = cpu.clone();
+    let cpu_copy: CPU = cpu;
     cpu.register_x = 5;
     memory.data[0xFFFC] = opcode;
     memory.data[0xFFFD] = 0x42;
@@ -45,7 +44,7 @@ pub fn load_register_zeropage_x(opcode: Byte, register_type: RegisterType) {
 
 pub fn load_register_zeropage_y(opcode: Byte, register_type: RegisterType) {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy: CPU = cpu.clone();
+    let cpu_copy: CPU = cpu;
     cpu.register_y = 5;
     memory.data[0xFFFC] = opcode;
     memory.data[0xFFFD] = 0x42;
@@ -60,7 +59,7 @@ pub fn load_register_zeropage_y(opcode: Byte, register_type: RegisterType) {
 
 pub fn load_register_absolute(opcode: Byte, register_type: RegisterType) {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy: CPU = cpu.clone();
+    let cpu_copy: CPU = cpu;
     cpu.processor_status.set_zero(true);
     cpu.processor_status.set_negative(true);
     memory.data[0xFFFC] = opcode;
@@ -77,7 +76,7 @@ pub fn load_register_absolute(opcode: Byte, register_type: RegisterType) {
 
 pub fn load_register_absolute_x(opcode: Byte, register_type: RegisterType) {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy: CPU = cpu.clone();
+    let cpu_copy: CPU = cpu;
     cpu.processor_status.set_zero(true);
     cpu.processor_status.set_negative(true);
     cpu.register_x = 1;
@@ -95,7 +94,7 @@ pub fn load_register_absolute_x(opcode: Byte, register_type: RegisterType) {
 
 pub fn load_register_absolute_y(opcode: Byte, register_type: RegisterType) {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy: CPU = cpu.clone();
+    let cpu_copy: CPU = cpu;
     cpu.processor_status.set_zero(true);
     cpu.processor_status.set_negative(true);
     cpu.register_y = 1;
@@ -113,7 +112,7 @@ pub fn load_register_absolute_y(opcode: Byte, register_type: RegisterType) {
 
 pub fn load_register_absolute_x_with_page_cross(opcode: Byte, register_type: RegisterType) {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy: CPU = cpu.clone();
+    let cpu_copy: CPU = cpu;
     cpu.register_x = 0x1;
     memory.data[0xFFFC] = opcode;
     memory.data[0xFFFD] = 0xFF;
@@ -129,7 +128,7 @@ pub fn load_register_absolute_x_with_page_cross(opcode: Byte, register_type: Reg
 
 pub fn load_register_absolute_y_with_page_cross(opcode: Byte, register_type: RegisterType) {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy: CPU = cpu.clone();
+    let cpu_copy: CPU = cpu;
     cpu.register_y = 0x1;
     memory.data[0xFFFC] = opcode;
     memory.data[0xFFFD] = 0xFF;
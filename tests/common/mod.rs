@@ -1,10 +1,15 @@
+#![allow(dead_code)]
+
 use rust6502::{Memory, CPU};
 
 pub mod load_common;
+pub mod recording_bus;
+pub mod single_step;
 pub mod store_common;
+pub mod trap;
 
 pub fn setup() -> (CPU, Memory) {
-    let cpu: CPU = CPU::reset();
+    let cpu: CPU = CPU::at_reset_vector_unsequenced();
     let memory: Memory = Memory::reset();
     (cpu, memory)
 }
@@ -0,0 +1,39 @@
+use rust6502::{Bus, Byte, Memory, Word};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Access {
+    Read(Word, Byte),
+    Write(Word, Byte),
+}
+
+/// Flat 64KB of memory that logs every access the CPU makes.
+pub struct RecordingBus {
+    pub memory: Memory,
+    pub accesses: Vec<Access>,
+}
+
+impl RecordingBus {
+    pub fn new() -> Self {
+        Self {
+            memory: Memory::reset(),
+            accesses: Vec::new(),
+        }
+    }
+}
+
+impl Bus for RecordingBus {
+    fn read(&mut self, address: Word) -> Byte {
+        let data = self.memory.data[address as usize];
+        self.accesses.push(Access::Read(address, data));
+        data
+    }
+
+    fn write(&mut self, address: Word, data: Byte) {
+        self.memory.data[address as usize] = data;
+        self.accesses.push(Access::Write(address, data));
+    }
+
+    fn peek(&mut self, address: Word) -> Byte {
+        self.memory.data[address as usize]
+    }
+}
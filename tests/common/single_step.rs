@@ -0,0 +1,178 @@
+use std::fmt::Write;
+
+use rust6502::{Byte, ProcessorStatus, UndocumentedOpcodes, Word, CPU};
+use serde_json::Value;
+
+use super::recording_bus::{Access, RecordingBus};
+
+/// Bits 4 and 5 of the status register only exist when it is pushed, so
+/// they are not compared.
+const STATUS_MASK: Byte = 0b1100_1111;
+
+/// Registers and memory before or after one SingleStepTests vector.
+#[derive(Clone, Debug, PartialEq)]
+pub struct State {
+    pub pc: Word,
+    pub s: Byte,
+    pub a: Byte,
+    pub x: Byte,
+    pub y: Byte,
+    pub p: Byte,
+    pub ram: Vec<(Word, Byte)>,
+}
+
+/// One vector in the format of Tom Harte's SingleStepTests (ProcessorTests):
+/// the state before and after a single instruction, and every bus access it
+/// makes in between.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestCase {
+    pub name: String,
+    pub initial: State,
+    pub expected: State,
+    pub cycles: Vec<Access>,
+}
+
+/// Parses a file of vectors, which is a JSON array of test cases.
+pub fn parse(json: &str) -> Result<Vec<TestCase>, String> {
+    let value: Value = serde_json::from_str(json).map_err(|error| error.to_string())?;
+    value
+        .as_array()
+        .ok_or("expected an array of test cases")?
+        .iter()
+        .map(parse_test_case)
+        .collect()
+}
+
+fn parse_test_case(value: &Value) -> Result<TestCase, String> {
+    let name: String = value["name"].as_str().ok_or("missing name")?.to_string();
+    let cycles: Vec<Access> = value["cycles"]
+        .as_array()
+        .ok_or_else(|| format!("{}: missing cycles", name))?
+        .iter()
+        .map(|cycle| {
+            let address: Word = number(&cycle[0])?;
+            let data: Byte = number(&cycle[1])?;
+            match cycle[2].as_str() {
+                Some("read") => Ok(Access::Read(address, data)),
+                Some("write") => Ok(Access::Write(address, data)),
+                _ => Err(format!("invalid cycle {}", cycle)),
+            }
+        })
+        .collect::<Result<_, String>>()
+        .map_err(|error| format!("{}: {}", name, error))?;
+    Ok(TestCase {
+        initial: parse_state(&value["initial"]).map_err(|error| format!("{}: {}", name, error))?,
+        expected: parse_state(&value["final"]).map_err(|error| format!("{}: {}", name, error))?,
+        cycles,
+        name,
+    })
+}
+
+fn parse_state(value: &Value) -> Result<State, String> {
+    let ram: Vec<(Word, Byte)> = value["ram"]
+        .as_array()
+        .ok_or("missing ram")?
+        .iter()
+        .map(|entry| Ok((number(&entry[0])?, number(&entry[1])?)))
+        .collect::<Result<_, String>>()?;
+    Ok(State {
+        pc: number(&value["pc"])?,
+        s: number(&value["s"])?,
+        a: number(&value["a"])?,
+        x: number(&value["x"])?,
+        y: number(&value["y"])?,
+        p: number(&value["p"])?,
+        ram,
+    })
+}
+
+fn number<T: TryFrom<u64>>(value: &Value) -> Result<T, String> {
+    value
+        .as_u64()
+        .and_then(|number| T::try_from(number).ok())
+        .ok_or_else(|| format!("invalid number {}", value))
+}
+
+/// Runs one vector and returns a line per difference from the expected
+/// state, or `Ok` if there were none.
+pub fn run(test: &TestCase) -> Result<(), String> {
+    let mut bus: RecordingBus = RecordingBus::new();
+    for &(address, data) in &test.initial.ram {
+        bus.memory.data[address as usize] = data;
+    }
+    let mut cpu: CPU = CPU::at_reset_vector_unsequenced();
+    // The suite was recorded from a chip with this XAA/LXA constant
+    cpu.undocumented_opcodes = Some(UndocumentedOpcodes { magic: 0xEE });
+    cpu.program_counter = test.initial.pc;
+    cpu.stack_pointer = test.initial.s;
+    cpu.accumulator = test.initial.a;
+    cpu.register_x = test.initial.x;
+    cpu.register_y = test.initial.y;
+    cpu.processor_status = ProcessorStatus(test.initial.p);
+
+    let mut diff: String = String::new();
+    if let Err(error) = cpu.step(&mut bus) {
+        writeln!(diff, "  {}", error).unwrap();
+    }
+    let expected: &State = &test.expected;
+    let mut compare = |field: &str, expected: u16, actual: u16, width: usize| {
+        if expected != actual {
+            writeln!(
+                diff,
+                "  {}: expected ${:0width$X}, got ${:0width$X}",
+                field, expected, actual
+            )
+            .unwrap();
+        }
+    };
+    compare("pc", expected.pc, cpu.program_counter, 4);
+    compare("s", expected.s.into(), cpu.stack_pointer.into(), 2);
+    compare("a", expected.a.into(), cpu.accumulator.into(), 2);
+    compare("x", expected.x.into(), cpu.register_x.into(), 2);
+    compare("y", expected.y.into(), cpu.register_y.into(), 2);
+    compare(
+        "p",
+        (expected.p & STATUS_MASK).into(),
+        (cpu.processor_status.0 & STATUS_MASK).into(),
+        2,
+    );
+    for &(address, data) in &expected.ram {
+        let field: String = format!("ram[${:04X}]", address);
+        compare(
+            &field,
+            data.into(),
+            bus.memory.data[address as usize].into(),
+            2,
+        );
+    }
+
+    let length: usize = test.cycles.len().max(bus.accesses.len());
+    for cycle in 0..length {
+        let expected: Option<&Access> = test.cycles.get(cycle);
+        let actual: Option<&Access> = bus.accesses.get(cycle);
+        if expected != actual {
+            writeln!(
+                diff,
+                "  cycle {}: expected {}, got {}",
+                cycle + 1,
+                describe(expected),
+                describe(actual)
+            )
+            .unwrap();
+        }
+    }
+
+    if diff.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{}:\n{}", test.name, diff))
+    }
+}
+
+fn describe(access: Option<&Access>) -> String {
+    match access {
+        Some(Access::Read(address, data)) => format!("read ${:02X} from ${:04X}", data, address),
+        Some(Access::Write(address, data)) => format!("write ${:02X} to ${:04X}", data, address),
+        None => String::from("nothing"),
+    }
+}
@@ -2,7 +2,7 @@ use crate::*;
 
 pub fn store_register_zeropage(opcode: Byte, register_type: RegisterType) {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy = cpu.clone();
+    let cpu_copy = cpu;
     *cpu.get_register(&register_type) = 0x2F;
     memory.data[0xFFFC] = opcode;
     memory.data[0xFFFD] = 0x80;
@@ -15,7 +15,7 @@ pub fn store_register_zeropage(opcode: Byte, register_type: RegisterType) {
 
 pub fn store_register_zeropage_x(opcode: Byte, register_type: RegisterType) {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy = cpu.clone();
+    let cpu_copy = cpu;
     *cpu.get_register(&register_type) = 0x42;
     cpu.register_x = 0x0F;
     memory.data[0xFFFC] = opcode;
@@ -29,7 +29,7 @@ pub fn store_register_zeropage_x(opcode: Byte, register_type: RegisterType) {
 
 pub fn store_register_zeropage_y(opcode: Byte, register_type: RegisterType) {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy = cpu.clone();
+    let cpu_copy = cpu;
     *cpu.get_register(&register_type) = 0x42;
     cpu.register_y = 0x0F;
     memory.data[0xFFFC] = opcode;
@@ -43,7 +43,7 @@ pub fn store_register_zeropage_y(opcode: Byte, register_type: RegisterType) {
 
 pub fn store_register_absolute(opcode: Byte, register_type: RegisterType) {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy = cpu.clone();
+    let cpu_copy = cpu;
     *cpu.get_register(&register_type) = 0x2F;
     memory.data[0xFFFC] = opcode;
     memory.data[0xFFFD] = 0x00;
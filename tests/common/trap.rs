@@ -0,0 +1,56 @@
+use rust6502::{Byte, Memory, Step, Word, CPU};
+
+/// An instruction that jumps or branches to itself, which test programs use
+/// to stop and signal their result.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Trap {
+    pub address: Word,
+    pub instructions: u64,
+}
+
+/// Loads a binary image into memory at `address`, panicking if the file is
+/// missing.
+pub fn load_binary(path: &str, address: Word) -> Memory {
+    let bytes: Vec<Byte> = std::fs::read(path)
+        .unwrap_or_else(|error| panic!("{}: {} (see tests/roms/README.md)", path, error));
+    let mut memory: Memory = Memory::reset();
+    let start: usize = address as usize;
+    assert!(
+        start + bytes.len() <= memory.data.len(),
+        "{} does not fit in memory at {:#06x}",
+        path,
+        address
+    );
+    memory.data[start..start + bytes.len()].copy_from_slice(&bytes);
+    memory
+}
+
+/// Runs from the current program counter until the CPU reaches a trap.
+///
+/// Panics if the CPU halts or if no trap is reached within
+/// `max_instructions`, reporting the test number held at `test_number`.
+pub fn run_until_trap(
+    cpu: &mut CPU,
+    memory: &mut Memory,
+    test_number: Word,
+    max_instructions: u64,
+) -> Trap {
+    for instructions in 1..=max_instructions {
+        let step: Step = cpu.step(memory).unwrap_or_else(|error| {
+            panic!(
+                "{} during test {:#04x}",
+                error, memory.data[test_number as usize]
+            )
+        });
+        if step.interrupt.is_none() && cpu.program_counter == step.address {
+            return Trap {
+                address: step.address,
+                instructions,
+            };
+        }
+    }
+    panic!(
+        "no trap reached after {} instructions, test {:#04x} at {:#06x}",
+        max_instructions, memory.data[test_number as usize], cpu.program_counter
+    );
+}
@@ -0,0 +1,202 @@
+use asm_macros::asm;
+use rust6502::{debugger::*, *};
+
+mod common;
+
+use common::recording_bus::{Access, RecordingBus};
+
+/// Loads a program and points the CPU at its origin.
+fn debugger<const N: usize>(program: &asm::ProgramImage<N>) -> Debugger<Memory> {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    program.load_into(&mut memory.data);
+    cpu.program_counter = program.origin;
+    let mut debugger: Debugger<Memory> = Debugger::new(cpu, memory);
+    debugger.instruction_limit = Some(1000);
+    debugger
+}
+
+#[test]
+fn run_stops_at_a_breakpoint_and_continues_past_it() {
+    let program = asm! {
+        .org $0400
+        ldx #3
+    loop: dex
+        bne loop
+    done: jmp done
+    };
+    let mut debugger = debugger(&program);
+    let id: BreakpointId = debugger.add_breakpoint(program.symbol("loop").unwrap());
+    assert_eq!(debugger.run().unwrap(), StopReason::Breakpoint(id));
+    assert_eq!(debugger.cpu.register_x, 3);
+    assert_eq!(debugger.run().unwrap(), StopReason::Breakpoint(id));
+    assert_eq!(debugger.cpu.register_x, 2);
+    assert!(debugger.remove_breakpoint(id));
+    assert!(!debugger.remove_breakpoint(id));
+    assert_eq!(debugger.run().unwrap(), StopReason::InstructionLimit);
+    assert_eq!(
+        debugger.cpu.program_counter,
+        program.symbol("done").unwrap()
+    );
+}
+
+#[test]
+fn conditional_breakpoint_stops_only_when_the_condition_holds() {
+    let program = asm! {
+        .org $0400
+        ldx #5
+    loop: dex
+        bne loop
+    done: jmp done
+    };
+    let mut debugger = debugger(&program);
+    let loop_address: Word = program.symbol("loop").unwrap();
+    let id: BreakpointId = debugger.add_conditional_breakpoint(
+        loop_address,
+        Condition::Register(RegisterType::RegisterX, Comparison::Equal, 2),
+    );
+    assert_eq!(debugger.run().unwrap(), StopReason::Breakpoint(id));
+    assert_eq!(debugger.cpu.register_x, 2);
+
+    let flag_id: BreakpointId = debugger.add_conditional_breakpoint(
+        program.symbol("done").unwrap(),
+        Condition::Flag(Flag::Zero, true),
+    );
+    assert_eq!(debugger.run().unwrap(), StopReason::Breakpoint(flag_id));
+    assert_eq!(debugger.cpu.register_x, 0);
+}
+
+#[test]
+fn temporary_breakpoint_is_removed_when_hit() {
+    let program = asm! {
+        .org $0400
+    loop: inx
+        jmp loop
+    };
+    let mut debugger = debugger(&program);
+    let id: BreakpointId = debugger.add_temporary_breakpoint(program.symbol("loop").unwrap());
+    assert_eq!(debugger.run().unwrap(), StopReason::Breakpoint(id));
+    assert!(debugger.breakpoints().is_empty());
+    assert_eq!(debugger.run().unwrap(), StopReason::InstructionLimit);
+}
+
+#[test]
+fn watchpoints_stop_after_the_matching_access() {
+    let program = asm! {
+        .org $0400
+        lda $2000
+        sta $2001
+        inc $3000
+    };
+    let mut debugger = debugger(&program);
+    debugger.bus.data[0x2000] = 0x42;
+    let write: WatchpointId = debugger.add_watchpoint(0x2000..=0x2001, WatchKind::Write);
+    let read: WatchpointId = debugger.add_watchpoint(0x2000..=0x2000, WatchKind::Read);
+    let access: WatchpointId = debugger.add_watchpoint(0x3000..=0x3000, WatchKind::Access);
+    let hit = |id, address, data, write| {
+        StopReason::Watchpoint(WatchHit {
+            id,
+            address,
+            data,
+            write,
+        })
+    };
+    assert_eq!(debugger.run().unwrap(), hit(read, 0x2000, 0x42, false));
+    assert_eq!(debugger.cpu.program_counter, 0x0403);
+    assert_eq!(debugger.run().unwrap(), hit(write, 0x2001, 0x42, true));
+    assert_eq!(debugger.step().unwrap(), hit(access, 0x3000, 0x00, false));
+    assert!(debugger.remove_watchpoint(read));
+    assert_eq!(debugger.watchpoints().len(), 2);
+}
+
+#[test]
+fn step_over_runs_a_whole_subroutine() {
+    let program = asm! {
+        .org $0400
+        jsr add_two
+        nop
+    add_two: inx
+        jsr add_one
+    add_one: inx
+        rts
+    };
+    let mut debugger = debugger(&program);
+    assert_eq!(debugger.step_over().unwrap(), StopReason::Step);
+    assert_eq!(debugger.cpu.program_counter, 0x0403);
+    assert_eq!(debugger.cpu.register_x, 3);
+    assert_eq!(debugger.cpu.cycles, 6 + 2 + 6 + 2 + 6 + 2 + 6);
+    assert_eq!(debugger.step_over().unwrap(), StopReason::Step);
+    assert_eq!(debugger.cpu.program_counter, 0x0404);
+}
+
+#[test]
+fn step_over_only_makes_the_accesses_of_the_instruction() {
+    let mut cpu: CPU = CPU::at_reset_vector_unsequenced();
+    let mut bus: RecordingBus = RecordingBus::new();
+    cpu.program_counter = 0x0400;
+    bus.memory.data[0x0400] = instructions::INSTRUCTION_NOP;
+    let mut debugger: Debugger<RecordingBus> = Debugger::new(cpu, bus);
+    assert_eq!(debugger.step_over().unwrap(), StopReason::Step);
+    assert_eq!(
+        debugger.bus.accesses,
+        vec![
+            Access::Read(0x0400, instructions::INSTRUCTION_NOP),
+            Access::Read(0x0401, 0x00)
+        ]
+    );
+}
+
+#[test]
+fn step_over_stops_at_a_breakpoint_inside_the_subroutine() {
+    let program = asm! {
+        .org $0400
+        jsr sub
+        nop
+    sub: inx
+    inner: inx
+        rts
+    };
+    let mut debugger = debugger(&program);
+    let id: BreakpointId = debugger.add_breakpoint(program.symbol("inner").unwrap());
+    assert_eq!(debugger.step_over().unwrap(), StopReason::Breakpoint(id));
+    assert_eq!(debugger.cpu.register_x, 1);
+}
+
+#[test]
+fn step_out_runs_until_the_matching_rts() {
+    let program = asm! {
+        .org $0400
+        jsr outer
+        nop
+    outer: jsr inner
+        inx
+        rts
+    inner: iny
+        rts
+    };
+    let mut debugger = debugger(&program);
+    debugger.step().unwrap();
+    debugger.step().unwrap();
+    assert_eq!(
+        debugger.cpu.program_counter,
+        program.symbol("inner").unwrap()
+    );
+    assert_eq!(debugger.step_out().unwrap(), StopReason::Step);
+    assert_eq!(debugger.cpu.program_counter, 0x0407);
+    assert_eq!(debugger.cpu.register_x, 0);
+    assert_eq!(debugger.step_out().unwrap(), StopReason::Step);
+    assert_eq!(debugger.cpu.program_counter, 0x0403);
+    assert_eq!(debugger.cpu.register_x, 1);
+    assert_eq!(debugger.cpu.register_y, 1);
+}
+
+#[test]
+fn run_returns_execution_errors() {
+    let program = asm! {
+        .org $0400
+        nop
+        .byte $02
+    };
+    let mut debugger = debugger(&program);
+    let error: ExecutionError = debugger.run().unwrap_err();
+    assert_eq!(error.address(), 0x0401);
+}
@@ -0,0 +1,164 @@
+use rust6502::{instructions::*, *};
+
+mod common;
+
+use common::trap::{self, Trap};
+
+/// Bruce Clark's decimal mode test, which checks the accumulator and the
+/// N, V, Z and C flags of ADC and SBC for every pair of operands and both
+/// carry values against the results he measured on real chips.
+const DECIMAL_TEST: &str = include_str!("roms/decimal_test.a65");
+/// Where the test leaves its result: 0 if it passed, 1 if it failed.
+const ERROR: usize = 0x04;
+const TEST_NUMBER: Word = 0x0004;
+
+/// Assembles the decimal test at $0200 behind a `JSR TEST` that ends in a
+/// trap, with the given ADC and SBC prediction routines.
+fn decimal_test(add: &str, subtract: &str) -> asm::Program {
+    let source: String = format!(
+        ".org $0200\n JSR TEST\nFINISH: JMP FINISH\n{}",
+        DECIMAL_TEST
+            .replace("JSR A6502", &format!("JSR {}", add))
+            .replace("JSR S6502", &format!("JSR {}", subtract))
+    );
+    asm::assemble(&source).unwrap()
+}
+
+fn run_decimal_test(variant: Variant, add: &str, subtract: &str) {
+    let program: asm::Program = decimal_test(add, subtract);
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    program.load_into(&mut memory.data);
+    cpu.variant = variant;
+    cpu.program_counter = program.origin();
+    let trap: Trap = trap::run_until_trap(&mut cpu, &mut memory, TEST_NUMBER, 100_000_000);
+    assert_eq!(trap.address, program.symbols["FINISH"]);
+    assert_eq!(
+        memory.data[ERROR], 0,
+        "failed with N1={:#04x} N2={:#04x}",
+        memory.data[0x07], memory.data[0x0A]
+    );
+}
+
+fn execute_decimal(
+    cpu: &mut CPU,
+    memory: &mut Memory,
+    opcode: Byte,
+    a: Byte,
+    b: Byte,
+    carry: bool,
+) -> i32 {
+    cpu.program_counter = 0xFFFC;
+    cpu.accumulator = a;
+    cpu.processor_status = ProcessorStatus(0x00);
+    cpu.processor_status.set_decimal(true);
+    cpu.processor_status.set_carry(carry);
+    memory.data[0xFFFC] = opcode;
+    memory.data[0xFFFD] = b;
+    cpu.execute(2, memory)
+}
+
+fn to_bcd(value: u32) -> Byte {
+    (((value / 10) << 4) | (value % 10)) as Byte
+}
+
+#[test]
+fn nmos_passes_bruce_clarks_decimal_test() {
+    run_decimal_test(Variant::Nmos6502, "A6502", "S6502");
+}
+
+#[test]
+fn wdc65c02_passes_bruce_clarks_decimal_test() {
+    run_decimal_test(Variant::Wdc65C02, "A65C02", "S65C02");
+}
+
+#[test]
+fn wdc65816_passes_bruce_clarks_decimal_test() {
+    let program: asm::Program = decimal_test("A65816", "S65816");
+    let finish: Word = program.symbols["FINISH"];
+    let mut cpu: CPU65816 = CPU65816::reset();
+    let mut memory: LongMemory = LongMemory::reset();
+    program.load_into(&mut memory.data);
+    cpu.program_counter = program.origin();
+    let mut instructions: u64 = 0;
+    while cpu.program_counter != finish {
+        assert!(instructions < 100_000_000, "decimal test did not finish");
+        cpu.step(&mut memory);
+        instructions += 1;
+    }
+    assert_eq!(
+        memory.data[ERROR], 0,
+        "failed with N1={:#04x} N2={:#04x}",
+        memory.data[0x07], memory.data[0x0A]
+    );
+}
+
+#[test]
+fn adc_decimal_adds_valid_bcd_numbers() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    for a in 0..100 {
+        for b in 0..100 {
+            for carry in [false, true] {
+                execute_decimal(
+                    &mut cpu,
+                    &mut memory,
+                    INSTRUCTION_ADC_IMM,
+                    to_bcd(a),
+                    to_bcd(b),
+                    carry,
+                );
+                let sum: u32 = a + b + carry as u32;
+                assert_eq!(cpu.accumulator, to_bcd(sum % 100));
+                assert_eq!(cpu.processor_status.carry(), sum >= 100);
+            }
+        }
+    }
+}
+
+#[test]
+fn sbc_decimal_subtracts_valid_bcd_numbers() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    for a in 0..100 {
+        for b in 0..100 {
+            for carry in [false, true] {
+                execute_decimal(
+                    &mut cpu,
+                    &mut memory,
+                    INSTRUCTION_SBC_IMM,
+                    to_bcd(a),
+                    to_bcd(b),
+                    carry,
+                );
+                let difference: i32 = a as i32 - b as i32 - !carry as i32;
+                assert_eq!(cpu.accumulator, to_bcd(difference.rem_euclid(100) as u32));
+                assert_eq!(cpu.processor_status.carry(), difference >= 0);
+            }
+        }
+    }
+}
+
+#[test]
+fn adc_decimal_can_wrap_to_zero_with_carry() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    execute_decimal(
+        &mut cpu,
+        &mut memory,
+        INSTRUCTION_ADC_IMM,
+        0x99,
+        0x01,
+        false,
+    );
+    assert_eq!(cpu.accumulator, 0x00);
+    assert!(cpu.processor_status.carry());
+    // Z follows the binary result ($9A) on NMOS parts
+    assert!(!cpu.processor_status.zero());
+}
+
+#[test]
+fn adc_binary_mode_ignores_bcd_adjustment_when_decimal_is_clear() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0x09;
+    memory.data[0xFFFC] = INSTRUCTION_ADC_IMM;
+    memory.data[0xFFFD] = 0x01;
+    cpu.execute(2, &mut memory);
+    assert_eq!(cpu.accumulator, 0x0A);
+}
@@ -0,0 +1,92 @@
+use rust6502::{disassembler::*, instructions::*, *};
+mod common;
+
+#[test]
+fn disassemble_decodes_opcode_and_operand() {
+    let (_, mut memory) = common::setup();
+    memory.data[0x0200] = INSTRUCTION_LDA_ABS_X;
+    memory.data[0x0201] = 0x34;
+    memory.data[0x0202] = 0x12;
+    let instruction = disassemble(&mut memory, 0x0200);
+    assert_eq!(instruction.mnemonic, Mnemonic::LDA);
+    assert_eq!(instruction.addressing_mode, AddressingMode::AbsoluteX);
+    assert_eq!(instruction.operand, Some(0x1234));
+    assert_eq!(instruction.bytes, vec![INSTRUCTION_LDA_ABS_X, 0x34, 0x12]);
+    assert_eq!(instruction.to_string(), "LDA $1234,X");
+}
+
+#[test]
+fn disassemble_uses_standard_syntax_for_every_addressing_mode() {
+    let cases: [(&[Byte], &str); 13] = [
+        (&[INSTRUCTION_NOP], "NOP"),
+        (&[INSTRUCTION_ASL_ACC], "ASL A"),
+        (&[INSTRUCTION_LDA_IMM, 0x42], "LDA #$42"),
+        (&[INSTRUCTION_LDA_ZERO, 0x42], "LDA $42"),
+        (&[INSTRUCTION_LDA_ZERO_X, 0x42], "LDA $42,X"),
+        (&[INSTRUCTION_LDX_ZERO_Y, 0x42], "LDX $42,Y"),
+        (&[INSTRUCTION_BNE, 0xFE], "BNE $0200"),
+        (&[INSTRUCTION_JMP_ABS, 0x00, 0x80], "JMP $8000"),
+        (&[INSTRUCTION_LDA_ABS_X, 0x00, 0x80], "LDA $8000,X"),
+        (&[INSTRUCTION_LDA_ABS_Y, 0x00, 0x80], "LDA $8000,Y"),
+        (&[INSTRUCTION_JMP_INDR, 0xFC, 0xFF], "JMP ($FFFC)"),
+        (&[INSTRUCTION_LDA_INDR_X, 0x42], "LDA ($42,X)"),
+        (&[INSTRUCTION_LDA_INDR_Y, 0x42], "LDA ($42),Y"),
+    ];
+    for (bytes, text) in cases {
+        let (_, mut memory) = common::setup();
+        memory.data[0x0200..0x0200 + bytes.len()].copy_from_slice(bytes);
+        assert_eq!(disassemble(&mut memory, 0x0200).to_string(), text);
+    }
+}
+
+#[test]
+fn disassemble_shows_branch_targets_as_absolute_addresses() {
+    let (_, mut memory) = common::setup();
+    memory.data[0x02F0] = INSTRUCTION_BEQ;
+    memory.data[0x02F1] = 0x10;
+    let instruction = disassemble(&mut memory, 0x02F0);
+    assert_eq!(instruction.branch_target(), Some(0x0302));
+    assert_eq!(instruction.to_string(), "BEQ $0302");
+}
+
+#[test]
+fn disassemble_marks_undocumented_opcodes() {
+    let (_, mut memory) = common::setup();
+    memory.data[0x0200] = 0xA7;
+    memory.data[0x0201] = 0x42;
+    let instruction = disassemble(&mut memory, 0x0200);
+    assert!(!instruction.documented);
+    assert_eq!(instruction.to_string(), "*LAX $42");
+}
+
+#[test]
+fn disassemble_to_string_renders_a_range() {
+    let (_, mut memory) = common::setup();
+    memory.data[0x0200..0x0206].copy_from_slice(&[
+        INSTRUCTION_LDA_IMM,
+        0x01,
+        INSTRUCTION_STA_ABS,
+        0x00,
+        0x60,
+        INSTRUCTION_RTS,
+    ]);
+    assert_eq!(
+        disassemble_to_string(&mut memory, 0x0200, 0x0205, false),
+        "0200  LDA #$01\n0202  STA $6000\n0205  RTS\n"
+    );
+    assert_eq!(
+        disassemble_to_string(&mut memory, 0x0200, 0x0205, true),
+        "0200  A9 01     LDA #$01\n0202  8D 00 60  STA $6000\n0205  60        RTS\n"
+    );
+}
+
+#[test]
+fn disassemble_range_stops_at_the_top_of_memory() {
+    let (_, mut memory) = common::setup();
+    memory.data[0xFFFE] = INSTRUCTION_JMP_ABS;
+    memory.data[0xFFFF] = 0x34;
+    memory.data[0x0000] = 0x12;
+    let instructions = disassemble_range(&mut memory, 0xFFFE, 0xFFFF);
+    assert_eq!(instructions.len(), 1);
+    assert_eq!(instructions[0].operand, Some(0x1234));
+}
@@ -0,0 +1,98 @@
+use rust6502::{instructions::*, *};
+
+mod common;
+
+#[test]
+fn try_execute_returns_error_for_illegal_opcode() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.program_counter = 0x8000;
+    cpu.accumulator = 0x42;
+    memory.data[0x8000] = INSTRUCTION_NOP;
+    memory.data[0x8001] = 0xFF;
+    let error = cpu.try_execute(10, &mut memory).unwrap_err();
+    assert!(matches!(error, ExecutionError::IllegalOpcode { .. }));
+    assert_eq!(error.opcode(), 0xFF);
+    assert_eq!(error.address(), 0x8001);
+    assert_eq!(error.cpu().accumulator, 0x42);
+    assert_eq!(cpu.program_counter, 0x8001);
+}
+
+#[test]
+fn try_execute_returns_error_for_jam_opcode() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.program_counter = 0x8000;
+    memory.data[0x8000] = 0x02;
+    let error = cpu.try_execute(2, &mut memory).unwrap_err();
+    assert!(matches!(error, ExecutionError::Jam { .. }));
+    assert_eq!(error.address(), 0x8000);
+    assert_eq!(
+        error.to_string(),
+        "CPU jammed by instruction: 0x02 at address 0x8000"
+    );
+}
+
+#[test]
+fn try_execute_returns_cycles_used_when_successful() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    memory.data[0xFFFC] = INSTRUCTION_LDA_IMM;
+    memory.data[0xFFFD] = 0x42;
+    assert_eq!(cpu.try_execute(2, &mut memory).unwrap(), 2);
+}
+
+#[test]
+#[should_panic(expected = "Unknown instruction: 0xff at address 0xfffc")]
+fn execute_panics_on_illegal_opcode() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    memory.data[0xFFFC] = 0xFF;
+    cpu.execute(2, &mut memory);
+}
+
+#[test]
+fn nop_policy_skips_illegal_opcode_and_operands() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.unknown_opcode_policy = UnknownOpcodePolicy::Nop;
+    cpu.program_counter = 0x8000;
+    // SLO abs,X: its operand is addressed and read, then left unmodified
+    memory.data[0x8000] = 0x1F;
+    memory.data[0x8001] = 0x00;
+    memory.data[0x8002] = 0x90;
+    memory.data[0x8003] = INSTRUCTION_LDA_IMM;
+    memory.data[0x8004] = 0x42;
+    let cycles_used = cpu.try_execute(7, &mut memory).unwrap();
+    assert_eq!(cycles_used, 7);
+    assert_eq!(cpu.accumulator, 0x42);
+    assert_eq!(memory.data[0x9000], 0x00);
+}
+
+#[test]
+fn nop_policy_still_halts_on_jam_opcode() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.unknown_opcode_policy = UnknownOpcodePolicy::Nop;
+    memory.data[0xFFFC] = 0x12;
+    let error = cpu.try_execute(2, &mut memory).unwrap_err();
+    assert!(matches!(error, ExecutionError::Jam { .. }));
+}
+
+#[test]
+fn handler_policy_can_emulate_unknown_opcode() {
+    fn handler(cpu: &mut CPU, error: &ExecutionError) -> Option<i32> {
+        if error.opcode() == 0xFF {
+            cpu.register_x = 0x37;
+            Some(1)
+        } else {
+            None
+        }
+    }
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.unknown_opcode_policy = UnknownOpcodePolicy::Handler(handler);
+    cpu.program_counter = 0x8000;
+    memory.data[0x8000] = 0xFF;
+    memory.data[0x8001] = 0x02;
+    let cycles_used = cpu.try_execute(2, &mut memory).unwrap();
+    assert_eq!(cycles_used, 2);
+    assert_eq!(cpu.register_x, 0x37);
+    assert_eq!(cpu.program_counter, 0x8001);
+    let error = cpu.try_execute(2, &mut memory).unwrap_err();
+    assert_eq!(error.opcode(), 0x02);
+    assert_eq!(cpu.program_counter, 0x8001);
+}
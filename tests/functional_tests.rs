@@ -0,0 +1,145 @@
+use asm_macros::asm;
+use rust6502::{trace, *};
+
+mod common;
+
+use common::trap::{self, Trap};
+
+/// Klaus Dormann's 6502_functional_test, assembled with the default options:
+/// loaded at $0000, started at $0400, with the test number kept at $0200.
+/// `FUNCTIONAL_TEST_ROM` can point at a copy kept elsewhere.
+const FUNCTIONAL_TEST: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/roms/6502_functional_test.bin"
+);
+const FUNCTIONAL_TEST_START: Word = 0x0400;
+const FUNCTIONAL_TEST_SUCCESS: Word = 0x3469;
+const TEST_NUMBER: Word = 0x0200;
+
+/// kevtris's nestest.nes. Its automated mode starts at $C000 and finishes
+/// with an RTS at $C66E, leaving the number of the first failed test in $02
+/// (documented opcodes) and $03 (undocumented ones), or zero if all passed.
+/// `NESTEST_ROM` and `NESTEST_LOG` can point at copies kept elsewhere.
+const NESTEST: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/roms/nestest.nes");
+/// The log of a real NES running nestest, checked line by line if present.
+const NESTEST_LOG: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/roms/nestest.log");
+const NESTEST_START: Word = 0xC000;
+const NESTEST_END: Word = 0xC66E;
+/// The cycle count nestest.log shows for the final RTS.
+const NESTEST_END_CYCLES: u64 = 26554;
+const INES_HEADER_LENGTH: usize = 16;
+const PRG_BANK_LENGTH: usize = 0x4000;
+
+/// The file named by the environment variable `variable`, or `default`.
+fn rom_path(variable: &str, default: &str) -> String {
+    std::env::var(variable).unwrap_or_else(|_| default.to_string())
+}
+
+#[test]
+#[ignore = "needs 6502_functional_test.bin, see tests/roms/README.md"]
+fn klaus_dormann_functional_test() {
+    let path: String = rom_path("FUNCTIONAL_TEST_ROM", FUNCTIONAL_TEST);
+    let mut memory: Memory = trap::load_binary(&path, 0x0000);
+    let mut cpu: CPU = CPU::at_reset_vector_unsequenced();
+    cpu.program_counter = FUNCTIONAL_TEST_START;
+    let trap: Trap = trap::run_until_trap(&mut cpu, &mut memory, TEST_NUMBER, 100_000_000);
+    assert_eq!(
+        trap.address, FUNCTIONAL_TEST_SUCCESS,
+        "failed test {:#04x}: trapped at {:#06x}",
+        memory.data[TEST_NUMBER as usize], trap.address
+    );
+}
+
+#[test]
+#[ignore = "needs nestest.nes, see tests/roms/README.md"]
+fn nestest_automated_mode() {
+    let path: String = rom_path("NESTEST_ROM", NESTEST);
+    let rom: Vec<Byte> = std::fs::read(&path)
+        .unwrap_or_else(|error| panic!("{}: {} (see tests/roms/README.md)", path, error));
+    let prg: &[Byte] = &rom[INES_HEADER_LENGTH..INES_HEADER_LENGTH + PRG_BANK_LENGTH];
+    let mut memory: Memory = Memory::reset();
+    // The only PRG bank is mirrored at $8000 and $C000
+    memory.data[0x8000..0xC000].copy_from_slice(prg);
+    memory.data[0xC000..].copy_from_slice(prg);
+    let mut cpu: CPU = CPU::at_reset_vector_unsequenced();
+    cpu.variant = Variant::Ricoh2A03;
+    cpu.undocumented_opcodes = Some(UndocumentedOpcodes::default());
+    cpu.program_counter = NESTEST_START;
+    cpu.stack_pointer = 0xFD;
+    cpu.processor_status = ProcessorStatus(0x24);
+
+    let mut tracer: Tracer<Vec<u8>> = Tracer::new(Vec::new());
+    tracer.cycles = 7;
+    while cpu.program_counter != NESTEST_END {
+        assert!(
+            tracer.cycles < NESTEST_END_CYCLES,
+            "nestest did not finish, stopped at {:#06x}",
+            cpu.program_counter
+        );
+        tracer.step(&mut cpu, &mut memory).unwrap();
+    }
+    assert_eq!(tracer.cycles, NESTEST_END_CYCLES);
+    assert_eq!(
+        (memory.data[0x02], memory.data[0x03]),
+        (0x00, 0x00),
+        "nestest failed"
+    );
+    tracer.step(&mut cpu, &mut memory).unwrap();
+
+    if let Ok(log) = std::fs::read_to_string(rom_path("NESTEST_LOG", NESTEST_LOG)) {
+        let output: String = String::from_utf8(tracer.into_inner()).unwrap();
+        if let Some(divergence) = trace::compare_traces(&output, &log) {
+            panic!("{}", divergence);
+        }
+    }
+}
+
+#[test]
+fn run_until_trap_stops_on_a_failure_trap() {
+    let program = asm! {
+        .org $0400
+        lda #$01
+        sta $0200
+        cmp #$02
+    fail: bne fail
+        jmp *
+    };
+    let mut memory: Memory = Memory::reset();
+    program.load_into(&mut memory.data);
+    let mut cpu: CPU = CPU::at_reset_vector_unsequenced();
+    cpu.program_counter = program.origin;
+    let trap: Trap = trap::run_until_trap(&mut cpu, &mut memory, TEST_NUMBER, 100);
+    assert_eq!(trap.address, program.symbol("fail").unwrap());
+    assert_eq!(trap.instructions, 4);
+    assert_eq!(memory.data[TEST_NUMBER as usize], 0x01);
+}
+
+#[test]
+fn run_until_trap_stops_on_a_success_trap() {
+    let program = asm! {
+        .org $0400
+        ldx #$03
+    loop: dex
+        bne loop
+    done: jmp done
+    };
+    let mut memory: Memory = Memory::reset();
+    program.load_into(&mut memory.data);
+    let mut cpu: CPU = CPU::at_reset_vector_unsequenced();
+    cpu.program_counter = program.origin;
+    let trap: Trap = trap::run_until_trap(&mut cpu, &mut memory, TEST_NUMBER, 100);
+    assert_eq!(trap.address, program.symbol("done").unwrap());
+    assert_eq!(trap.instructions, 8);
+}
+
+#[test]
+#[should_panic(expected = "no trap reached")]
+fn run_until_trap_gives_up_after_the_instruction_limit() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.program_counter = 0x0400;
+    memory.data[0x0400] = instructions::INSTRUCTION_NOP;
+    memory.data[0x0401] = instructions::INSTRUCTION_JMP_ABS;
+    memory.data[0x0402] = 0x00;
+    memory.data[0x0403] = 0x04;
+    trap::run_until_trap(&mut cpu, &mut memory, TEST_NUMBER, 100);
+}
@@ -0,0 +1,192 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread::{self, JoinHandle};
+
+use asm_macros::asm;
+use rust6502::gdb::{encode_packet, GdbStub};
+use rust6502::*;
+
+mod common;
+
+fn debugger() -> Debugger<Memory> {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    let program = asm! {
+        .org $0400
+        ldx #$00
+    loop: inx
+        stx $2000
+        jmp loop
+    };
+    program.load_into(&mut memory.data);
+    cpu.program_counter = program.origin;
+    Debugger::new(cpu, memory)
+}
+
+/// Serves one client on a free local port from another thread, handing the
+/// debugger back once the client goes away.
+fn spawn_server() -> (TcpStream, JoinHandle<Debugger<Memory>>) {
+    let listener: TcpListener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let mut debugger: Debugger<Memory> = debugger();
+        let (stream, _) = listener.accept().unwrap();
+        GdbStub::new(&mut debugger).serve(stream).unwrap();
+        debugger
+    });
+    (TcpStream::connect(address).unwrap(), server)
+}
+
+fn read_reply(stream: &mut TcpStream) -> String {
+    let mut reply: Vec<u8> = Vec::new();
+    let mut byte: [u8; 1] = [0];
+    loop {
+        stream.read_exact(&mut byte).unwrap();
+        if byte[0] == b'$' {
+            break;
+        }
+    }
+    loop {
+        stream.read_exact(&mut byte).unwrap();
+        if byte[0] == b'#' {
+            break;
+        }
+        reply.push(byte[0]);
+    }
+    let mut checksum: [u8; 2] = [0; 2];
+    stream.read_exact(&mut checksum).unwrap();
+    let reply: String = String::from_utf8(reply).unwrap();
+    assert_eq!(
+        encode_packet(&reply).as_bytes()[reply.len() + 2..],
+        checksum
+    );
+    stream.write_all(b"+").unwrap();
+    reply
+}
+
+/// Sends a packet, checks that it was acknowledged and returns the reply.
+fn send(stream: &mut TcpStream, packet: &str) -> String {
+    stream.write_all(encode_packet(packet).as_bytes()).unwrap();
+    let mut ack: [u8; 1] = [0];
+    stream.read_exact(&mut ack).unwrap();
+    assert_eq!(ack[0], b'+', "packet {} was not acknowledged", packet);
+    read_reply(stream)
+}
+
+#[test]
+fn encode_packet_appends_the_checksum() {
+    assert_eq!(encode_packet("OK"), "$OK#9a");
+    assert_eq!(encode_packet(""), "$#00");
+}
+
+#[test]
+fn registers_and_memory_can_be_read_and_written() {
+    let mut debugger: Debugger<Memory> = debugger();
+    let mut stub = GdbStub::new(&mut debugger);
+    assert_eq!(stub.handle_packet("g"), "00000000ff0004");
+    assert_eq!(stub.handle_packet("G0102030405f0e1"), "OK");
+    assert_eq!(stub.handle_packet("p5"), "f0e1");
+    assert_eq!(stub.handle_packet("P0=aa"), "OK");
+    assert_eq!(stub.handle_packet("p0"), "aa");
+    assert_eq!(stub.handle_packet("P5=00"), "E01");
+    assert_eq!(stub.handle_packet("m400,3"), "a200e8");
+    assert_eq!(stub.handle_packet("M3000,2:beef"), "OK");
+    assert_eq!(stub.handle_packet("m3000,2"), "beef");
+    assert_eq!(stub.handle_packet("M3000,2:be"), "E01");
+    assert_eq!(stub.handle_packet("vMustReplyEmpty"), "");
+    drop(stub);
+    assert_eq!(debugger.cpu.accumulator, 0xAA);
+    assert_eq!(debugger.cpu.program_counter, 0xE1F0);
+}
+
+#[test]
+fn non_ascii_packets_are_rejected_without_panicking() {
+    let mut debugger: Debugger<Memory> = debugger();
+    let mut stub = GdbStub::new(&mut debugger);
+    assert_eq!(stub.handle_packet("é"), "");
+    assert_eq!(stub.handle_packet("€400,1"), "");
+    assert_eq!(stub.handle_packet("mé"), "E01");
+    assert_eq!(stub.handle_packet("M3000,1:é"), "E01");
+}
+
+#[test]
+fn memory_reads_see_the_6510_port() {
+    let mut debugger: Debugger<Memory> = debugger();
+    debugger.cpu.variant = Variant::Mos6510;
+    debugger.bus.data[0x0001] = 0xAA;
+    let mut stub = GdbStub::new(&mut debugger);
+    assert_eq!(stub.handle_packet("m0,2"), "0017");
+}
+
+#[test]
+fn target_description_is_served_in_chunks() {
+    let mut debugger: Debugger<Memory> = debugger();
+    let mut stub = GdbStub::new(&mut debugger);
+    assert!(stub
+        .handle_packet("qSupported:multiprocess+")
+        .contains("qXfer:features:read+"));
+    let first: String = stub.handle_packet("qXfer:features:read:target.xml:0,10");
+    assert_eq!(first, "m<?xml version=\"1");
+    let rest: String = stub.handle_packet("qXfer:features:read:target.xml:10,1000");
+    assert!(rest.starts_with('l'));
+    assert!(rest.contains("<reg name=\"pc\" bitsize=\"16\""));
+}
+
+#[test]
+fn step_and_breakpoints() {
+    let mut debugger: Debugger<Memory> = debugger();
+    let mut stub = GdbStub::new(&mut debugger);
+    assert_eq!(stub.handle_packet("s"), "S05");
+    assert_eq!(stub.handle_packet("p5"), "0204");
+    assert_eq!(stub.handle_packet("Z0,405,1"), "OK");
+    assert_eq!(stub.handle_packet("Z5,405,1"), "");
+    assert_eq!(stub.handle_packet("z0,405,1"), "OK");
+    assert_eq!(stub.handle_packet("z0,405,1"), "E01");
+    drop(stub);
+    assert!(debugger.breakpoints().is_empty());
+}
+
+#[test]
+fn scripted_client_session() {
+    let (mut client, server) = spawn_server();
+    assert_eq!(send(&mut client, "?"), "S05");
+    assert_eq!(send(&mut client, "Z0,403,1"), "OK");
+    assert_eq!(send(&mut client, "c"), "S05");
+    assert_eq!(send(&mut client, "p5"), "0304");
+    assert_eq!(send(&mut client, "p1"), "01");
+    assert_eq!(send(&mut client, "z0,403,1"), "OK");
+    assert_eq!(send(&mut client, "Z2,2000,1"), "OK");
+    assert_eq!(send(&mut client, "c"), "T05watch:2000;");
+    assert_eq!(send(&mut client, "m2000,1"), "01");
+    assert_eq!(send(&mut client, "z2,2000,1"), "OK");
+    assert_eq!(send(&mut client, "Z0,406,1"), "OK");
+    assert_eq!(send(&mut client, "D"), "OK");
+    let debugger: Debugger<Memory> = server.join().unwrap();
+    assert_eq!(debugger.cpu.register_x, 0x01);
+    assert!(debugger.breakpoints().is_empty());
+}
+
+#[test]
+fn continue_can_be_interrupted() {
+    let (mut client, server) = spawn_server();
+    client.write_all(encode_packet("c").as_bytes()).unwrap();
+    let mut ack: [u8; 1] = [0];
+    client.read_exact(&mut ack).unwrap();
+    client.write_all(&[0x03]).unwrap();
+    assert_eq!(read_reply(&mut client), "S02");
+    assert_eq!(send(&mut client, "QStartNoAckMode"), "OK");
+    client.write_all(encode_packet("k").as_bytes()).unwrap();
+    let debugger: Debugger<Memory> = server.join().unwrap();
+    assert!(debugger.cpu.cycles > 0);
+}
+
+#[test]
+fn bad_checksums_are_rejected() {
+    let (mut client, server) = spawn_server();
+    client.write_all(b"$g#00").unwrap();
+    let mut nak: [u8; 1] = [0];
+    client.read_exact(&mut nak).unwrap();
+    assert_eq!(nak[0], b'-');
+    assert_eq!(send(&mut client, "g"), "00000000ff0004");
+    drop(client);
+    server.join().unwrap();
+}
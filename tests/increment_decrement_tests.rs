@@ -0,0 +1,89 @@
+use rust6502::{instructions::*, *};
+
+mod common;
+
+#[test]
+fn inc_zeropage_can_increment_memory() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    memory.data[0xFFFC] = INSTRUCTION_INC_ZERO;
+    memory.data[0xFFFD] = 0x42;
+    memory.data[0x0042] = 0x7F;
+    let cycles_used = cpu.execute(5, &mut memory);
+    assert_eq!(cycles_used, 5);
+    assert_eq!(memory.data[0x0042], 0x80);
+    assert!(!cpu.processor_status.zero());
+    assert!(cpu.processor_status.negative());
+}
+
+#[test]
+fn inc_absolute_x_wraps_to_zero() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.register_x = 0x10;
+    memory.data[0xFFFC] = INSTRUCTION_INC_ABS_X;
+    memory.data[0xFFFD] = 0x00;
+    memory.data[0xFFFE] = 0x80;
+    memory.data[0x8010] = 0xFF;
+    let cycles_used = cpu.execute(7, &mut memory);
+    assert_eq!(cycles_used, 7);
+    assert_eq!(memory.data[0x8010], 0x00);
+    assert!(cpu.processor_status.zero());
+    assert!(!cpu.processor_status.negative());
+}
+
+#[test]
+fn dec_absolute_can_decrement_memory() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    memory.data[0xFFFC] = INSTRUCTION_DEC_ABS;
+    memory.data[0xFFFD] = 0x00;
+    memory.data[0xFFFE] = 0x80;
+    memory.data[0x8000] = 0x00;
+    let cycles_used = cpu.execute(6, &mut memory);
+    assert_eq!(cycles_used, 6);
+    assert_eq!(memory.data[0x8000], 0xFF);
+    assert!(cpu.processor_status.negative());
+}
+
+#[test]
+fn inx_can_increment_register_x() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.register_x = 0xFF;
+    memory.data[0xFFFC] = INSTRUCTION_INX;
+    let cycles_used = cpu.execute(2, &mut memory);
+    assert_eq!(cycles_used, 2);
+    assert_eq!(cpu.register_x, 0x00);
+    assert!(cpu.processor_status.zero());
+}
+
+#[test]
+fn iny_can_increment_register_y() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.register_y = 0x41;
+    memory.data[0xFFFC] = INSTRUCTION_INY;
+    let cycles_used = cpu.execute(2, &mut memory);
+    assert_eq!(cycles_used, 2);
+    assert_eq!(cpu.register_y, 0x42);
+    assert!(!cpu.processor_status.zero());
+    assert!(!cpu.processor_status.negative());
+}
+
+#[test]
+fn dex_can_decrement_register_x() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.register_x = 0x01;
+    memory.data[0xFFFC] = INSTRUCTION_DEX;
+    let cycles_used = cpu.execute(2, &mut memory);
+    assert_eq!(cycles_used, 2);
+    assert_eq!(cpu.register_x, 0x00);
+    assert!(cpu.processor_status.zero());
+}
+
+#[test]
+fn dey_can_decrement_register_y() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.register_y = 0x00;
+    memory.data[0xFFFC] = INSTRUCTION_DEY;
+    let cycles_used = cpu.execute(2, &mut memory);
+    assert_eq!(cycles_used, 2);
+    assert_eq!(cpu.register_y, 0xFF);
+    assert!(cpu.processor_status.negative());
+}
@@ -0,0 +1,139 @@
+use rust6502::{instructions::*, *};
+
+mod common;
+
+fn setup_vectors(memory: &mut Memory) {
+    // NMI -> $9000, RESET -> $8000, IRQ -> $A000
+    memory.data[0xFFFA] = 0x00;
+    memory.data[0xFFFB] = 0x90;
+    memory.data[0xFFFC] = 0x00;
+    memory.data[0xFFFD] = 0x80;
+    memory.data[0xFFFE] = 0x00;
+    memory.data[0xFFFF] = 0xA0;
+}
+
+#[test]
+fn reset_sequence_loads_reset_vector() {
+    let mut cpu: CPU = CPU::new();
+    let mut memory: Memory = Memory::reset();
+    setup_vectors(&mut memory);
+    memory.data[0x8000] = INSTRUCTION_LDA_IMM;
+    memory.data[0x8001] = 0x42;
+    let cycles_used = cpu.execute(7, &mut memory);
+    assert_eq!(cycles_used, 7);
+    assert_eq!(cpu.program_counter, 0x8000);
+    assert_eq!(cpu.stack_pointer, 0xFC);
+    assert!(cpu.processor_status.interrupt());
+    cpu.execute(2, &mut memory);
+    assert_eq!(cpu.accumulator, 0x42);
+}
+
+#[test]
+fn reset_does_not_write_to_stack() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    setup_vectors(&mut memory);
+    cpu.program_counter = 0x1234;
+    cpu.trigger_reset();
+    cpu.execute(7, &mut memory);
+    assert_eq!(cpu.program_counter, 0x8000);
+    assert_eq!(memory.data[0x01FF], 0x00);
+    assert_eq!(memory.data[0x01FE], 0x00);
+    assert_eq!(memory.data[0x01FD], 0x00);
+}
+
+#[test]
+fn irq_is_ignored_while_interrupts_are_disabled() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    setup_vectors(&mut memory);
+    cpu.program_counter = 0x8000;
+    cpu.processor_status.set_interrupt(true);
+    memory.data[0x8000] = INSTRUCTION_NOP;
+    cpu.set_irq(true);
+    let cycles_used = cpu.execute(2, &mut memory);
+    assert_eq!(cycles_used, 2);
+    assert_eq!(cpu.program_counter, 0x8001);
+}
+
+#[test]
+fn irq_pushes_state_and_jumps_through_irq_vector() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    setup_vectors(&mut memory);
+    cpu.program_counter = 0x8000;
+    cpu.processor_status.set_carry(true);
+    cpu.set_irq(true);
+    let cycles_used = cpu.execute(7, &mut memory);
+    assert_eq!(cycles_used, 7);
+    assert_eq!(cpu.program_counter, 0xA000);
+    assert_eq!(cpu.stack_pointer, 0xFC);
+    assert_eq!(memory.data[0x01FF], 0x80);
+    assert_eq!(memory.data[0x01FE], 0x00);
+    // break bit clear, unused bit set
+    assert_eq!(memory.data[0x01FD], 0b00100001);
+    assert!(cpu.processor_status.interrupt());
+}
+
+#[test]
+fn irq_is_level_triggered() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    setup_vectors(&mut memory);
+    cpu.program_counter = 0x8000;
+    memory.data[0xA000] = INSTRUCTION_RTI;
+    cpu.set_irq(true);
+    // IRQ, RTI, then the still asserted line triggers another IRQ
+    cpu.execute(7 + 6 + 7, &mut memory);
+    assert_eq!(cpu.program_counter, 0xA000);
+    assert_eq!(cpu.stack_pointer, 0xFC);
+}
+
+#[test]
+fn nmi_is_taken_even_when_interrupts_are_disabled() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    setup_vectors(&mut memory);
+    cpu.program_counter = 0x8000;
+    cpu.processor_status.set_interrupt(true);
+    cpu.set_nmi(true);
+    let cycles_used = cpu.execute(7, &mut memory);
+    assert_eq!(cycles_used, 7);
+    assert_eq!(cpu.program_counter, 0x9000);
+    assert_eq!(memory.data[0x01FD] & 0b00010000, 0x00);
+}
+
+#[test]
+fn nmi_is_edge_triggered() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    setup_vectors(&mut memory);
+    cpu.program_counter = 0x8000;
+    memory.data[0x9000] = INSTRUCTION_RTI;
+    memory.data[0x8000] = INSTRUCTION_NOP;
+    memory.data[0x8001] = INSTRUCTION_NOP;
+    cpu.set_nmi(true);
+    cpu.execute(7 + 6 + 2, &mut memory);
+    assert_eq!(cpu.program_counter, 0x8001);
+
+    cpu.set_nmi(true);
+    cpu.execute(1, &mut memory);
+    assert_eq!(cpu.program_counter, 0x8002);
+
+    cpu.set_nmi(false);
+    cpu.set_nmi(true);
+    cpu.execute(1, &mut memory);
+    assert_eq!(cpu.program_counter, 0x9000);
+}
+
+#[test]
+fn cli_delays_irq_by_one_instruction() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    setup_vectors(&mut memory);
+    cpu.program_counter = 0x8000;
+    cpu.processor_status.set_interrupt(true);
+    memory.data[0x8000] = INSTRUCTION_CLI;
+    memory.data[0x8001] = INSTRUCTION_NOP;
+    cpu.set_irq(true);
+    cpu.execute(2, &mut memory);
+    assert_eq!(cpu.program_counter, 0x8001);
+    cpu.execute(2, &mut memory);
+    assert_eq!(cpu.program_counter, 0x8002);
+    cpu.execute(7, &mut memory);
+    assert_eq!(cpu.program_counter, 0xA000);
+    assert_eq!(memory.data[0x01FE], 0x02);
+}
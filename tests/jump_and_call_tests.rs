@@ -20,7 +20,7 @@ fn jsr_can_jump_to_subroutine_and_return() {
 #[test]
 fn jsr_does_not_affect_processor_status() {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy = cpu.clone();
+    let cpu_copy = cpu;
     cpu.program_counter = 0xFF00;
     memory.data[0xFF00] = INSTRUCTION_JSR;
     memory.data[0xFF01] = 0x00;
@@ -28,14 +28,14 @@ fn jsr_does_not_affect_processor_status() {
     let cycles_used = cpu.execute(6, &mut memory);
     assert_eq!(cycles_used, 6);
     assert_eq!(cpu.processor_status, cpu_copy.processor_status);
-    assert_eq!(cpu.stack_pointer, cpu_copy.stack_pointer);
+    assert_eq!(cpu.stack_pointer, cpu_copy.stack_pointer - 2);
     assert_eq!(cpu.program_counter, 0x8000);
 }
 
 #[test]
 fn rts_does_not_affect_processor_status() {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy = cpu.clone();
+    let cpu_copy = cpu;
     cpu.program_counter = 0xFF00;
     memory.data[0xFF00] = INSTRUCTION_JSR;
     memory.data[0xFF01] = 0x00;
@@ -44,13 +44,14 @@ fn rts_does_not_affect_processor_status() {
     let cycles_used = cpu.execute(12, &mut memory);
     assert_eq!(cycles_used, 12);
     assert_eq!(cpu.processor_status, cpu_copy.processor_status);
-    assert_eq!(cpu.program_counter, 0x8000);
+    assert_eq!(cpu.stack_pointer, cpu_copy.stack_pointer);
+    assert_eq!(cpu.program_counter, 0xFF03);
 }
 
 #[test]
 fn jmp_absolute_can_jump_to_location() {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy = cpu.clone();
+    let cpu_copy = cpu;
     cpu.program_counter = 0xFF00;
     memory.data[0xFF00] = INSTRUCTION_JMP_ABS;
     memory.data[0xFF01] = 0x00;
@@ -65,7 +66,7 @@ fn jmp_absolute_can_jump_to_location() {
 #[test]
 fn jmp_indirect_can_jump_to_location() {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy = cpu.clone();
+    let cpu_copy = cpu;
     cpu.program_counter = 0xFF00;
     memory.data[0xFF00] = INSTRUCTION_JMP_INDR;
     memory.data[0xFF01] = 0x00;
@@ -78,3 +79,18 @@ fn jmp_indirect_can_jump_to_location() {
     assert_eq!(cpu.stack_pointer, cpu_copy.stack_pointer);
     assert_eq!(cpu.program_counter, 0x9000);
 }
+
+#[test]
+fn jmp_indirect_does_not_cross_page_when_fetching_address() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.program_counter = 0xFF00;
+    memory.data[0xFF00] = INSTRUCTION_JMP_INDR;
+    memory.data[0xFF01] = 0xFF;
+    memory.data[0xFF02] = 0x80;
+    memory.data[0x80FF] = 0x00;
+    memory.data[0x8000] = 0x90;
+    memory.data[0x8100] = 0x50;
+    let cycles_used = cpu.execute(5, &mut memory);
+    assert_eq!(cycles_used, 5);
+    assert_eq!(cpu.program_counter, 0x9000);
+}
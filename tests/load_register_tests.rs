@@ -34,7 +34,7 @@ fn lda_immediate_can_load_into_accumulator() {
 #[test]
 fn lda_immediate_can_affect_zero_flag() {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy: CPU = cpu.clone();
+    let cpu_copy: CPU = cpu;
     cpu.accumulator = 0x44;
     memory.data[0xFFFC] = INSTRUCTION_LDA_IMM;
     memory.data[0xFFFD] = 0x0;
@@ -77,7 +77,7 @@ fn lda_zeropage_with_offset_x_can_load_into_accumulator() {
 #[test]
 fn lda_zeropage_with_offset_x_can_load_into_accumulator_when_it_wraps() {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy: CPU = cpu.clone();
+    let cpu_copy: CPU = cpu;
     cpu.register_x = 0xFF;
     memory.data[0xFFFC] = INSTRUCTION_LDA_ZERO_X;
     memory.data[0xFFFD] = 0x80;
@@ -186,7 +186,7 @@ fn ldy_absolute_with_offset_x_can_load_into_register_y_when_crosses_page_boundar
 #[test]
 fn lda_indirect_x_can_load_into_accumulator() {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy: CPU = cpu.clone();
+    let cpu_copy: CPU = cpu;
     cpu.processor_status.set_zero(true);
     cpu.processor_status.set_negative(true);
     cpu.register_x = 0x04;
@@ -206,7 +206,7 @@ fn lda_indirect_x_can_load_into_accumulator() {
 #[test]
 fn lda_indirect_y_can_load_into_accumulator() {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy: CPU = cpu.clone();
+    let cpu_copy: CPU = cpu;
     cpu.processor_status.set_zero(true);
     cpu.processor_status.set_negative(true);
     cpu.register_y = 0x04;
@@ -226,7 +226,7 @@ fn lda_indirect_y_can_load_into_accumulator() {
 #[test]
 fn lda_indirect_y_can_load_into_accumulator_when_crosses_page_boundary() {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy: CPU = cpu.clone();
+    let cpu_copy: CPU = cpu;
     cpu.register_y = 0x1;
     memory.data[0xFFFC] = INSTRUCTION_LDA_INDR_Y;
     memory.data[0xFFFD] = 0x05;
@@ -0,0 +1,217 @@
+use rust6502::loader::{self, Format, Image, Segment};
+use rust6502::*;
+
+mod common;
+
+/// `lda #$42; sta $0200; jmp $0405`, split over two records at $0400.
+const PROGRAM: [Byte; 8] = [0xA9, 0x42, 0x8D, 0x00, 0x02, 0x4C, 0x05, 0x04];
+
+const INTEL_HEX: &str = "\
+:05040000A9428D00027D
+:030405004C05049F
+:0400000500000400F3
+:00000001FF
+";
+
+const S_RECORDS: &str = "\
+S00600004844521B
+S1080400A9428D000279
+S10604054C05049B
+S5030002FA
+S9030400F8
+";
+
+fn segment(address: Word, data: &[Byte]) -> Segment {
+    Segment {
+        address,
+        data: data.to_vec(),
+    }
+}
+
+fn assert_program_loaded(memory: &Memory) {
+    assert_eq!(memory.data[0x0400..0x0408], PROGRAM);
+    assert_eq!(memory.data[0x03FF], 0x00);
+    assert_eq!(memory.data[0x0408], 0x00);
+}
+
+#[test]
+fn raw_binary_loads_at_the_given_address() {
+    let (_, mut memory): (CPU, Memory) = common::setup();
+    let image: Image = loader::load(Format::Raw(0x0400), &PROGRAM, &mut memory).unwrap();
+    assert_program_loaded(&memory);
+    assert_eq!(image.segments, vec![segment(0x0400, &PROGRAM)]);
+    assert_eq!(image.start, None);
+    assert_eq!(image.entry(), Some(0x0400));
+
+    assert!(loader::parse_raw(&[0; 0x100], 0xFF00).is_ok());
+    assert_eq!(
+        loader::parse_raw(&[0; 0x101], 0xFF00),
+        Err(LoadError::OutOfRange { address: 0x10000 })
+    );
+}
+
+#[test]
+fn intel_hex_loads_records_and_start_address() {
+    let (_, mut memory): (CPU, Memory) = common::setup();
+    let image: Image = loader::load(Format::IntelHex, INTEL_HEX.as_bytes(), &mut memory).unwrap();
+    assert_program_loaded(&memory);
+    assert_eq!(image.segments.len(), 2);
+    assert_eq!(image.start, Some(0x0400));
+
+    // Windows line endings and lower case digits are fine
+    let crlf: String = INTEL_HEX.to_lowercase().replace('\n', "\r\n");
+    assert_eq!(loader::parse_intel_hex(crlf.as_bytes()), Ok(image));
+}
+
+#[test]
+fn intel_hex_errors() {
+    let bad_checksum: &str = ":05040000A9428D00027E\n:00000001FF\n";
+    assert_eq!(
+        loader::parse_intel_hex(bad_checksum.as_bytes()),
+        Err(LoadError::BadChecksum { line: 1 })
+    );
+    let no_colon: &str = ":030405004C05049F\n05040000A9428D00027D\n";
+    assert_eq!(
+        loader::parse_intel_hex(no_colon.as_bytes()),
+        Err(LoadError::InvalidRecord { line: 2 })
+    );
+    let wrong_length: &str = ":06040000A9428D00027D\n";
+    assert_eq!(
+        loader::parse_intel_hex(wrong_length.as_bytes()),
+        Err(LoadError::InvalidRecord { line: 1 })
+    );
+    let unterminated: &str = ":030405004C05049F\n";
+    assert_eq!(
+        loader::parse_intel_hex(unterminated.as_bytes()),
+        Err(LoadError::MissingEndRecord)
+    );
+    // An extended linear address moves the next record past 64KB
+    let high: &str = ":020000040001F9\n:0100000001FE\n:00000001FF\n";
+    assert_eq!(
+        loader::parse_intel_hex(high.as_bytes()),
+        Err(LoadError::OutOfRange { address: 0x10000 })
+    );
+    let overlap: &str = ":05040000A9428D00027D\n:030404004C0504A0\n:00000001FF\n";
+    assert_eq!(
+        loader::parse_intel_hex(overlap.as_bytes()),
+        Err(LoadError::Overlap { address: 0x0404 })
+    );
+}
+
+#[test]
+fn s_records_load_records_and_start_address() {
+    let (_, mut memory): (CPU, Memory) = common::setup();
+    let image: Image = loader::load(Format::SRecord, S_RECORDS.as_bytes(), &mut memory).unwrap();
+    assert_program_loaded(&memory);
+    assert_eq!(image.segments.len(), 2);
+    assert_eq!(image.start, Some(0x0400));
+}
+
+#[test]
+fn s_record_errors() {
+    let bad_checksum: String = S_RECORDS.replace("S10604054C05049B", "S10604054C05049C");
+    assert_eq!(
+        loader::parse_srecord(bad_checksum.as_bytes()),
+        Err(LoadError::BadChecksum { line: 3 })
+    );
+    let wrong_count: String = S_RECORDS.replace("S5030002FA", "S5030003F9");
+    assert_eq!(
+        loader::parse_srecord(wrong_count.as_bytes()),
+        Err(LoadError::RecordCountMismatch { line: 4 })
+    );
+    let unterminated: String = S_RECORDS.replace("S9030400F8\n", "");
+    assert_eq!(
+        loader::parse_srecord(unterminated.as_bytes()),
+        Err(LoadError::MissingEndRecord)
+    );
+    let high: &str = "S3060001000001F7\nS9030400F8\n";
+    assert_eq!(
+        loader::parse_srecord(high.as_bytes()),
+        Err(LoadError::OutOfRange { address: 0x10000 })
+    );
+    let garbage: &str = "S1080400A9428D0002ZZ\n";
+    assert_eq!(
+        loader::parse_srecord(garbage.as_bytes()),
+        Err(LoadError::InvalidRecord { line: 1 })
+    );
+}
+
+#[test]
+fn prg_uses_its_load_address_header() {
+    let (_, mut memory): (CPU, Memory) = common::setup();
+    let mut file: Vec<Byte> = vec![0x00, 0x04];
+    file.extend_from_slice(&PROGRAM);
+    let image: Image = loader::load(Format::Prg, &file, &mut memory).unwrap();
+    assert_program_loaded(&memory);
+    assert_eq!(image.segments, vec![segment(0x0400, &PROGRAM)]);
+
+    assert_eq!(loader::parse_prg(&[0x01]), Err(LoadError::Truncated));
+    assert_eq!(
+        loader::parse_prg(&[0xFF, 0xFF, 0x01, 0x02]),
+        Err(LoadError::OutOfRange { address: 0x10000 })
+    );
+}
+
+#[test]
+fn xex_loads_segments_and_run_and_init_addresses() {
+    let (_, mut memory): (CPU, Memory) = common::setup();
+    let file: Vec<Byte> = [
+        &[0xFF, 0xFF, 0x00, 0x04, 0x04, 0x04][..],
+        &PROGRAM[..5],
+        // Init address, then a repeated header before the next segment
+        &[0xE2, 0x02, 0xE3, 0x02, 0x00, 0x06],
+        &[0xFF, 0xFF, 0x05, 0x04, 0x07, 0x04],
+        &PROGRAM[5..],
+        &[0xE2, 0x02, 0xE3, 0x02, 0x00, 0x07],
+        &[0xE0, 0x02, 0xE1, 0x02, 0x00, 0x04],
+    ]
+    .concat();
+    let image: Image = loader::load(Format::Xex, &file, &mut memory).unwrap();
+    assert_program_loaded(&memory);
+    assert_eq!(
+        image.segments,
+        vec![
+            segment(0x0400, &PROGRAM[..5]),
+            segment(0x0405, &PROGRAM[5..])
+        ]
+    );
+    assert_eq!(image.init, vec![0x0600, 0x0700]);
+    assert_eq!(image.start, Some(0x0400));
+}
+
+#[test]
+fn xex_errors() {
+    assert_eq!(
+        loader::parse_xex(&[0x00, 0x04, 0x00, 0x04, 0xEA]),
+        Err(LoadError::MissingHeader)
+    );
+    assert_eq!(
+        loader::parse_xex(&[0xFF, 0xFF, 0x00, 0x04, 0x01, 0x04, 0xEA]),
+        Err(LoadError::Truncated)
+    );
+    assert_eq!(
+        loader::parse_xex(&[0xFF, 0xFF, 0x01, 0x04, 0x00, 0x04]),
+        Err(LoadError::InvalidSegment {
+            start: 0x0401,
+            end: 0x0400
+        })
+    );
+    let overlap: Vec<Byte> = vec![
+        0xFF, 0xFF, 0x00, 0x04, 0x01, 0x04, 0xEA, 0xEA, 0x01, 0x04, 0x01, 0x04, 0x60,
+    ];
+    assert_eq!(
+        loader::parse_xex(&overlap),
+        Err(LoadError::Overlap { address: 0x0401 })
+    );
+}
+
+#[test]
+fn format_is_chosen_by_extension() {
+    let format = |path: &str| Format::from_path(std::path::Path::new(path), 0x0800);
+    assert_eq!(format("game.HEX"), Format::IntelHex);
+    assert_eq!(format("rom.s19"), Format::SRecord);
+    assert_eq!(format("demo.prg"), Format::Prg);
+    assert_eq!(format("demo.xex"), Format::Xex);
+    assert_eq!(format("kernal.bin"), Format::Raw(0x0800));
+    assert_eq!(format("noextension"), Format::Raw(0x0800));
+}
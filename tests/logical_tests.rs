@@ -0,0 +1,94 @@
+use rust6502::{instructions::*, *};
+
+mod common;
+
+#[test]
+fn and_immediate_can_mask_accumulator() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0b11001100;
+    memory.data[0xFFFC] = INSTRUCTION_AND_IMM;
+    memory.data[0xFFFD] = 0b10101010;
+    let cycles_used = cpu.execute(2, &mut memory);
+    assert_eq!(cycles_used, 2);
+    assert_eq!(cpu.accumulator, 0b10001000);
+    assert!(!cpu.processor_status.zero());
+    assert!(cpu.processor_status.negative());
+}
+
+#[test]
+fn and_zeropage_can_affect_zero_flag() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0x0F;
+    memory.data[0xFFFC] = INSTRUCTION_AND_ZERO;
+    memory.data[0xFFFD] = 0x42;
+    memory.data[0x0042] = 0xF0;
+    let cycles_used = cpu.execute(3, &mut memory);
+    assert_eq!(cycles_used, 3);
+    assert_eq!(cpu.accumulator, 0x00);
+    assert!(cpu.processor_status.zero());
+    assert!(!cpu.processor_status.negative());
+}
+
+#[test]
+fn eor_absolute_x_can_exclusive_or_accumulator() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0xFF;
+    cpu.register_x = 0x01;
+    memory.data[0xFFFC] = INSTRUCTION_EOR_ABS_X;
+    memory.data[0xFFFD] = 0x80;
+    memory.data[0xFFFE] = 0x44;
+    memory.data[0x4481] = 0x0F;
+    let cycles_used = cpu.execute(4, &mut memory);
+    assert_eq!(cycles_used, 4);
+    assert_eq!(cpu.accumulator, 0xF0);
+    assert!(cpu.processor_status.negative());
+}
+
+#[test]
+fn ora_indirect_x_can_inclusive_or_accumulator() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0x01;
+    cpu.register_x = 0x04;
+    memory.data[0xFFFC] = INSTRUCTION_ORA_INDR_X;
+    memory.data[0xFFFD] = 0x02;
+    memory.data[0x0006] = 0x00;
+    memory.data[0x0007] = 0x80;
+    memory.data[0x8000] = 0x40;
+    let cycles_used = cpu.execute(6, &mut memory);
+    assert_eq!(cycles_used, 6);
+    assert_eq!(cpu.accumulator, 0x41);
+    assert!(!cpu.processor_status.zero());
+    assert!(!cpu.processor_status.negative());
+}
+
+#[test]
+fn bit_zeropage_copies_memory_bits_into_flags() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0x01;
+    memory.data[0xFFFC] = INSTRUCTION_BIT_ZERO;
+    memory.data[0xFFFD] = 0x42;
+    memory.data[0x0042] = 0b11000000;
+    let cycles_used = cpu.execute(3, &mut memory);
+    assert_eq!(cycles_used, 3);
+    assert_eq!(cpu.accumulator, 0x01);
+    assert!(cpu.processor_status.zero());
+    assert!(cpu.processor_status.overflow());
+    assert!(cpu.processor_status.negative());
+}
+
+#[test]
+fn bit_absolute_clears_zero_when_bits_match() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0x01;
+    cpu.processor_status.set_overflow(true);
+    cpu.processor_status.set_negative(true);
+    memory.data[0xFFFC] = INSTRUCTION_BIT_ABS;
+    memory.data[0xFFFD] = 0x00;
+    memory.data[0xFFFE] = 0x80;
+    memory.data[0x8000] = 0x01;
+    let cycles_used = cpu.execute(4, &mut memory);
+    assert_eq!(cycles_used, 4);
+    assert!(!cpu.processor_status.zero());
+    assert!(!cpu.processor_status.overflow());
+    assert!(!cpu.processor_status.negative());
+}
@@ -0,0 +1,195 @@
+use rust6502::monitor::Monitor;
+use rust6502::*;
+
+mod common;
+
+use common::recording_bus::RecordingBus;
+
+fn monitor() -> Monitor<Memory> {
+    let (cpu, memory): (CPU, Memory) = common::setup();
+    Monitor::new(Debugger::new(cpu, memory))
+}
+
+fn run(monitor: &mut Monitor<Memory>, commands: &[&str]) -> String {
+    commands
+        .iter()
+        .map(|command| monitor.execute(command).unwrap())
+        .collect()
+}
+
+#[test]
+fn deposit_and_examine_memory() {
+    let mut monitor = monitor();
+    run(&mut monitor, &["> 2000 48 49 $21"]);
+    assert_eq!(
+        monitor.debugger.bus.data[0x2000..0x2003],
+        [0x48, 0x49, 0x21]
+    );
+    assert_eq!(
+        run(&mut monitor, &["m 2000 2003"]),
+        "2000  48 49 21 00                                      HI!.\n"
+    );
+    assert_eq!(run(&mut monitor, &["m 1ff0"]).lines().count(), 8);
+}
+
+#[test]
+fn examining_memory_does_not_touch_the_bus() {
+    let (cpu, _): (CPU, Memory) = common::setup();
+    let mut monitor: Monitor<RecordingBus> = Monitor::new(Debugger::new(cpu, RecordingBus::new()));
+    monitor.debugger.bus.memory.data[0x0400] = instructions::INSTRUCTION_LDA_IMM;
+    let path = std::env::temp_dir().join(format!("monitor_peek_{}.bin", std::process::id()));
+    let path: &str = path.to_str().unwrap();
+    for command in ["m 0400 040f", "d 0400", &format!("s {} 0400 040f", path)] {
+        monitor.execute(command).unwrap();
+    }
+    std::fs::remove_file(path).unwrap();
+    assert!(monitor.debugger.bus.accesses.is_empty());
+}
+
+#[test]
+fn mos6510_port_shows_in_memory_dumps() {
+    let mut monitor = monitor();
+    monitor.debugger.cpu.variant = Variant::Mos6510;
+    monitor.debugger.bus.data[0x0001] = 0xAA;
+    assert!(run(&mut monitor, &["m 0000 0001"]).starts_with("0000  00 17"));
+}
+
+#[test]
+fn assemble_and_disassemble_in_place() {
+    let mut monitor = monitor();
+    assert_eq!(
+        run(&mut monitor, &["a 0400 lda #$42"]),
+        "0400  A9 42     LDA #$42\n"
+    );
+    run(&mut monitor, &["a 0402 sta $2000,x"]);
+    assert_eq!(
+        run(&mut monitor, &["d 0400 0402"]),
+        "0400  A9 42     LDA #$42\n0402  9D 00 20  STA $2000,X\n"
+    );
+    assert!(monitor.execute("a 0400 jmp #$10").is_err());
+}
+
+#[test]
+fn assemble_uses_the_cpu_variant() {
+    let mut monitor = monitor();
+    assert!(monitor.execute("a 0400 stz $12").is_err());
+    monitor.debugger.cpu.variant = Variant::Wdc65C02;
+    assert_eq!(
+        run(&mut monitor, &["a 0400 stz $12"]),
+        "0400  64 12     STZ $12\n"
+    );
+    assert_eq!(
+        run(&mut monitor, &["a 0402 bbr3 $12,$0400"]),
+        "0402  3F 12 FB  BBR3 $12,$0400\n"
+    );
+}
+
+#[test]
+fn set_registers_and_step() {
+    let mut monitor = monitor();
+    run(&mut monitor, &["a 0400 inx", "a 0401 inx"]);
+    assert_eq!(
+        run(&mut monitor, &["r pc=0400 x=10 p=01"]),
+        "PC:0400 A:00 X:10 Y:00 SP:FF P:01 nv-bdizC\n"
+    );
+    assert_eq!(
+        run(&mut monitor, &["z 2"]),
+        "PC:0402 A:00 X:12 Y:00 SP:FF P:01 nv-bdizC\n0402  00        BRK\n"
+    );
+    assert_eq!(monitor.prompt(), "0402> ");
+    assert!(monitor.execute("r q=1").is_err());
+}
+
+#[test]
+fn breakpoints_watchpoints_and_go() {
+    let mut monitor = monitor();
+    run(
+        &mut monitor,
+        &["a 0400 inx", "a 0401 stx $2000", "a 0404 jmp $0400"],
+    );
+    assert_eq!(
+        run(&mut monitor, &["break 0404 if x >= 3"]),
+        "breakpoint 1\n"
+    );
+    assert_eq!(run(&mut monitor, &["watch w 2000 20ff"]), "watchpoint 2\n");
+    assert_eq!(
+        run(&mut monitor, &["break", "watch"]),
+        "1: 0404 if x >= $03\n2: w 2000-20FF\n"
+    );
+    let output: String = run(&mut monitor, &["g 0400"]);
+    assert!(output.starts_with("watchpoint 2: write $01 at 2000\n"));
+    run(&mut monitor, &["del 2"]);
+    let output: String = run(&mut monitor, &["g"]);
+    assert!(output.starts_with("breakpoint 1\nPC:0404 A:00 X:03"));
+    let output: String = run(&mut monitor, &["until 0401"]);
+    assert!(output.starts_with("breakpoint 3\nPC:0401 A:00 X:04"));
+    assert!(monitor.execute("del 2").is_err());
+}
+
+#[test]
+fn step_over_and_out_of_subroutines() {
+    let mut monitor = monitor();
+    run(
+        &mut monitor,
+        &["a 0400 jsr $0500", "a 0403 nop", "a 0500 iny", "a 0501 rts"],
+    );
+    run(&mut monitor, &["r pc=0400"]);
+    assert!(run(&mut monitor, &["n"]).starts_with("PC:0403 A:00 X:00 Y:01"));
+    run(&mut monitor, &["r pc=0400", "z"]);
+    assert!(run(&mut monitor, &["ret"]).starts_with("PC:0403 A:00 X:00 Y:02"));
+}
+
+#[test]
+fn save_and_load_files() {
+    let path = std::env::temp_dir().join(format!("monitor_test_{}.bin", std::process::id()));
+    let path: &str = path.to_str().unwrap();
+    let mut monitor = monitor();
+    run(&mut monitor, &["> 3000 01 02 03"]);
+    assert_eq!(
+        run(&mut monitor, &[&format!("s {} 3000 3002", path)]),
+        "saved 3000-3002\n"
+    );
+    assert_eq!(
+        run(&mut monitor, &[&format!("l {} 4000", path)]),
+        "loaded 4000-4002\n"
+    );
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(
+        monitor.debugger.bus.data[0x4000..0x4003],
+        [0x01, 0x02, 0x03]
+    );
+    assert!(monitor.execute(&format!("l {}", path)).is_err());
+}
+
+#[test]
+fn quit_and_unknown_commands() {
+    let mut monitor = monitor();
+    assert!(monitor.execute("frobnicate").is_err());
+    assert_eq!(run(&mut monitor, &[""]), "");
+    assert!(!monitor.is_finished());
+    run(&mut monitor, &["x"]);
+    assert!(monitor.is_finished());
+}
+
+#[test]
+fn load_picks_the_format_from_the_extension() {
+    let path = std::env::temp_dir().join(format!("monitor_test_{}.hex", std::process::id()));
+    let path: &str = path.to_str().unwrap();
+    std::fs::write(
+        path,
+        ":03050000A9428D80\n:0400000500000500F2\n:00000001FF\n",
+    )
+    .unwrap();
+    let mut monitor = monitor();
+    assert!(monitor.execute(&format!("l {} 4000", path)).is_err());
+    assert_eq!(
+        run(&mut monitor, &[&format!("l {}", path)]),
+        "loaded 0500-0502\nstart 0500\n"
+    );
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(
+        monitor.debugger.bus.data[0x0500..0x0503],
+        [0xA9, 0x42, 0x8D]
+    );
+    assert_eq!(monitor.debugger.cpu.program_counter, 0x0500);
+}
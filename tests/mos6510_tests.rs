@@ -0,0 +1,159 @@
+use asm_macros::asm;
+use rust6502::{instructions::*, snapshot, *};
+
+mod common;
+
+/// Memory that remembers every level the 6510 port drove, standing in for
+/// the C64's banking logic.
+struct BankingBus {
+    memory: Memory,
+    pins: Vec<Byte>,
+}
+
+impl Bus for BankingBus {
+    fn read(&mut self, address: Word) -> Byte {
+        self.memory.data[address as usize]
+    }
+
+    fn write(&mut self, address: Word, data: Byte) {
+        self.memory.data[address as usize] = data;
+    }
+
+    fn io_port_changed(&mut self, pins: Byte) {
+        self.pins.push(pins);
+    }
+}
+
+fn setup() -> (CPU, Memory) {
+    let (mut cpu, memory): (CPU, Memory) = common::setup();
+    cpu.variant = Variant::Mos6510;
+    (cpu, memory)
+}
+
+#[test]
+fn port_registers_replace_the_first_two_bytes_of_memory() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    let program = asm! {
+        .org $0400
+        lda #$2F
+        sta $00
+        lda #$37
+        sta $01
+        lda $00
+        ldx $01
+    };
+    program.load_into(&mut memory.data);
+    cpu.program_counter = program.origin;
+    for _ in 0..6 {
+        cpu.step(&mut memory).unwrap();
+    }
+    assert_eq!(cpu.io_port.direction, 0x2F);
+    assert_eq!(cpu.io_port.data, 0x37);
+    assert_eq!(memory.data[0x0000], 0x00);
+    assert_eq!(memory.data[0x0001], 0x00);
+    assert_eq!(cpu.accumulator, 0x2F);
+    assert_eq!(cpu.register_x, 0x37);
+}
+
+#[test]
+fn other_variants_leave_the_first_two_bytes_to_the_bus() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0x37;
+    memory.data[0xFFFC] = INSTRUCTION_STA_ZERO;
+    memory.data[0xFFFD] = 0x01;
+    cpu.execute(3, &mut memory);
+    assert_eq!(memory.data[0x0001], 0x37);
+    assert_eq!(cpu.io_port.data, 0x00);
+}
+
+#[test]
+fn inputs_read_the_outside_levels() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.io_port.direction = 0b0010_1111;
+    cpu.io_port.data = 0b0000_0000;
+    memory.data[0xFFFC] = INSTRUCTION_LDA_ZERO;
+    memory.data[0xFFFD] = 0x01;
+    cpu.execute(3, &mut memory);
+    // Only the cassette sense line (bit 4) is an input, and it is pulled up
+    assert_eq!(cpu.accumulator, 0b0001_0000);
+}
+
+#[test]
+fn the_bus_sees_the_pins_after_writes_and_reset() {
+    let mut bus: BankingBus = BankingBus {
+        memory: Memory::reset(),
+        pins: Vec::new(),
+    };
+    let program = asm! {
+        .org $0400
+        lda #$2F
+        sta $00
+        lda #$35
+        sta $01
+    };
+    program.load_into(&mut bus.memory.data);
+    bus.memory.data[0xFFFC..0xFFFE].copy_from_slice(&program.origin.to_le_bytes());
+    let mut cpu: CPU = CPU::with_variant(Variant::Mos6510);
+    for _ in 0..5 {
+        cpu.step(&mut bus).unwrap();
+    }
+    // Reset makes every pin an input, so the pull-ups select all ROMs.
+    // The data register is still 0 when the first pins become outputs.
+    assert_eq!(bus.pins, vec![0x17, 0x10, 0x35]);
+}
+
+#[test]
+fn undriven_pins_fade_to_zero() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.io_port.fade_cycles = 100;
+    let program = asm! {
+        .org $0400
+        lda #$FF
+        sta $00
+        lda #$C0
+        sta $01
+        lda #$3F
+        sta $00
+    loop: lda $01
+        jmp loop
+    };
+    program.load_into(&mut memory.data);
+    cpu.program_counter = program.origin;
+    for _ in 0..7 {
+        cpu.step(&mut memory).unwrap();
+    }
+    assert_eq!(cpu.accumulator & 0xC0, 0xC0);
+    let faded_at: u64 = cpu.cycles + 100;
+    while cpu.cycles < faded_at + 10 {
+        cpu.step(&mut memory).unwrap();
+    }
+    assert_eq!(cpu.accumulator & 0xC0, 0x00);
+}
+
+#[test]
+fn driving_a_pin_low_discharges_it_at_once() {
+    let mut port: IoPort = IoPort::c64();
+    port.write(io_port::DIRECTION_ADDRESS, 0xC0, 0);
+    port.write(io_port::DATA_ADDRESS, 0xC0, 10);
+    port.write(io_port::DIRECTION_ADDRESS, 0x40, 20);
+    port.write(io_port::DATA_ADDRESS, 0x00, 30);
+    port.write(io_port::DIRECTION_ADDRESS, 0x00, 40);
+    assert_eq!(port.read(io_port::DATA_ADDRESS, 50) & 0xC0, 0x80);
+    assert_eq!(
+        port.read(io_port::DATA_ADDRESS, 20 + io_port::C64_FADE_CYCLES) & 0xC0,
+        0x00
+    );
+}
+
+#[test]
+fn snapshots_keep_the_port() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.io_port.write(io_port::DIRECTION_ADDRESS, 0xEF, 0);
+    cpu.io_port.write(io_port::DATA_ADDRESS, 0xC5, 0);
+    cpu.io_port.write(io_port::DIRECTION_ADDRESS, 0x2F, 0);
+    let data: Vec<Byte> = snapshot::save(&cpu, &memory);
+    let (mut restored, _): (CPU, Memory) = setup();
+    snapshot::load(&data, &mut restored, &mut memory).unwrap();
+    assert_eq!(restored.io_port, cpu.io_port);
+    assert_eq!(restored.io_port.read(io_port::DATA_ADDRESS, 1000), 0xD5);
+}
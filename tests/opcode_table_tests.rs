@@ -0,0 +1,89 @@
+use rust6502::{instructions::*, *};
+mod common;
+
+#[test]
+fn opcode_table_is_indexed_by_opcode() {
+    for (index, info) in OPCODES.iter().enumerate() {
+        assert_eq!(info.opcode as usize, index);
+        assert_eq!(
+            info.length,
+            1 + info.addressing_mode.operand_length(),
+            "opcode {:#04x}",
+            info.opcode
+        );
+    }
+}
+
+#[test]
+fn opcode_table_documents_the_151_official_opcodes() {
+    assert_eq!(OPCODES.iter().filter(|info| info.documented).count(), 151);
+    assert!(OPCODES
+        .iter()
+        .filter(|info| info.mnemonic == Mnemonic::JAM)
+        .all(|info| !info.documented && is_jam(info.opcode)));
+}
+
+#[test]
+fn decode_agrees_with_opcode_table() {
+    for info in OPCODES.iter() {
+        let expected = info.documented.then(|| info.instruction());
+        assert_eq!(decode(info.opcode), expected);
+    }
+}
+
+#[test]
+fn documented_opcodes_take_their_base_cycles_without_crossing_a_page() {
+    for info in OPCODES.iter().filter(|info| info.documented) {
+        let (mut cpu, mut memory) = common::setup();
+        cpu.program_counter = 0x0200;
+        memory.data[0x0200] = info.opcode;
+        memory.data[0x0201] = 0x02;
+        memory.data[0x0202] = 0x12;
+        let step = cpu.step(&mut memory).unwrap();
+        let branch_taken =
+            info.addressing_mode == AddressingMode::Relative && cpu.program_counter == 0x0204;
+        assert_eq!(
+            step.cycles,
+            info.cycles as i32 + branch_taken as i32,
+            "opcode {:#04x}",
+            info.opcode
+        );
+    }
+}
+
+#[test]
+fn documented_opcodes_only_take_a_page_cross_penalty_when_the_table_says_so() {
+    let indexed = OPCODES
+        .iter()
+        .filter(|info| info.documented && info.addressing_mode != AddressingMode::Relative);
+    for info in indexed {
+        let (mut cpu, mut memory) = common::setup();
+        cpu.program_counter = 0x0200;
+        cpu.register_x = 0xFF;
+        cpu.register_y = 0xFF;
+        memory.data[0x0200] = info.opcode;
+        memory.data[0x0201] = 0x80;
+        memory.data[0x0202] = 0x12;
+        memory.data[0x0080] = 0xFF;
+        memory.data[0x0081] = 0x12;
+        let step = cpu.step(&mut memory).unwrap();
+        assert_eq!(
+            step.cycles,
+            info.cycles as i32 + (info.page_cross_penalty && step.page_crossed) as i32,
+            "opcode {:#04x}",
+            info.opcode
+        );
+    }
+}
+
+#[test]
+fn flag_instructions_only_affect_their_own_flag() {
+    assert_eq!(opcode_info(INSTRUCTION_CLC).affected_flags, FLAG_CARRY);
+    assert_eq!(opcode_info(INSTRUCTION_SED).affected_flags, FLAG_DECIMAL);
+    assert_eq!(opcode_info(INSTRUCTION_CLV).affected_flags, FLAG_OVERFLOW);
+    assert_eq!(opcode_info(INSTRUCTION_STA_ABS).affected_flags, 0);
+    assert_eq!(
+        opcode_info(INSTRUCTION_ADC_IMM).affected_flags,
+        FLAG_NEGATIVE | FLAG_OVERFLOW | FLAG_ZERO | FLAG_CARRY
+    );
+}
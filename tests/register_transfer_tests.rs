@@ -0,0 +1,62 @@
+use rust6502::{instructions::*, *};
+
+mod common;
+
+fn transfer_register(opcode: Byte, from: RegisterType, to: RegisterType) {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    *cpu.get_register(&from) = 0x84;
+    memory.data[0xFFFC] = opcode;
+    let cycles_used = cpu.execute(2, &mut memory);
+    assert_eq!(cycles_used, 2);
+    assert_eq!(*cpu.get_register(&to), 0x84);
+    assert!(!cpu.processor_status.zero());
+    assert!(cpu.processor_status.negative());
+}
+
+#[test]
+fn tax_can_transfer_accumulator_to_register_x() {
+    transfer_register(
+        INSTRUCTION_TAX,
+        RegisterType::Accumulator,
+        RegisterType::RegisterX,
+    );
+}
+
+#[test]
+fn tay_can_transfer_accumulator_to_register_y() {
+    transfer_register(
+        INSTRUCTION_TAY,
+        RegisterType::Accumulator,
+        RegisterType::RegisterY,
+    );
+}
+
+#[test]
+fn txa_can_transfer_register_x_to_accumulator() {
+    transfer_register(
+        INSTRUCTION_TXA,
+        RegisterType::RegisterX,
+        RegisterType::Accumulator,
+    );
+}
+
+#[test]
+fn tya_can_transfer_register_y_to_accumulator() {
+    transfer_register(
+        INSTRUCTION_TYA,
+        RegisterType::RegisterY,
+        RegisterType::Accumulator,
+    );
+}
+
+#[test]
+fn tax_can_affect_zero_flag() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.register_x = 0x42;
+    cpu.processor_status.set_negative(true);
+    memory.data[0xFFFC] = INSTRUCTION_TAX;
+    cpu.execute(2, &mut memory);
+    assert_eq!(cpu.register_x, 0x00);
+    assert!(cpu.processor_status.zero());
+    assert!(!cpu.processor_status.negative());
+}
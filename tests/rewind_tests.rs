@@ -0,0 +1,204 @@
+use asm_macros::asm;
+use rust6502::{debugger::*, gdb::GdbStub, monitor::Monitor, *};
+
+mod common;
+
+/// Loads a program, points the CPU at its origin and starts recording.
+fn debugger<const N: usize>(
+    program: &asm::ProgramImage<N>,
+    interval: u64,
+    capacity: usize,
+) -> Debugger<Memory> {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    program.load_into(&mut memory.data);
+    cpu.program_counter = program.origin;
+    let mut debugger: Debugger<Memory> = Debugger::new(cpu, memory);
+    debugger.enable_rewind(interval, capacity);
+    debugger
+}
+
+#[test]
+fn step_back_undoes_registers_memory_and_cycles() {
+    let program = asm! {
+        .org $0400
+        ldx #0
+    loop: inx
+        stx $0200
+        jmp loop
+    };
+    let mut debugger = debugger(&program, 4, 8);
+    let mut states: Vec<(CPU, Byte)> = Vec::new();
+    for _ in 0..20 {
+        states.push((debugger.cpu, debugger.bus.data[0x0200]));
+        debugger.step().unwrap();
+    }
+    while let Some((cpu, stored)) = states.pop() {
+        assert!(debugger.step_back());
+        assert_eq!(debugger.cpu.program_counter, cpu.program_counter);
+        assert_eq!(debugger.cpu.register_x, cpu.register_x);
+        assert_eq!(debugger.cpu.cycles, cpu.cycles);
+        assert_eq!(debugger.bus.data[0x0200], stored);
+    }
+    assert!(!debugger.step_back());
+    assert_eq!(debugger.cpu.program_counter, 0x0400);
+}
+
+#[test]
+fn run_back_to_write_stops_before_the_last_writer() {
+    let program = asm! {
+        .org $0400
+        lda #$11
+        sta $10
+        lda #$22
+    corrupt: sta $10
+        ldx #5
+    spin: dex
+        bne spin
+    done: jmp done
+    };
+    let mut debugger = debugger(&program, 3, 32);
+    debugger.instruction_limit = Some(50);
+    assert_eq!(debugger.run().unwrap(), StopReason::InstructionLimit);
+    assert_eq!(debugger.bus.data[0x10], 0x22);
+
+    assert!(debugger.run_back_to_write(0x0010));
+    assert_eq!(
+        debugger.cpu.program_counter,
+        program.symbol("corrupt").unwrap()
+    );
+    assert_eq!(debugger.bus.data[0x10], 0x11);
+    assert_eq!(debugger.cpu.accumulator, 0x22);
+
+    assert!(debugger.run_back_to_write(0x0010));
+    assert_eq!(debugger.cpu.program_counter, 0x0402);
+    assert_eq!(debugger.bus.data[0x10], 0x00);
+    assert!(!debugger.run_back_to_write(0x0010));
+}
+
+#[test]
+fn journal_records_every_write_of_an_instruction() {
+    let program = asm! {
+        .org $0400
+        ldx #$FF
+        txs
+        lda #$42
+        pha
+        jsr sub
+    sub: inc $20
+    };
+    let mut debugger = debugger(&program, 100, 1);
+    for _ in 0..6 {
+        debugger.step().unwrap();
+    }
+    let history = debugger.history().unwrap();
+    let writes: Vec<(Word, Vec<(Word, Byte)>)> = history
+        .journal()
+        .filter(|entry| !entry.writes.is_empty())
+        .map(|entry| (entry.address, entry.writes.clone()))
+        .collect();
+    assert_eq!(
+        writes,
+        vec![
+            (0x0405, vec![(0x01FF, 0x42)]),
+            (0x0406, vec![(0x01FE, 0x04), (0x01FD, 0x08)]),
+            // INC writes the unchanged value back before the result
+            (0x0409, vec![(0x0020, 0x00), (0x0020, 0x01)]),
+        ]
+    );
+}
+
+#[test]
+fn history_is_limited_to_the_checkpoint_ring() {
+    let program = asm! {
+        .org $0400
+    loop: inc $30
+        jmp loop
+    };
+    let mut debugger = debugger(&program, 10, 3);
+    for _ in 0..100 {
+        debugger.step().unwrap();
+    }
+    let history = debugger.history().unwrap();
+    assert_eq!(history.instructions(), 100);
+    assert_eq!(history.depth(), 30);
+    assert_eq!(history.journal().count(), 30);
+
+    for _ in 0..30 {
+        assert!(debugger.step_back());
+    }
+    assert!(!debugger.step_back());
+    assert_eq!(debugger.bus.data[0x30], 35);
+}
+
+#[test]
+fn clear_history_restarts_recording_after_a_direct_change() {
+    let program = asm! {
+        .org $0400
+    loop: inc $30
+        jmp loop
+    };
+    let mut debugger = debugger(&program, 2, 4);
+    for _ in 0..6 {
+        debugger.step().unwrap();
+    }
+    assert!(debugger.step_back());
+    assert!(debugger.step_back());
+    debugger.bus.data[0x30] = 0x80;
+    debugger.clear_history();
+    debugger.step().unwrap();
+    assert_eq!(debugger.bus.data[0x30], 0x81);
+    assert!(debugger.step_back());
+    assert_eq!(debugger.bus.data[0x30], 0x80);
+    assert!(!debugger.step_back());
+}
+
+#[test]
+fn gdb_writes_clear_the_history() {
+    let program = asm! {
+        .org $0400
+    loop: inc $30
+        jmp loop
+    };
+    for packet in ["M30,1:80", "P0=80", "G80000024fd0004"] {
+        let mut debugger = debugger(&program, 2, 4);
+        for _ in 0..6 {
+            debugger.step().unwrap();
+        }
+        assert_eq!(GdbStub::new(&mut debugger).handle_packet(packet), "OK");
+        assert!(!debugger.step_back(), "{}", packet);
+    }
+}
+
+#[test]
+fn rewind_is_off_by_default() {
+    let program = asm! {
+        .org $0400
+        sta $10
+    };
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    program.load_into(&mut memory.data);
+    cpu.program_counter = program.origin;
+    let mut debugger: Debugger<Memory> = Debugger::new(cpu, memory);
+    debugger.step().unwrap();
+    assert!(debugger.history().is_none());
+    assert!(!debugger.step_back());
+    assert!(!debugger.run_back_to_write(0x0010));
+}
+
+#[test]
+fn monitor_steps_back() {
+    let program = asm! {
+        .org $0400
+        lda #$AA
+        sta $0200
+        lda #$BB
+    };
+    let mut monitor: Monitor<Memory> = Monitor::new(debugger(&program, 16, 4));
+    monitor.execute("z 3").unwrap();
+    let output: String = monitor.execute("bw 200").unwrap();
+    assert!(output.starts_with("PC:0402 A:AA"), "{}", output);
+    let output: String = monitor.execute("zb").unwrap();
+    assert!(output.starts_with("PC:0400 A:00"), "{}", output);
+    assert!(monitor.execute("zb").is_err());
+    assert!(monitor.execute("bw 300").is_err());
+}
@@ -0,0 +1,76 @@
+use rust6502::{instructions::*, *};
+
+mod common;
+
+fn setup() -> (CPU, Memory) {
+    let (mut cpu, memory): (CPU, Memory) = common::setup();
+    cpu.variant = Variant::Ricoh2A03;
+    cpu.processor_status.set_decimal(true);
+    (cpu, memory)
+}
+
+#[test]
+fn adc_ignores_decimal_mode() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0x09;
+    memory.data[0xFFFC] = INSTRUCTION_ADC_IMM;
+    memory.data[0xFFFD] = 0x01;
+    let cycles: i32 = cpu.execute(2, &mut memory);
+    assert_eq!(cycles, 2);
+    assert_eq!(cpu.accumulator, 0x0A);
+    assert!(cpu.processor_status.decimal());
+}
+
+#[test]
+fn sbc_ignores_decimal_mode() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0x10;
+    cpu.processor_status.set_carry(true);
+    memory.data[0xFFFC] = INSTRUCTION_SBC_IMM;
+    memory.data[0xFFFD] = 0x01;
+    cpu.execute(2, &mut memory);
+    assert_eq!(cpu.accumulator, 0x0F);
+    assert!(cpu.processor_status.carry());
+}
+
+#[test]
+fn sed_still_sets_the_flag_pushed_by_php() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.processor_status.set_decimal(false);
+    memory.data[0xFFFC] = INSTRUCTION_SED;
+    memory.data[0xFFFD] = INSTRUCTION_PHP;
+    cpu.execute(5, &mut memory);
+    assert!(cpu.processor_status.decimal());
+    assert_eq!(memory.data[0x01FF] & 0b0000_1000, 0b0000_1000);
+}
+
+#[test]
+fn undocumented_opcodes_use_binary_arithmetic() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.undocumented_opcodes = Some(UndocumentedOpcodes::default());
+    cpu.processor_status.set_carry(true);
+    cpu.accumulator = 0x10;
+    // ISC $10: increment $0010 to $0A, then subtract it from A
+    memory.data[0x0010] = 0x09;
+    memory.data[0xFFFC] = 0xE7;
+    memory.data[0xFFFD] = 0x10;
+    cpu.execute(5, &mut memory);
+    assert_eq!(memory.data[0x0010], 0x0A);
+    assert_eq!(cpu.accumulator, 0x06);
+
+    // ARR #$FF with carry set rotates $06 without a decimal correction
+    cpu.program_counter = 0x0200;
+    memory.data[0x0200] = 0x6B;
+    memory.data[0x0201] = 0xFF;
+    cpu.execute(2, &mut memory);
+    assert_eq!(cpu.accumulator, 0x83);
+}
+
+#[test]
+fn undocumented_opcodes_are_opt_in_as_on_nmos() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    memory.data[0xFFFC] = 0xA7;
+    let error = cpu.try_execute(3, &mut memory).unwrap_err();
+    assert!(matches!(error, ExecutionError::IllegalOpcode { .. }));
+    assert_eq!(Variant::Ricoh2A03.opcodes(), &OPCODES);
+}
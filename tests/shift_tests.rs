@@ -0,0 +1,109 @@
+use rust6502::{instructions::*, *};
+
+mod common;
+
+#[test]
+fn asl_accumulator_can_shift_left_into_carry() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0b10000001;
+    memory.data[0xFFFC] = INSTRUCTION_ASL_ACC;
+    let cycles_used = cpu.execute(2, &mut memory);
+    assert_eq!(cycles_used, 2);
+    assert_eq!(cpu.accumulator, 0b00000010);
+    assert!(cpu.processor_status.carry());
+    assert!(!cpu.processor_status.zero());
+    assert!(!cpu.processor_status.negative());
+}
+
+#[test]
+fn asl_zeropage_can_shift_memory_left() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    memory.data[0xFFFC] = INSTRUCTION_ASL_ZERO;
+    memory.data[0xFFFD] = 0x42;
+    memory.data[0x0042] = 0b01000000;
+    let cycles_used = cpu.execute(5, &mut memory);
+    assert_eq!(cycles_used, 5);
+    assert_eq!(memory.data[0x0042], 0b10000000);
+    assert!(!cpu.processor_status.carry());
+    assert!(cpu.processor_status.negative());
+}
+
+#[test]
+fn lsr_accumulator_can_shift_right_into_carry() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0b00000001;
+    cpu.processor_status.set_negative(true);
+    memory.data[0xFFFC] = INSTRUCTION_LSR_ACC;
+    let cycles_used = cpu.execute(2, &mut memory);
+    assert_eq!(cycles_used, 2);
+    assert_eq!(cpu.accumulator, 0x00);
+    assert!(cpu.processor_status.carry());
+    assert!(cpu.processor_status.zero());
+    assert!(!cpu.processor_status.negative());
+}
+
+#[test]
+fn lsr_absolute_x_always_takes_seven_cycles() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.register_x = 0x01;
+    memory.data[0xFFFC] = INSTRUCTION_LSR_ABS_X;
+    memory.data[0xFFFD] = 0x00;
+    memory.data[0xFFFE] = 0x80;
+    memory.data[0x8001] = 0b00000100;
+    let cycles_used = cpu.execute(7, &mut memory);
+    assert_eq!(cycles_used, 7);
+    assert_eq!(memory.data[0x8001], 0b00000010);
+    assert!(!cpu.processor_status.carry());
+}
+
+#[test]
+fn rol_accumulator_rotates_carry_into_bit_zero() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0b10000000;
+    cpu.processor_status.set_carry(true);
+    memory.data[0xFFFC] = INSTRUCTION_ROL_ACC;
+    let cycles_used = cpu.execute(2, &mut memory);
+    assert_eq!(cycles_used, 2);
+    assert_eq!(cpu.accumulator, 0b00000001);
+    assert!(cpu.processor_status.carry());
+}
+
+#[test]
+fn rol_zeropage_with_offset_x_can_rotate_memory() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.register_x = 0x02;
+    memory.data[0xFFFC] = INSTRUCTION_ROL_ZERO_X;
+    memory.data[0xFFFD] = 0x40;
+    memory.data[0x0042] = 0b01010101;
+    let cycles_used = cpu.execute(6, &mut memory);
+    assert_eq!(cycles_used, 6);
+    assert_eq!(memory.data[0x0042], 0b10101010);
+    assert!(!cpu.processor_status.carry());
+    assert!(cpu.processor_status.negative());
+}
+
+#[test]
+fn ror_accumulator_rotates_carry_into_bit_seven() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0b00000001;
+    cpu.processor_status.set_carry(true);
+    memory.data[0xFFFC] = INSTRUCTION_ROR_ACC;
+    let cycles_used = cpu.execute(2, &mut memory);
+    assert_eq!(cycles_used, 2);
+    assert_eq!(cpu.accumulator, 0b10000000);
+    assert!(cpu.processor_status.carry());
+    assert!(cpu.processor_status.negative());
+}
+
+#[test]
+fn ror_absolute_can_rotate_memory() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    memory.data[0xFFFC] = INSTRUCTION_ROR_ABS;
+    memory.data[0xFFFD] = 0x00;
+    memory.data[0xFFFE] = 0x80;
+    memory.data[0x8000] = 0b00000010;
+    let cycles_used = cpu.execute(6, &mut memory);
+    assert_eq!(cycles_used, 6);
+    assert_eq!(memory.data[0x8000], 0b00000001);
+    assert!(!cpu.processor_status.carry());
+}
@@ -0,0 +1,96 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use rust6502::*;
+
+mod common;
+
+use common::single_step::{self, TestCase};
+
+/// Runs every `<opcode>.json` file in `directory` and panics with a diff of
+/// each failing vector. Files for JAM opcodes are skipped, since they
+/// expect the bus activity of a CPU that has locked up.
+fn run_directory(directory: &Path) -> usize {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(directory)
+        .unwrap_or_else(|error| panic!("{}: {}", directory.display(), error))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect();
+    paths.sort();
+    let mut passed: usize = 0;
+    let mut failures: Vec<String> = Vec::new();
+    for path in paths {
+        let opcode: Option<Byte> = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| Byte::from_str_radix(stem, 16).ok());
+        if opcode.is_some_and(instructions::is_jam) {
+            continue;
+        }
+        let json: String = std::fs::read_to_string(&path).unwrap();
+        let tests: Vec<TestCase> = single_step::parse(&json)
+            .unwrap_or_else(|error| panic!("{}: {}", path.display(), error));
+        for test in &tests {
+            match single_step::run(test) {
+                Ok(()) => passed += 1,
+                Err(diff) => failures.push(diff),
+            }
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} of {} vectors failed, first {}:\n{}",
+        failures.len(),
+        passed + failures.len(),
+        failures.len().min(10),
+        failures[..failures.len().min(10)].concat()
+    );
+    passed
+}
+
+/// Hand-written vectors in the SingleStepTests format, not upstream cases.
+#[test]
+fn hand_written_single_step_vectors() {
+    let directory: PathBuf = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/single_step");
+    assert!(run_directory(&directory) > 0);
+}
+
+/// Set `SINGLE_STEP_TESTS` to a checkout's `6502/v1` directory and pass
+/// `--ignored` to run the full suite.
+#[test]
+#[ignore = "needs SINGLE_STEP_TESTS, see tests/single_step/README.md"]
+fn full_single_step_suite() {
+    let directory: OsString =
+        std::env::var_os("SINGLE_STEP_TESTS").expect("SINGLE_STEP_TESTS is not set");
+    run_directory(Path::new(&directory));
+}
+
+#[test]
+fn run_reports_every_difference() {
+    let json: &str = r#"[{
+        "name": "a9 84",
+        "initial": {"pc": 1024, "s": 253, "a": 0, "x": 0, "y": 0, "p": 36,
+                    "ram": [[1024, 169], [1025, 132]]},
+        "final": {"pc": 1026, "s": 253, "a": 132, "x": 1, "y": 0, "p": 36,
+                  "ram": [[1024, 169], [1025, 132], [16, 1]]},
+        "cycles": [[1024, 169, "read"], [1025, 132, "read"], [1026, 0, "read"]]
+    }]"#;
+    let tests: Vec<TestCase> = single_step::parse(json).unwrap();
+    assert_eq!(
+        single_step::run(&tests[0]).unwrap_err(),
+        "a9 84:\n  \
+         x: expected $01, got $00\n  \
+         p: expected $04, got $84\n  \
+         ram[$0010]: expected $01, got $00\n  \
+         cycle 3: expected read $00 from $0402, got nothing\n"
+    );
+}
+
+#[test]
+fn parse_rejects_malformed_vectors() {
+    assert!(single_step::parse("{}").is_err());
+    assert!(single_step::parse(r#"[{"name": "a9"}]"#).is_err());
+}
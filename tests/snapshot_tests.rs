@@ -0,0 +1,182 @@
+use asm_macros::asm;
+use rust6502::snapshot::{self, SaveState};
+use rust6502::*;
+
+mod common;
+
+fn running_machine() -> (CPU, Memory) {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    let program = asm! {
+        .org $0400
+        cli
+        ldx #$10
+    loop: inc $2000,x
+        dex
+        bne loop
+        jmp loop
+    irq: inc $3000
+        rti
+    };
+    program.load_into(&mut memory.data);
+    memory.data[0xFFFE..].copy_from_slice(&program.symbol("irq").unwrap().to_le_bytes());
+    memory.data[0xFFFA..0xFFFC].copy_from_slice(&program.symbol("irq").unwrap().to_le_bytes());
+    cpu.program_counter = program.origin;
+    for _ in 0..20 {
+        cpu.step(&mut memory).unwrap();
+    }
+    (cpu, memory)
+}
+
+#[test]
+fn snapshot_round_trips_cpu_memory_and_interrupts() {
+    let (mut cpu, mut memory) = running_machine();
+    cpu.set_irq(true);
+    cpu.set_nmi(true);
+    let data: Vec<Byte> = snapshot::save(&cpu, &memory);
+
+    let (mut restored_cpu, mut restored_memory): (CPU, Memory) = common::setup();
+    snapshot::load(&data, &mut restored_cpu, &mut restored_memory).unwrap();
+    assert_eq!(restored_cpu.program_counter, cpu.program_counter);
+    assert_eq!(restored_cpu.register_x, cpu.register_x);
+    assert_eq!(restored_cpu.processor_status, cpu.processor_status);
+    assert_eq!(restored_cpu.cycles, cpu.cycles);
+    assert_eq!(restored_memory.data, memory.data);
+
+    // The pending NMI and the held IRQ line must come back too
+    for _ in 0..50 {
+        let step: Step = cpu.step(&mut memory).unwrap();
+        assert_eq!(restored_cpu.step(&mut restored_memory).unwrap(), step);
+    }
+    assert_eq!(restored_memory.data, memory.data);
+    assert_eq!(restored_cpu.cycles, cpu.cycles);
+    assert_ne!(memory.data[0x3000], 0);
+}
+
+#[test]
+fn snapshot_rejects_other_versions() {
+    let (cpu, memory) = running_machine();
+    let mut data: Vec<Byte> = snapshot::save(&cpu, &memory);
+    data[8..10].copy_from_slice(&(snapshot::VERSION + 1).to_le_bytes());
+    let (mut target_cpu, mut target_memory): (CPU, Memory) = common::setup();
+    assert_eq!(
+        snapshot::load(&data, &mut target_cpu, &mut target_memory),
+        Err(SnapshotError::UnsupportedVersion(snapshot::VERSION + 1))
+    );
+    assert_eq!(
+        snapshot::load(b"not a snapshot", &mut target_cpu, &mut target_memory),
+        Err(SnapshotError::NotASnapshot)
+    );
+}
+
+#[test]
+fn snapshot_rejects_truncated_data_without_changing_anything() {
+    let (cpu, memory) = running_machine();
+    let data: Vec<Byte> = snapshot::save(&cpu, &memory);
+    let (mut target_cpu, mut target_memory): (CPU, Memory) = common::setup();
+    target_memory.data[0x1234] = 0x56;
+    for length in [10, 11, 13, 21, 29, 33, data.len() - 1] {
+        assert_eq!(
+            snapshot::load(&data[..length], &mut target_cpu, &mut target_memory),
+            Err(SnapshotError::Truncated),
+            "length {}",
+            length
+        );
+    }
+    assert_eq!(target_cpu.program_counter, 0xFFFC);
+    assert_eq!(target_cpu.cycles, 0);
+    assert_eq!(target_memory.data[0x1234], 0x56);
+
+    let mut longer: Vec<Byte> = data.clone();
+    longer.push(0x00);
+    assert_eq!(
+        snapshot::load(&longer, &mut target_cpu, &mut target_memory),
+        Err(SnapshotError::TrailingData)
+    );
+}
+
+#[test]
+fn snapshot_restores_the_variant() {
+    let (mut cpu, memory) = running_machine();
+    cpu.variant = Variant::Wdc65C02;
+    let mut data: Vec<Byte> = snapshot::save(&cpu, &memory);
+    let (mut target_cpu, mut target_memory): (CPU, Memory) = common::setup();
+    snapshot::load(&data, &mut target_cpu, &mut target_memory).unwrap();
+    assert_eq!(target_cpu.variant, Variant::Wdc65C02);
+
+    data[10] = 0xFF;
+    let (mut target_cpu, mut target_memory): (CPU, Memory) = common::setup();
+    assert_eq!(
+        snapshot::load(&data, &mut target_cpu, &mut target_memory),
+        Err(SnapshotError::UnknownVariant(0xFF))
+    );
+    assert_eq!(target_cpu.variant, Variant::Nmos6502);
+}
+
+/// Memory with a free-running counter at $D000, standing in for a
+/// peripheral with state of its own.
+#[derive(Clone)]
+struct TimerBus {
+    memory: Memory,
+    timer: u16,
+}
+
+impl Bus for TimerBus {
+    fn read(&mut self, address: Word) -> Byte {
+        self.timer = self.timer.wrapping_add(1);
+        match address {
+            0xD000 => self.timer as Byte,
+            _ => self.memory.data[address as usize],
+        }
+    }
+
+    fn write(&mut self, address: Word, data: Byte) {
+        self.timer = self.timer.wrapping_add(1);
+        self.memory.data[address as usize] = data;
+    }
+
+    fn peek(&mut self, address: Word) -> Byte {
+        match address {
+            0xD000 => self.timer.wrapping_add(1) as Byte,
+            _ => self.memory.data[address as usize],
+        }
+    }
+}
+
+impl SaveState for TimerBus {
+    fn save_state(&self, output: &mut Vec<Byte>) {
+        self.memory.save_state(output);
+        output.extend_from_slice(&self.timer.to_le_bytes());
+    }
+
+    fn load_state(&mut self, data: &[Byte]) -> Result<(), SnapshotError> {
+        let (memory, timer) = data
+            .split_at_checked(0x10000)
+            .ok_or(SnapshotError::InvalidBusState)?;
+        let timer: [Byte; 2] = timer
+            .try_into()
+            .map_err(|_| SnapshotError::InvalidBusState)?;
+        self.memory.load_state(memory)?;
+        self.timer = u16::from_le_bytes(timer);
+        Ok(())
+    }
+}
+
+#[test]
+fn snapshot_includes_peripheral_state() {
+    let (mut cpu, memory) = running_machine();
+    let mut bus = TimerBus { memory, timer: 0 };
+    cpu.step(&mut bus).unwrap();
+    let data: Vec<Byte> = snapshot::save(&cpu, &bus);
+
+    let (mut restored_cpu, memory): (CPU, Memory) = common::setup();
+    let mut restored_bus = TimerBus { memory, timer: 0 };
+    snapshot::load(&data, &mut restored_cpu, &mut restored_bus).unwrap();
+    assert_eq!(restored_bus.timer, bus.timer);
+
+    // A plain Memory cannot take the timer's extra bytes
+    let (_, mut plain_memory): (CPU, Memory) = common::setup();
+    assert_eq!(
+        snapshot::load(&data, &mut restored_cpu, &mut plain_memory),
+        Err(SnapshotError::InvalidBusState)
+    );
+}
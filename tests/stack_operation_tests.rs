@@ -0,0 +1,80 @@
+use rust6502::{instructions::*, *};
+
+mod common;
+
+#[test]
+fn tsx_can_transfer_stack_pointer_to_register_x() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    memory.data[0xFFFC] = INSTRUCTION_TSX;
+    let cycles_used = cpu.execute(2, &mut memory);
+    assert_eq!(cycles_used, 2);
+    assert_eq!(cpu.register_x, 0xFF);
+    assert!(cpu.processor_status.negative());
+}
+
+#[test]
+fn txs_does_not_affect_processor_status() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    let cpu_copy = cpu;
+    cpu.register_x = 0x00;
+    memory.data[0xFFFC] = INSTRUCTION_TXS;
+    let cycles_used = cpu.execute(2, &mut memory);
+    assert_eq!(cycles_used, 2);
+    assert_eq!(cpu.stack_pointer, 0x00);
+    assert_eq!(cpu.processor_status, cpu_copy.processor_status);
+}
+
+#[test]
+fn pha_can_push_accumulator_onto_stack() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.accumulator = 0x42;
+    memory.data[0xFFFC] = INSTRUCTION_PHA;
+    let cycles_used = cpu.execute(3, &mut memory);
+    assert_eq!(cycles_used, 3);
+    assert_eq!(memory.data[0x01FF], 0x42);
+    assert_eq!(cpu.stack_pointer, 0xFE);
+}
+
+#[test]
+fn pla_can_pull_accumulator_from_stack() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.stack_pointer = 0xFE;
+    memory.data[0x01FF] = 0x00;
+    memory.data[0xFFFC] = INSTRUCTION_PLA;
+    let cycles_used = cpu.execute(4, &mut memory);
+    assert_eq!(cycles_used, 4);
+    assert_eq!(cpu.accumulator, 0x00);
+    assert_eq!(cpu.stack_pointer, 0xFF);
+    assert!(cpu.processor_status.zero());
+}
+
+#[test]
+fn php_pushes_status_with_break_and_unused_bits_set() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.processor_status.set_carry(true);
+    cpu.processor_status.set_negative(true);
+    memory.data[0xFFFC] = INSTRUCTION_PHP;
+    let cycles_used = cpu.execute(3, &mut memory);
+    assert_eq!(cycles_used, 3);
+    assert_eq!(memory.data[0x01FF], 0b10110001);
+    assert_eq!(cpu.stack_pointer, 0xFE);
+    assert!(!cpu.processor_status.r#break());
+}
+
+#[test]
+fn plp_can_pull_status_from_stack_ignoring_break_bit() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.stack_pointer = 0xFE;
+    memory.data[0x01FF] = 0b11011111;
+    memory.data[0xFFFC] = INSTRUCTION_PLP;
+    let cycles_used = cpu.execute(4, &mut memory);
+    assert_eq!(cycles_used, 4);
+    assert_eq!(cpu.stack_pointer, 0xFF);
+    assert!(cpu.processor_status.carry());
+    assert!(cpu.processor_status.zero());
+    assert!(cpu.processor_status.interrupt());
+    assert!(cpu.processor_status.decimal());
+    assert!(!cpu.processor_status.r#break());
+    assert!(cpu.processor_status.overflow());
+    assert!(cpu.processor_status.negative());
+}
@@ -0,0 +1,47 @@
+use rust6502::{instructions::*, *};
+
+mod common;
+
+fn change_status_flag(opcode: Byte, flag: fn(&ProcessorStatus) -> bool, expected: bool) {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.processor_status = ProcessorStatus(if expected { 0x00 } else { 0xFF });
+    memory.data[0xFFFC] = opcode;
+    let cycles_used = cpu.execute(2, &mut memory);
+    assert_eq!(cycles_used, 2);
+    assert_eq!(flag(&cpu.processor_status), expected);
+}
+
+#[test]
+fn clc_can_clear_carry_flag() {
+    change_status_flag(INSTRUCTION_CLC, ProcessorStatus::carry, false);
+}
+
+#[test]
+fn cld_can_clear_decimal_flag() {
+    change_status_flag(INSTRUCTION_CLD, ProcessorStatus::decimal, false);
+}
+
+#[test]
+fn cli_can_clear_interrupt_flag() {
+    change_status_flag(INSTRUCTION_CLI, ProcessorStatus::interrupt, false);
+}
+
+#[test]
+fn clv_can_clear_overflow_flag() {
+    change_status_flag(INSTRUCTION_CLV, ProcessorStatus::overflow, false);
+}
+
+#[test]
+fn sec_can_set_carry_flag() {
+    change_status_flag(INSTRUCTION_SEC, ProcessorStatus::carry, true);
+}
+
+#[test]
+fn sed_can_set_decimal_flag() {
+    change_status_flag(INSTRUCTION_SED, ProcessorStatus::decimal, true);
+}
+
+#[test]
+fn sei_can_set_interrupt_flag() {
+    change_status_flag(INSTRUCTION_SEI, ProcessorStatus::interrupt, true);
+}
@@ -0,0 +1,101 @@
+use rust6502::{instructions::*, *};
+
+mod common;
+
+#[test]
+fn step_executes_exactly_one_instruction() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.program_counter = 0x8000;
+    memory.data[0x8000] = INSTRUCTION_LDA_IMM;
+    memory.data[0x8001] = 0x42;
+    memory.data[0x8002] = INSTRUCTION_LDX_IMM;
+    memory.data[0x8003] = 0x37;
+    let step: Step = cpu.step(&mut memory).unwrap();
+    assert_eq!(cpu.accumulator, 0x42);
+    assert_eq!(cpu.register_x, 0x00);
+    assert_eq!(cpu.program_counter, 0x8002);
+    assert_eq!(
+        step,
+        Step {
+            address: 0x8000,
+            opcode: INSTRUCTION_LDA_IMM,
+            instruction: Some(Instruction {
+                mnemonic: Mnemonic::LDA,
+                addressing_mode: AddressingMode::Immediate,
+            }),
+            operand: Some(0x42),
+            effective_address: None,
+            cycles: 2,
+            page_crossed: false,
+            interrupt: None,
+        }
+    );
+}
+
+#[test]
+fn step_reports_effective_address_and_page_cross() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.program_counter = 0x8000;
+    cpu.register_y = 0x01;
+    memory.data[0x8000] = INSTRUCTION_LDA_INDR_Y;
+    memory.data[0x8001] = 0x10;
+    memory.data[0x0010] = 0xFF;
+    memory.data[0x0011] = 0x44;
+    let step: Step = cpu.step(&mut memory).unwrap();
+    assert_eq!(step.operand, Some(0x10));
+    assert_eq!(step.effective_address, Some(0x4500));
+    assert!(step.page_crossed);
+    assert_eq!(step.cycles, 6);
+}
+
+#[test]
+fn step_reports_absolute_operand_as_word() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.program_counter = 0x8000;
+    cpu.register_x = 0x01;
+    memory.data[0x8000] = INSTRUCTION_STA_ABS_X;
+    memory.data[0x8001] = 0x00;
+    memory.data[0x8002] = 0x90;
+    let step: Step = cpu.step(&mut memory).unwrap();
+    assert_eq!(step.operand, Some(0x9000));
+    assert_eq!(step.effective_address, Some(0x9001));
+    assert!(!step.page_crossed);
+    assert_eq!(step.cycles, 5);
+}
+
+#[test]
+fn step_reports_branch_target() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.program_counter = 0x80FD;
+    memory.data[0x80FD] = INSTRUCTION_BNE;
+    memory.data[0x80FE] = 0x02;
+    let step: Step = cpu.step(&mut memory).unwrap();
+    assert_eq!(step.operand, Some(0x02));
+    assert_eq!(step.effective_address, Some(0x8101));
+    assert!(step.page_crossed);
+    assert_eq!(step.cycles, 4);
+    assert_eq!(cpu.program_counter, 0x8101);
+}
+
+#[test]
+fn step_reports_interrupt_sequence_as_its_own_step() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.program_counter = 0x8000;
+    memory.data[0xFFFA] = 0x00;
+    memory.data[0xFFFB] = 0x90;
+    cpu.set_nmi(true);
+    let step: Step = cpu.step(&mut memory).unwrap();
+    assert_eq!(step.address, 0x8000);
+    assert_eq!(step.opcode, INSTRUCTION_BRK);
+    assert_eq!(step.interrupt, Some(Interrupt::Nmi));
+    assert_eq!(step.cycles, 7);
+    assert_eq!(cpu.program_counter, 0x9000);
+}
+
+#[test]
+fn step_returns_error_on_illegal_opcode() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    memory.data[0xFFFC] = 0xFF;
+    let error = cpu.step(&mut memory).unwrap_err();
+    assert_eq!(error.opcode(), 0xFF);
+}
@@ -22,7 +22,7 @@ fn sta_absolute_can_store_accumulator_into_memory() {
 #[test]
 fn sta_absolute_with_offset_x_can_store_accumulator_into_memory() {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy = cpu.clone();
+    let cpu_copy = cpu;
     cpu.accumulator = 0x42;
     cpu.register_x = 0x0F;
     memory.data[0xFFFC] = INSTRUCTION_STA_ABS_X;
@@ -37,7 +37,7 @@ fn sta_absolute_with_offset_x_can_store_accumulator_into_memory() {
 #[test]
 fn sta_absolute_with_offset_y_can_store_accumulator_into_memory() {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy = cpu.clone();
+    let cpu_copy = cpu;
     cpu.accumulator = 0x42;
     cpu.register_y = 0x0F;
     memory.data[0xFFFC] = INSTRUCTION_STA_ABS_Y;
@@ -52,7 +52,7 @@ fn sta_absolute_with_offset_y_can_store_accumulator_into_memory() {
 #[test]
 fn sta_indirect_x_can_store_accumulator_into_memory() {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy = cpu.clone();
+    let cpu_copy = cpu;
     cpu.accumulator = 0x42;
     cpu.register_x = 0x0F;
     memory.data[0xFFFC] = INSTRUCTION_STA_INDR_X;
@@ -70,7 +70,7 @@ fn sta_indirect_x_can_store_accumulator_into_memory() {
 #[test]
 fn sta_indirect_y_can_store_accumulator_into_memory() {
     let (mut cpu, mut memory): (CPU, Memory) = common::setup();
-    let cpu_copy = cpu.clone();
+    let cpu_copy = cpu;
     cpu.accumulator = 0x42;
     cpu.register_y = 0x0F;
     memory.data[0xFFFC] = INSTRUCTION_STA_INDR_Y;
@@ -0,0 +1,50 @@
+use rust6502::{instructions::*, *};
+
+mod common;
+
+#[test]
+fn nop_does_nothing_for_two_cycles() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    let cpu_copy = cpu;
+    memory.data[0xFFFC] = INSTRUCTION_NOP;
+    let cycles_used = cpu.execute(2, &mut memory);
+    assert_eq!(cycles_used, 2);
+    assert_eq!(cpu.program_counter, 0xFFFD);
+    assert_eq!(cpu.processor_status, cpu_copy.processor_status);
+}
+
+#[test]
+fn brk_jumps_through_irq_vector() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.program_counter = 0xFF00;
+    cpu.processor_status.set_carry(true);
+    memory.data[0xFF00] = INSTRUCTION_BRK;
+    memory.data[0xFFFE] = 0x00;
+    memory.data[0xFFFF] = 0x80;
+    let cycles_used = cpu.execute(7, &mut memory);
+    assert_eq!(cycles_used, 7);
+    assert_eq!(cpu.program_counter, 0x8000);
+    assert_eq!(cpu.stack_pointer, 0xFC);
+    assert_eq!(memory.data[0x01FF], 0xFF);
+    assert_eq!(memory.data[0x01FE], 0x02);
+    assert_eq!(memory.data[0x01FD], 0b00110001);
+    assert!(cpu.processor_status.interrupt());
+}
+
+#[test]
+fn rti_returns_from_brk() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.program_counter = 0xFF00;
+    cpu.processor_status.set_carry(true);
+    memory.data[0xFF00] = INSTRUCTION_BRK;
+    memory.data[0xFFFE] = 0x00;
+    memory.data[0xFFFF] = 0x80;
+    memory.data[0x8000] = INSTRUCTION_RTI;
+    let cycles_used = cpu.execute(13, &mut memory);
+    assert_eq!(cycles_used, 13);
+    assert_eq!(cpu.program_counter, 0xFF02);
+    assert_eq!(cpu.stack_pointer, 0xFF);
+    assert!(cpu.processor_status.carry());
+    assert!(!cpu.processor_status.interrupt());
+    assert!(!cpu.processor_status.r#break());
+}
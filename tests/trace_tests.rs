@@ -0,0 +1,142 @@
+use rust6502::{instructions::*, trace::*, *};
+
+mod common;
+
+use common::recording_bus::RecordingBus;
+
+/// The first lines of nestest.log, which starts at $C000 with the CPU just
+/// out of RESET.
+const NESTEST_LOG: &str = "\
+C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7
+C5F5  A2 00     LDX #$00                        A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 30 CYC:10
+C5F7  86 00     STX $00 = 00                    A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 39 CYC:12
+C5F9  86 10     STX $10 = 00                    A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 48 CYC:15
+C5FB  86 11     STX $11 = 00                    A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 57 CYC:18
+C5FD  20 2D C7  JSR $C72D                       A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 66 CYC:21
+";
+
+fn nestest_start() -> (CPU, Memory) {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    memory.data[0xC000..0xC003].copy_from_slice(&[0x4C, 0xF5, 0xC5]);
+    memory.data[0xC5F5..0xC600].copy_from_slice(&[
+        0xA2, 0x00, 0x86, 0x00, 0x86, 0x10, 0x86, 0x11, 0x20, 0x2D, 0xC7,
+    ]);
+    cpu.program_counter = 0xC000;
+    cpu.stack_pointer = 0xFD;
+    cpu.processor_status = ProcessorStatus(0x24);
+    (cpu, memory)
+}
+
+fn trace(cpu: &mut CPU, memory: &mut Memory, instructions: usize) -> String {
+    let mut tracer: Tracer<Vec<u8>> = Tracer::new(Vec::new());
+    tracer.cycles = 7;
+    for _ in 0..instructions {
+        tracer.step(cpu, memory).unwrap();
+    }
+    String::from_utf8(tracer.into_inner()).unwrap()
+}
+
+#[test]
+fn tracer_writes_nestest_lines() {
+    let (mut cpu, mut memory) = nestest_start();
+    let output: String = trace(&mut cpu, &mut memory, 6);
+    assert_eq!(
+        output.lines().next().unwrap(),
+        "C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD CYC:7"
+    );
+    assert_eq!(compare_traces(&output, NESTEST_LOG), None);
+}
+
+#[test]
+fn trace_line_shows_operand_addresses_and_values() {
+    let cases: [(&[Byte], &str); 8] = [
+        (&[INSTRUCTION_LDA_ZERO, 0x80], "LDA $80 = 11"),
+        (&[INSTRUCTION_LDA_ZERO_X, 0x7E], "LDA $7E,X @ 80 = 11"),
+        (&[INSTRUCTION_LDA_ABS, 0x00, 0x03], "LDA $0300 = 89"),
+        (
+            &[INSTRUCTION_LDA_ABS_Y, 0xFD, 0x02],
+            "LDA $02FD,Y @ 02FF = 77",
+        ),
+        (
+            &[INSTRUCTION_LDA_INDR_X, 0x7E],
+            "LDA ($7E,X) @ 80 = 0311 = 5A",
+        ),
+        (
+            &[INSTRUCTION_LDA_INDR_Y, 0x80],
+            "LDA ($80),Y = 0311 @ 0313 = 24",
+        ),
+        (&[INSTRUCTION_JMP_INDR, 0xFF, 0x02], "JMP ($02FF) = 0377"),
+        (&[INSTRUCTION_JMP_ABS, 0x00, 0x03], "JMP $0300"),
+    ];
+    for (bytes, text) in cases {
+        let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+        cpu.program_counter = 0x0400;
+        cpu.register_x = 0x02;
+        cpu.register_y = 0x02;
+        memory.data[0x0400..0x0400 + bytes.len()].copy_from_slice(bytes);
+        memory.data[0x0080] = 0x11;
+        memory.data[0x0081] = 0x03;
+        memory.data[0x0200] = 0x03;
+        memory.data[0x02FF] = 0x77;
+        memory.data[0x0300] = 0x89;
+        memory.data[0x0311] = 0x5A;
+        memory.data[0x0313] = 0x24;
+        let line: String = trace_line(&cpu, &mut memory, 0);
+        assert_eq!(line[16..48].trim_end(), text);
+    }
+}
+
+#[test]
+fn trace_line_marks_undocumented_opcodes() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.program_counter = 0xC6BD;
+    memory.data[0xC6BD] = 0x04;
+    memory.data[0xC6BE] = 0xA9;
+    let line: String = trace_line(&cpu, &mut memory, 0);
+    assert!(line.starts_with("C6BD  04 A9    *NOP $A9 = 00"));
+}
+
+#[test]
+fn trace_line_does_not_touch_the_bus() {
+    let mut cpu: CPU = CPU::at_reset_vector_unsequenced();
+    let mut bus: RecordingBus = RecordingBus::new();
+    cpu.program_counter = 0x0400;
+    bus.memory.data[0x0400..0x0402].copy_from_slice(&[INSTRUCTION_LDA_INDR_Y, 0x80]);
+    bus.memory.data[0x0080] = 0x11;
+    bus.memory.data[0x0081] = 0x03;
+    let line: String = trace_line(&cpu, &mut bus, 0);
+    assert_eq!(line[16..48].trim_end(), "LDA ($80),Y = 0311 @ 0311 = 00");
+    assert!(bus.accesses.is_empty());
+}
+
+#[test]
+fn trace_line_shows_the_6510_port() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.variant = Variant::Mos6510;
+    cpu.program_counter = 0x0400;
+    memory.data[0x0400..0x0402].copy_from_slice(&[INSTRUCTION_LDA_ZERO, 0x01]);
+    memory.data[0x0001] = 0xAA;
+    let line: String = trace_line(&cpu, &mut memory, 0);
+    assert_eq!(line[16..48].trim_end(), "LDA $01 = 17");
+}
+
+#[test]
+fn compare_traces_stops_at_the_first_difference() {
+    let (mut cpu, mut memory) = nestest_start();
+    cpu.register_y = 0x01;
+    let output: String = trace(&mut cpu, &mut memory, 6);
+    let divergence: Divergence = compare_traces(&output, NESTEST_LOG).unwrap();
+    assert_eq!(divergence.line, 1);
+    assert_eq!(divergence.expected.as_deref(), NESTEST_LOG.lines().next());
+    assert_eq!(divergence.actual.as_deref(), output.lines().next());
+}
+
+#[test]
+fn compare_traces_reports_a_trace_that_ends_early() {
+    let (mut cpu, mut memory) = nestest_start();
+    let output: String = trace(&mut cpu, &mut memory, 4);
+    let divergence: Divergence = compare_traces(&output, NESTEST_LOG).unwrap();
+    assert_eq!(divergence.line, 5);
+    assert_eq!(divergence.actual, None);
+    assert!(divergence.to_string().contains("<end of trace>"));
+}
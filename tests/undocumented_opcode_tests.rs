@@ -0,0 +1,356 @@
+use rust6502::{instructions::*, *};
+
+mod common;
+
+fn setup() -> (CPU, Memory) {
+    let (mut cpu, memory): (CPU, Memory) = common::setup();
+    cpu.undocumented_opcodes = Some(UndocumentedOpcodes::default());
+    (cpu, memory)
+}
+
+#[test]
+fn undocumented_opcodes_are_off_by_default() {
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    memory.data[0xFFFC] = 0xA7;
+    let error = cpu.try_execute(3, &mut memory).unwrap_err();
+    assert!(matches!(error, ExecutionError::IllegalOpcode { .. }));
+}
+
+#[test]
+fn every_undocumented_opcode_takes_its_listed_cycles() {
+    for info in OPCODES.iter().filter(|info| !info.documented) {
+        if matches!(info.mnemonic, Mnemonic::JAM) {
+            continue;
+        }
+        let (mut cpu, mut memory): (CPU, Memory) = setup();
+        cpu.program_counter = 0x0400;
+        memory.data[0x0400] = info.opcode;
+        let step: Step = cpu.step(&mut memory).unwrap();
+        assert_eq!(step.cycles, info.cycles as i32, "{:02X}", info.opcode);
+        assert_eq!(
+            cpu.program_counter,
+            0x0400 + info.length as Word,
+            "{:02X}",
+            info.opcode
+        );
+    }
+}
+
+#[test]
+fn jam_halts_and_stays_halted_until_reset() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.unknown_opcode_policy = UnknownOpcodePolicy::Nop;
+    memory.data[0xFFFC] = 0x02;
+    for _ in 0..2 {
+        let error = cpu.step(&mut memory).unwrap_err();
+        assert!(matches!(error, ExecutionError::Jam { .. }));
+        assert_eq!(cpu.program_counter, 0xFFFC);
+    }
+    memory.data[0xFFFC] = 0x00;
+    memory.data[0xFFFD] = 0x80;
+    memory.data[0x8000] = INSTRUCTION_NOP;
+    cpu.trigger_reset();
+    assert_eq!(
+        cpu.step(&mut memory).unwrap().interrupt,
+        Some(Interrupt::Reset)
+    );
+    assert_eq!(cpu.step(&mut memory).unwrap().opcode, INSTRUCTION_NOP);
+}
+
+#[test]
+fn lax_loads_accumulator_and_x() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.register_y = 0x01;
+    // LAX abs,Y with a page crossing
+    memory.data[0xFFFC] = 0xBF;
+    memory.data[0xFFFD] = 0xFF;
+    memory.data[0xFFFE] = 0x20;
+    memory.data[0x2100] = 0x80;
+    let cycles_used = cpu.execute(5, &mut memory);
+    assert_eq!(cycles_used, 5);
+    assert_eq!(cpu.accumulator, 0x80);
+    assert_eq!(cpu.register_x, 0x80);
+    assert!(cpu.processor_status.negative());
+}
+
+#[test]
+fn sax_stores_accumulator_and_x_without_flags() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0b11110000;
+    cpu.register_x = 0b00111100;
+    memory.data[0xFFFC] = 0x87;
+    memory.data[0xFFFD] = 0x42;
+    let cycles_used = cpu.execute(3, &mut memory);
+    assert_eq!(cycles_used, 3);
+    assert_eq!(memory.data[0x0042], 0b00110000);
+    assert_eq!(cpu.processor_status.0, 0x00);
+}
+
+#[test]
+fn dcp_decrements_memory_and_compares() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0x41;
+    memory.data[0xFFFC] = 0xC7;
+    memory.data[0xFFFD] = 0x42;
+    memory.data[0x0042] = 0x42;
+    let cycles_used = cpu.execute(5, &mut memory);
+    assert_eq!(cycles_used, 5);
+    assert_eq!(memory.data[0x0042], 0x41);
+    assert!(cpu.processor_status.zero());
+    assert!(cpu.processor_status.carry());
+}
+
+#[test]
+fn isc_increments_memory_and_subtracts() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0x10;
+    cpu.processor_status.set_carry(true);
+    memory.data[0xFFFC] = 0xE7;
+    memory.data[0xFFFD] = 0x42;
+    memory.data[0x0042] = 0x0F;
+    cpu.execute(5, &mut memory);
+    assert_eq!(memory.data[0x0042], 0x10);
+    assert_eq!(cpu.accumulator, 0x00);
+    assert!(cpu.processor_status.zero());
+    assert!(cpu.processor_status.carry());
+}
+
+#[test]
+fn isc_subtracts_in_decimal_mode() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0x20;
+    cpu.processor_status.set_carry(true);
+    cpu.processor_status.set_decimal(true);
+    memory.data[0xFFFC] = 0xE7;
+    memory.data[0xFFFD] = 0x42;
+    memory.data[0x0042] = 0x08;
+    cpu.execute(5, &mut memory);
+    assert_eq!(cpu.accumulator, 0x11);
+}
+
+#[test]
+fn slo_shifts_memory_and_ors_into_accumulator() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0b00000001;
+    memory.data[0xFFFC] = 0x07;
+    memory.data[0xFFFD] = 0x42;
+    memory.data[0x0042] = 0b11000000;
+    cpu.execute(5, &mut memory);
+    assert_eq!(memory.data[0x0042], 0b10000000);
+    assert_eq!(cpu.accumulator, 0b10000001);
+    assert!(cpu.processor_status.carry());
+    assert!(cpu.processor_status.negative());
+}
+
+#[test]
+fn rla_rotates_memory_and_ands_into_accumulator() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0b00000011;
+    cpu.processor_status.set_carry(true);
+    memory.data[0xFFFC] = 0x27;
+    memory.data[0xFFFD] = 0x42;
+    memory.data[0x0042] = 0b10000000;
+    cpu.execute(5, &mut memory);
+    assert_eq!(memory.data[0x0042], 0b00000001);
+    assert_eq!(cpu.accumulator, 0b00000001);
+    assert!(cpu.processor_status.carry());
+}
+
+#[test]
+fn sre_shifts_memory_and_eors_into_accumulator() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0b00000001;
+    memory.data[0xFFFC] = 0x47;
+    memory.data[0xFFFD] = 0x42;
+    memory.data[0x0042] = 0b00000011;
+    cpu.execute(5, &mut memory);
+    assert_eq!(memory.data[0x0042], 0b00000001);
+    assert_eq!(cpu.accumulator, 0x00);
+    assert!(cpu.processor_status.carry());
+    assert!(cpu.processor_status.zero());
+}
+
+#[test]
+fn rra_rotates_memory_and_adds_with_the_new_carry() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0x10;
+    memory.data[0xFFFC] = 0x67;
+    memory.data[0xFFFD] = 0x42;
+    memory.data[0x0042] = 0x03;
+    cpu.execute(5, &mut memory);
+    // $03 rotates to $01 with carry set, then $10 + $01 + 1
+    assert_eq!(memory.data[0x0042], 0x01);
+    assert_eq!(cpu.accumulator, 0x12);
+    assert!(!cpu.processor_status.carry());
+}
+
+#[test]
+fn anc_copies_negative_into_carry() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0xFF;
+    memory.data[0xFFFC] = 0x0B;
+    memory.data[0xFFFD] = 0x80;
+    cpu.execute(2, &mut memory);
+    assert_eq!(cpu.accumulator, 0x80);
+    assert!(cpu.processor_status.carry());
+    assert!(cpu.processor_status.negative());
+}
+
+#[test]
+fn alr_ands_then_shifts_right() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0xFF;
+    memory.data[0xFFFC] = 0x4B;
+    memory.data[0xFFFD] = 0x03;
+    cpu.execute(2, &mut memory);
+    assert_eq!(cpu.accumulator, 0x01);
+    assert!(cpu.processor_status.carry());
+}
+
+#[test]
+fn arr_takes_carry_and_overflow_from_bits_six_and_five() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0xFF;
+    cpu.processor_status.set_carry(true);
+    memory.data[0xFFFC] = 0x6B;
+    memory.data[0xFFFD] = 0x40;
+    cpu.execute(2, &mut memory);
+    // $40 rotates to $A0: bit 6 clear, bit 5 set
+    assert_eq!(cpu.accumulator, 0xA0);
+    assert!(!cpu.processor_status.carry());
+    assert!(cpu.processor_status.overflow());
+    assert!(cpu.processor_status.negative());
+}
+
+#[test]
+fn arr_applies_decimal_correction() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0xFF;
+    cpu.processor_status.set_decimal(true);
+    memory.data[0xFFFC] = 0x6B;
+    memory.data[0xFFFD] = 0xFF;
+    cpu.execute(2, &mut memory);
+    // $FF rotates to $7F, then both nibbles are corrected
+    assert_eq!(cpu.accumulator, 0xD5);
+    assert!(cpu.processor_status.carry());
+    assert!(!cpu.processor_status.negative());
+    assert!(!cpu.processor_status.overflow());
+}
+
+#[test]
+fn sbx_subtracts_from_accumulator_and_x_into_x() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0xF0;
+    cpu.register_x = 0x3C;
+    cpu.processor_status.set_decimal(true);
+    memory.data[0xFFFC] = 0xCB;
+    memory.data[0xFFFD] = 0x31;
+    cpu.execute(2, &mut memory);
+    assert_eq!(cpu.register_x, 0xFF);
+    assert_eq!(cpu.accumulator, 0xF0);
+    assert!(!cpu.processor_status.carry());
+    assert!(cpu.processor_status.negative());
+}
+
+#[test]
+fn xaa_and_lxa_use_the_magic_constant() {
+    for magic in [0x00, 0xEE, 0xFF] {
+        let (mut cpu, mut memory): (CPU, Memory) = setup();
+        cpu.undocumented_opcodes = Some(UndocumentedOpcodes { magic });
+        cpu.accumulator = 0x01;
+        cpu.register_x = 0x1F;
+        memory.data[0xFFFC] = 0x8B;
+        memory.data[0xFFFD] = 0xF3;
+        memory.data[0xFFFE] = 0xAB;
+        memory.data[0xFFFF] = 0x3C;
+        cpu.execute(2, &mut memory);
+        assert_eq!(cpu.accumulator, (0x01 | magic) & 0x1F & 0xF3);
+        let accumulator: Byte = cpu.accumulator;
+        cpu.execute(2, &mut memory);
+        assert_eq!(cpu.accumulator, (accumulator | magic) & 0x3C);
+        assert_eq!(cpu.register_x, cpu.accumulator);
+    }
+}
+
+#[test]
+fn las_ands_memory_with_stack_pointer() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.stack_pointer = 0xF3;
+    memory.data[0xFFFC] = 0xBB;
+    memory.data[0xFFFD] = 0x00;
+    memory.data[0xFFFE] = 0x20;
+    memory.data[0x2000] = 0x3F;
+    cpu.execute(4, &mut memory);
+    assert_eq!(cpu.accumulator, 0x33);
+    assert_eq!(cpu.register_x, 0x33);
+    assert_eq!(cpu.stack_pointer, 0x33);
+}
+
+#[test]
+fn sha_ands_with_high_byte_plus_one() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0xFF;
+    cpu.register_x = 0xF7;
+    cpu.register_y = 0x10;
+    // SHA abs,Y at $1200,Y stores A & X & $13
+    memory.data[0xFFFC] = 0x9F;
+    memory.data[0xFFFD] = 0x00;
+    memory.data[0xFFFE] = 0x12;
+    let cycles_used = cpu.execute(5, &mut memory);
+    assert_eq!(cycles_used, 5);
+    assert_eq!(memory.data[0x1210], 0x13);
+}
+
+#[test]
+fn shy_page_crossing_replaces_the_high_address_byte() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.register_x = 0x20;
+    cpu.register_y = 0x0F;
+    // $12F0,X crosses into $1310, so the value $0F & $13 = $03 is written
+    // to $0310 instead
+    memory.data[0xFFFC] = 0x9C;
+    memory.data[0xFFFD] = 0xF0;
+    memory.data[0xFFFE] = 0x12;
+    let step: Step = cpu.step(&mut memory).unwrap();
+    assert_eq!(step.effective_address, Some(0x0310));
+    assert_eq!(memory.data[0x0310], 0x03);
+    assert_eq!(memory.data[0x1310], 0x00);
+}
+
+#[test]
+fn tas_sets_stack_pointer_and_stores() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0xF0;
+    cpu.register_x = 0x3F;
+    memory.data[0xFFFC] = 0x9B;
+    memory.data[0xFFFD] = 0x00;
+    memory.data[0xFFFE] = 0x2F;
+    cpu.execute(5, &mut memory);
+    assert_eq!(cpu.stack_pointer, 0x30);
+    assert_eq!(memory.data[0x2F00], 0x30);
+}
+
+#[test]
+fn unofficial_nops_read_their_operand() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.register_x = 0x01;
+    // NOP abs,X takes an extra cycle when indexing crosses a page
+    memory.data[0xFFFC] = 0x1C;
+    memory.data[0xFFFD] = 0xFF;
+    memory.data[0xFFFE] = 0x20;
+    let step: Step = cpu.step(&mut memory).unwrap();
+    assert_eq!(step.cycles, 5);
+    assert_eq!(step.effective_address, Some(0x2100));
+}
+
+#[test]
+fn sbc_eb_matches_the_documented_sbc() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0x50;
+    cpu.processor_status.set_carry(true);
+    memory.data[0xFFFC] = 0xEB;
+    memory.data[0xFFFD] = 0x10;
+    cpu.execute(2, &mut memory);
+    assert_eq!(cpu.accumulator, 0x40);
+    assert!(cpu.processor_status.carry());
+}
@@ -0,0 +1,433 @@
+use rust6502::{instructions::*, wdc65816::*, *};
+
+fn setup() -> (CPU65816, LongMemory) {
+    (CPU65816::reset(), LongMemory::reset())
+}
+
+/// Switches to native mode with CLC, XCE and then REP to make the given
+/// registers 16 bits wide.
+fn setup_native(rep: Byte) -> (CPU65816, LongMemory) {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup();
+    memory.data[0xFFFC..0xFFFF].copy_from_slice(&[0x18, 0xFB, 0xC2]);
+    memory.data[0xFFFF] = rep;
+    cpu.execute(7, &mut memory);
+    cpu.program_counter = 0x0200;
+    cpu.processor_status.set_carry(false);
+    (cpu, memory)
+}
+
+fn load(memory: &mut LongMemory, address: LongAddress, program: &[Byte]) {
+    let start: usize = address as usize;
+    memory.data[start..start + program.len()].copy_from_slice(program);
+}
+
+#[test]
+fn opcode_table_is_indexed_by_opcode() {
+    for (index, info) in OPCODES_65816.iter().enumerate() {
+        assert_eq!(info.opcode as usize, index);
+        assert_eq!(
+            info.length,
+            1 + info.addressing_mode.operand_length(),
+            "opcode {:#04x}",
+            info.opcode
+        );
+    }
+    assert!(OPCODES_65816.iter().all(|info| info.documented));
+    assert_eq!(
+        OPCODES_65816[0xAF].addressing_mode,
+        AddressingMode::AbsoluteLong
+    );
+    assert_eq!(OPCODES_65816[0xB7].mnemonic, Mnemonic::LDA);
+    assert_eq!(OPCODES_65816[0x83].mnemonic, Mnemonic::STA);
+}
+
+#[test]
+fn every_opcode_takes_its_listed_cycles_in_emulation_mode() {
+    for info in OPCODES_65816.iter() {
+        let (mut cpu, mut memory): (CPU65816, LongMemory) = setup();
+        cpu.program_counter = 0x0200;
+        load(&mut memory, 0x0200, &[info.opcode, 0x02, 0x12, 0x00]);
+        let cycles: i32 = cpu.step(&mut memory);
+        let branch_taken: bool = info.addressing_mode == AddressingMode::Relative
+            && cpu.program_counter != 0x0200 + info.length as Word;
+        assert_eq!(
+            cycles,
+            info.cycles as i32 + branch_taken as i32,
+            "opcode {:#04x}",
+            info.opcode
+        );
+    }
+}
+
+#[test]
+fn reset_enters_emulation_mode() {
+    let mut cpu: CPU65816 = CPU65816::new();
+    let mut memory: LongMemory = LongMemory::reset();
+    cpu.emulation = false;
+    cpu.direct_page = 0x1234;
+    cpu.data_bank = 0x7E;
+    cpu.program_bank = 0x12;
+    cpu.stack_pointer = 0x1FF0;
+    memory.data[0xFFFC] = 0x00;
+    memory.data[0xFFFD] = 0x80;
+    let cycles: i32 = cpu.step(&mut memory);
+    assert_eq!(cycles, 7);
+    assert!(cpu.emulation);
+    assert_eq!(cpu.program_address(), 0x008000);
+    assert_eq!(cpu.direct_page, 0x0000);
+    assert_eq!(cpu.data_bank, 0x00);
+    assert_eq!(cpu.stack_pointer, 0x01ED);
+    assert!(cpu.processor_status.memory_8bit());
+    assert!(cpu.processor_status.index_8bit());
+    assert!(cpu.processor_status.interrupt());
+}
+
+#[test]
+fn xce_switches_between_emulation_and_native_mode() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup();
+    // CLC, XCE, REP #$30, SEC, XCE
+    load(&mut memory, 0xFFFC, &[0x18, 0xFB, 0xC2, 0x30]);
+    cpu.execute(7, &mut memory);
+    assert!(!cpu.emulation);
+    assert!(cpu.processor_status.carry());
+    assert!(!cpu.processor_status.memory_8bit());
+    assert!(!cpu.processor_status.index_8bit());
+
+    cpu.register_x = 0x1234;
+    cpu.stack_pointer = 0x1FFF;
+    cpu.program_counter = 0x0200;
+    load(&mut memory, 0x0200, &[0x38, 0xFB]);
+    cpu.execute(4, &mut memory);
+    assert!(cpu.emulation);
+    assert!(!cpu.processor_status.carry());
+    assert!(cpu.processor_status.memory_8bit());
+    assert_eq!(cpu.register_x, 0x0034);
+    assert_eq!(cpu.stack_pointer, 0x01FF);
+}
+
+#[test]
+fn rep_widens_immediate_operands() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup_native(0x30);
+    // LDA #$1234, LDX #$8000
+    load(&mut memory, 0x0200, &[0xA9, 0x34, 0x12, 0xA2, 0x00, 0x80]);
+    let cycles: i32 = cpu.execute(6, &mut memory);
+    assert_eq!(cycles, 6);
+    assert_eq!(cpu.accumulator, 0x1234);
+    assert_eq!(cpu.register_x, 0x8000);
+    assert!(cpu.processor_status.negative());
+    assert_eq!(cpu.program_counter, 0x0206);
+}
+
+#[test]
+fn an_8bit_accumulator_keeps_its_high_byte() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup_native(0x30);
+    // LDA #$1234, SEP #$20, LDA #$FF, XBA
+    load(
+        &mut memory,
+        0x0200,
+        &[0xA9, 0x34, 0x12, 0xE2, 0x20, 0xA9, 0xFF, 0xEB],
+    );
+    for _ in 0..3 {
+        cpu.step(&mut memory);
+    }
+    assert_eq!(cpu.accumulator, 0x12FF);
+    assert!(cpu.processor_status.negative());
+    let cycles: i32 = cpu.step(&mut memory);
+    assert_eq!(cycles, 3);
+    assert_eq!(cpu.accumulator, 0xFF12);
+    assert!(!cpu.processor_status.negative());
+}
+
+#[test]
+fn setting_x_clears_the_high_bytes_of_the_index_registers() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup_native(0x10);
+    cpu.register_x = 0x1234;
+    cpu.register_y = 0xABCD;
+    load(&mut memory, 0x0200, &[0xE2, 0x10, 0xE8]);
+    cpu.execute(5, &mut memory);
+    assert_eq!(cpu.register_x, 0x0035);
+    assert_eq!(cpu.register_y, 0x00CD);
+}
+
+#[test]
+fn sixteen_bit_data_takes_an_extra_cycle_per_byte() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup_native(0x20);
+    cpu.accumulator = 0xBEEF;
+    // STA $10, INC $10
+    load(&mut memory, 0x0200, &[0x85, 0x10, 0xE6, 0x10]);
+    assert_eq!(cpu.step(&mut memory), 4);
+    assert_eq!(memory.data[0x0010..0x0012], [0xEF, 0xBE]);
+    assert_eq!(cpu.step(&mut memory), 7);
+    assert_eq!(memory.data[0x0010..0x0012], [0xF0, 0xBE]);
+}
+
+#[test]
+fn direct_page_relocates_the_zero_page() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup_native(0x00);
+    cpu.direct_page = 0x2001;
+    memory.data[0x2011] = 0x42;
+    load(&mut memory, 0x0200, &[0xA5, 0x10]);
+    let cycles: i32 = cpu.step(&mut memory);
+    assert_eq!(cpu.accumulator & 0xFF, 0x42);
+    // One cycle more because the direct page is not page aligned
+    assert_eq!(cycles, 4);
+
+    // TCD moves the full accumulator
+    cpu.accumulator = 0x3000;
+    load(&mut memory, 0x0202, &[0x5B]);
+    cpu.step(&mut memory);
+    assert_eq!(cpu.direct_page, 0x3000);
+}
+
+#[test]
+fn emulation_mode_direct_page_indexing_wraps_within_the_page() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup();
+    cpu.direct_page = 0x0300;
+    cpu.register_x = 0x20;
+    memory.data[0x0310] = 0x11;
+    memory.data[0x0410] = 0x22;
+    load(&mut memory, 0xFFFC, &[0xB5, 0xF0]);
+    cpu.step(&mut memory);
+    assert_eq!(cpu.accumulator, 0x0011);
+}
+
+#[test]
+fn data_bank_and_long_addressing_reach_every_bank() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup_native(0x00);
+    cpu.data_bank = 0x12;
+    cpu.register_x = 0x0010;
+    memory.data[0x123456] = 0xAA;
+    memory.data[0x7EFFFF + 0x10] = 0xBB;
+    memory.data[0xFF0000] = 0xCC;
+    // LDA $3456, LDA $7EFFFF,X, LDA $FF0000
+    load(
+        &mut memory,
+        0x0200,
+        &[
+            0xAD, 0x56, 0x34, 0xBF, 0xFF, 0xFF, 0x7E, 0xAF, 0x00, 0x00, 0xFF,
+        ],
+    );
+    cpu.step(&mut memory);
+    assert_eq!(cpu.accumulator, 0x00AA);
+    cpu.step(&mut memory);
+    assert_eq!(cpu.accumulator, 0x00BB);
+    let cycles: i32 = cpu.step(&mut memory);
+    assert_eq!(cpu.accumulator, 0x00CC);
+    assert_eq!(cycles, 5);
+}
+
+#[test]
+fn indirect_long_pointers_carry_a_bank() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup_native(0x20);
+    cpu.register_y = 0x0002;
+    load(&mut memory, 0x0040, &[0x00, 0x80, 0x05]);
+    load(&mut memory, 0x058002, &[0x34, 0x12]);
+    // LDA [$40],Y
+    load(&mut memory, 0x0200, &[0xB7, 0x40]);
+    let cycles: i32 = cpu.step(&mut memory);
+    assert_eq!(cpu.accumulator, 0x1234);
+    assert_eq!(cycles, 7);
+}
+
+#[test]
+fn stack_relative_addressing() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup_native(0x00);
+    cpu.stack_pointer = 0x1FF0;
+    cpu.data_bank = 0x02;
+    cpu.register_y = 0x0001;
+    cpu.accumulator = 0x0099;
+    load(&mut memory, 0x1FF3, &[0x00, 0x30]);
+    // LDA $03,S then STA ($03,S),Y
+    load(&mut memory, 0x0200, &[0xA3, 0x03, 0x93, 0x03]);
+    cpu.step(&mut memory);
+    assert_eq!(cpu.accumulator, 0x0000);
+    cpu.accumulator = 0x0099;
+    cpu.step(&mut memory);
+    assert_eq!(memory.data[0x023001], 0x99);
+}
+
+#[test]
+fn jsl_and_rtl_cross_banks() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup_native(0x00);
+    cpu.stack_pointer = 0x1FFF;
+    // JSL $048000, then RTL there
+    load(&mut memory, 0x0200, &[0x22, 0x00, 0x80, 0x04]);
+    memory.data[0x048000] = 0x6B;
+    assert_eq!(cpu.step(&mut memory), 8);
+    assert_eq!(cpu.program_address(), 0x048000);
+    assert_eq!(memory.data[0x1FFD..0x2000], [0x03, 0x02, 0x00]);
+    assert_eq!(cpu.step(&mut memory), 6);
+    assert_eq!(cpu.program_address(), 0x000204);
+    assert_eq!(cpu.stack_pointer, 0x1FFF);
+}
+
+#[test]
+fn jml_jumps_through_a_long_pointer_in_bank_0() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup_native(0x00);
+    load(&mut memory, 0x0300, &[0x56, 0x34, 0x12]);
+    load(&mut memory, 0x0200, &[0xDC, 0x00, 0x03]);
+    cpu.step(&mut memory);
+    assert_eq!(cpu.program_address(), 0x123456);
+}
+
+#[test]
+fn mvn_copies_a_block_upwards_one_byte_per_step() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup_native(0x30);
+    load(&mut memory, 0x011000, &[1, 2, 3, 4]);
+    cpu.accumulator = 0x0003;
+    cpu.register_x = 0x1000;
+    cpu.register_y = 0x2000;
+    // MVN $01,$02
+    load(&mut memory, 0x0200, &[0x54, 0x02, 0x01, 0xEA]);
+    let mut steps: i32 = 0;
+    while cpu.program_counter == 0x0200 {
+        assert_eq!(cpu.step(&mut memory), 7);
+        steps += 1;
+    }
+    assert_eq!(steps, 4);
+    assert_eq!(memory.data[0x022000..0x022004], [1, 2, 3, 4]);
+    assert_eq!(cpu.accumulator, 0xFFFF);
+    assert_eq!(cpu.register_x, 0x1004);
+    assert_eq!(cpu.register_y, 0x2004);
+    assert_eq!(cpu.data_bank, 0x02);
+    assert_eq!(cpu.program_counter, 0x0203);
+}
+
+#[test]
+fn mvp_copies_a_block_downwards() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup_native(0x30);
+    load(&mut memory, 0x1000, &[1, 2, 3]);
+    cpu.accumulator = 0x0002;
+    cpu.register_x = 0x1002;
+    cpu.register_y = 0x1003;
+    // MVP $00,$00 moves the block up one byte without overwriting itself
+    load(&mut memory, 0x0200, &[0x44, 0x00, 0x00]);
+    cpu.execute(21, &mut memory);
+    assert_eq!(memory.data[0x1000..0x1004], [1, 1, 2, 3]);
+    assert_eq!(cpu.register_x, 0x0FFF);
+}
+
+#[test]
+fn native_interrupts_push_the_program_bank() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup_native(0x00);
+    cpu.program_bank = 0x03;
+    cpu.stack_pointer = 0x1FFF;
+    cpu.processor_status.set_interrupt(false);
+    cpu.processor_status.set_decimal(true);
+    load(&mut memory, 0xFFEE, &[0x00, 0x90]);
+    memory.data[0x9000] = 0x40;
+    cpu.set_irq(true);
+    assert_eq!(cpu.step(&mut memory), 8);
+    cpu.set_irq(false);
+    assert_eq!(cpu.program_address(), 0x009000);
+    assert_eq!(memory.data[0x1FFF], 0x03);
+    assert_eq!(memory.data[0x1FFD..0x1FFF], [0x00, 0x02]);
+    assert!(!cpu.processor_status.decimal());
+
+    assert_eq!(cpu.step(&mut memory), 7);
+    assert_eq!(cpu.program_address(), 0x030200);
+    assert!(cpu.processor_status.decimal());
+}
+
+#[test]
+fn emulation_mode_brk_and_cop_use_the_6502_vectors() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup();
+    load(&mut memory, 0xFFF4, &[0x00, 0x40]);
+    load(&mut memory, 0xFFFE, &[0x00, 0x50]);
+    cpu.program_counter = 0x0200;
+    load(&mut memory, 0x0200, &[0x02, 0x00]);
+    load(&mut memory, 0x4000, &[0x00, 0x00]);
+    cpu.step(&mut memory);
+    assert_eq!(cpu.program_counter, 0x4000);
+    cpu.step(&mut memory);
+    assert_eq!(cpu.program_counter, 0x5000);
+    // The pushed status has the break bit set
+    assert_eq!(memory.data[0x01FA] & 0b0001_0000, 0b0001_0000);
+    assert_eq!(cpu.stack_pointer, 0x01F9);
+}
+
+#[test]
+fn emulation_mode_stack_stays_in_page_1() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup();
+    cpu.stack_pointer = 0x0100;
+    cpu.accumulator = 0x0042;
+    load(&mut memory, 0xFFFC, &[0x48]);
+    cpu.step(&mut memory);
+    assert_eq!(memory.data[0x0100], 0x42);
+    assert_eq!(cpu.stack_pointer, 0x01FF);
+}
+
+#[test]
+fn pea_pei_and_per_push_words() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup_native(0x00);
+    cpu.stack_pointer = 0x1FFF;
+    load(&mut memory, 0x0010, &[0xCD, 0xAB]);
+    // PEA $1234, PEI ($10), PER $0010
+    load(
+        &mut memory,
+        0x0200,
+        &[0xF4, 0x34, 0x12, 0xD4, 0x10, 0x62, 0x10, 0x00],
+    );
+    cpu.execute(17, &mut memory);
+    assert_eq!(
+        memory.data[0x1FFA..0x2000],
+        [0x18, 0x02, 0xCD, 0xAB, 0x34, 0x12]
+    );
+}
+
+#[test]
+fn brl_reaches_anywhere_in_the_bank() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup_native(0x00);
+    load(&mut memory, 0x0200, &[0x82, 0xFD, 0x7F]);
+    assert_eq!(cpu.step(&mut memory), 4);
+    assert_eq!(cpu.program_counter, 0x8200);
+}
+
+#[test]
+fn sixteen_bit_decimal_arithmetic() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup_native(0x20);
+    cpu.processor_status.set_decimal(true);
+    cpu.accumulator = 0x1999;
+    // ADC #$8001, then SBC #$0001
+    load(&mut memory, 0x0200, &[0x69, 0x01, 0x80, 0xE9, 0x01, 0x00]);
+    cpu.step(&mut memory);
+    assert_eq!(cpu.accumulator, 0x0000);
+    assert!(cpu.processor_status.carry());
+    assert!(cpu.processor_status.zero());
+    cpu.step(&mut memory);
+    assert_eq!(cpu.accumulator, 0x9999);
+    assert!(!cpu.processor_status.carry());
+    assert!(cpu.processor_status.negative());
+}
+
+#[test]
+fn sixteen_bit_binary_arithmetic_and_compare() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup_native(0x30);
+    cpu.accumulator = 0x7FFF;
+    cpu.register_x = 0x0100;
+    // ADC #$0001, CPX #$0200
+    load(&mut memory, 0x0200, &[0x69, 0x01, 0x00, 0xE0, 0x00, 0x02]);
+    cpu.step(&mut memory);
+    assert_eq!(cpu.accumulator, 0x8000);
+    assert!(cpu.processor_status.overflow());
+    assert!(cpu.processor_status.negative());
+    cpu.step(&mut memory);
+    assert!(!cpu.processor_status.carry());
+    assert!(cpu.processor_status.negative());
+}
+
+#[test]
+fn wai_waits_for_an_interrupt() {
+    let (mut cpu, mut memory): (CPU65816, LongMemory) = setup_native(0x00);
+    cpu.processor_status.set_interrupt(true);
+    load(&mut memory, 0x0200, &[0xCB, 0xEA]);
+    cpu.step(&mut memory);
+    assert!(cpu.is_waiting());
+    cpu.step(&mut memory);
+    assert_eq!(cpu.program_counter, 0x0201);
+    // I is set, so the IRQ only ends the wait
+    cpu.set_irq(true);
+    cpu.step(&mut memory);
+    assert!(!cpu.is_waiting());
+    assert_eq!(cpu.program_counter, 0x0202);
+}
@@ -0,0 +1,488 @@
+use rust6502::{disassembler::*, instructions::*, *};
+
+mod common;
+
+use common::recording_bus::{Access, RecordingBus};
+
+fn setup() -> (CPU, Memory) {
+    let (mut cpu, memory): (CPU, Memory) = common::setup();
+    cpu.variant = Variant::Wdc65C02;
+    (cpu, memory)
+}
+
+#[test]
+fn nmos_is_the_default_variant() {
+    assert_eq!(CPU::new().variant, Variant::Nmos6502);
+    assert_eq!(
+        CPU::at_reset_vector_unsequenced().variant,
+        Variant::Nmos6502
+    );
+    assert_eq!(
+        CPU::with_variant(Variant::Wdc65C02).variant,
+        Variant::Wdc65C02
+    );
+
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    memory.data[0xFFFC] = INSTRUCTION_BRA;
+    let error = cpu.try_execute(3, &mut memory).unwrap_err();
+    assert!(matches!(error, ExecutionError::IllegalOpcode { .. }));
+}
+
+#[test]
+fn opcode_table_is_indexed_by_opcode() {
+    for (index, info) in OPCODES_65C02.iter().enumerate() {
+        assert_eq!(info.opcode as usize, index);
+        assert_eq!(
+            info.length,
+            1 + info.addressing_mode.operand_length(),
+            "opcode {:#04x}",
+            info.opcode
+        );
+    }
+    assert_eq!(
+        OPCODES_65C02.iter().filter(|info| info.documented).count(),
+        212
+    );
+    assert!(OPCODES_65C02
+        .iter()
+        .all(|info| info.documented || info.mnemonic == Mnemonic::NOP));
+}
+
+#[test]
+fn every_opcode_takes_its_listed_cycles() {
+    for info in OPCODES_65C02.iter() {
+        let (mut cpu, mut memory): (CPU, Memory) = setup();
+        cpu.program_counter = 0x0200;
+        memory.data[0x0200] = info.opcode;
+        memory.data[0x0201] = 0x02;
+        memory.data[0x0202] = 0x12;
+        let step: Step = cpu.step(&mut memory).unwrap();
+        let branch: bool = matches!(
+            info.addressing_mode,
+            AddressingMode::Relative | AddressingMode::ZeroPageRelative
+        );
+        let branch_taken: bool = branch && cpu.program_counter != 0x0200 + info.length as Word;
+        assert_eq!(
+            step.cycles,
+            info.cycles as i32 + branch_taken as i32,
+            "opcode {:#04x}",
+            info.opcode
+        );
+    }
+}
+
+#[test]
+fn reserved_opcodes_are_nops_of_various_lengths() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    let program: [Byte; 7] = [0x03, 0x02, 0xFF, 0x5C, 0x34, 0x12, 0xDC];
+    memory.data[0x0200..0x0207].copy_from_slice(&program);
+    cpu.program_counter = 0x0200;
+    let lengths: Vec<(Word, i32)> = (0..3)
+        .map(|_| {
+            let step: Step = cpu.step(&mut memory).unwrap();
+            (step.address, step.cycles)
+        })
+        .collect();
+    assert_eq!(lengths, vec![(0x0200, 1), (0x0201, 2), (0x0203, 8)]);
+    assert_eq!(cpu.program_counter, 0x0206);
+    assert_eq!(cpu.accumulator, 0x00);
+}
+
+#[test]
+fn bra_always_branches() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.processor_status.set_zero(true);
+    memory.data[0xFFFC] = INSTRUCTION_BRA;
+    memory.data[0xFFFD] = 0x80;
+    let cycles: i32 = cpu.execute(1, &mut memory);
+    assert_eq!(cpu.program_counter, 0xFF7E);
+    assert_eq!(cycles, 3);
+}
+
+#[test]
+fn phx_and_ply_move_x_to_y_through_the_stack() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.register_x = 0x80;
+    memory.data[0xFFFC] = INSTRUCTION_PHX;
+    memory.data[0xFFFD] = INSTRUCTION_PLY;
+    let cycles: i32 = cpu.execute(7, &mut memory);
+    assert_eq!(cycles, 7);
+    assert_eq!(cpu.register_y, 0x80);
+    assert_eq!(cpu.stack_pointer, 0xFF);
+    assert!(cpu.processor_status.negative());
+    assert!(!cpu.processor_status.zero());
+}
+
+#[test]
+fn stz_stores_zero() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0x42;
+    cpu.register_x = 0x01;
+    memory.data[0x4481] = 0xFF;
+    memory.data[0xFFFC] = INSTRUCTION_STZ_ABS_X;
+    memory.data[0xFFFD] = 0x80;
+    memory.data[0xFFFE] = 0x44;
+    let cycles: i32 = cpu.execute(5, &mut memory);
+    assert_eq!(cycles, 5);
+    assert_eq!(memory.data[0x4481], 0x00);
+}
+
+#[test]
+fn tsb_and_trb_set_and_clear_accumulator_bits() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0b0000_1111;
+    memory.data[0x0042] = 0b0011_0000;
+    memory.data[0xFFFC] = INSTRUCTION_TSB_ZERO;
+    memory.data[0xFFFD] = 0x42;
+    memory.data[0xFFFE] = INSTRUCTION_TRB_ZERO;
+    memory.data[0xFFFF] = 0x42;
+
+    cpu.execute(5, &mut memory);
+    assert_eq!(memory.data[0x0042], 0b0011_1111);
+    assert!(cpu.processor_status.zero());
+
+    cpu.accumulator = 0b0000_0011;
+    cpu.execute(5, &mut memory);
+    assert_eq!(memory.data[0x0042], 0b0011_1100);
+    assert!(!cpu.processor_status.zero());
+    assert_eq!(cpu.accumulator, 0b0000_0011);
+}
+
+#[test]
+fn zero_page_indirect_reads_and_writes_through_a_pointer() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    // The pointer at $FF wraps around to $00 for its high byte
+    memory.data[0x00FF] = 0x34;
+    memory.data[0x0000] = 0x12;
+    memory.data[0x1234] = 0x99;
+    memory.data[0xFFFC] = INSTRUCTION_LDA_INDR_ZERO;
+    memory.data[0xFFFD] = 0xFF;
+    let cycles: i32 = cpu.execute(5, &mut memory);
+    assert_eq!(cycles, 5);
+    assert_eq!(cpu.accumulator, 0x99);
+    assert!(cpu.processor_status.negative());
+
+    cpu.program_counter = 0x0200;
+    cpu.accumulator = 0x24;
+    memory.data[0x0200] = INSTRUCTION_STA_INDR_ZERO;
+    memory.data[0x0201] = 0xFF;
+    cpu.execute(5, &mut memory);
+    assert_eq!(memory.data[0x1234], 0x24);
+}
+
+#[test]
+fn bit_immediate_only_changes_zero() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0x0F;
+    cpu.processor_status.set_negative(false);
+    cpu.processor_status.set_overflow(true);
+    memory.data[0xFFFC] = INSTRUCTION_BIT_IMM;
+    memory.data[0xFFFD] = 0xF0;
+    let cycles: i32 = cpu.execute(2, &mut memory);
+    assert_eq!(cycles, 2);
+    assert!(cpu.processor_status.zero());
+    assert!(!cpu.processor_status.negative());
+    assert!(cpu.processor_status.overflow());
+    assert_eq!(
+        Variant::Wdc65C02
+            .opcode_info(INSTRUCTION_BIT_IMM)
+            .affected_flags,
+        FLAG_ZERO
+    );
+}
+
+#[test]
+fn inc_and_dec_work_on_the_accumulator() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.accumulator = 0xFF;
+    memory.data[0xFFFC] = INSTRUCTION_INC_A;
+    memory.data[0xFFFD] = INSTRUCTION_DEC_A;
+    cpu.execute(2, &mut memory);
+    assert_eq!(cpu.accumulator, 0x00);
+    assert!(cpu.processor_status.zero());
+    cpu.execute(2, &mut memory);
+    assert_eq!(cpu.accumulator, 0xFF);
+    assert!(cpu.processor_status.negative());
+}
+
+#[test]
+fn rmb_and_smb_clear_and_set_one_bit() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    memory.data[0x0042] = 0xFF;
+    memory.data[0x0043] = 0x00;
+    memory.data[0xFFFC] = INSTRUCTION_RMB0 | (3 << 4);
+    memory.data[0xFFFD] = 0x42;
+    memory.data[0xFFFE] = INSTRUCTION_SMB0 | (6 << 4);
+    memory.data[0xFFFF] = 0x43;
+    let cycles: i32 = cpu.execute(10, &mut memory);
+    assert_eq!(cycles, 10);
+    assert_eq!(memory.data[0x0042], 0b1111_0111);
+    assert_eq!(memory.data[0x0043], 0b0100_0000);
+}
+
+#[test]
+fn bbr_and_bbs_branch_on_one_bit() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    memory.data[0x0042] = 0b0000_0100;
+    cpu.program_counter = 0x0200;
+    // BBR2 is not taken, BBS2 is
+    memory.data[0x0200..0x0206].copy_from_slice(&[
+        INSTRUCTION_BBR0 | (2 << 4),
+        0x42,
+        0x10,
+        INSTRUCTION_BBS0 | (2 << 4),
+        0x42,
+        0x10,
+    ]);
+    let step: Step = cpu.step(&mut memory).unwrap();
+    assert_eq!(step.cycles, 5);
+    assert_eq!(step.operand, Some(0x1042));
+    assert_eq!(cpu.program_counter, 0x0203);
+    let step: Step = cpu.step(&mut memory).unwrap();
+    assert_eq!(step.cycles, 6);
+    assert_eq!(cpu.program_counter, 0x0216);
+}
+
+#[test]
+fn jmp_indirect_does_not_wrap_within_the_page() {
+    for (variant, target) in [(Variant::Nmos6502, 0x1234), (Variant::Wdc65C02, 0x5634)] {
+        let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+        cpu.variant = variant;
+        memory.data[0x02FF] = 0x34;
+        memory.data[0x0200] = 0x12;
+        memory.data[0x0300] = 0x56;
+        memory.data[0xFFFC] = INSTRUCTION_JMP_INDR;
+        memory.data[0xFFFD] = 0xFF;
+        memory.data[0xFFFE] = 0x02;
+        let step: Step = cpu.step(&mut memory).unwrap();
+        assert_eq!(cpu.program_counter, target, "{:?}", variant);
+        assert_eq!(
+            step.cycles,
+            variant.opcode_info(INSTRUCTION_JMP_INDR).cycles as i32
+        );
+    }
+}
+
+#[test]
+fn jmp_indexed_indirect_adds_x_to_the_pointer_address() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.register_x = 0x04;
+    memory.data[0x1204] = 0x00;
+    memory.data[0x1205] = 0x80;
+    memory.data[0xFFFC] = INSTRUCTION_JMP_INDR_X;
+    memory.data[0xFFFD] = 0x00;
+    memory.data[0xFFFE] = 0x12;
+    let cycles: i32 = cpu.execute(6, &mut memory);
+    assert_eq!(cycles, 6);
+    assert_eq!(cpu.program_counter, 0x8000);
+}
+
+#[test]
+fn decimal_mode_sets_n_and_z_from_the_result_and_takes_a_cycle_more() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.processor_status.set_decimal(true);
+    cpu.accumulator = 0x99;
+    memory.data[0xFFFC] = INSTRUCTION_ADC_IMM;
+    memory.data[0xFFFD] = 0x01;
+    let cycles: i32 = cpu.execute(2, &mut memory);
+    assert_eq!(cycles, 3);
+    assert_eq!(cpu.accumulator, 0x00);
+    assert!(cpu.processor_status.zero());
+    assert!(!cpu.processor_status.negative());
+    assert!(cpu.processor_status.carry());
+
+    cpu.program_counter = 0x0200;
+    cpu.processor_status.set_carry(true);
+    memory.data[0x0200] = INSTRUCTION_SBC_IMM;
+    memory.data[0x0201] = 0x01;
+    let cycles: i32 = cpu.execute(2, &mut memory);
+    assert_eq!(cycles, 3);
+    assert_eq!(cpu.accumulator, 0x99);
+    assert!(!cpu.processor_status.zero());
+    assert!(cpu.processor_status.negative());
+    assert!(!cpu.processor_status.carry());
+}
+
+#[test]
+fn decimal_subtraction_differs_from_nmos_for_invalid_bcd() {
+    // $10 - $0F: the chips only agree when both operands are valid BCD
+    let mut results: Vec<Byte> = Vec::new();
+    for variant in [Variant::Nmos6502, Variant::Wdc65C02] {
+        let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+        cpu.variant = variant;
+        cpu.processor_status.set_decimal(true);
+        cpu.processor_status.set_carry(true);
+        cpu.accumulator = 0x10;
+        memory.data[0xFFFC] = INSTRUCTION_SBC_IMM;
+        memory.data[0xFFFD] = 0x0F;
+        cpu.execute(2, &mut memory);
+        results.push(cpu.accumulator);
+    }
+    assert_eq!(results, vec![0x0B, 0xFB]);
+}
+
+#[test]
+fn brk_and_interrupts_clear_decimal() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.processor_status.set_decimal(true);
+    memory.data[0xFFFC] = INSTRUCTION_BRK;
+    cpu.execute(7, &mut memory);
+    assert!(!cpu.processor_status.decimal());
+    assert_eq!(memory.data[0x01FD] & 0b0000_1000, 0b0000_1000);
+
+    cpu.processor_status.set_decimal(true);
+    cpu.set_nmi(true);
+    let step: Step = cpu.step(&mut memory).unwrap();
+    assert_eq!(step.interrupt, Some(Interrupt::Nmi));
+    assert!(!cpu.processor_status.decimal());
+
+    // The NMOS chip leaves D alone
+    let (mut cpu, mut memory): (CPU, Memory) = common::setup();
+    cpu.processor_status.set_decimal(true);
+    memory.data[0xFFFC] = INSTRUCTION_BRK;
+    cpu.execute(7, &mut memory);
+    assert!(cpu.processor_status.decimal());
+}
+
+#[test]
+fn wai_waits_for_an_interrupt() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.program_counter = 0x0200;
+    memory.data[0x0200] = INSTRUCTION_WAI;
+    memory.data[0x0201] = INSTRUCTION_INX;
+    memory.data[0xFFFE] = 0x00;
+    memory.data[0xFFFF] = 0x80;
+    memory.data[0x8000] = INSTRUCTION_NOP;
+
+    assert_eq!(cpu.step(&mut memory).unwrap().cycles, 3);
+    for _ in 0..3 {
+        let step: Step = cpu.step(&mut memory).unwrap();
+        assert!(cpu.is_waiting());
+        assert_eq!(step.cycles, 1);
+        assert_eq!(step.opcode, INSTRUCTION_WAI);
+        assert_eq!(cpu.program_counter, 0x0201);
+    }
+
+    cpu.set_irq(true);
+    let step: Step = cpu.step(&mut memory).unwrap();
+    assert_eq!(step.interrupt, Some(Interrupt::Irq));
+    assert!(!cpu.is_waiting());
+    assert_eq!(cpu.program_counter, 0x8000);
+}
+
+#[test]
+fn masked_irq_ends_wai_without_being_serviced() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.processor_status.set_interrupt(true);
+    cpu.program_counter = 0x0200;
+    memory.data[0x0200] = INSTRUCTION_WAI;
+    memory.data[0x0201] = INSTRUCTION_INX;
+    cpu.step(&mut memory).unwrap();
+    cpu.step(&mut memory).unwrap();
+    assert!(cpu.is_waiting());
+
+    cpu.set_irq(true);
+    let step: Step = cpu.step(&mut memory).unwrap();
+    assert_eq!(step.interrupt, None);
+    assert_eq!(step.opcode, INSTRUCTION_INX);
+    assert!(!cpu.is_waiting());
+    assert_eq!(cpu.register_x, 0x01);
+}
+
+#[test]
+fn stp_stops_until_reset() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    cpu.program_counter = 0x0200;
+    memory.data[0x0200] = INSTRUCTION_STP;
+    memory.data[0xFFFC] = 0x00;
+    memory.data[0xFFFD] = 0x80;
+    cpu.step(&mut memory).unwrap();
+    assert!(cpu.is_stopped());
+
+    cpu.set_irq(true);
+    cpu.set_nmi(true);
+    for _ in 0..3 {
+        let step: Step = cpu.step(&mut memory).unwrap();
+        assert_eq!(step.opcode, INSTRUCTION_STP);
+        assert_eq!(step.cycles, 1);
+    }
+    assert_eq!(cpu.program_counter, 0x0201);
+
+    cpu.trigger_reset();
+    let step: Step = cpu.step(&mut memory).unwrap();
+    assert_eq!(step.interrupt, Some(Interrupt::Reset));
+    assert!(!cpu.is_stopped());
+    assert_eq!(cpu.program_counter, 0x8000);
+}
+
+#[test]
+fn read_modify_write_reads_twice_instead_of_writing_twice() {
+    let mut cpu: CPU = CPU::at_reset_vector_unsequenced();
+    cpu.variant = Variant::Wdc65C02;
+    let mut bus: RecordingBus = RecordingBus::new();
+    bus.memory.data[0xFFFC] = INSTRUCTION_INC_ZERO;
+    bus.memory.data[0xFFFD] = 0x42;
+    bus.memory.data[0x0042] = 0x07;
+    cpu.execute(5, &mut bus);
+    assert_eq!(
+        bus.accesses,
+        vec![
+            Access::Read(0xFFFC, INSTRUCTION_INC_ZERO),
+            Access::Read(0xFFFD, 0x42),
+            Access::Read(0x0042, 0x07),
+            Access::Read(0x0042, 0x07),
+            Access::Write(0x0042, 0x08),
+        ]
+    );
+}
+
+#[test]
+fn indexing_reads_the_last_operand_byte_while_fixing_the_address() {
+    let mut cpu: CPU = CPU::at_reset_vector_unsequenced();
+    cpu.variant = Variant::Wdc65C02;
+    cpu.register_x = 0x01;
+    let mut bus: RecordingBus = RecordingBus::new();
+    bus.memory.data[0xFFFC] = INSTRUCTION_LDA_ABS_X;
+    bus.memory.data[0xFFFD] = 0xFF;
+    bus.memory.data[0xFFFE] = 0x12;
+    cpu.execute(5, &mut bus);
+    assert_eq!(bus.accesses[3], Access::Read(0xFFFE, 0x12));
+    assert_eq!(bus.accesses[4], Access::Read(0x1300, 0x00));
+}
+
+#[test]
+fn disassembly_uses_the_variant_table() {
+    let (_, mut memory): (CPU, Memory) = setup();
+    let program: [Byte; 12] = [
+        0xB2, 0x12, 0x7C, 0x34, 0x12, 0x3F, 0x20, 0x04, 0xC7, 0x20, 0x80, 0xF4,
+    ];
+    memory.data[0x0200..0x020C].copy_from_slice(&program);
+    let text: Vec<String> =
+        disassemble_range_variant(&mut memory, 0x0200, 0x020A, Variant::Wdc65C02)
+            .iter()
+            .map(|instruction| instruction.to_string())
+            .collect();
+    assert_eq!(
+        text,
+        vec![
+            "LDA ($12)",
+            "JMP ($1234,X)",
+            "BBR3 $20,$020C",
+            "SMB4 $20",
+            "BRA $0200"
+        ]
+    );
+    assert_eq!(disassemble(&mut memory, 0x0200).to_string(), "*JAM");
+}
+
+#[test]
+fn snapshots_keep_wai_and_stp() {
+    let (mut cpu, mut memory): (CPU, Memory) = setup();
+    memory.data[0xFFFC] = INSTRUCTION_WAI;
+    cpu.step(&mut memory).unwrap();
+    let data: Vec<Byte> = snapshot::save(&cpu, &memory);
+    let (mut restored, mut restored_memory): (CPU, Memory) = setup();
+    snapshot::load(&data, &mut restored, &mut restored_memory).unwrap();
+    assert!(restored.is_waiting());
+    assert!(!restored.is_stopped());
+}